@@ -0,0 +1,133 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// Counts the heap allocations, and times, the CSV parser, the row tokenizer
+// and `group_by` make on batches of rows, with and without a `Bump` arena
+// that is reset between batches. The arena versions should make a handful
+// of allocations a batch where the others make several per row
+//
+// Run with `cargo bench --bench bump --features bump`
+
+use rust_collections::bump::Bump;
+use rust_collections::csv_map::{parse_csv, parse_csv_in};
+use rust_collections::grouping::{group_by, group_by_in};
+use rust_collections::spreadsheet::{parse_cells, parse_fields_in, SpreadsheetCell};
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::hint::black_box;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+const BATCHES: usize = 20;
+const ROWS_PER_BATCH: usize = 10_000;
+
+// Passes everything to the system allocator, counting each allocation and
+// reallocation
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+// Groups of four rows, by their id
+fn group_key(line: &&str) -> usize {
+    let id = &line[..line.find(',').unwrap()];
+    id.parse::<usize>().unwrap() / 4
+}
+
+// Allocations per batch and milliseconds in all for running `f` on every
+// batch
+fn measure<F: FnMut(&str)>(batches: &[String], mut f: F) -> (usize, f64) {
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    for batch in batches {
+        f(batch);
+    }
+    let elapsed = start.elapsed().as_secs_f64() * 1000.0;
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    (allocations / batches.len(), elapsed)
+}
+
+fn report(name: &str, plain: (usize, f64), bump: (usize, f64)) {
+    println!(
+        "{:<10} plain {:>7} allocs/batch {:>7.1} ms, bump {:>7} allocs/batch {:>7.1} ms",
+        name, plain.0, plain.1, bump.0, bump.1
+    );
+}
+
+fn main() {
+    let batches: Vec<String> = (0..BATCHES)
+        .map(|batch| {
+            (0..ROWS_PER_BATCH)
+                .map(|row| {
+                    let id = batch * ROWS_PER_BATCH + row;
+                    format!(
+                        "{},region-{},\"Item, {}\",{}.5\n",
+                        id,
+                        id % 37,
+                        id,
+                        id % 1000
+                    )
+                })
+                .collect()
+        })
+        .collect();
+    let mut arena = Bump::new();
+
+    let plain = measure(&batches, |text| {
+        black_box(parse_csv(text).unwrap());
+    });
+    let bump = measure(&batches, |text| {
+        arena.reset();
+        black_box(parse_csv_in(text, &arena).unwrap());
+    });
+    report("parse_csv", plain, bump);
+
+    // `parse_cells` also infers each cell's type, which the arena version
+    // leaves to the caller
+    let plain = measure(&batches, |text| {
+        black_box(
+            parse_cells(text.lines(), ',')
+                .collect::<Result<Vec<Vec<SpreadsheetCell>>, _>>()
+                .unwrap(),
+        );
+    });
+    let bump = measure(&batches, |text| {
+        arena.reset();
+        black_box(parse_fields_in(text.lines(), ',', &arena).unwrap());
+    });
+    report("tokenize", plain, bump);
+
+    let lines: Vec<Vec<&str>> = batches
+        .iter()
+        .map(|batch| batch.lines().collect())
+        .collect();
+    let mut batch_lines = lines.iter();
+    let plain = measure(&batches, |_| {
+        let lines = batch_lines.next().unwrap();
+        black_box(group_by(lines.iter().copied(), group_key));
+    });
+    let mut batch_lines = lines.iter();
+    let bump = measure(&batches, |_| {
+        let lines = batch_lines.next().unwrap();
+        arena.reset();
+        black_box(group_by_in(lines.iter().copied(), group_key, &arena));
+    });
+    report("group_by", plain, bump);
+}
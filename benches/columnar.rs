@@ -0,0 +1,58 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// Compares aggregating one column of a row-oriented `Spreadsheet` against
+// the same column of a `ColumnarSheet`, for an all-integer column, an
+// all-float column, and an integer column with gaps in it
+//
+// Run with `cargo bench --bench columnar`
+
+use rust_collections::columnar::ColumnarSheet;
+use rust_collections::spreadsheet::{SheetOps, Spreadsheet, SpreadsheetCell};
+use std::hint::black_box;
+use std::time::Instant;
+
+const ROWS: [usize; 3] = [1_000, 100_000, 1_000_000];
+const REPEATS: u32 = 20;
+
+fn sheet(rows: usize) -> Spreadsheet {
+    (0..rows)
+        .map(|row| {
+            vec![
+                SpreadsheetCell::Int(row as i32 % 1000),
+                SpreadsheetCell::Float(row as f64 * 0.25),
+                if row % 10 == 0 {
+                    SpreadsheetCell::Empty
+                } else {
+                    SpreadsheetCell::Int(row as i32 % 7)
+                },
+                SpreadsheetCell::Text(format!("row {}", row)),
+            ]
+        })
+        .collect()
+}
+
+// Microseconds per aggregation of column `col`, averaged over `REPEATS`
+fn time_aggregates<S: SheetOps>(sheet: &S, col: usize) -> f64 {
+    let start = Instant::now();
+    for _ in 0..REPEATS {
+        black_box(sheet.column_aggregates(black_box(col)));
+    }
+    start.elapsed().as_micros() as f64 / f64::from(REPEATS)
+}
+
+fn main() {
+    for &rows in ROWS.iter() {
+        let rowwise = sheet(rows);
+        let columnar = ColumnarSheet::from_spreadsheet(&rowwise);
+        for &(col, name) in [(0, "int"), (1, "float"), (2, "gappy int")].iter() {
+            println!(
+                "{:>9} rows, {:>9} column: Spreadsheet {:>9.1} us, ColumnarSheet {:>9.1} us",
+                rows,
+                name,
+                time_aggregates(&rowwise, col),
+                time_aggregates(&columnar, col)
+            );
+        }
+    }
+}
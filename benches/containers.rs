@@ -0,0 +1,41 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// Prints the numbers from `comparison::comparison` as one table per key
+// distribution and size
+//
+// Run with `cargo bench --bench containers`
+
+use rust_collections::comparison::{comparison, Container, KeyDistribution, Operation};
+
+const SIZES: [usize; 3] = [1_000, 100_000, 1_000_000];
+
+fn main() {
+    for &size in SIZES.iter() {
+        let measurements = comparison(size);
+        for &distribution in KeyDistribution::ALL.iter() {
+            println!("{} {} keys (ns per key)", size, distribution);
+            print!("{:>14}", "");
+            for operation in Operation::ALL.iter() {
+                print!("{:>10}", operation.to_string());
+            }
+            println!();
+            for &container in Container::ALL.iter() {
+                print!("{:>14}", container.name());
+                for &operation in Operation::ALL.iter() {
+                    let nanos = measurements
+                        .iter()
+                        .find(|m| {
+                            m.container == container
+                                && m.distribution == distribution
+                                && m.operation == operation
+                        })
+                        .map_or(f64::NAN, |m| m.nanos_per_op);
+                    print!("{:>10.1}", nanos);
+                }
+                println!();
+            }
+            println!();
+        }
+    }
+}
@@ -0,0 +1,48 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// Compares read-heavy lookups in a `FrozenMap` against a `HashMap`
+//
+// Run with `cargo bench --bench frozen_map`
+
+use rust_collections::frozen_map::FrozenMapBuilder;
+use std::collections::HashMap;
+use std::hint::black_box;
+use std::time::Instant;
+
+const SIZES: [u64; 3] = [1_000, 100_000, 1_000_000];
+const LOOKUPS: u64 = 1_000_000;
+
+// Spreads sequential numbers over the whole `u64` range
+fn scramble(n: u64) -> u64 {
+    n.wrapping_mul(0x9E37_79B9_7F4A_7C15).rotate_left(17)
+}
+
+fn main() {
+    for &size in SIZES.iter() {
+        let mut builder = FrozenMapBuilder::with_capacity(size as usize);
+        let mut hash_map = HashMap::with_capacity(size as usize);
+        for n in 0..size {
+            builder.insert(scramble(n), n);
+            hash_map.insert(scramble(n), n);
+        }
+        let frozen = builder.freeze();
+
+        let start = Instant::now();
+        for n in 0..LOOKUPS {
+            black_box(frozen.get(&scramble(n % size)));
+        }
+        let frozen_ns = start.elapsed().as_nanos() as f64 / LOOKUPS as f64;
+
+        let start = Instant::now();
+        for n in 0..LOOKUPS {
+            black_box(hash_map.get(&scramble(n % size)));
+        }
+        let hash_ns = start.elapsed().as_nanos() as f64 / LOOKUPS as f64;
+
+        println!(
+            "{:>9} entries: FrozenMap {:>6.1} ns/lookup, HashMap {:>6.1} ns/lookup",
+            size, frozen_ns, hash_ns
+        );
+    }
+}
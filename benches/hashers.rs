@@ -0,0 +1,53 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// Compares `HashMap` lookups with the default SipHash hasher against
+// the crate's `FastHasher`
+//
+// Run with `cargo bench --bench hashers`
+
+use rust_collections::fast_hash::FastBuildHasher;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::hint::black_box;
+use std::time::Instant;
+
+const ENTRIES: usize = 100_000;
+const LOOKUPS: usize = 1_000_000;
+
+// Builds a map with `hasher` and returns the average lookup time in ns
+fn time_lookups<K, S>(keys: &[K], hasher: S) -> f64
+where
+    K: Eq + Hash + Clone,
+    S: BuildHasher,
+{
+    let mut map = HashMap::with_capacity_and_hasher(keys.len(), hasher);
+    for (n, key) in keys.iter().enumerate() {
+        map.insert(key.clone(), n);
+    }
+
+    let start = Instant::now();
+    for n in 0..LOOKUPS {
+        black_box(map.get(&keys[n % keys.len()]));
+    }
+    start.elapsed().as_nanos() as f64 / LOOKUPS as f64
+}
+
+fn main() {
+    let int_keys: Vec<u64> = (0..ENTRIES as u64)
+        .map(|n| n.wrapping_mul(0x9E37_79B9_7F4A_7C15))
+        .collect();
+    let string_keys: Vec<String> = (0..ENTRIES).map(|n| format!("key-{}", n)).collect();
+
+    println!(
+        "u64 keys:    SipHash {:>6.1} ns/lookup, FastHasher {:>6.1} ns/lookup",
+        time_lookups(&int_keys, RandomState::new()),
+        time_lookups(&int_keys, FastBuildHasher::default())
+    );
+    println!(
+        "String keys: SipHash {:>6.1} ns/lookup, FastHasher {:>6.1} ns/lookup",
+        time_lookups(&string_keys, RandomState::new()),
+        time_lookups(&string_keys, FastBuildHasher::default())
+    );
+}
@@ -0,0 +1,82 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// Compares `RobinHoodMap` against the linear-probing `ProbeMap`, both on a
+// fresh table and after a round of deletions has churned it
+//
+// Run with `cargo bench --bench open_addressing`
+
+use rust_collections::probe_map::ProbeMap;
+use rust_collections::robin_hood_map::RobinHoodMap;
+use std::hint::black_box;
+use std::time::Instant;
+
+const SIZES: [u64; 3] = [1_000, 100_000, 1_000_000];
+const LOOKUPS: u64 = 1_000_000;
+
+// Spreads sequential numbers over the whole `u64` range
+fn scramble(n: u64) -> u64 {
+    n.wrapping_mul(0x9E37_79B9_7F4A_7C15).rotate_left(17)
+}
+
+// Nanoseconds per call of `f`, averaged over `count` calls
+fn time_per_op<F: FnMut(u64)>(count: u64, mut f: F) -> f64 {
+    let start = Instant::now();
+    for n in 0..count {
+        f(n);
+    }
+    start.elapsed().as_nanos() as f64 / count as f64
+}
+
+fn main() {
+    for &size in SIZES.iter() {
+        let mut probe = ProbeMap::new();
+        let mut robin = RobinHoodMap::new();
+
+        let probe_insert = time_per_op(size, |n| {
+            probe.insert(scramble(n), n);
+        });
+        let robin_insert = time_per_op(size, |n| {
+            robin.insert(scramble(n), n);
+        });
+        println!(
+            "{:>9} entries: insert  ProbeMap {:>6.1} ns, RobinHoodMap {:>6.1} ns",
+            size, probe_insert, robin_insert
+        );
+
+        // Hits and misses alternate, since misses are where the two differ
+        let probe_lookup = time_per_op(LOOKUPS, |n| {
+            black_box(probe.get(&scramble(n % (size * 2))));
+        });
+        let robin_lookup = time_per_op(LOOKUPS, |n| {
+            black_box(robin.get(&scramble(n % (size * 2))));
+        });
+        println!(
+            "{:>9} entries: lookup  ProbeMap {:>6.1} ns, RobinHoodMap {:>6.1} ns",
+            size, probe_lookup, robin_lookup
+        );
+
+        // Remove every other key and put fresh ones in their place
+        for n in (0..size).step_by(2) {
+            probe.remove(&scramble(n));
+            robin.remove(&scramble(n));
+            probe.insert(scramble(n + size), n);
+            robin.insert(scramble(n + size), n);
+        }
+        let probe_churned = time_per_op(LOOKUPS, |n| {
+            black_box(probe.get(&scramble(n % (size * 2))));
+        });
+        let robin_churned = time_per_op(LOOKUPS, |n| {
+            black_box(robin.get(&scramble(n % (size * 2))));
+        });
+        println!(
+            "{:>9} entries: churned ProbeMap {:>6.1} ns, RobinHoodMap {:>6.1} ns \
+             (longest probe {} vs {})",
+            size,
+            probe_churned,
+            robin_churned,
+            probe.max_probe_length(),
+            robin.max_probe_distance() + 1
+        );
+    }
+}
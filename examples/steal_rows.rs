@@ -0,0 +1,137 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// A small work-stealing thread pool that totals the numbers in each row of
+// a `Spreadsheet`
+//
+// Every worker owns a `StealDeque` of row ranges. It splits the range it's
+// working on in half, pushing the back half for later, until it's small
+// enough to total. All the rows start with the first worker, so the others
+// only get work by stealing it, and a worker whose own deque runs dry steals
+// from the rest in turn.
+//
+// Run with `cargo run --release --example steal_rows`
+
+use rust_collections::spreadsheet::{Spreadsheet, SpreadsheetCell};
+use rust_collections::steal_deque::{StealDeque, Stealer};
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+const ROWS: usize = 200_000;
+const WORKERS: usize = 4;
+
+// Ranges no longer than this are totalled rather than split
+const LEAF_ROWS: usize = 512;
+
+type Task = Range<usize>;
+
+fn row_total(row: &[SpreadsheetCell]) -> f64 {
+    row.iter()
+        .map(|cell| match cell {
+            SpreadsheetCell::Int(value) => *value as f64,
+            SpreadsheetCell::Float(value) => *value,
+            _ => 0.0,
+        })
+        .sum()
+}
+
+// What one worker did
+struct Report {
+    totals: Vec<(usize, f64)>,
+    steals: usize,
+}
+
+fn steal(index: usize, stealers: &[Stealer<Task>]) -> Option<Task> {
+    // Start with the next worker along, so thieves spread over the deques
+    (1..stealers.len())
+        .map(|offset| &stealers[(index + offset) % stealers.len()])
+        .find_map(Stealer::steal_retrying)
+}
+
+fn work(
+    index: usize,
+    sheet: &Spreadsheet,
+    own: StealDeque<Task>,
+    stealers: &[Stealer<Task>],
+    remaining: &AtomicUsize,
+) -> Report {
+    let mut report = Report {
+        totals: Vec::new(),
+        steals: 0,
+    };
+    while remaining.load(Ordering::Acquire) > 0 {
+        let mut task = match own.pop() {
+            Some(task) => task,
+            None => match steal(index, stealers) {
+                Some(task) => {
+                    report.steals += 1;
+                    task
+                }
+                None => {
+                    thread::yield_now();
+                    continue;
+                }
+            },
+        };
+        while task.len() > LEAF_ROWS {
+            let middle = task.start + task.len() / 2;
+            own.push(middle..task.end);
+            task.end = middle;
+        }
+        for row in task.clone() {
+            let cells = sheet.row(row).expect("task rows are in the sheet");
+            report.totals.push((row, row_total(cells)));
+        }
+        remaining.fetch_sub(task.len(), Ordering::AcqRel);
+    }
+    report
+}
+
+fn main() {
+    let mut sheet = Spreadsheet::new();
+    for row in 0..ROWS {
+        sheet.push_row(vec![
+            SpreadsheetCell::Int(row as i32),
+            SpreadsheetCell::Float(row as f64 * 0.5),
+            SpreadsheetCell::Text(format!("row {}", row)),
+            SpreadsheetCell::Int((row % 7) as i32),
+        ]);
+    }
+
+    let deques: Vec<StealDeque<Task>> = (0..WORKERS).map(|_| StealDeque::new()).collect();
+    let stealers: Vec<Stealer<Task>> = deques.iter().map(StealDeque::stealer).collect();
+    deques[0].push(0..ROWS);
+    let remaining = AtomicUsize::new(ROWS);
+
+    let reports: Vec<Report> = thread::scope(|scope| {
+        let workers: Vec<_> = deques
+            .into_iter()
+            .enumerate()
+            .map(|(index, own)| {
+                let (sheet, stealers, remaining) = (&sheet, &stealers, &remaining);
+                scope.spawn(move || work(index, sheet, own, stealers, remaining))
+            })
+            .collect();
+        workers
+            .into_iter()
+            .map(|worker| worker.join().expect("worker panicked"))
+            .collect()
+    });
+
+    let mut totals = vec![f64::NAN; ROWS];
+    for (index, report) in reports.iter().enumerate() {
+        println!(
+            "worker {}: {} rows, {} steals",
+            index,
+            report.totals.len(),
+            report.steals
+        );
+        for &(row, total) in &report.totals {
+            totals[row] = total;
+        }
+    }
+    let expected: Vec<f64> = sheet.rows().map(row_total).collect();
+    assert_eq!(totals, expected, "every row totalled exactly once");
+    println!("total of all rows: {}", totals.iter().sum::<f64>());
+}
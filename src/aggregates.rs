@@ -0,0 +1,155 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! SUM, COUNT, MIN and MAX over the numbers in a spreadsheet column
+//!
+//! `ColumnAggregates::of` scans a column from scratch. A `Spreadsheet` can
+//! also be asked to track a column, in which case it keeps a
+//! `RunningAggregates` up to date as cells are set and rows are added, and
+//! reading the aggregates costs nothing. The minimum and maximum survive
+//! removals because the running state keeps every value in an ordered
+//! multiset, so each update is O(log n) in the number of distinct values.
+//!
+//! Only `Int` and `Float` cells count, as in a spreadsheet's SUM and COUNT.
+//! Values are ordered with `f64::total_cmp`, so a NaN is the maximum.
+//! A running float sum that has values taken back out of it can drift from
+//! a fresh sum in the last bits, and once a NaN or infinity has been added
+//! the running sum stays NaN or infinite until the column is tracked anew.
+
+use crate::spreadsheet::SpreadsheetCell;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+
+/// The aggregates of the numeric cells in one column
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ColumnAggregates {
+    pub count: usize,
+    pub sum: f64,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+impl ColumnAggregates {
+    /// Computes the aggregates of `cells` in one pass
+    pub fn of<'a, I>(cells: I) -> ColumnAggregates
+    where
+        I: IntoIterator<Item = &'a SpreadsheetCell>,
+    {
+        let mut aggregates = ColumnAggregates {
+            count: 0,
+            sum: 0.0,
+            min: None,
+            max: None,
+        };
+        for value in cells.into_iter().filter_map(numeric_value) {
+            aggregates.count += 1;
+            aggregates.sum += value;
+            let smaller = |min: f64| min.total_cmp(&value) == Ordering::Less;
+            if !aggregates.min.is_some_and(smaller) {
+                aggregates.min = Some(value);
+            }
+            let larger = |max: f64| max.total_cmp(&value) == Ordering::Greater;
+            if !aggregates.max.is_some_and(larger) {
+                aggregates.max = Some(value);
+            }
+        }
+        aggregates
+    }
+
+    /// `sum / count`, or `None` for a column with no numbers
+    pub fn mean(&self) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(self.sum / self.count as f64)
+        }
+    }
+}
+
+/// The value a cell contributes to the aggregates, if it's a number
+pub fn numeric_value(cell: &SpreadsheetCell) -> Option<f64> {
+    match cell {
+        SpreadsheetCell::Int(int) => Some(f64::from(*int)),
+        SpreadsheetCell::Float(float) => Some(*float),
+        _ => None,
+    }
+}
+
+/// Aggregates that are updated as cells come and go
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RunningAggregates {
+    sum: f64,
+    count: usize,
+    // Every value and how many times it occurs
+    values: BTreeMap<TotalF64, usize>,
+}
+
+impl RunningAggregates {
+    pub fn new() -> Self {
+        RunningAggregates::default()
+    }
+
+    pub fn add(&mut self, cell: &SpreadsheetCell) {
+        if let Some(value) = numeric_value(cell) {
+            self.sum += value;
+            self.count += 1;
+            *self.values.entry(TotalF64(value)).or_insert(0) += 1;
+        }
+    }
+
+    /// Takes back a cell previously passed to `add`
+    pub fn remove(&mut self, cell: &SpreadsheetCell) {
+        let value = match numeric_value(cell) {
+            Some(value) => value,
+            None => return,
+        };
+        let key = TotalF64(value);
+        match self.values.get_mut(&key) {
+            Some(occurrences) if *occurrences > 1 => *occurrences -= 1,
+            Some(_) => {
+                self.values.remove(&key);
+            }
+            None => return,
+        }
+        self.count -= 1;
+        // Start exactly from zero again rather than carrying rounding error
+        self.sum = if self.count == 0 {
+            0.0
+        } else {
+            self.sum - value
+        };
+    }
+
+    pub fn aggregates(&self) -> ColumnAggregates {
+        ColumnAggregates {
+            count: self.count,
+            sum: self.sum,
+            min: self.values.keys().next().map(|value| value.0),
+            max: self.values.keys().next_back().map(|value| value.0),
+        }
+    }
+}
+
+// An `f64` ordered by `total_cmp`, so it can key a `BTreeMap`
+#[derive(Debug, Clone, Copy)]
+struct TotalF64(f64);
+
+impl Ord for TotalF64 {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.0.total_cmp(&other.0)
+    }
+}
+
+impl PartialOrd for TotalF64 {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for TotalF64 {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for TotalF64 {}
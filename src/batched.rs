@@ -0,0 +1,107 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Splitting an iterator into fixed-size batches
+//!
+//! `batched` yields each batch as a new `Vec`. For hot loops such as bulk
+//! inserts or buffered writes, `batched_into` refills one caller-owned `Vec`
+//! instead, so that after the first batch no more allocation is needed.
+
+/// Yields the items of `iter` in `Vec`s of `size` items, the last of which
+/// may be shorter
+///
+/// Panics if `size` is zero.
+pub fn batched<I: IntoIterator>(iter: I, size: usize) -> Batched<I::IntoIter> {
+    assert!(size > 0, "batch size must be positive");
+    Batched {
+        iter: iter.into_iter(),
+        size,
+    }
+}
+
+/// Clears `buffer` and refills it with up to `size` items from `iter`,
+/// returning how many it took, which is 0 once `iter` is exhausted
+///
+/// Panics if `size` is zero.
+pub fn batched_into<I: Iterator>(iter: &mut I, size: usize, buffer: &mut Vec<I::Item>) -> usize {
+    assert!(size > 0, "batch size must be positive");
+    buffer.clear();
+    buffer.extend(iter.take(size));
+    buffer.len()
+}
+
+/// Iterator returned by `batched`
+#[derive(Debug, Clone)]
+pub struct Batched<I> {
+    iter: I,
+    size: usize,
+}
+
+impl<I: Iterator> Iterator for Batched<I> {
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Vec<I::Item>> {
+        let first = self.iter.next()?;
+        // A huge `size` over a short iterator shouldn't reserve room for
+        // items that will never come
+        let (lower, _) = self.iter.size_hint();
+        let mut batch = Vec::with_capacity(self.size.min(lower.saturating_add(1)));
+        batch.push(first);
+        batch.extend(self.iter.by_ref().take(self.size - 1));
+        Some(batch)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        (
+            lower.div_ceil(self.size),
+            upper.map(|upper| upper.div_ceil(self.size)),
+        )
+    }
+}
+
+impl<I: ExactSizeIterator> ExactSizeIterator for Batched<I> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_last_batch_may_be_short() {
+        let batches: Vec<Vec<u32>> = batched(1..=7, 3).collect();
+        assert_eq!(batches, [vec![1, 2, 3], vec![4, 5, 6], vec![7]]);
+        assert_eq!(batched(0..6, 3).len(), 2);
+        assert_eq!(batched(0..0, 3).next(), None);
+    }
+
+    #[test]
+    fn a_huge_size_does_not_reserve_a_huge_batch() {
+        let mut batches = batched(0..3, usize::MAX);
+        let batch = batches.next().unwrap();
+        assert_eq!(batch, [0, 1, 2]);
+        assert!(batch.capacity() < 16);
+        assert_eq!(batches.next(), None);
+
+        // An iterator that gives no hint still fills a batch
+        let mut unhinted = batched((0..100).filter(|n| n % 2 == 0), 1000);
+        assert_eq!(unhinted.next().map(|batch| batch.len()), Some(50));
+    }
+
+    #[test]
+    fn batched_into_reuses_the_buffer() {
+        let mut iter = 0..5;
+        let mut buffer = Vec::new();
+        assert_eq!(batched_into(&mut iter, 2, &mut buffer), 2);
+        assert_eq!(batched_into(&mut iter, 2, &mut buffer), 2);
+        assert_eq!(buffer, [2, 3]);
+        assert_eq!(batched_into(&mut iter, 2, &mut buffer), 1);
+        assert_eq!(batched_into(&mut iter, 2, &mut buffer), 0);
+        assert!(buffer.is_empty());
+    }
+
+    #[test]
+    #[should_panic(expected = "batch size must be positive")]
+    fn a_zero_size_panics() {
+        batched(0..3, 0);
+    }
+}
@@ -0,0 +1,186 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// A small parser for `--option value`, `--option=value`, `--flag` and
+// positional arguments, enough for the subcommands without a dependency
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Why a subcommand's arguments were rejected
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgError {
+    UnknownOption(String),
+    /// An option that takes a value came last
+    MissingValue(String),
+    InvalidValue {
+        option: String,
+        value: String,
+        reason: String,
+    },
+    /// A positional argument that doesn't parse
+    InvalidArgument {
+        arg: String,
+        reason: String,
+    },
+    /// A positional argument given to a subcommand that takes none
+    UnexpectedArgument(String),
+    /// A positional argument that wasn't given, by the name its usage uses
+    MissingArgument(String),
+    /// `--help` or `-h` came before any `--`, other than as an option's value
+    HelpRequested,
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgError::UnknownOption(option) => write!(f, "unknown option '--{}'", option),
+            ArgError::MissingValue(option) => write!(f, "option '--{}' needs a value", option),
+            ArgError::InvalidValue {
+                option,
+                value,
+                reason,
+            } => write!(
+                f,
+                "invalid value '{}' for '--{}': {}",
+                value, option, reason
+            ),
+            ArgError::InvalidArgument { arg, reason } => {
+                write!(f, "invalid argument '{}': {}", arg, reason)
+            }
+            ArgError::UnexpectedArgument(arg) => write!(f, "unexpected argument '{}'", arg),
+            ArgError::MissingArgument(name) => write!(f, "missing argument {}", name),
+            ArgError::HelpRequested => write!(f, "help requested"),
+        }
+    }
+}
+
+impl Error for ArgError {}
+
+/// The parsed arguments of one subcommand
+#[derive(Debug, Clone, Default)]
+pub struct Args {
+    positional: Vec<String>,
+    values: HashMap<String, String>,
+    flags: HashSet<String>,
+}
+
+impl Args {
+    /// Splits `raw` into the `options` that take a value, the `flags` that
+    /// don't, and positional arguments, which is everything after `--` too
+    ///
+    /// `--help` and `-h` give `ArgError::HelpRequested`, unless they come
+    /// after `--` or are the value of an option.
+    pub fn parse<I>(raw: I, options: &[&str], flags: &[&str]) -> Result<Args, ArgError>
+    where
+        I: IntoIterator<Item = String>,
+    {
+        let mut args = Args::default();
+        let mut raw = raw.into_iter();
+        while let Some(arg) = raw.next() {
+            if arg == "--" {
+                args.positional.extend(raw.by_ref());
+                break;
+            }
+            if arg == "--help" || arg == "-h" {
+                return Err(ArgError::HelpRequested);
+            }
+            let name = match arg.strip_prefix("--") {
+                Some(name) => name,
+                None => {
+                    args.positional.push(arg);
+                    continue;
+                }
+            };
+            let (name, inline) = match name.split_once('=') {
+                Some((name, value)) => (name, Some(value.to_string())),
+                None => (name, None),
+            };
+            if flags.contains(&name) && inline.is_none() {
+                args.flags.insert(name.to_string());
+            } else if options.contains(&name) {
+                let value = match inline {
+                    Some(value) => value,
+                    None => raw
+                        .next()
+                        .ok_or_else(|| ArgError::MissingValue(name.to_string()))?,
+                };
+                args.values.insert(name.to_string(), value);
+            } else {
+                return Err(ArgError::UnknownOption(name.to_string()));
+            }
+        }
+        Ok(args)
+    }
+
+    pub fn flag(&self, name: &str) -> bool {
+        self.flags.contains(name)
+    }
+
+    /// The value given for `name`, parsed, or `None` if it wasn't given
+    pub fn value<T>(&self, name: &str) -> Result<Option<T>, ArgError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.parse_value(name, |value| value.parse())
+    }
+
+    /// Like `value`, but ignoring underscores, so `1_000_000` is accepted
+    pub fn number<T>(&self, name: &str) -> Result<Option<T>, ArgError>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.parse_value(name, |value| value.replace('_', "").parse())
+    }
+
+    pub fn positional(&self) -> &[String] {
+        &self.positional
+    }
+
+    /// Fails if any positional arguments were given
+    pub fn no_positional(&self) -> Result<(), ArgError> {
+        match self.positional.first() {
+            Some(arg) => Err(ArgError::UnexpectedArgument(arg.clone())),
+            None => Ok(()),
+        }
+    }
+
+    fn parse_value<T, E, F>(&self, name: &str, parse: F) -> Result<Option<T>, ArgError>
+    where
+        E: fmt::Display,
+        F: FnOnce(&str) -> Result<T, E>,
+    {
+        let value = match self.values.get(name) {
+            Some(value) => value,
+            None => return Ok(None),
+        };
+        parse(value)
+            .map(Some)
+            .map_err(|err| ArgError::InvalidValue {
+                option: name.to_string(),
+                value: value.clone(),
+                reason: err.to_string(),
+            })
+    }
+}
+
+/// An `InvalidValue` error for a value that parsed but is out of range
+pub fn invalid(option: &str, value: impl fmt::Display, reason: &str) -> ArgError {
+    ArgError::InvalidValue {
+        option: option.to_string(),
+        value: value.to_string(),
+        reason: reason.to_string(),
+    }
+}
+
+/// An `InvalidArgument` error for a positional argument
+pub fn invalid_argument(arg: &str, reason: &str) -> ArgError {
+    ArgError::InvalidArgument {
+        arg: arg.to_string(),
+        reason: reason.to_string(),
+    }
+}
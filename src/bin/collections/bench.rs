@@ -0,0 +1,133 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// `collections bench`, a quick throughput and latency run of one workload
+// from `workload`, for numbers on the machine at hand
+
+use crate::args::{invalid, Args};
+use crate::{CommandResult, Subcommand};
+use rust_collections::workload::{self, KeyPattern, Target, Workload};
+use std::io::{self, BufWriter, Write};
+
+pub const COMMAND: Subcommand = Subcommand {
+    name: "bench",
+    summary: "time a workload against one of the crate's containers",
+    usage: USAGE,
+    run,
+};
+
+const USAGE: &str = "\
+usage: collections bench [--type T] [--ops N] [--keys K] [options]
+
+options:
+    --type T            hashmap, btreemap, probe, robin-hood, cuckoo,
+                        dense-set or lfu (default hashmap)
+    --ops N             operations to run (default 1_000_000)
+    --keys K            sequential, uniform or zipf (default uniform)
+    --zipf-exponent S   how skewed zipf keys are (default 1)
+    --key-space N       keys are drawn from 0..N (default 100_000)
+    --lookups R         fraction of operations that are lookups, the rest
+                        being inserts (default 0.8)
+    --capacity N        entries an lfu cache holds (default a tenth of the
+                        key space)
+    --seed N            seed for the keys and operations
+
+Numbers may contain underscores.";
+
+const OPTIONS: &[&str] = &[
+    "type",
+    "ops",
+    "keys",
+    "zipf-exponent",
+    "key-space",
+    "lookups",
+    "capacity",
+    "seed",
+];
+
+// Latency percentiles printed, besides the maximum
+const PERCENTILES: [f64; 4] = [50.0, 90.0, 99.0, 99.9];
+
+fn run(raw: Vec<String>) -> CommandResult {
+    let args = Args::parse(raw, OPTIONS, &[])?;
+    args.no_positional()?;
+
+    let target = args.value("type")?.unwrap_or(Target::HashMap);
+    let ops = args.number("ops")?.unwrap_or(1_000_000);
+    if ops == 0 {
+        return Err(invalid("ops", ops, "must be at least 1").into());
+    }
+    let mut workload = Workload::new(target, ops);
+    if let Some(keys) = args.value("keys")? {
+        workload.keys = keys;
+    }
+    if let Some(exponent) = args.value::<f64>("zipf-exponent")? {
+        if !(exponent >= 0.0 && exponent.is_finite()) {
+            return Err(invalid("zipf-exponent", exponent, "must be a finite number >= 0").into());
+        }
+        match &mut workload.keys {
+            KeyPattern::Zipf { exponent: current } => *current = exponent,
+            _ => return Err(invalid("zipf-exponent", exponent, "needs '--keys zipf'").into()),
+        }
+    }
+    if let Some(key_space) = args.number("key-space")? {
+        if key_space == 0 {
+            return Err(invalid("key-space", key_space, "must be at least 1").into());
+        }
+        workload.key_space = key_space;
+    }
+    if let Some(lookups) = args.value::<f64>("lookups")? {
+        if !(0.0..=1.0).contains(&lookups) {
+            return Err(invalid("lookups", lookups, "must be between 0 and 1").into());
+        }
+        workload.lookup_ratio = lookups;
+    }
+    workload.capacity = match args.number("capacity")? {
+        Some(0) => return Err(invalid("capacity", 0, "must be at least 1").into()),
+        Some(capacity) => capacity,
+        None => (workload.key_space / 10).max(1) as usize,
+    };
+    if let Some(seed) = args.number("seed")? {
+        workload.seed = seed;
+    }
+
+    let report = workload::run(&workload);
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    write!(
+        out,
+        "{}: {} ops, {} keys over {}, {:.0}% lookups",
+        workload.target,
+        workload.ops,
+        workload.keys,
+        workload.key_space,
+        workload.lookup_ratio * 100.0
+    )?;
+    if workload.target.is_cache() {
+        write!(out, ", capacity {}", workload.capacity)?;
+    }
+    writeln!(out)?;
+    writeln!(
+        out,
+        "throughput  {:.2} M ops/s ({:.1} ns/op) over {:?}",
+        report.ops_per_second() / 1e6,
+        report.elapsed.as_nanos() as f64 / workload.ops as f64,
+        report.elapsed
+    )?;
+    if report.lookups > 0 {
+        writeln!(
+            out,
+            "hit rate    {:.1}% of {} lookups",
+            report.hit_rate() * 100.0,
+            report.lookups
+        )?;
+    }
+    write!(out, "latency    ")?;
+    for &percent in PERCENTILES.iter() {
+        write!(out, " p{} {:?},", percent, report.percentile(percent))?;
+    }
+    writeln!(out, " max {:?}", report.max_latency())?;
+    out.flush()?;
+    Ok(())
+}
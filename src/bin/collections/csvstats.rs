@@ -0,0 +1,245 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// `collections csvstats`, a summary of each column of a CSV file
+//
+// The file is parsed into a `Spreadsheet` with `parse_cells`, so each cell's
+// type is inferred the same way as everywhere else in the crate. The
+// numbers come from `Spreadsheet::column_aggregates`. Empty fields, and the
+// padding of rows shorter than the header, count as nulls, but a row with
+// nothing but empty fields, such as a blank line, is skipped.
+
+use crate::args::{invalid, invalid_argument, Args};
+use crate::{CommandResult, Subcommand};
+use rust_collections::aggregates::ColumnAggregates;
+use rust_collections::spreadsheet::{parse_cells, Spreadsheet, SpreadsheetCell};
+use std::collections::HashSet;
+use std::fmt;
+use std::fs;
+use std::io::{self, BufWriter, Read, Write};
+
+pub const COMMAND: Subcommand = Subcommand {
+    name: "csvstats",
+    summary: "summarise each column of a CSV file",
+    usage: USAGE,
+    run,
+};
+
+const USAGE: &str = "\
+usage: collections csvstats [FILE] [--delimiter D]
+
+Reads stdin when no file is given, or for a FILE of '-'. The first row
+names the columns, and rows whose fields are all empty are skipped.
+
+options:
+    --delimiter D   the character between fields (default ',')
+
+For each column prints its inferred type, how many values and nulls it
+has, the min, max and mean of its numbers, and how many distinct values
+it holds. Values are compared as printed, so 2 and 2.0 count once.";
+
+// What the cells of a column turned out to be, ignoring nulls
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    /// Nothing but nulls
+    Empty,
+    Int,
+    /// Numbers, at least one of them a float
+    Float,
+    Text,
+    /// Numbers and text
+    Mixed,
+}
+
+impl ColumnType {
+    fn with(self, cell: &SpreadsheetCell) -> ColumnType {
+        let cell = match cell {
+            SpreadsheetCell::Int(_) => ColumnType::Int,
+            SpreadsheetCell::Float(_) => ColumnType::Float,
+            SpreadsheetCell::Text(_) => ColumnType::Text,
+            SpreadsheetCell::Empty => return self,
+        };
+        match (self, cell) {
+            (ColumnType::Empty, cell) => cell,
+            (a, b) if a == b => a,
+            (ColumnType::Int, ColumnType::Float) | (ColumnType::Float, ColumnType::Int) => {
+                ColumnType::Float
+            }
+            _ => ColumnType::Mixed,
+        }
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColumnType::Empty => "empty",
+            ColumnType::Int => "int",
+            ColumnType::Float => "float",
+            ColumnType::Text => "text",
+            ColumnType::Mixed => "mixed",
+        };
+        f.pad(name)
+    }
+}
+
+// The summary of one column
+struct ColumnStats {
+    name: String,
+    column_type: ColumnType,
+    count: usize,
+    nulls: usize,
+    numbers: ColumnAggregates,
+    // Distinct non-null values of any type
+    distinct: usize,
+}
+
+fn run(raw: Vec<String>) -> CommandResult {
+    let args = Args::parse(raw, &["delimiter"], &[])?;
+    let delimiter = match args.value::<String>("delimiter")? {
+        None => ',',
+        Some(raw) => {
+            let mut chars = raw.chars();
+            match (chars.next(), chars.next()) {
+                (Some(delimiter), None) => delimiter,
+                _ => return Err(invalid("delimiter", raw, "must be one character").into()),
+            }
+        }
+    };
+    let text = match args.positional() {
+        [] => read_stdin()?,
+        [path] if path == "-" => read_stdin()?,
+        [path] => {
+            let bytes =
+                fs::read(path).map_err(|err| format!("could not read '{}': {}", path, err))?;
+            String::from_utf8_lossy(&bytes).into_owned()
+        }
+        [_, extra, ..] => return Err(invalid_argument(extra, "only one file is read").into()),
+    };
+
+    let mut rows = parse_cells(text.lines(), delimiter);
+    let header = match rows.next() {
+        Some(header) => header?,
+        None => return Err("the input has no header row".into()),
+    };
+    let names: Vec<String> = header.iter().map(ToString::to_string).collect();
+    let mut rows = rows.collect::<Result<Vec<_>, _>>()?;
+    rows.retain(|row| !row.iter().all(is_null));
+    // `from_rows_padded` pads to the longest row, which could still be
+    // shorter than the header
+    for row in &mut rows {
+        if row.len() < names.len() {
+            row.resize(names.len(), SpreadsheetCell::Empty);
+        }
+    }
+    let sheet = Spreadsheet::from_rows_padded(rows);
+
+    let columns = names.len().max(sheet.column_count());
+    let stats: Vec<ColumnStats> = (0..columns)
+        .map(|col| {
+            let name = names
+                .get(col)
+                .cloned()
+                .unwrap_or_else(|| format!("column {}", col + 1));
+            column_stats(&sheet, col, name)
+        })
+        .collect();
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    print_table(&mut out, sheet.row_count(), &stats)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn read_stdin() -> io::Result<String> {
+    let mut bytes = Vec::new();
+    io::stdin().lock().read_to_end(&mut bytes)?;
+    Ok(String::from_utf8_lossy(&bytes).into_owned())
+}
+
+fn is_null(cell: &SpreadsheetCell) -> bool {
+    match cell {
+        SpreadsheetCell::Empty => true,
+        SpreadsheetCell::Text(text) => text.trim().is_empty(),
+        _ => false,
+    }
+}
+
+fn column_stats(sheet: &Spreadsheet, col: usize, name: String) -> ColumnStats {
+    let mut column_type = ColumnType::Empty;
+    let mut nulls = 0;
+    let mut values = HashSet::new();
+    for row in sheet.rows() {
+        let cell = &row[col];
+        if is_null(cell) {
+            nulls += 1;
+            continue;
+        }
+        column_type = column_type.with(cell);
+        values.insert(cell.to_string());
+    }
+    ColumnStats {
+        name,
+        column_type,
+        count: sheet.row_count() - nulls,
+        nulls,
+        numbers: sheet.column_aggregates(col),
+        distinct: values.len(),
+    }
+}
+
+fn print_table(out: &mut impl Write, rows: usize, stats: &[ColumnStats]) -> io::Result<()> {
+    const HEADINGS: [&str; 8] = [
+        "column", "type", "count", "nulls", "min", "max", "mean", "distinct",
+    ];
+    let number = |value: Option<f64>| value.map_or_else(|| "-".to_string(), |v| v.to_string());
+    let table: Vec<[String; 8]> = stats
+        .iter()
+        .map(|column| {
+            [
+                column.name.clone(),
+                column.column_type.to_string(),
+                column.count.to_string(),
+                column.nulls.to_string(),
+                number(column.numbers.min),
+                number(column.numbers.max),
+                column
+                    .numbers
+                    .mean()
+                    .map_or_else(|| "-".to_string(), |mean| format!("{:.3}", mean)),
+                column.distinct.to_string(),
+            ]
+        })
+        .collect();
+
+    let mut widths = HEADINGS.map(|heading| heading.chars().count());
+    for row in &table {
+        for (width, cell) in widths.iter_mut().zip(row.iter()) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    writeln!(out, "{} rows, {} columns", rows, stats.len())?;
+    let mut line = |cells: &[&str]| {
+        let padded: Vec<String> = cells
+            .iter()
+            .zip(widths.iter())
+            .enumerate()
+            .map(|(index, (cell, &width))| {
+                // The name and type read left to right, numbers line up on
+                // the right
+                if index < 2 {
+                    format!("{:<width$}", cell, width = width)
+                } else {
+                    format!("{:>width$}", cell, width = width)
+                }
+            })
+            .collect();
+        writeln!(out, "{}", padded.join("  ").trim_end())
+    };
+    line(&HEADINGS)?;
+    for row in &table {
+        let cells: Vec<&str> = row.iter().map(String::as_str).collect();
+        line(&cells)?;
+    }
+    Ok(())
+}
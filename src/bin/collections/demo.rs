@@ -0,0 +1,101 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// What the walkthrough subcommands share: picking a section with
+// `--section` and showing each result either as a line of text or, with
+// `--json`, as a field of one JSON document printed at the end
+
+use crate::args::{invalid, ArgError, Args};
+use rust_collections::json::{JsonObject, JsonValue};
+use std::fmt;
+
+/// Options every walkthrough subcommand takes, besides its own
+pub const OPTIONS: &[&str] = &["section"];
+pub const FLAGS: &[&str] = &["json"];
+
+/// The run of one walkthrough subcommand
+pub struct Demo {
+    name: &'static str,
+    json: bool,
+    // The one section picked with `--section`, if any
+    only: Option<String>,
+    sections: JsonObject,
+    current: Option<(&'static str, JsonObject)>,
+}
+
+impl Demo {
+    /// Fails if `--section` names none of `sections`
+    pub fn new(name: &'static str, sections: &[&str], args: &Args) -> Result<Demo, ArgError> {
+        let only: Option<String> = args.value("section")?;
+        if let Some(section) = &only {
+            if !sections.contains(&section.as_str()) {
+                let expected = format!("expected one of {}", sections.join(", "));
+                return Err(invalid("section", section, &expected));
+            }
+        }
+        Ok(Demo {
+            name,
+            json: args.flag("json"),
+            only,
+            sections: JsonObject::new(),
+            current: None,
+        })
+    }
+
+    /// Starts `section`, returning whether it should run
+    pub fn section(&mut self, section: &'static str) -> bool {
+        self.end_section();
+        if self.only.as_deref().is_some_and(|only| only != section) {
+            return false;
+        }
+        if !self.json {
+            if !self.sections.is_empty() {
+                println!();
+            }
+            println!("-- {} --", section);
+        }
+        self.current = Some((section, JsonObject::new()));
+        true
+    }
+
+    /// Prints `text`, or records `value` under `key` with `--json`
+    pub fn show(&mut self, key: &str, value: impl Into<JsonValue>, text: impl fmt::Display) {
+        match &mut self.current {
+            Some((_, fields)) if self.json => {
+                fields.insert(key.to_string(), value.into());
+            }
+            _ => println!("{}", text),
+        }
+    }
+
+    /// Prints the JSON document, with `--json`
+    pub fn finish(mut self) {
+        self.end_section();
+        if self.json {
+            let mut document = JsonObject::new();
+            document.insert("demo".to_string(), self.name.into());
+            document.insert("sections".to_string(), self.sections.into());
+            println!("{}", JsonValue::Object(document).to_string_pretty());
+        }
+    }
+
+    fn end_section(&mut self) {
+        if let Some((section, fields)) = self.current.take() {
+            self.sections.insert(section.to_string(), fields.into());
+        }
+    }
+}
+
+/// A JSON array of `values`
+pub fn array<T, I>(values: I) -> JsonValue
+where
+    T: Into<JsonValue>,
+    I: IntoIterator<Item = T>,
+{
+    JsonValue::Array(values.into_iter().map(Into::into).collect())
+}
+
+/// `value`, or `null` if there isn't one
+pub fn optional<T: Into<JsonValue>>(value: Option<T>) -> JsonValue {
+    value.map_or(JsonValue::Null, Into::into)
+}
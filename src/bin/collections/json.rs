@@ -0,0 +1,76 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// `collections json`, a JSON pretty-printer and minifier built on the
+// crate's own parser
+//
+// Object members and numbers are printed as they were written, through
+// `json::reformat`. `--sort-keys` parses into a `JsonValue` instead, whose
+// objects are `BTreeMap`s and so come out sorted by key.
+
+use crate::args::{invalid_argument, Args};
+use crate::{CommandResult, Subcommand};
+use rust_collections::json::{reformat, JsonLayout, JsonValue};
+use std::fs;
+use std::io::{self, Read};
+
+pub const COMMAND: Subcommand = Subcommand {
+    name: "json",
+    summary: "pretty-print or minify JSON",
+    usage: USAGE,
+    run,
+};
+
+const USAGE: &str = "\
+usage: collections json [FILE] [--pretty | --minify] [--sort-keys]
+
+Reads stdin when no file is given, or for a FILE of '-'.
+
+options:
+    --pretty      print across several lines, indented by two spaces
+                  (the default)
+    --minify      print on one line with no spaces
+    --sort-keys   sort the members of each object by key instead of
+                  keeping them in the order they were written
+
+Numbers are copied out exactly as written. --sort-keys reads them as
+64-bit floats instead, so integers beyond 2^53 lose precision and numbers
+too large for a float are rejected.";
+
+fn run(raw: Vec<String>) -> CommandResult {
+    let args = Args::parse(raw, &[], &["pretty", "minify", "sort-keys"])?;
+    if args.flag("pretty") && args.flag("minify") {
+        return Err(invalid_argument("--minify", "can't be used with '--pretty'").into());
+    }
+    let layout = if args.flag("minify") {
+        JsonLayout::Compact
+    } else {
+        JsonLayout::Pretty
+    };
+    let text = match args.positional() {
+        [] => read_stdin()?,
+        [path] if path == "-" => read_stdin()?,
+        [path] => {
+            fs::read_to_string(path).map_err(|err| format!("could not read '{}': {}", path, err))?
+        }
+        [_, extra, ..] => return Err(invalid_argument(extra, "only one file is read").into()),
+    };
+
+    let output = if args.flag("sort-keys") {
+        let value = JsonValue::parse(&text)?;
+        match layout {
+            JsonLayout::Compact => value.to_string(),
+            JsonLayout::Pretty => value.to_string_pretty(),
+        }
+    } else {
+        reformat(&text, layout)?
+    };
+    println!("{}", output);
+    Ok(())
+}
+
+fn read_stdin() -> io::Result<String> {
+    let mut text = String::new();
+    io::stdin().lock().read_to_string(&mut text)?;
+    Ok(text)
+}
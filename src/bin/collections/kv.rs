@@ -0,0 +1,160 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// `collections kv`, a key-value store kept in a map snapshot file
+//
+// Every invocation loads the whole store with `snapshot::load_map`, and
+// `set` and `del` write it back with `save_map`. `save_map` renames a new
+// file into place, so the lock is taken on a separate `<FILE>.lock` file
+// that stays put: readers share it and writers hold it alone, so two
+// writers can't lose each other's changes and nobody reads a store that
+// is being replaced.
+
+use crate::args::{invalid, invalid_argument, ArgError, Args};
+use crate::{CommandResult, Subcommand};
+use rust_collections::prefix_map::PrefixMap;
+use rust_collections::snapshot::{load_map, save_map, SnapshotError};
+use std::collections::HashMap;
+use std::error::Error;
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+pub const COMMAND: Subcommand = Subcommand {
+    name: "kv",
+    summary: "a key-value store kept in a snapshot file",
+    usage: USAGE,
+    run,
+};
+
+const USAGE: &str = "\
+usage: collections kv set KEY VALUE [--file F]
+       collections kv get KEY [--file F]
+       collections kv del KEY [--file F]
+       collections kv list [--prefix P] [--file F]
+
+options:
+    --file F     the store to use (default 'collections.kv'), created by
+                 the first set
+    --prefix P   list only the keys that start with P
+
+get prints the value of KEY, and list prints each key and its value
+separated by a tab, in key order. get and del fail if KEY isn't set.
+Put '--' before a key or value that starts with '--'.";
+
+const DEFAULT_FILE: &str = "collections.kv";
+
+type Store = HashMap<String, String>;
+
+fn run(raw: Vec<String>) -> CommandResult {
+    let args = Args::parse(raw, &["file", "prefix"], &[])?;
+    let path = PathBuf::from(
+        args.value::<String>("file")?
+            .unwrap_or_else(|| DEFAULT_FILE.to_string()),
+    );
+    let prefix: Option<String> = args.value("prefix")?;
+    let (action, operands) = match args.positional().split_first() {
+        Some((action, operands)) => (action.as_str(), operands),
+        None => return Err(ArgError::MissingArgument("ACTION".to_string()).into()),
+    };
+    if let (Some(prefix), false) = (&prefix, action == "list") {
+        return Err(invalid("prefix", prefix, "only list takes a prefix").into());
+    }
+
+    match action {
+        "set" => {
+            let [key, value] = operands_for(operands, &["KEY", "VALUE"])?;
+            let _lock = lock(&path, true)?;
+            let mut store = load(&path)?;
+            store.insert(key.clone(), value.clone());
+            save(&store, &path)?;
+        }
+        "get" => {
+            let [key] = operands_for(operands, &["KEY"])?;
+            let _lock = lock(&path, false)?;
+            let store = load(&path)?;
+            let value = store.get(key).ok_or_else(|| not_set(key))?;
+            println!("{}", value);
+        }
+        "del" => {
+            let [key] = operands_for(operands, &["KEY"])?;
+            let _lock = lock(&path, true)?;
+            let mut store = load(&path)?;
+            store.remove(key).ok_or_else(|| not_set(key))?;
+            save(&store, &path)?;
+        }
+        "list" => {
+            operands_for(operands, &[])?;
+            let store = {
+                let _lock = lock(&path, false)?;
+                load(&path)?
+            };
+            let store: PrefixMap<String> = store.into();
+            let stdout = io::stdout();
+            let mut out = BufWriter::new(stdout.lock());
+            for (key, value) in store.iter_prefix(prefix.as_deref().unwrap_or("")) {
+                writeln!(out, "{}\t{}", key, value)?;
+            }
+            out.flush()?;
+        }
+        _ => {
+            return Err(invalid_argument(action, "expected set, get, del or list").into());
+        }
+    }
+    Ok(())
+}
+
+// The operands of an action, checked against the `names` it expects
+fn operands_for<'a, const N: usize>(
+    operands: &'a [String],
+    names: &[&str; N],
+) -> Result<[&'a String; N], ArgError> {
+    if let Some(extra) = operands.get(N) {
+        return Err(ArgError::UnexpectedArgument(extra.clone()));
+    }
+    if let Some(missing) = names.get(operands.len()) {
+        return Err(ArgError::MissingArgument(missing.to_string()));
+    }
+    Ok(std::array::from_fn(|index| &operands[index]))
+}
+
+fn not_set(key: &str) -> Box<dyn Error> {
+    format!("key '{}' is not set", key).into()
+}
+
+// Holds the store's lock file locked until the returned file is dropped,
+// shared between readers or held alone by a writer
+fn lock(path: &Path, exclusive: bool) -> Result<File, Box<dyn Error>> {
+    let mut name: OsString = path.as_os_str().to_owned();
+    name.push(".lock");
+    let lock_path = PathBuf::from(name);
+    let locked = OpenOptions::new()
+        .write(true)
+        .create(true)
+        .truncate(false)
+        .open(&lock_path)
+        .and_then(|file| {
+            if exclusive {
+                file.lock()?;
+            } else {
+                file.lock_shared()?;
+            }
+            Ok(file)
+        });
+    locked.map_err(|err| format!("could not lock '{}': {}", lock_path.display(), err).into())
+}
+
+// The store in `path`, or an empty one if the file doesn't exist yet
+fn load(path: &Path) -> Result<Store, Box<dyn Error>> {
+    match load_map(path) {
+        Ok(store) => Ok(store),
+        Err(SnapshotError::Io(err)) if err.kind() == io::ErrorKind::NotFound => Ok(Store::new()),
+        Err(err) => Err(format!("could not read '{}': {}", path.display(), err).into()),
+    }
+}
+
+fn save(store: &Store, path: &Path) -> Result<(), Box<dyn Error>> {
+    save_map(store, path)
+        .map_err(|err| format!("could not write '{}': {}", path.display(), err).into())
+}
@@ -0,0 +1,104 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// `collections <subcommand>`, small command-line tools built on the crate
+//
+// Run `collections help` for the list of subcommands and
+// `collections <subcommand> --help` for the options of one
+
+mod args;
+mod bench;
+mod csvstats;
+mod demo;
+mod json;
+mod kv;
+mod maps;
+mod repl;
+mod strings;
+mod vectors;
+mod wordcount;
+
+use args::ArgError;
+use std::env;
+use std::error::Error;
+use std::io::{self, Write};
+use std::process;
+
+/// What a subcommand returns; an `ArgError` also prints its usage
+pub type CommandResult = Result<(), Box<dyn Error>>;
+
+/// One `collections` subcommand
+pub struct Subcommand {
+    pub name: &'static str,
+    /// A line for `collections help`
+    pub summary: &'static str,
+    pub usage: &'static str,
+    pub run: fn(Vec<String>) -> CommandResult,
+}
+
+const SUBCOMMANDS: &[Subcommand] = &[
+    vectors::COMMAND,
+    strings::COMMAND,
+    maps::COMMAND,
+    bench::COMMAND,
+    csvstats::COMMAND,
+    json::COMMAND,
+    kv::COMMAND,
+    repl::COMMAND,
+    wordcount::COMMAND,
+];
+
+fn print_help() {
+    println!("usage: collections <subcommand> [options]");
+    println!();
+    println!("subcommands:");
+    for command in SUBCOMMANDS {
+        println!("    {:<12}{}", command.name, command.summary);
+    }
+    println!("    {:<12}print this message", "help");
+}
+
+fn main() {
+    let mut raw = env::args().skip(1);
+    let name = match raw.next() {
+        Some(name) => name,
+        None => {
+            print_help();
+            process::exit(2);
+        }
+    };
+    if matches!(name.as_str(), "help" | "--help" | "-h") {
+        print_help();
+        return;
+    }
+    let command = match SUBCOMMANDS.iter().find(|command| command.name == name) {
+        Some(command) => command,
+        None => {
+            eprintln!("collections: unknown subcommand '{}'", name);
+            eprintln!("run 'collections help' for the list of subcommands");
+            process::exit(2);
+        }
+    };
+
+    if let Err(err) = (command.run)(raw.collect()) {
+        if let Some(ArgError::HelpRequested) = err.downcast_ref::<ArgError>() {
+            // Help piped into something like `head` may find stdout closed
+            let _ = writeln!(io::stdout(), "{}", command.usage);
+            return;
+        }
+        // Output piped into something like `head` that stopped reading
+        let closed = err
+            .downcast_ref::<io::Error>()
+            .is_some_and(|err| err.kind() == io::ErrorKind::BrokenPipe);
+        if closed {
+            return;
+        }
+        eprintln!("collections {}: {}", command.name, err);
+        if err.downcast_ref::<ArgError>().is_some() {
+            eprintln!();
+            eprintln!("{}", command.usage);
+            process::exit(2);
+        }
+        process::exit(1);
+    }
+}
@@ -0,0 +1,204 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// `collections maps`, the walkthrough of `HashMap`
+//
+// Positional `KEY=VALUE` arguments replace the map the accessing and
+// iterating sections work on, and `--key` picks the key looked up, so
+// `collections maps red=1 green=2 --key green` finds `2`.
+
+use crate::args::{invalid_argument, Args};
+use crate::demo::{self, optional, Demo};
+use crate::{CommandResult, Subcommand};
+use rust_collections::deterministic_map::iter_sorted_by_key;
+use rust_collections::fast_hash::FastBuildHasher;
+use rust_collections::json::{JsonObject, JsonValue};
+use std::collections::HashMap;
+
+pub const COMMAND: Subcommand = Subcommand {
+    name: "maps",
+    summary: "the walkthrough of HashMap",
+    usage: USAGE,
+    run,
+};
+
+const USAGE: &str = "\
+usage: collections maps [KEY=VALUE...] [--key K] [--section S] [--json]
+
+options:
+    KEY=VALUE...  entries for the map to look up and iterate over
+                  (default two favourite colours)
+    --key K       the key to look up (default 'Favorite Color')
+    --section S   inserting, accessing, iterating, updating or hashers
+    --json        print the results as one JSON document";
+
+const SECTIONS: &[&str] = &["inserting", "accessing", "iterating", "updating", "hashers"];
+
+fn run(raw: Vec<String>) -> CommandResult {
+    let options = [demo::OPTIONS, &["key"]].concat();
+    let args = Args::parse(raw, &options, demo::FLAGS)?;
+    let mut demo = Demo::new("maps", SECTIONS, &args)?;
+    let mut map = HashMap::new();
+    for entry in args.positional() {
+        let (key, value) = entry
+            .split_once('=')
+            .ok_or_else(|| invalid_argument(entry, "expected KEY=VALUE"))?;
+        map.insert(key.to_string(), value.to_string());
+    }
+    if map.is_empty() {
+        map = favourite_colors();
+    }
+    let key: String = args
+        .value("key")?
+        .unwrap_or_else(|| String::from("Favorite Color"));
+
+    if demo.section("inserting") {
+        inserting(&mut demo);
+    }
+    if demo.section("accessing") {
+        accessing(&mut demo, &map, &key);
+    }
+    if demo.section("iterating") {
+        iterating(&mut demo, &map);
+    }
+    if demo.section("updating") {
+        updating(&mut demo, favourite_colors());
+    }
+    if demo.section("hashers") {
+        hashers(&mut demo);
+    }
+    demo.finish();
+    Ok(())
+}
+
+fn favourite_colors() -> HashMap<String, String> {
+    let field_name = String::from("Favorite Color");
+    let field_value = String::from("Blue");
+
+    let mut map = HashMap::new();
+    map.insert(field_name, field_value);
+
+    // At this point field_name and field_value are invalid, since they were
+    // moved into the map by the insert() method
+    // But, we can re-use the variable names to make more value
+    let field_name = String::from("Second Favorite Color");
+    let field_value = String::from("Green");
+
+    map.insert(field_name, field_value);
+    map
+}
+
+// The map as a JSON object, which keeps its keys sorted
+fn object(map: &HashMap<String, String>) -> JsonValue {
+    let object: JsonObject = map
+        .iter()
+        .map(|(key, value)| (key.clone(), value.as_str().into()))
+        .collect();
+    object.into()
+}
+
+fn inserting(demo: &mut Demo) {
+    // The third collection type in Rust is a Hash Map, which are
+    // just associative arrays
+    //
+    // Hash Maps are also created using the `new()` method
+    let mut scores = HashMap::new();
+
+    // Add values to hash maps using the `insert()` method
+    // This hash map has keys of type String and values of type i32
+    scores.insert(String::from("Blue"), 10);
+    scores.insert(String::from("Yellow"), 50);
+
+    let object: JsonObject = scores
+        .iter()
+        .map(|(team, &score)| (team.clone(), score.into()))
+        .collect();
+    demo.show("scores", object, format!("scores is {:?}", scores));
+}
+
+fn accessing(demo: &mut Demo, map: &HashMap<String, String>, key: &str) {
+    // Values in hash maps are accessed using the `get()` method with
+    // the associated key
+    let value = map.get(key);
+
+    demo.show(
+        "get",
+        optional(value.map(String::as_str)),
+        format!("{} is '{:?}'", key, value),
+    );
+}
+
+fn iterating(demo: &mut Demo, map: &HashMap<String, String>) {
+    // You can also iterate over value/key pairs in hash maps with a `for` loop
+    // Note: These values will be printed in an arbitrary order
+    let mut lines = Vec::new();
+    for (key, value) in map {
+        lines.push(format!("{}: {}", key, value));
+    }
+    demo.show("arbitrary_order", object(map), lines.join("\n"));
+
+    // When the order matters, such as for output that gets compared between
+    // runs, `iter_sorted_by_key` visits the entries sorted by key instead
+    let mut lines = Vec::new();
+    for (key, value) in iter_sorted_by_key(map) {
+        lines.push(format!("{}: {}", key, value));
+    }
+    let keys: Vec<JsonValue> = iter_sorted_by_key(map)
+        .map(|(key, _)| key.as_str().into())
+        .collect();
+    demo.show("sorted_keys", keys, lines.join("\n"));
+}
+
+fn updating(demo: &mut Demo, mut map: HashMap<String, String>) {
+    let key = String::from("Favorite Color");
+
+    map.insert(String::from("Favorite Color"), String::from("Green"));
+
+    demo.show(
+        "replaced",
+        optional(map.get(&key).map(String::as_str)),
+        format!("{} is {:?}", key, map.get(&key)),
+    );
+
+    // Overwriting values in hash maps can be done when the key name
+    // already maps to a value
+    map.insert(String::from("Favorite Color"), String::from("Pink"));
+
+    demo.show(
+        "overwritten",
+        optional(map.get(&key).map(String::as_str)),
+        format!("{} is now {:?}", key, map.get(&key)),
+    );
+
+    map.insert(String::from("Blue"), String::from("50"));
+
+    // Only inserting a value to a hash map if the key does not already have a value
+    // using the `or_insert()` method from `entry`
+    map.entry(String::from("Yellow"))
+        .or_insert(String::from("10"));
+    map.entry(String::from("Blue"))
+        .or_insert(String::from("10"));
+
+    // The key 'Blue' still has a value of '50'
+    demo.show("map", object(&map), format!("map is {:?}", map));
+}
+
+fn hashers(demo: &mut Demo) {
+    // Hash maps use the SipHash algorithm by default, which protects against
+    // keys chosen to cause collisions but is slower than it needs to be when
+    // the keys are trusted. A different hasher can be plugged in with the
+    // `with_hasher()` method
+    let mut fast_scores = HashMap::with_hasher(FastBuildHasher::default());
+    fast_scores.insert(String::from("Blue"), 10);
+    fast_scores.insert(String::from("Yellow"), 50);
+
+    let object: JsonObject = fast_scores
+        .iter()
+        .map(|(team, &score)| (team.clone(), score.into()))
+        .collect();
+    demo.show(
+        "fast_scores",
+        object,
+        format!("fast_scores is {:?}", fast_scores),
+    );
+}
@@ -0,0 +1,504 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// `collections repl`, a prompt for creating named collections, changing
+// them and looking at what they hold and how much memory they take
+//
+// Values are inferred the way spreadsheet cells are, so `push v 5` stores
+// an integer and `push v five` text. Commands are read a line at a time
+// from stdin, so a script can be piped in as well.
+
+use crate::args::Args;
+use crate::{CommandResult, Subcommand};
+use rust_collections::deep_size::DeepSize;
+use rust_collections::map_stats::MapStats;
+use rust_collections::online_stats::OnlineStats;
+use rust_collections::spreadsheet::SpreadsheetCell;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::io::{self, BufRead, IsTerminal, Write};
+use std::mem;
+
+pub const COMMAND: Subcommand = Subcommand {
+    name: "repl",
+    summary: "explore vectors, maps and sets interactively",
+    usage: USAGE,
+    run,
+};
+
+const USAGE: &str = "\
+usage: collections repl
+
+Reads commands from stdin; type 'help' at the prompt for the list.";
+
+const HELP: &str = "\
+new vec|map|set NAME        create an empty collection
+push VEC VALUE...           append to a vector
+pop VEC                     remove a vector's last value
+insert MAP KEY VALUE        insert or replace a map entry
+insert SET VALUE...         add to a set
+get VEC INDEX | MAP KEY     look a value up
+remove VEC INDEX | MAP KEY | SET VALUE
+contains MAP KEY | SET VALUE
+show NAME                   print the contents
+stats NAME                  length, capacity, memory and value summary
+clear NAME                  remove everything, keeping the capacity
+drop NAME                   forget a collection
+list                        every collection and its size
+help                        print this message
+quit                        leave; so does end of input
+
+Quote a value with spaces in it: insert m greeting \"hello there\"";
+
+fn run(raw: Vec<String>) -> CommandResult {
+    Args::parse(raw, &[], &[])?.no_positional()?;
+
+    let interactive = io::stdin().is_terminal();
+    let mut session = Session::default();
+    let stdin = io::stdin();
+    let mut lines = stdin.lock().lines();
+    loop {
+        if interactive {
+            print!("> ");
+            io::stdout().flush()?;
+        }
+        let line = match lines.next() {
+            Some(line) => line?,
+            None => break,
+        };
+        let words = match split_words(&line) {
+            Ok(words) => words,
+            Err(err) => {
+                println!("error: {}", err);
+                continue;
+            }
+        };
+        match words.first().map(String::as_str) {
+            None => continue,
+            Some("quit") | Some("exit") => break,
+            Some(_) => {}
+        }
+        match session.execute(&words) {
+            Ok(output) if output.is_empty() => {}
+            Ok(output) => println!("{}", output.trim_end()),
+            Err(err) => println!("error: {}", err),
+        }
+    }
+    Ok(())
+}
+
+// What a command couldn't do
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum ReplError {
+    UnknownCommand(String),
+    Usage(&'static str),
+    NoSuchCollection(String),
+    AlreadyExists(String),
+    /// The command doesn't apply to the collection's kind
+    WrongKind {
+        name: String,
+        kind: &'static str,
+        command: &'static str,
+    },
+    BadIndex(String),
+    UnterminatedQuote,
+}
+
+impl fmt::Display for ReplError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ReplError::UnknownCommand(command) => {
+                write!(f, "unknown command '{}', try 'help'", command)
+            }
+            ReplError::Usage(usage) => write!(f, "usage: {}", usage),
+            ReplError::NoSuchCollection(name) => write!(f, "no collection named '{}'", name),
+            ReplError::AlreadyExists(name) => write!(f, "'{}' already exists", name),
+            ReplError::WrongKind {
+                name,
+                kind,
+                command,
+            } => write!(
+                f,
+                "'{}' is a {}, which '{}' doesn't work on",
+                name, kind, command
+            ),
+            ReplError::BadIndex(index) => write!(f, "'{}' is not an index in range", index),
+            ReplError::UnterminatedQuote => write!(f, "a quoted value is never closed"),
+        }
+    }
+}
+
+// Splits on whitespace, keeping anything in double quotes together
+fn split_words(line: &str) -> Result<Vec<String>, ReplError> {
+    let mut words = Vec::new();
+    let mut chars = line.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c == '"' {
+            chars.next();
+            let mut word = String::new();
+            loop {
+                match chars.next() {
+                    Some('"') => break,
+                    Some(c) => word.push(c),
+                    None => return Err(ReplError::UnterminatedQuote),
+                }
+            }
+            words.push(word);
+        } else {
+            let mut word = String::new();
+            while let Some(&c) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                word.push(c);
+                chars.next();
+            }
+            words.push(word);
+        }
+    }
+    Ok(words)
+}
+
+enum Collection {
+    Vec(Vec<SpreadsheetCell>),
+    Map(HashMap<String, SpreadsheetCell>),
+    Set(HashSet<String>),
+}
+
+impl Collection {
+    fn kind(&self) -> &'static str {
+        match self {
+            Collection::Vec(_) => "vec",
+            Collection::Map(_) => "map",
+            Collection::Set(_) => "set",
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Collection::Vec(vec) => vec.len(),
+            Collection::Map(map) => map.len(),
+            Collection::Set(set) => set.len(),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        match self {
+            Collection::Vec(vec) => vec.capacity(),
+            Collection::Map(map) => map.capacity(),
+            Collection::Set(set) => set.capacity(),
+        }
+    }
+
+    fn heap_bytes(&self) -> usize {
+        match self {
+            Collection::Vec(vec) => vec.heap_bytes(),
+            Collection::Map(map) => map.heap_bytes(),
+            Collection::Set(set) => set.heap_bytes(),
+        }
+    }
+
+    fn inline_bytes(&self) -> usize {
+        match self {
+            Collection::Vec(vec) => mem::size_of_val(vec),
+            Collection::Map(map) => mem::size_of_val(map),
+            Collection::Set(set) => mem::size_of_val(set),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Session {
+    collections: BTreeMap<String, Collection>,
+}
+
+impl Session {
+    // Runs one command, returning what to print
+    fn execute(&mut self, words: &[String]) -> Result<String, ReplError> {
+        let command = words[0].as_str();
+        let args: Vec<&str> = words[1..].iter().map(String::as_str).collect();
+        match command {
+            "help" => Ok(HELP.to_string()),
+            "list" => Ok(self.list()),
+            "new" => self.create(&args),
+            "push" => self.push(&args),
+            "pop" => self.pop(&args),
+            "insert" => self.insert(&args),
+            "get" => self.get(&args),
+            "remove" => self.remove(&args),
+            "contains" => self.contains(&args),
+            "show" => self.show(&args),
+            "stats" => self.stats(&args),
+            "clear" => self.clear(&args),
+            "drop" => self.drop_collection(&args),
+            _ => Err(ReplError::UnknownCommand(command.to_string())),
+        }
+    }
+
+    fn collection(&mut self, name: &str) -> Result<&mut Collection, ReplError> {
+        self.collections
+            .get_mut(name)
+            .ok_or_else(|| ReplError::NoSuchCollection(name.to_string()))
+    }
+
+    fn list(&self) -> String {
+        if self.collections.is_empty() {
+            return "no collections yet; make one with 'new vec|map|set NAME'".to_string();
+        }
+        self.collections
+            .iter()
+            .map(|(name, collection)| {
+                format!("{}: {} of {}\n", name, collection.kind(), collection.len())
+            })
+            .collect()
+    }
+
+    fn create(&mut self, args: &[&str]) -> Result<String, ReplError> {
+        const USAGE: &str = "new vec|map|set NAME";
+        let (kind, name) = match args {
+            [kind, name] => (*kind, *name),
+            _ => return Err(ReplError::Usage(USAGE)),
+        };
+        let collection = match kind {
+            "vec" => Collection::Vec(Vec::new()),
+            "map" => Collection::Map(HashMap::new()),
+            "set" => Collection::Set(HashSet::new()),
+            _ => return Err(ReplError::Usage(USAGE)),
+        };
+        if self.collections.contains_key(name) {
+            return Err(ReplError::AlreadyExists(name.to_string()));
+        }
+        self.collections.insert(name.to_string(), collection);
+        Ok(String::new())
+    }
+
+    fn push(&mut self, args: &[&str]) -> Result<String, ReplError> {
+        const USAGE: &str = "push VEC VALUE...";
+        let (name, values) = match args {
+            [name, values @ ..] if !values.is_empty() => (*name, values),
+            _ => return Err(ReplError::Usage(USAGE)),
+        };
+        match self.collection(name)? {
+            Collection::Vec(vec) => {
+                vec.extend(values.iter().map(|value| SpreadsheetCell::infer(value)));
+                Ok(String::new())
+            }
+            other => Err(wrong_kind(name, other, "push")),
+        }
+    }
+
+    fn pop(&mut self, args: &[&str]) -> Result<String, ReplError> {
+        let name = match args {
+            [name] => *name,
+            _ => return Err(ReplError::Usage("pop VEC")),
+        };
+        match self.collection(name)? {
+            Collection::Vec(vec) => Ok(vec
+                .pop()
+                .map_or_else(|| "(empty)".to_string(), |value| value.to_string())),
+            other => Err(wrong_kind(name, other, "pop")),
+        }
+    }
+
+    fn insert(&mut self, args: &[&str]) -> Result<String, ReplError> {
+        const USAGE: &str = "insert MAP KEY VALUE | insert SET VALUE...";
+        let (name, rest) = match args {
+            [name, rest @ ..] if !rest.is_empty() => (*name, rest),
+            _ => return Err(ReplError::Usage(USAGE)),
+        };
+        match self.collection(name)? {
+            Collection::Map(map) => match rest {
+                [key, value] => Ok(map
+                    .insert(key.to_string(), SpreadsheetCell::infer(value))
+                    .map_or_else(String::new, |old| format!("replaced {}", old))),
+                _ => Err(ReplError::Usage(USAGE)),
+            },
+            Collection::Set(set) => {
+                let added = rest
+                    .iter()
+                    .filter(|value| set.insert(value.to_string()))
+                    .count();
+                Ok(format!("{} added", added))
+            }
+            other => Err(wrong_kind(name, other, "insert")),
+        }
+    }
+
+    fn get(&mut self, args: &[&str]) -> Result<String, ReplError> {
+        let (name, key) = match args {
+            [name, key] => (*name, *key),
+            _ => return Err(ReplError::Usage("get VEC INDEX | get MAP KEY")),
+        };
+        match self.collection(name)? {
+            Collection::Vec(vec) => {
+                let value = index(key, vec.len()).map(|index| &vec[index])?;
+                Ok(value.to_string())
+            }
+            Collection::Map(map) => Ok(map
+                .get(key)
+                .map_or_else(|| "(none)".to_string(), |value| value.to_string())),
+            other => Err(wrong_kind(name, other, "get")),
+        }
+    }
+
+    fn remove(&mut self, args: &[&str]) -> Result<String, ReplError> {
+        let (name, key) = match args {
+            [name, key] => (*name, *key),
+            _ => {
+                return Err(ReplError::Usage(
+                    "remove VEC INDEX | remove MAP KEY | remove SET VALUE",
+                ))
+            }
+        };
+        match self.collection(name)? {
+            Collection::Vec(vec) => {
+                let index = index(key, vec.len())?;
+                Ok(vec.remove(index).to_string())
+            }
+            Collection::Map(map) => Ok(map
+                .remove(key)
+                .map_or_else(|| "(none)".to_string(), |value| value.to_string())),
+            Collection::Set(set) => Ok(set.remove(key).to_string()),
+        }
+    }
+
+    fn contains(&mut self, args: &[&str]) -> Result<String, ReplError> {
+        let (name, key) = match args {
+            [name, key] => (*name, *key),
+            _ => return Err(ReplError::Usage("contains MAP KEY | contains SET VALUE")),
+        };
+        match self.collection(name)? {
+            Collection::Map(map) => Ok(map.contains_key(key).to_string()),
+            Collection::Set(set) => Ok(set.contains(key).to_string()),
+            other => Err(wrong_kind(name, other, "contains")),
+        }
+    }
+
+    fn show(&mut self, args: &[&str]) -> Result<String, ReplError> {
+        let name = match args {
+            [name] => *name,
+            _ => return Err(ReplError::Usage("show NAME")),
+        };
+        // Maps and sets are shown sorted, so the output doesn't depend on
+        // the hasher
+        let shown = match self.collection(name)? {
+            Collection::Vec(vec) => {
+                let values: Vec<String> = vec.iter().map(ToString::to_string).collect();
+                format!("[{}]", values.join(", "))
+            }
+            Collection::Map(map) => {
+                let mut entries: Vec<String> = map
+                    .iter()
+                    .map(|(key, value)| format!("{}: {}", key, value))
+                    .collect();
+                entries.sort();
+                format!("{{{}}}", entries.join(", "))
+            }
+            Collection::Set(set) => {
+                let mut values: Vec<&str> = set.iter().map(String::as_str).collect();
+                values.sort_unstable();
+                format!("{{{}}}", values.join(", "))
+            }
+        };
+        Ok(shown)
+    }
+
+    fn stats(&mut self, args: &[&str]) -> Result<String, ReplError> {
+        let name = match args {
+            [name] => *name,
+            _ => return Err(ReplError::Usage("stats NAME")),
+        };
+        let collection = self.collection(name)?;
+        let mut out = format!(
+            "{}: {} of {}, capacity {}\nmemory: {} bytes inline, {} on the heap\n",
+            name,
+            collection.kind(),
+            collection.len(),
+            collection.capacity(),
+            collection.inline_bytes(),
+            collection.heap_bytes()
+        );
+        match collection {
+            Collection::Vec(vec) => out.push_str(&number_summary(vec.iter())),
+            Collection::Map(map) => {
+                out.push_str(&number_summary(map.values()));
+                if !map.is_empty() {
+                    out.push_str(&MapStats::analyze(map.keys(), map.hasher()).render_histogram());
+                }
+            }
+            Collection::Set(set) => {
+                if !set.is_empty() {
+                    out.push_str(&MapStats::analyze(set.iter(), set.hasher()).render_histogram());
+                }
+            }
+        }
+        Ok(out)
+    }
+
+    fn clear(&mut self, args: &[&str]) -> Result<String, ReplError> {
+        let name = match args {
+            [name] => *name,
+            _ => return Err(ReplError::Usage("clear NAME")),
+        };
+        match self.collection(name)? {
+            Collection::Vec(vec) => vec.clear(),
+            Collection::Map(map) => map.clear(),
+            Collection::Set(set) => set.clear(),
+        }
+        Ok(String::new())
+    }
+
+    fn drop_collection(&mut self, args: &[&str]) -> Result<String, ReplError> {
+        let name = match args {
+            [name] => *name,
+            _ => return Err(ReplError::Usage("drop NAME")),
+        };
+        self.collections
+            .remove(name)
+            .map(|_| String::new())
+            .ok_or_else(|| ReplError::NoSuchCollection(name.to_string()))
+    }
+}
+
+fn wrong_kind(name: &str, collection: &Collection, command: &'static str) -> ReplError {
+    ReplError::WrongKind {
+        name: name.to_string(),
+        kind: collection.kind(),
+        command,
+    }
+}
+
+fn index(raw: &str, len: usize) -> Result<usize, ReplError> {
+    raw.parse()
+        .ok()
+        .filter(|&index| index < len)
+        .ok_or_else(|| ReplError::BadIndex(raw.to_string()))
+}
+
+// A line summarising the numeric values, or nothing if there are none
+fn number_summary<'a, I: Iterator<Item = &'a SpreadsheetCell>>(values: I) -> String {
+    let mut total = 0;
+    let mut stats = OnlineStats::new();
+    for value in values {
+        total += 1;
+        match value {
+            SpreadsheetCell::Int(value) => stats.push(*value as f64),
+            SpreadsheetCell::Float(value) => stats.push(*value),
+            _ => {}
+        }
+    }
+    match (stats.min(), stats.max(), stats.mean(), stats.std_dev()) {
+        (Some(min), Some(max), Some(mean), Some(std_dev)) => format!(
+            "numbers: {} of {} values, min {}, max {}, mean {:.3}, std dev {:.3}\n",
+            stats.count(),
+            total,
+            min,
+            max,
+            mean,
+            std_dev
+        ),
+        _ => String::new(),
+    }
+}
@@ -0,0 +1,178 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// `collections strings`, the walkthrough of `String`
+//
+// `--text` replaces the sample strings the slicing, chars and bytes
+// sections take apart, so `collections strings --section slicing --text
+// héllo --bytes 2` shows what happens when a slice ends mid-character.
+
+use crate::args::Args;
+use crate::demo::{self, array, optional, Demo};
+use crate::{CommandResult, Subcommand};
+
+pub const COMMAND: Subcommand = Subcommand {
+    name: "strings",
+    summary: "the walkthrough of String",
+    usage: USAGE,
+    run,
+};
+
+const USAGE: &str = "\
+usage: collections strings [--text T] [--bytes N] [--section S] [--json]
+
+options:
+    --text T      the string to slice and iterate over
+    --bytes N     how many bytes the slicing section takes (default 4)
+    --section S   creating, appending, slicing, chars or bytes
+    --json        print the results as one JSON document";
+
+const SECTIONS: &[&str] = &["creating", "appending", "slicing", "chars", "bytes"];
+
+fn run(raw: Vec<String>) -> CommandResult {
+    let options = [demo::OPTIONS, &["text", "bytes"]].concat();
+    let args = Args::parse(raw, &options, demo::FLAGS)?;
+    args.no_positional()?;
+    let mut demo = Demo::new("strings", SECTIONS, &args)?;
+    let text: Option<String> = args.value("text")?;
+    let bytes: usize = args.number("bytes")?.unwrap_or(4);
+
+    if demo.section("creating") {
+        creating(&mut demo);
+    }
+    if demo.section("appending") {
+        appending(&mut demo);
+    }
+    if demo.section("slicing") {
+        slicing(&mut demo, text.as_deref().unwrap_or("Здравствуйте"), bytes);
+    }
+    if demo.section("chars") {
+        chars(&mut demo, text.as_deref().unwrap_or("नमस्ते"));
+    }
+    if demo.section("bytes") {
+        bytes_of(&mut demo, text.as_deref().unwrap_or("नमस्ते"));
+    }
+    demo.finish();
+    Ok(())
+}
+
+fn creating(demo: &mut Demo) {
+    // The second collection type in Rust is the `String` type
+    //
+    // The String type, which is provided by Rust’s standard library rather than coded into
+    // the core language, is a growable, mutable, owned, UTF-8 encoded string type
+    //
+    // Many of the same operations available to vectors are available to Strings as well
+    let empty = String::new();
+    demo.show(
+        "new",
+        empty.as_str(),
+        format!("String::new() is '{}'", empty),
+    );
+
+    let data = "initial data";
+
+    // `to_string` works on any type that implements `Display`, string
+    // literals included
+    let s1 = data.to_string();
+
+    demo.show("to_string", s1.as_str(), format!("s1 is now '{}'", s1));
+}
+
+fn appending(demo: &mut Demo) {
+    let mut s1 = String::from("foo");
+    let s2 = "bar";
+
+    // Appending to a string can be done using the `push_str` method
+    s1.push_str(s2);
+
+    // `push_str` takes a string slice, so `s2` can still be used
+    demo.show("push_str", s1.as_str(), format!("s1 is now '{}'", s1));
+    demo.show("s2", s2, format!("s2 is still '{}'", s2));
+
+    // Concatenating String variables can be done using the `+` operator
+    // or the `format!` macro
+    let s3 = String::from("Hello, ");
+    let s4 = String::from("world!");
+
+    // Note, s3 has been moved here and can no longer be used afterwards
+    let s5 = s3 + &s4;
+
+    demo.show("plus", s5.as_str(), format!("s5 is now '{}'", s5));
+
+    // The `+` operator gets unweildy with lots of values
+    // This is where the `format!` macro comes in handy
+    let s6 = String::from("tic");
+    let s7 = String::from("tac");
+    let s8 = String::from("toe");
+
+    let s = format!("{}-{}-{}", s6, s7, s8);
+
+    demo.show("format", s.as_str(), format!("s is now '{}'", s));
+}
+
+fn slicing(demo: &mut Demo, hello: &str, bytes: usize) {
+    // A `String` is a wrapper over a Vec<u8> object
+    //
+    // The length, or size, of a String is how many bytes it takes
+    // to encode the value in UTF-8. Because of this, Rust does not allow
+    // you to index directly into strings (e.g., `s[10]`) even if the index
+    // seemingly falls within the length of the String object. You have to
+    // be more specific by using a String slice
+    //
+    // String slices should still be used with caution as they can crash
+    // your program if you try to read from an index range that is not
+    // on a char boundary, so this uses `get`, which returns `None` instead
+    //
+    // With the default text, each character needs 2 bytes to be UTF-8
+    // encoded, so the first four bytes are 'Зд'
+    demo.show(
+        "len",
+        hello.len() as f64,
+        format!("'{}' is {} bytes long", hello, hello.len()),
+    );
+    let slice = hello.get(0..bytes);
+    let text = match slice {
+        Some(slice) => format!(
+            "The first {} bytes of '{}' are encoded as '{}'",
+            bytes, hello, slice
+        ),
+        None if bytes > hello.len() => format!("'{}' has fewer than {} bytes", hello, bytes),
+        None => format!(
+            "Byte {} of '{}' is not on a char boundary, so &text[0..{}] would panic",
+            bytes, hello, bytes
+        ),
+    };
+    demo.show("slice", optional(slice), text);
+}
+
+fn chars(demo: &mut Demo, text: &str) {
+    // There are methods to iterate over Strings to avoid invalid access
+    // errors at runtime using String slices
+    let mut lines = Vec::new();
+    let mut char_idx = 0;
+    for c in text.chars() {
+        char_idx += 1;
+        lines.push(format!("Char {} is {}", char_idx, c));
+    }
+    demo.show(
+        "chars",
+        array(text.chars().map(String::from)),
+        lines.join("\n"),
+    );
+}
+
+fn bytes_of(demo: &mut Demo, text: &str) {
+    // The raw bytes can be printed as well using the `bytes()` method
+    let mut lines = Vec::new();
+    let mut byte_idx = 0;
+    for b in text.bytes() {
+        byte_idx += 1;
+        lines.push(format!("Byte {} is {}", byte_idx, b));
+    }
+    demo.show(
+        "bytes",
+        array(text.bytes().map(i32::from)),
+        lines.join("\n"),
+    );
+}
@@ -0,0 +1,192 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// `collections vectors`, the walkthrough of `Vec`
+//
+// Positional arguments replace the vector the indexing and iterating
+// sections work on, so `collections vectors 10 20 30 --index 1` looks up
+// the second of those.
+
+// The walkthrough spells out each collection the long way on purpose
+#![allow(clippy::useless_vec, clippy::vec_init_then_push)]
+
+use crate::args::{invalid_argument, Args};
+use crate::demo::{self, array, optional, Demo};
+use crate::{CommandResult, Subcommand};
+use rust_collections::json::JsonValue;
+// An `enum` can be used to store multiple types, see `spreadsheet.rs`
+use rust_collections::spreadsheet::SpreadsheetCell;
+
+pub const COMMAND: Subcommand = Subcommand {
+    name: "vectors",
+    summary: "the walkthrough of Vec",
+    usage: USAGE,
+    run,
+};
+
+const USAGE: &str = "\
+usage: collections vectors [VALUE...] [--index N] [--section S] [--json]
+
+options:
+    VALUE...      integers to index and iterate over (default 1 2 3 4 5)
+    --index N     the index to look up (default 2)
+    --section S   creating, indexing, iterating or enums
+    --json        print the results as one JSON document";
+
+const SECTIONS: &[&str] = &["creating", "indexing", "iterating", "enums"];
+
+fn run(raw: Vec<String>) -> CommandResult {
+    let options = [demo::OPTIONS, &["index"]].concat();
+    let args = Args::parse(raw, &options, demo::FLAGS)?;
+    let mut demo = Demo::new("vectors", SECTIONS, &args)?;
+    let values = args
+        .positional()
+        .iter()
+        .map(|raw| {
+            raw.parse::<i32>()
+                .map_err(|err| invalid_argument(raw, &err.to_string()))
+        })
+        .collect::<Result<Vec<i32>, _>>()?;
+    let v = if values.is_empty() {
+        vec![1, 2, 3, 4, 5]
+    } else {
+        values
+    };
+    let index: usize = args.number("index")?.unwrap_or(2);
+
+    if demo.section("creating") {
+        creating(&mut demo);
+    }
+    if demo.section("indexing") {
+        indexing(&mut demo, &v, index);
+    }
+    if demo.section("iterating") {
+        iterating(&mut demo, &v);
+    }
+    if demo.section("enums") {
+        enums(&mut demo);
+    }
+    demo.finish();
+    Ok(())
+}
+
+fn creating(demo: &mut Demo) {
+    // The first collection type in Rust is a vector
+    //
+    // Vectors allow you to store more than one value into a single
+    // data structure such that all of the values are next to each
+    // other in memory
+    //
+    // To initialize an empty vector, use `Vec::new` with type annotation
+    let v1: Vec<i32> = Vec::new();
+    demo.show("new", array(v1.clone()), format!("Vec::new() is {:?}", v1));
+
+    // Initializing a static vector with some values
+    let v2 = vec![1, 2, 3];
+    demo.show(
+        "macro",
+        array(v2.clone()),
+        format!("vec![1, 2, 3] is {:?}", v2),
+    );
+
+    let mut v3 = Vec::new();
+
+    // Use `push` to add elements to an existing vector
+    v3.push(3);
+    v3.push(4);
+    v3.push(5);
+    v3.push(6);
+    v3.push(7);
+    demo.show(
+        "pushed",
+        array(v3.clone()),
+        format!("After pushing 3 to 7, v3 is {:?}", v3),
+    );
+
+    // A vector is freed when it goes out of scope
+    {
+        let _v = vec![1, 2, 3, 4];
+
+        // do stuff with _v
+    } // <- _v goes out of scope and is freed here
+}
+
+fn indexing(demo: &mut Demo, v: &[i32], index: usize) {
+    // There are two methods of accessing elements in a vector in Rust
+    // Using indexing syntax or the `get` method
+    //
+    // Indexing past the end is a panic at runtime, so it's only done here
+    // when the index is known to be in bounds
+    if index < v.len() {
+        let element: &i32 = &v[index];
+        demo.show(
+            "indexed",
+            *element,
+            format!("Element {} of v is {}!", index, element),
+        );
+    }
+
+    // The `get` method returns `None` instead of panicking
+    let text = match v.get(index) {
+        Some(element) => format!("Element {} of v is {}!", index, element),
+        None => format!("There is no element {} in v", index),
+    };
+    demo.show("get", optional(v.get(index).copied()), text);
+
+    // Rust has a 'borrow-checker' to ensure that elements do not change value
+    // if they are being held somewhere else
+    // Therefor, the following code is a compiler error in Rust
+    //
+    // let mut v = vec![1, 2, 3, 4, 5];
+    //
+    // let first = &v[0]; // A reference to `v` is being held by `first`
+    //
+    // Attempting to add an element to a vector while a reference to it is held elsewhere
+    // v.push(6);
+}
+
+fn iterating(demo: &mut Demo, v: &[i32]) {
+    // Iterating over vectors can be done using a `for` loop
+    let mut lines = Vec::new();
+    for i in v {
+        lines.push(i.to_string());
+    }
+    demo.show("elements", array(v.to_vec()), lines.join("\n"));
+
+    // Modifying vectors can also be done using a `for` loop, so long
+    // as the vector is mutable; `saturating_add` keeps values given on the
+    // command line from overflowing
+    let mut v4 = v.to_vec();
+    for i in &mut v4 {
+        *i = i.saturating_add(50);
+    }
+    demo.show(
+        "plus_50",
+        array(v4.clone()),
+        format!("Adding 50 to each gives {:?}", v4),
+    );
+}
+
+fn enums(demo: &mut Demo) {
+    // A vector holds one type, but that type can be an enum whose variants
+    // carry different types
+    let row = vec![
+        SpreadsheetCell::Int(3),
+        SpreadsheetCell::Text(String::from("blue")),
+        SpreadsheetCell::Float(10.12),
+    ];
+    demo.show(
+        "row",
+        array(row.iter().map(cell_json)),
+        format!("A spreadsheet row is {:?}", row),
+    );
+}
+
+fn cell_json(cell: &SpreadsheetCell) -> JsonValue {
+    match cell {
+        SpreadsheetCell::Int(value) => (*value).into(),
+        SpreadsheetCell::Float(value) => (*value).into(),
+        SpreadsheetCell::Text(text) => text.as_str().into(),
+        SpreadsheetCell::Empty => JsonValue::Null,
+    }
+}
@@ -0,0 +1,109 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// `collections wordcount`, the words of files or stdin counted with a
+// `Counter` and printed most common first
+//
+// Input is read a line at a time, so stdin can be streamed through. Words
+// are runs of non-whitespace with any punctuation at either end trimmed
+// off, so `"Hello,"` counts as `Hello`; bytes that aren't UTF-8 are
+// replaced rather than rejected.
+
+use crate::args::{invalid, Args};
+use crate::{CommandResult, Subcommand};
+use rust_collections::counter::Counter;
+use std::cmp::Reverse;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+pub const COMMAND: Subcommand = Subcommand {
+    name: "wordcount",
+    summary: "count the words in files or stdin",
+    usage: USAGE,
+    run,
+};
+
+const USAGE: &str = "\
+usage: collections wordcount [FILE...] [--top N] [--ignore-case] [--min-len L]
+
+Reads stdin when no files are given, or for a FILE of '-'.
+
+options:
+    --top N         print only the N most common words
+    --ignore-case   count words regardless of case, printed in lower case
+    --min-len L     skip words shorter than L characters (default 1)
+
+Words are printed with their counts, most common first, and words with
+the same count in alphabetical order.";
+
+// How words are picked out and counted
+struct Options {
+    ignore_case: bool,
+    min_len: usize,
+}
+
+fn run(raw: Vec<String>) -> CommandResult {
+    let args = Args::parse(raw, &["top", "min-len"], &["ignore-case"])?;
+    let top: Option<usize> = args.number("top")?;
+    let options = Options {
+        ignore_case: args.flag("ignore-case"),
+        min_len: args.number("min-len")?.unwrap_or(1),
+    };
+    if top == Some(0) {
+        return Err(invalid("top", 0, "must be at least 1").into());
+    }
+
+    let mut counter = Counter::new();
+    let files = args.positional();
+    if files.is_empty() {
+        count_words(io::stdin().lock(), &options, &mut counter)?;
+    }
+    for path in files {
+        let result = if path == "-" {
+            count_words(io::stdin().lock(), &options, &mut counter)
+        } else {
+            File::open(path)
+                .and_then(|file| count_words(BufReader::new(file), &options, &mut counter))
+        };
+        result.map_err(|err| format!("could not read '{}': {}", path, err))?;
+    }
+
+    let mut words: Vec<(&String, usize)> = counter.iter().collect();
+    words.sort_unstable_by(|a, b| (Reverse(a.1), a.0).cmp(&(Reverse(b.1), b.0)));
+    words.truncate(top.unwrap_or(words.len()));
+
+    let stdout = io::stdout();
+    let mut out = BufWriter::new(stdout.lock());
+    let width = words
+        .first()
+        .map_or(1, |(_, count)| count.to_string().len());
+    for (word, count) in words {
+        writeln!(out, "{:>width$} {}", count, word, width = width)?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn count_words<R: BufRead>(
+    mut reader: R,
+    options: &Options,
+    counter: &mut Counter<String>,
+) -> io::Result<()> {
+    let mut line = Vec::new();
+    while reader.read_until(b'\n', &mut line)? > 0 {
+        let text = String::from_utf8_lossy(&line);
+        for word in text.split_whitespace() {
+            let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+            if word.is_empty() || word.chars().count() < options.min_len {
+                continue;
+            }
+            if options.ignore_case {
+                counter.add(word.to_lowercase());
+            } else {
+                counter.add(word.to_string());
+            }
+        }
+        line.clear();
+    }
+    Ok(())
+}
@@ -0,0 +1,150 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A set of small non-negative integers stored as one bit each
+//!
+//! Where a `HashSet<usize>` spends a whole entry per member, a `BitSet` spends
+//! one bit per possible member, packed into `u64` words. That makes it a good
+//! fit for dense sets such as "which slots are used" or the bit array inside a
+//! Bloom filter. The set grows as larger members are inserted.
+
+use crate::deep_size::DeepSize;
+use std::iter::FromIterator;
+
+const WORD_BITS: usize = 64;
+
+/// A growable set of `usize` values backed by a bit vector
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct BitSet {
+    words: Vec<u64>,
+}
+
+impl BitSet {
+    pub fn new() -> Self {
+        BitSet { words: Vec::new() }
+    }
+
+    /// Creates an empty set with room for members below `bits` without
+    /// growing
+    pub fn with_capacity(bits: usize) -> Self {
+        BitSet {
+            words: vec![0; bits.div_ceil(WORD_BITS)],
+        }
+    }
+
+    /// Rebuilds a set from the words returned by `as_words`
+    pub fn from_words(words: Vec<u64>) -> Self {
+        BitSet { words }
+    }
+
+    /// The raw bit storage, lowest members in the low bits of the first word
+    pub fn as_words(&self) -> &[u64] {
+        &self.words
+    }
+
+    /// Number of members the set can hold without growing
+    pub fn capacity(&self) -> usize {
+        self.words.len() * WORD_BITS
+    }
+
+    /// Number of members, found by counting set bits
+    pub fn len(&self) -> usize {
+        self.words
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|&word| word == 0)
+    }
+
+    /// Adds `value`, returning true if it was not already present
+    pub fn insert(&mut self, value: usize) -> bool {
+        let (index, mask) = split(value);
+        if index >= self.words.len() {
+            self.words.resize(index + 1, 0);
+        }
+        let was_set = self.words[index] & mask != 0;
+        self.words[index] |= mask;
+        !was_set
+    }
+
+    /// Removes `value`, returning true if it was present
+    pub fn remove(&mut self, value: usize) -> bool {
+        let (index, mask) = split(value);
+        match self.words.get_mut(index) {
+            Some(word) => {
+                let was_set = *word & mask != 0;
+                *word &= !mask;
+                was_set
+            }
+            None => false,
+        }
+    }
+
+    pub fn contains(&self, value: usize) -> bool {
+        let (index, mask) = split(value);
+        self.words.get(index).is_some_and(|word| word & mask != 0)
+    }
+
+    /// Adds every member of `other` to this set
+    pub fn union_with(&mut self, other: &BitSet) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (word, other) in self.words.iter_mut().zip(&other.words) {
+            *word |= other;
+        }
+    }
+
+    /// Keeps only the members also in `other`
+    pub fn intersect_with(&mut self, other: &BitSet) {
+        for (index, word) in self.words.iter_mut().enumerate() {
+            *word &= other.words.get(index).copied().unwrap_or(0);
+        }
+    }
+
+    /// Iterates over the members in ascending order
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.words.iter().enumerate().flat_map(|(index, &word)| {
+            (0..WORD_BITS)
+                .filter(move |bit| word & (1 << bit) != 0)
+                .map(move |bit| index * WORD_BITS + bit)
+        })
+    }
+
+    /// Removes every member, keeping the allocated words
+    pub fn clear(&mut self) {
+        for word in self.words.iter_mut() {
+            *word = 0;
+        }
+    }
+}
+
+impl DeepSize for BitSet {
+    fn heap_bytes(&self) -> usize {
+        self.words.heap_bytes()
+    }
+}
+
+impl FromIterator<usize> for BitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = BitSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for BitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+// Which word holds `value`, and the mask selecting its bit within that word
+fn split(value: usize) -> (usize, u64) {
+    (value / WORD_BITS, 1 << (value % WORD_BITS))
+}
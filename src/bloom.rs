@@ -0,0 +1,180 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A Bloom filter for cheap "definitely not present" checks
+//!
+//! A Bloom filter answers membership queries with no false negatives and a
+//! tunable rate of false positives, in a small fraction of the memory a set
+//! would need. Checking it before an expensive map lookup, or a lookup on
+//! disk, skips most of the lookups for keys that were never stored.
+//!
+//! Items are hashed with `FastHasher`, whose output doesn't change between
+//! runs, so a filter written out with `to_bytes` can be read back elsewhere
+//! and still recognise the same items.
+
+use crate::bit_set::BitSet;
+use crate::fast_hash::FastHasher;
+use std::error::Error;
+use std::f64::consts::LN_2;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+// Written ahead of the item for the second hash, so both hashes differ
+const SECOND_HASH_SALT: u64 = 0x9e_37_79_b9_7f_4a_7c_15;
+
+// Bytes before the bit array in `to_bytes`: the bit count, then hash count
+const HEADER_LEN: usize = 12;
+
+/// Why two filters couldn't be combined or a filter couldn't be decoded
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BloomError {
+    /// The filters were built with different sizes or hash counts
+    Incompatible,
+    /// The bytes passed to `from_bytes` don't describe a filter
+    Malformed,
+}
+
+impl fmt::Display for BloomError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BloomError::Incompatible => {
+                write!(f, "bloom filters have different sizes or hash counts")
+            }
+            BloomError::Malformed => write!(f, "bytes do not describe a bloom filter"),
+        }
+    }
+}
+
+impl Error for BloomError {}
+
+/// A probabilistic set that may report false positives but never false
+/// negatives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BloomFilter {
+    bits: BitSet,
+    num_bits: usize,
+    num_hashes: u32,
+}
+
+impl BloomFilter {
+    /// Sizes a filter so that after `expected_items` insertions, roughly
+    /// `fp_rate` of queries for absent items come back positive
+    ///
+    /// Panics if `fp_rate` is not strictly between 0 and 1.
+    pub fn with_rate(expected_items: usize, fp_rate: f64) -> Self {
+        assert!(
+            fp_rate > 0.0 && fp_rate < 1.0,
+            "false-positive rate must be between 0 and 1"
+        );
+        let items = expected_items.max(1) as f64;
+        let num_bits = (-items * fp_rate.ln() / (LN_2 * LN_2)).ceil().max(1.0);
+        let num_hashes = (num_bits / items * LN_2).round().max(1.0);
+        BloomFilter::with_size(num_bits as usize, num_hashes as u32)
+    }
+
+    /// Creates a filter with exactly `num_bits` bits and `num_hashes` hash
+    /// functions, each raised to at least one
+    pub fn with_size(num_bits: usize, num_hashes: u32) -> Self {
+        let num_bits = num_bits.max(1);
+        BloomFilter {
+            bits: BitSet::with_capacity(num_bits),
+            num_bits,
+            num_hashes: num_hashes.max(1),
+        }
+    }
+
+    pub fn num_bits(&self) -> usize {
+        self.num_bits
+    }
+
+    pub fn num_hashes(&self) -> u32 {
+        self.num_hashes
+    }
+
+    pub fn insert<T: Hash + ?Sized>(&mut self, item: &T) {
+        for index in self.indexes(item) {
+            self.bits.insert(index);
+        }
+    }
+
+    /// Returns false if `item` was definitely never inserted, and true if it
+    /// probably was
+    pub fn contains<T: Hash + ?Sized>(&self, item: &T) -> bool {
+        self.indexes(item).all(|index| self.bits.contains(index))
+    }
+
+    /// Adds everything inserted into `other` to this filter
+    pub fn union(&mut self, other: &BloomFilter) -> Result<(), BloomError> {
+        if self.num_bits != other.num_bits || self.num_hashes != other.num_hashes {
+            return Err(BloomError::Incompatible);
+        }
+        self.bits.union_with(&other.bits);
+        Ok(())
+    }
+
+    /// Removes every item
+    pub fn clear(&mut self) {
+        self.bits.clear();
+    }
+
+    /// Encodes the filter as its bit count and hash count followed by the
+    /// bit array, all little-endian
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let words = self.bits.as_words();
+        let mut bytes = Vec::with_capacity(HEADER_LEN + words.len() * 8);
+        bytes.extend_from_slice(&(self.num_bits as u64).to_le_bytes());
+        bytes.extend_from_slice(&self.num_hashes.to_le_bytes());
+        for word in words {
+            bytes.extend_from_slice(&word.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// Decodes a filter written by `to_bytes`
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, BloomError> {
+        if bytes.len() < HEADER_LEN {
+            return Err(BloomError::Malformed);
+        }
+        let (header, body) = bytes.split_at(HEADER_LEN);
+        let mut num_bits = [0; 8];
+        num_bits.copy_from_slice(&header[..8]);
+        let mut num_hashes = [0; 4];
+        num_hashes.copy_from_slice(&header[8..]);
+        let num_bits = u64::from_le_bytes(num_bits) as usize;
+        let num_hashes = u32::from_le_bytes(num_hashes);
+
+        let expected_words = num_bits.div_ceil(64);
+        if num_bits == 0 || num_hashes == 0 || body.len() != expected_words * 8 {
+            return Err(BloomError::Malformed);
+        }
+
+        let words = body
+            .chunks_exact(8)
+            .map(|chunk| {
+                let mut word = [0; 8];
+                word.copy_from_slice(chunk);
+                u64::from_le_bytes(word)
+            })
+            .collect();
+        Ok(BloomFilter {
+            bits: BitSet::from_words(words),
+            num_bits,
+            num_hashes,
+        })
+    }
+
+    // Double hashing: the i-th index is `h1 + i * h2`, which behaves like
+    // `num_hashes` independent hashes for the cost of two
+    fn indexes<T: Hash + ?Sized>(&self, item: &T) -> impl Iterator<Item = usize> {
+        let mut first = FastHasher::default();
+        item.hash(&mut first);
+        let mut second = FastHasher::default();
+        second.write_u64(SECOND_HASH_SALT);
+        item.hash(&mut second);
+
+        let (h1, h2) = (first.finish(), second.finish() | 1);
+        let num_bits = self.num_bits as u64;
+        (0..u64::from(self.num_hashes))
+            .map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % num_bits) as usize)
+    }
+}
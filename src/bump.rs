@@ -0,0 +1,200 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A bump arena for the short-lived strings and slices of one batch
+//!
+//! Parsing a batch of rows makes a `String` for every field and a `Vec` for
+//! every row, only for all of them to be thrown away once the batch has been
+//! processed. A `Bump` hands that memory out of a few large chunks instead,
+//! just by moving an offset forward, and `reset` takes all of it back at
+//! once so the next batch reuses the same chunks.
+//!
+//! `csv_map::parse_csv_in`, `spreadsheet::parse_fields_in` and
+//! `grouping::group_by_in` are the arena-backed versions of the CSV parser,
+//! the row tokenizer and `group_by`. Nothing in the arena is ever dropped,
+//! so only `Copy` values can be stored. `benches/bump.rs` counts the
+//! allocations each version makes.
+
+use crate::deep_size::DeepSize;
+use std::alloc::{self, Layout};
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::mem;
+use std::ptr::NonNull;
+use std::slice;
+use std::str;
+
+const CHUNK_ALIGN: usize = 16;
+
+// Size of the first chunk of an arena made with `new`
+const FIRST_CHUNK: usize = 4096;
+
+struct Chunk {
+    start: NonNull<u8>,
+    layout: Layout,
+}
+
+impl Chunk {
+    fn new(size: usize, align: usize) -> Chunk {
+        let layout =
+            Layout::from_size_align(size, align.max(CHUNK_ALIGN)).expect("bump chunk too large");
+        // Safety: chunks are never zero-sized
+        let start = unsafe { alloc::alloc(layout) };
+        let start = NonNull::new(start).unwrap_or_else(|| alloc::handle_alloc_error(layout));
+        Chunk { start, layout }
+    }
+
+    fn size(&self) -> usize {
+        self.layout.size()
+    }
+}
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        // Safety: allocated in `Chunk::new` with this layout
+        unsafe { alloc::dealloc(self.start.as_ptr(), self.layout) }
+    }
+}
+
+/// An arena that allocates by moving an offset forward and frees everything
+/// at once
+pub struct Bump {
+    // Filled in order; only the last has room left
+    chunks: RefCell<Vec<Chunk>>,
+    // Bytes of the last chunk handed out
+    used: Cell<usize>,
+}
+
+impl Bump {
+    pub fn new() -> Self {
+        Bump {
+            chunks: RefCell::new(Vec::new()),
+            used: Cell::new(0),
+        }
+    }
+
+    /// Creates an arena whose first chunk holds `bytes` bytes
+    pub fn with_capacity(bytes: usize) -> Self {
+        let bump = Bump::new();
+        if bytes > 0 {
+            bump.chunks
+                .borrow_mut()
+                .push(Chunk::new(bytes, CHUNK_ALIGN));
+        }
+        bump
+    }
+
+    /// Copies `text` into the arena
+    pub fn alloc_str(&self, text: &str) -> &str {
+        let bytes = self.alloc_slice_copy(text.as_bytes());
+        // Safety: the bytes were copied from a `str`
+        unsafe { str::from_utf8_unchecked(bytes) }
+    }
+
+    /// Copies `items` into the arena
+    pub fn alloc_slice_copy<T: Copy>(&self, items: &[T]) -> &mut [T] {
+        self.alloc_slice_fill_iter(items.iter().copied())
+    }
+
+    /// Moves the items of `iter` into a slice in the arena
+    ///
+    /// Panics if `iter` yields a different number of items than its `len`.
+    // Every call hands out memory no other call has, which is the point
+    #[allow(clippy::mut_from_ref)]
+    pub fn alloc_slice_fill_iter<T, I>(&self, iter: I) -> &mut [T]
+    where
+        T: Copy,
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let mut iter = iter.into_iter();
+        let len = iter.len();
+        let layout = Layout::array::<T>(len).expect("bump allocation too large");
+        let start = if layout.size() == 0 {
+            NonNull::dangling()
+        } else {
+            self.alloc_layout(layout).cast::<T>()
+        };
+        for index in 0..len {
+            let item = iter.next().expect("iterator shorter than its len");
+            // Safety: `start` has room for `len` items
+            unsafe { start.as_ptr().add(index).write(item) }
+        }
+        assert!(iter.next().is_none(), "iterator longer than its len");
+        // Safety: all `len` items were just written, and the memory is
+        // handed out only once until `reset`, which needs `&mut self`
+        unsafe { slice::from_raw_parts_mut(start.as_ptr(), len) }
+    }
+
+    /// Frees everything allocated so far, keeping the largest chunk for
+    /// reuse
+    pub fn reset(&mut self) {
+        let chunks = self.chunks.get_mut();
+        // Each chunk is at least twice the size of the one before
+        if let Some(last) = chunks.pop() {
+            chunks.clear();
+            chunks.push(last);
+        }
+        self.used.set(0);
+    }
+
+    /// Bytes of memory the arena holds, used or not
+    pub fn allocated_bytes(&self) -> usize {
+        self.chunks.borrow().iter().map(Chunk::size).sum()
+    }
+
+    // Finds room for `layout` in the last chunk, or in a new one, which
+    // isn't zero-sized
+    fn alloc_layout(&self, layout: Layout) -> NonNull<u8> {
+        let mut chunks = self.chunks.borrow_mut();
+        if let Some(chunk) = chunks.last() {
+            let used = self.used.get();
+            // Safety: `used` is at most the chunk's size
+            let free = unsafe { chunk.start.as_ptr().add(used) };
+            let start = used.checked_add(free.align_offset(layout.align()));
+            if let Some(start) = start {
+                if start.saturating_add(layout.size()) <= chunk.size() {
+                    self.used.set(start + layout.size());
+                    // Safety: in bounds, as just checked
+                    return unsafe { NonNull::new_unchecked(chunk.start.as_ptr().add(start)) };
+                }
+            }
+        }
+
+        let last_size = chunks.last().map_or(0, Chunk::size);
+        let size = last_size
+            .saturating_mul(2)
+            .max(layout.size())
+            .max(FIRST_CHUNK);
+        let chunk = Chunk::new(size, layout.align());
+        let start = chunk.start;
+        chunks.push(chunk);
+        self.used.set(layout.size());
+        start
+    }
+}
+
+impl Default for Bump {
+    fn default() -> Self {
+        Bump::new()
+    }
+}
+
+impl fmt::Debug for Bump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Bump")
+            .field("allocated_bytes", &self.allocated_bytes())
+            .field("used", &self.used.get())
+            .finish()
+    }
+}
+
+impl DeepSize for Bump {
+    fn heap_bytes(&self) -> usize {
+        self.allocated_bytes() + self.chunks.borrow().capacity() * mem::size_of::<Chunk>()
+    }
+}
+
+// Safety: the chunks are owned by the arena alone, and anything borrowed
+// from them borrows the arena too, so it can't be sent while they're in use
+unsafe impl Send for Bump {}
@@ -0,0 +1,242 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A common interface over the crate's caches, and a wrapper that measures
+//! how well one is working
+//!
+//! `Cache` is implemented by `LfuCache` and `TtlMap`. `StatsCache` wraps any
+//! of them and counts hits, misses, inserts and evictions, and times every
+//! lookup, which is what's needed to tell whether a cache is the right size.
+//! It can also hand its report to a callback every so many lookups, for
+//! logging from a long-running process.
+
+use crate::lfu_cache::LfuCache;
+use crate::ttl_map::{Clock, TtlMap};
+use std::fmt;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// A key-value cache that may drop entries on its own
+pub trait Cache {
+    type Key;
+    type Value;
+
+    /// Looks up `key`, counting it as a use if the cache tracks usage
+    fn get(&mut self, key: &Self::Key) -> Option<&Self::Value>;
+
+    /// Inserts or updates `key`, returning the entry evicted to make room
+    fn put(&mut self, key: Self::Key, value: Self::Value) -> Option<(Self::Key, Self::Value)>;
+
+    fn remove(&mut self, key: &Self::Key) -> Option<Self::Value>;
+
+    fn len(&self) -> usize;
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> Cache for LfuCache<K, V> {
+    type Key = K;
+    type Value = V;
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        LfuCache::get(self, key)
+    }
+
+    fn put(&mut self, key: K, value: V) -> Option<(K, V)> {
+        LfuCache::put(self, key, value)
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        LfuCache::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        LfuCache::len(self)
+    }
+}
+
+/// Entries never need to make room in a `TtlMap`, so `put` never reports an
+/// eviction; entries leave by expiring instead
+impl<K: Eq + Hash, V, C: Clock> Cache for TtlMap<K, V, C> {
+    type Key = K;
+    type Value = V;
+
+    fn get(&mut self, key: &K) -> Option<&V> {
+        TtlMap::get(self, key)
+    }
+
+    fn put(&mut self, key: K, value: V) -> Option<(K, V)> {
+        self.insert(key, value);
+        None
+    }
+
+    fn remove(&mut self, key: &K) -> Option<V> {
+        TtlMap::remove(self, key)
+    }
+
+    fn len(&self) -> usize {
+        TtlMap::len(self)
+    }
+}
+
+/// The numbers collected by a `StatsCache`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheReport {
+    pub hits: u64,
+    pub misses: u64,
+    pub inserts: u64,
+    pub evictions: u64,
+    /// Time spent in every lookup added together
+    pub lookup_time: Duration,
+}
+
+impl CacheReport {
+    pub fn lookups(&self) -> u64 {
+        self.hits + self.misses
+    }
+
+    /// Fraction of lookups that found their key, or 0 before any lookup
+    pub fn hit_rate(&self) -> f64 {
+        match self.lookups() {
+            0 => 0.0,
+            lookups => self.hits as f64 / lookups as f64,
+        }
+    }
+
+    pub fn average_lookup(&self) -> Duration {
+        match self.lookups() {
+            0 => Duration::ZERO,
+            lookups => self.lookup_time / lookups.min(u32::MAX as u64) as u32,
+        }
+    }
+}
+
+impl fmt::Display for CacheReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} lookups ({} hits, {} misses, {:.1}% hit rate), {} inserts, {} evictions, {:?} average lookup",
+            self.lookups(),
+            self.hits,
+            self.misses,
+            self.hit_rate() * 100.0,
+            self.inserts,
+            self.evictions,
+            self.average_lookup()
+        )
+    }
+}
+
+type Logger = Box<dyn FnMut(&CacheReport)>;
+
+/// Wraps a `Cache` and records how it is used
+pub struct StatsCache<C> {
+    inner: C,
+    stats: CacheReport,
+    // Called with the running report every `.0` lookups
+    logger: Option<(u64, Logger)>,
+}
+
+impl<C: Cache> StatsCache<C> {
+    pub fn new(inner: C) -> Self {
+        StatsCache {
+            inner,
+            stats: CacheReport::default(),
+            logger: None,
+        }
+    }
+
+    /// Calls `log` with the running report after every `every` lookups
+    ///
+    /// Panics if `every` is zero.
+    pub fn log_every<F>(&mut self, every: u64, log: F)
+    where
+        F: FnMut(&CacheReport) + 'static,
+    {
+        assert!(every > 0, "logging interval must be positive");
+        self.logger = Some((every, Box::new(log)));
+    }
+
+    pub fn stop_logging(&mut self) {
+        self.logger = None;
+    }
+
+    pub fn get(&mut self, key: &C::Key) -> Option<&C::Value> {
+        let start = Instant::now();
+        let value = self.inner.get(key);
+        self.stats.lookup_time += start.elapsed();
+        if value.is_some() {
+            self.stats.hits += 1;
+        } else {
+            self.stats.misses += 1;
+        }
+
+        if let Some((every, log)) = &mut self.logger {
+            if self.stats.lookups().is_multiple_of(*every) {
+                log(&self.stats);
+            }
+        }
+        value
+    }
+
+    /// Inserts or updates `key`, returning the entry evicted to make room
+    pub fn put(&mut self, key: C::Key, value: C::Value) -> Option<(C::Key, C::Value)> {
+        self.stats.inserts += 1;
+        let evicted = self.inner.put(key, value);
+        if evicted.is_some() {
+            self.stats.evictions += 1;
+        }
+        evicted
+    }
+
+    pub fn remove(&mut self, key: &C::Key) -> Option<C::Value> {
+        self.inner.remove(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+
+    /// The numbers collected since creation or the last `reset_stats`
+    pub fn report(&self) -> CacheReport {
+        self.stats
+    }
+
+    pub fn reset_stats(&mut self) {
+        self.stats = CacheReport::default();
+    }
+
+    pub fn inner(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
+
+impl<C: fmt::Debug> fmt::Debug for StatsCache<C> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StatsCache")
+            .field("inner", &self.inner)
+            .field("stats", &self.stats)
+            .field("logging", &self.logger.is_some())
+            .finish()
+    }
+}
+
+// Each entry goes through `put`, so bulk inserts and the evictions they cause
+// show up in the report
+impl<C: Cache> Extend<(C::Key, C::Value)> for StatsCache<C> {
+    fn extend<I: IntoIterator<Item = (C::Key, C::Value)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.put(key, value);
+        }
+    }
+}
@@ -0,0 +1,436 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Managing how much memory a `HashMap` holds on to
+//!
+//! A `HashMap` grows as entries go in but never shrinks on its own, so a map
+//! that was briefly large keeps its full allocation after most entries are
+//! removed. These helpers reserve room up front, give back memory from sparse
+//! maps, and report how much of the allocation is in use. `bulk_insert`
+//! sizes a map for everything an iterator is about to add before adding it,
+//! so loading millions of entries doesn't rehash the table at every doubling.
+//!
+//! std's map keeps a power-of-two number of buckets and fills at most 7/8 of
+//! them before growing. `capacity()` already reports the usable part, so the
+//! bucket counts here are estimates worked back from it.
+//!
+//! `EstimatedCapacity` gives the crate's collections (and std's) a common
+//! constructor for when the final size is only roughly known, and
+//! `Tracked` wraps a collection to count how often it reallocates and how
+//! large its allocation ever got.
+
+use crate::bit_set::BitSet;
+use crate::cuckoo_map::CuckooMap;
+use crate::dense_set::DenseSet;
+use crate::priority_map::PriorityMap;
+use crate::probe_map::ProbeMap;
+use crate::robin_hood_map::RobinHoodMap;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+
+// Control bytes std's map keeps beyond one per bucket, so a probe can read a
+// full group of them without wrapping
+const EXTRA_CONTROL_BYTES: usize = 16;
+
+/// Makes sure `map` can hold `total` entries in all without reallocating
+///
+/// Unlike `HashMap::reserve`, which counts entries on top of those already
+/// present, this takes the final size, which is usually the number at hand
+/// when loading a known amount of data.
+pub fn reserve_for<K: Eq + Hash, V, S: BuildHasher>(map: &mut HashMap<K, V, S>, total: usize) {
+    if total > map.capacity() {
+        map.reserve(total - map.len());
+    }
+}
+
+/// What `bulk_insert` or `bulk_insert_exact` did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BulkInsertReport {
+    /// Entries taken from the iterator, including ones that replaced an
+    /// existing value
+    pub inserted: usize,
+    /// Total entries the map was sized for before inserting
+    pub reserved_for: usize,
+    /// Times the table grew while inserting, after the upfront reservation
+    pub rehashes: u64,
+}
+
+/// Inserts every pair from `iter`, first reserving room for as many as the
+/// iterator's size hint promises
+///
+/// Only the hint's lower bound is trusted, so an iterator that can't say how
+/// long it is, such as a `filter`, gets no reservation and grows as usual;
+/// `bulk_insert_exact` counts such iterators first. Keys repeated in `iter`
+/// or already in the map are reserved for as if they were new.
+pub fn bulk_insert<K, V, S, I>(map: &mut HashMap<K, V, S>, iter: I) -> BulkInsertReport
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+    I: IntoIterator<Item = (K, V)>,
+{
+    let iter = iter.into_iter();
+    let expected = iter.size_hint().0;
+    insert_reserved(map, iter, expected)
+}
+
+/// `bulk_insert` for iterators whose size hint is no use, walking a clone of
+/// the iterator once to count the pairs before inserting them
+pub fn bulk_insert_exact<K, V, S, I>(map: &mut HashMap<K, V, S>, iter: I) -> BulkInsertReport
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+    I: IntoIterator<Item = (K, V)>,
+    I::IntoIter: Clone,
+{
+    let iter = iter.into_iter();
+    let expected = iter.clone().count();
+    insert_reserved(map, iter, expected)
+}
+
+fn insert_reserved<K, V, S, I>(
+    map: &mut HashMap<K, V, S>,
+    iter: I,
+    expected: usize,
+) -> BulkInsertReport
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+    I: Iterator<Item = (K, V)>,
+{
+    let reserved_for = map.len().saturating_add(expected);
+    reserve_for(map, reserved_for);
+    let mut telemetry = GrowthTelemetry::starting_at(map.capacity());
+    let mut inserted = 0;
+    for (key, value) in iter {
+        map.insert(key, value);
+        inserted += 1;
+        telemetry.observe(map.capacity());
+    }
+    BulkInsertReport {
+        inserted,
+        reserved_for,
+        rehashes: telemetry.reallocations,
+    }
+}
+
+/// Shrinks `map` to fit its entries if fewer than `threshold` of its
+/// capacity is in use, returning whether it shrank
+///
+/// A `threshold` of 0.25 shrinks once three quarters of the room is unused.
+pub fn shrink_to_fit_if_sparse<K: Eq + Hash, V, S: BuildHasher>(
+    map: &mut HashMap<K, V, S>,
+    threshold: f64,
+) -> bool {
+    let capacity = map.capacity();
+    if capacity == 0 || map.len() as f64 / capacity as f64 >= threshold {
+        return false;
+    }
+    map.shrink_to_fit();
+    map.capacity() < capacity
+}
+
+/// A snapshot of how a `HashMap`'s allocation is being used
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MemoryReport {
+    /// Entries in the map
+    pub len: usize,
+    /// Entries the map can hold before it reallocates
+    pub capacity: usize,
+    /// Estimated number of buckets in the allocation
+    pub buckets: usize,
+    /// Bytes taken by one key and value pair
+    pub entry_size: usize,
+    /// Estimated size of the whole table allocation in bytes
+    pub allocated_bytes: usize,
+    /// Estimated bytes spent on buckets holding no entry
+    pub unused_bytes: usize,
+}
+
+impl MemoryReport {
+    /// Describes the current allocation of `map`
+    ///
+    /// Only the table itself is counted, not anything the keys or values
+    /// allocate on the heap, such as a `String`'s contents.
+    pub fn of<K, V, S>(map: &HashMap<K, V, S>) -> MemoryReport {
+        let capacity = map.capacity();
+        let buckets = buckets_for(capacity);
+        let entry_size = mem::size_of::<(K, V)>();
+        MemoryReport {
+            len: map.len(),
+            capacity,
+            buckets,
+            entry_size,
+            allocated_bytes: table_bytes(capacity, entry_size),
+            unused_bytes: (buckets - map.len().min(buckets)) * entry_size,
+        }
+    }
+
+    /// Fraction of buckets holding an entry
+    pub fn load_factor(&self) -> f64 {
+        if self.buckets == 0 {
+            0.0
+        } else {
+            self.len as f64 / self.buckets as f64
+        }
+    }
+}
+
+impl fmt::Display for MemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} entries in {} buckets (capacity {}, load {:.0}%), ~{} bytes allocated, ~{} unused",
+            self.len,
+            self.buckets,
+            self.capacity,
+            self.load_factor() * 100.0,
+            self.allocated_bytes,
+            self.unused_bytes
+        )
+    }
+}
+
+/// Estimated bytes allocated by a std hash table with room for `capacity`
+/// entries of `entry_size` bytes each
+pub(crate) fn table_bytes(capacity: usize, entry_size: usize) -> usize {
+    let buckets = buckets_for(capacity);
+    if buckets == 0 {
+        0
+    } else {
+        buckets * (entry_size + 1) + EXTRA_CONTROL_BYTES
+    }
+}
+
+// Small tables leave one bucket free; larger ones keep an eighth free
+fn buckets_for(capacity: usize) -> usize {
+    match capacity {
+        0 => 0,
+        1..=7 => (capacity + 1).next_power_of_two(),
+        _ => (capacity / 7 * 8).next_power_of_two(),
+    }
+}
+
+/// Collections that can be created with room for a rough number of entries
+pub trait EstimatedCapacity: Sized {
+    /// Creates an empty collection with room for `estimate` entries plus an
+    /// eighth more
+    ///
+    /// Estimates tend to fall a little short, and running out just past the
+    /// requested capacity costs a reallocation to double the size, so a
+    /// little headroom is cheaper than an exact fit.
+    fn with_estimated_capacity(estimate: usize) -> Self;
+}
+
+fn with_headroom(estimate: usize) -> usize {
+    estimate.saturating_add(estimate / 8)
+}
+
+impl<T> EstimatedCapacity for Vec<T> {
+    fn with_estimated_capacity(estimate: usize) -> Self {
+        Vec::with_capacity(with_headroom(estimate))
+    }
+}
+
+impl<T> EstimatedCapacity for VecDeque<T> {
+    fn with_estimated_capacity(estimate: usize) -> Self {
+        VecDeque::with_capacity(with_headroom(estimate))
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Default> EstimatedCapacity for HashMap<K, V, S> {
+    fn with_estimated_capacity(estimate: usize) -> Self {
+        HashMap::with_capacity_and_hasher(with_headroom(estimate), S::default())
+    }
+}
+
+impl<T: Eq + Hash, S: BuildHasher + Default> EstimatedCapacity for HashSet<T, S> {
+    fn with_estimated_capacity(estimate: usize) -> Self {
+        HashSet::with_capacity_and_hasher(with_headroom(estimate), S::default())
+    }
+}
+
+impl<K: Eq + Hash, V> EstimatedCapacity for ProbeMap<K, V> {
+    fn with_estimated_capacity(estimate: usize) -> Self {
+        ProbeMap::with_capacity(with_headroom(estimate))
+    }
+}
+
+impl<K: Eq + Hash, V> EstimatedCapacity for RobinHoodMap<K, V> {
+    fn with_estimated_capacity(estimate: usize) -> Self {
+        RobinHoodMap::with_capacity(with_headroom(estimate))
+    }
+}
+
+impl<K: Eq + Hash, V> EstimatedCapacity for CuckooMap<K, V> {
+    fn with_estimated_capacity(estimate: usize) -> Self {
+        CuckooMap::with_capacity(with_headroom(estimate))
+    }
+}
+
+impl<T: Copy + Eq + Hash> EstimatedCapacity for DenseSet<T> {
+    fn with_estimated_capacity(estimate: usize) -> Self {
+        DenseSet::with_capacity(with_headroom(estimate))
+    }
+}
+
+impl<K: Clone + Eq + Hash, P: Ord> EstimatedCapacity for PriorityMap<K, P> {
+    fn with_estimated_capacity(estimate: usize) -> Self {
+        PriorityMap::with_capacity(with_headroom(estimate))
+    }
+}
+
+// For a bit set the estimate is the largest expected member rather than a
+// count, so the headroom applies to that
+impl EstimatedCapacity for BitSet {
+    fn with_estimated_capacity(estimate: usize) -> Self {
+        BitSet::with_capacity(with_headroom(estimate))
+    }
+}
+
+/// Collections that can report the size of their current allocation
+pub trait HasCapacity {
+    /// Entries, or for the open-addressing maps slots, currently allocated
+    fn current_capacity(&self) -> usize;
+}
+
+impl<T> HasCapacity for Vec<T> {
+    fn current_capacity(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T> HasCapacity for VecDeque<T> {
+    fn current_capacity(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl HasCapacity for String {
+    fn current_capacity(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<K, V, S> HasCapacity for HashMap<K, V, S> {
+    fn current_capacity(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T, S> HasCapacity for HashSet<T, S> {
+    fn current_capacity(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<K: Eq + Hash, V> HasCapacity for ProbeMap<K, V> {
+    fn current_capacity(&self) -> usize {
+        self.slot_count()
+    }
+}
+
+impl<K: Eq + Hash, V> HasCapacity for RobinHoodMap<K, V> {
+    fn current_capacity(&self) -> usize {
+        self.slot_count()
+    }
+}
+
+impl<K: Eq + Hash, V> HasCapacity for CuckooMap<K, V> {
+    fn current_capacity(&self) -> usize {
+        self.slot_count()
+    }
+}
+
+impl<T, S> HasCapacity for DenseSet<T, S> {
+    fn current_capacity(&self) -> usize {
+        self.slot_count()
+    }
+}
+
+impl HasCapacity for BitSet {
+    fn current_capacity(&self) -> usize {
+        self.capacity()
+    }
+}
+
+/// How often a collection's allocation has changed size, and the largest it
+/// has been
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GrowthTelemetry {
+    /// Times the capacity was seen to change, up or down
+    pub reallocations: u64,
+    pub initial_capacity: usize,
+    pub peak_capacity: usize,
+    pub current_capacity: usize,
+}
+
+impl GrowthTelemetry {
+    /// Starts tracking from a collection's current capacity
+    pub fn starting_at(capacity: usize) -> Self {
+        GrowthTelemetry {
+            reallocations: 0,
+            initial_capacity: capacity,
+            peak_capacity: capacity,
+            current_capacity: capacity,
+        }
+    }
+
+    /// Records the capacity as it is now, counting a reallocation if it
+    /// changed since the last observation
+    pub fn observe(&mut self, capacity: usize) {
+        if capacity != self.current_capacity {
+            self.reallocations += 1;
+            self.current_capacity = capacity;
+            self.peak_capacity = self.peak_capacity.max(capacity);
+        }
+    }
+}
+
+impl fmt::Display for GrowthTelemetry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} reallocations, capacity {} -> {} (peak {})",
+            self.reallocations, self.initial_capacity, self.current_capacity, self.peak_capacity
+        )
+    }
+}
+
+/// A collection that records its `GrowthTelemetry` as it is changed
+///
+/// Changes go through `update`, and the capacity is checked after each
+/// call. A single call that reallocates several times, such as a long
+/// `extend`, counts once, so update one entry at a time for exact counts.
+#[derive(Debug, Clone)]
+pub struct Tracked<C> {
+    inner: C,
+    telemetry: GrowthTelemetry,
+}
+
+impl<C: HasCapacity> Tracked<C> {
+    pub fn new(inner: C) -> Self {
+        let telemetry = GrowthTelemetry::starting_at(inner.current_capacity());
+        Tracked { inner, telemetry }
+    }
+
+    /// Runs `f` on the collection and records any change in its capacity
+    pub fn update<R, F: FnOnce(&mut C) -> R>(&mut self, f: F) -> R {
+        let result = f(&mut self.inner);
+        self.telemetry.observe(self.inner.current_capacity());
+        result
+    }
+
+    pub fn get(&self) -> &C {
+        &self.inner
+    }
+
+    pub fn telemetry(&self) -> GrowthTelemetry {
+        self.telemetry
+    }
+
+    pub fn into_inner(self) -> C {
+        self.inner
+    }
+}
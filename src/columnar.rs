@@ -0,0 +1,317 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A spreadsheet stored column by column
+//!
+//! A `Spreadsheet` row is a `Vec` of enum cells, each as large as the
+//! largest variant, so summing one column drags every other column through
+//! the cache too. `ColumnarSheet` keeps each column in its own vector of the
+//! column's type: an all-integer column is a plain `Vec<i32>`, and
+//! aggregating it is a tight loop over four bytes per cell. Which rows have
+//! a value is recorded in a `BitSet` per column, the null bitmap; empty
+//! cells hold a zero or empty string that the bitmap marks as absent.
+//!
+//! A column whose cells don't all share one type falls back to a vector of
+//! `SpreadsheetCell`, and a typed column becomes one of those if a cell of
+//! another type is stored in it. Converting to a `Spreadsheet` and back
+//! keeps every cell as it was, padding ragged rows with `Empty`.
+
+use crate::aggregates::ColumnAggregates;
+use crate::bit_set::BitSet;
+use crate::fast_reduce::{fast_min_max, fast_sum};
+use crate::spreadsheet::{SheetOps, Spreadsheet, SpreadsheetCell};
+use std::mem;
+
+/// One column of a `ColumnarSheet`
+#[derive(Debug, Clone, PartialEq)]
+pub enum Column {
+    Int {
+        values: Vec<i32>,
+        present: BitSet,
+    },
+    Float {
+        values: Vec<f64>,
+        present: BitSet,
+    },
+    Text {
+        values: Vec<String>,
+        present: BitSet,
+    },
+    /// Cells of more than one type
+    Mixed(Vec<SpreadsheetCell>),
+}
+
+impl Column {
+    /// Picks the narrowest representation that holds every cell
+    fn from_cells(cells: Vec<SpreadsheetCell>) -> Column {
+        use SpreadsheetCell::*;
+        let kinds = cells.iter().filter(|cell| **cell != Empty);
+        let mut kinds = kinds.map(mem::discriminant);
+        let first = kinds.next();
+        if first.is_none() || !kinds.all(|kind| Some(kind) == first) {
+            return Column::Mixed(cells);
+        }
+
+        let mut present = BitSet::with_capacity(cells.len());
+        for (row, cell) in cells.iter().enumerate() {
+            if *cell != Empty {
+                present.insert(row);
+            }
+        }
+        match cells.iter().find(|cell| **cell != Empty) {
+            Some(Int(_)) => Column::Int {
+                values: cells
+                    .iter()
+                    .map(|cell| if let Int(int) = cell { *int } else { 0 })
+                    .collect(),
+                present,
+            },
+            Some(Float(_)) => Column::Float {
+                values: cells
+                    .iter()
+                    .map(|cell| if let Float(float) = cell { *float } else { 0.0 })
+                    .collect(),
+                present,
+            },
+            _ => Column::Text {
+                values: cells
+                    .into_iter()
+                    .map(|cell| {
+                        if let Text(text) = cell {
+                            text
+                        } else {
+                            String::new()
+                        }
+                    })
+                    .collect(),
+                present,
+            },
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self {
+            Column::Int { values, .. } => values.len(),
+            Column::Float { values, .. } => values.len(),
+            Column::Text { values, .. } => values.len(),
+            Column::Mixed(cells) => cells.len(),
+        }
+    }
+
+    fn cell(&self, row: usize) -> Option<SpreadsheetCell> {
+        if row >= self.len() {
+            return None;
+        }
+        Some(match self {
+            Column::Int { values, present } if present.contains(row) => {
+                SpreadsheetCell::Int(values[row])
+            }
+            Column::Float { values, present } if present.contains(row) => {
+                SpreadsheetCell::Float(values[row])
+            }
+            Column::Text { values, present } if present.contains(row) => {
+                SpreadsheetCell::Text(values[row].clone())
+            }
+            Column::Mixed(cells) => cells[row].clone(),
+            _ => SpreadsheetCell::Empty,
+        })
+    }
+
+    fn into_cells(self) -> Vec<SpreadsheetCell> {
+        let len = self.len();
+        match self {
+            Column::Mixed(cells) => cells,
+            column => (0..len)
+                .map(|row| column.cell(row).unwrap_or(SpreadsheetCell::Empty))
+                .collect(),
+        }
+    }
+
+    // Stores `cell` in place if it fits the column's type, otherwise turns
+    // the column into a `Mixed` one first
+    fn set(&mut self, row: usize, cell: SpreadsheetCell) -> SpreadsheetCell {
+        let old = self.cell(row).unwrap_or(SpreadsheetCell::Empty);
+        match (&mut *self, cell) {
+            (Column::Mixed(cells), cell) => cells[row] = cell,
+            // Absent cells go back to zero so the sums can run over them
+            (Column::Int { values, present }, SpreadsheetCell::Empty) => {
+                values[row] = 0;
+                present.remove(row);
+            }
+            (Column::Float { values, present }, SpreadsheetCell::Empty) => {
+                values[row] = 0.0;
+                present.remove(row);
+            }
+            (Column::Text { values, present }, SpreadsheetCell::Empty) => {
+                values[row] = String::new();
+                present.remove(row);
+            }
+            (Column::Int { values, present }, SpreadsheetCell::Int(int)) => {
+                values[row] = int;
+                present.insert(row);
+            }
+            (Column::Float { values, present }, SpreadsheetCell::Float(float)) => {
+                values[row] = float;
+                present.insert(row);
+            }
+            (Column::Text { values, present }, SpreadsheetCell::Text(text)) => {
+                values[row] = text;
+                present.insert(row);
+            }
+            (column, cell) => {
+                let mut cells = mem::replace(column, Column::Mixed(Vec::new())).into_cells();
+                cells[row] = cell;
+                *column = Column::Mixed(cells);
+            }
+        }
+        old
+    }
+
+    fn aggregates(&self) -> ColumnAggregates {
+        match self {
+            // Absent cells hold zero, so they don't change the sums, but
+            // they have to be left out of the extremes
+            Column::Int { values, present } => {
+                let count = present.len();
+                let extremes = if count == values.len() {
+                    fast_min_max(values).map(|(min, max)| (f64::from(min), f64::from(max)))
+                } else {
+                    total_min_max(present.iter().map(|row| f64::from(values[row])))
+                };
+                ColumnAggregates {
+                    count,
+                    sum: fast_sum(values) as f64,
+                    min: extremes.map(|(min, _)| min),
+                    max: extremes.map(|(_, max)| max),
+                }
+            }
+            Column::Float { values, present } => {
+                let count = present.len();
+                // `fast_min_max` skips NaN, where the aggregates count it as
+                // the maximum
+                let extremes =
+                    if count == values.len() && !values.iter().any(|value| value.is_nan()) {
+                        fast_min_max(values)
+                    } else {
+                        total_min_max(present.iter().map(|row| values[row]))
+                    };
+                ColumnAggregates {
+                    count,
+                    sum: fast_sum(values),
+                    min: extremes.map(|(min, _)| min),
+                    max: extremes.map(|(_, max)| max),
+                }
+            }
+            Column::Text { .. } => ColumnAggregates::of(None),
+            Column::Mixed(cells) => ColumnAggregates::of(cells),
+        }
+    }
+}
+
+// The extremes in the order of `f64::total_cmp`
+fn total_min_max<I: Iterator<Item = f64>>(mut values: I) -> Option<(f64, f64)> {
+    let first = values.next()?;
+    Some(values.fold((first, first), |(min, max), value| {
+        (
+            if value.total_cmp(&min).is_lt() {
+                value
+            } else {
+                min
+            },
+            if value.total_cmp(&max).is_gt() {
+                value
+            } else {
+                max
+            },
+        )
+    }))
+}
+
+/// A rectangular sheet stored as one typed `Column` per column
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ColumnarSheet {
+    columns: Vec<Column>,
+    rows: usize,
+}
+
+impl ColumnarSheet {
+    pub fn new() -> Self {
+        ColumnarSheet {
+            columns: Vec::new(),
+            rows: 0,
+        }
+    }
+
+    /// Copies `sheet` into columns, padding short rows with `Empty`
+    pub fn from_spreadsheet(sheet: &Spreadsheet) -> Self {
+        let width = sheet.column_count();
+        let mut columns: Vec<Vec<SpreadsheetCell>> = (0..width)
+            .map(|_| Vec::with_capacity(sheet.row_count()))
+            .collect();
+        for row in sheet.rows() {
+            for (col, column) in columns.iter_mut().enumerate() {
+                column.push(row.get(col).cloned().unwrap_or(SpreadsheetCell::Empty));
+            }
+        }
+        ColumnarSheet {
+            columns: columns.into_iter().map(Column::from_cells).collect(),
+            rows: sheet.row_count(),
+        }
+    }
+
+    /// Builds the row-oriented equivalent of this sheet
+    pub fn to_spreadsheet(&self) -> Spreadsheet {
+        (0..self.rows)
+            .map(|row| {
+                self.columns
+                    .iter()
+                    .map(|column| column.cell(row).unwrap_or(SpreadsheetCell::Empty))
+                    .collect()
+            })
+            .collect()
+    }
+
+    pub fn column(&self, col: usize) -> Option<&Column> {
+        self.columns.get(col)
+    }
+}
+
+impl SheetOps for ColumnarSheet {
+    fn row_count(&self) -> usize {
+        self.rows
+    }
+
+    fn column_count(&self) -> usize {
+        self.columns.len()
+    }
+
+    fn cell(&self, row: usize, col: usize) -> Option<SpreadsheetCell> {
+        self.columns.get(col)?.cell(row)
+    }
+
+    fn set(&mut self, row: usize, col: usize, cell: SpreadsheetCell) -> Option<SpreadsheetCell> {
+        if row >= self.rows {
+            return None;
+        }
+        Some(self.columns.get_mut(col)?.set(row, cell))
+    }
+
+    fn column_aggregates(&self, col: usize) -> ColumnAggregates {
+        match self.columns.get(col) {
+            Some(column) => column.aggregates(),
+            None => ColumnAggregates::of(None),
+        }
+    }
+}
+
+impl From<&Spreadsheet> for ColumnarSheet {
+    fn from(sheet: &Spreadsheet) -> Self {
+        ColumnarSheet::from_spreadsheet(sheet)
+    }
+}
+
+impl From<&ColumnarSheet> for Spreadsheet {
+    fn from(sheet: &ColumnarSheet) -> Self {
+        sheet.to_spreadsheet()
+    }
+}
@@ -0,0 +1,227 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Lazy permutations and combinations of a slice
+//!
+//! Both iterators work on positions in the slice and yield references to its
+//! items, so duplicates in the slice are treated as distinct items. Results
+//! come in lexicographic order of those positions, which for a sorted slice
+//! is lexicographic order of the items themselves. The number of results
+//! is known up front, so `count` and `size_hint` answer immediately instead
+//! of enumerating; where it doesn't fit in a `usize`, `permutation_count` and
+//! `combination_count` report `None`.
+
+use std::convert::TryFrom;
+
+/// Number of ordered selections of `k` items out of `n`, `n! / (n - k)!`,
+/// or `None` if it overflows
+pub fn permutation_count(n: usize, k: usize) -> Option<usize> {
+    if k > n {
+        return Some(0);
+    }
+    (n - k + 1..=n).try_fold(1usize, |count, factor| count.checked_mul(factor))
+}
+
+/// Number of unordered selections of `k` items out of `n`, `n choose k`,
+/// or `None` if it overflows
+pub fn combination_count(n: usize, k: usize) -> Option<usize> {
+    if k > n {
+        return Some(0);
+    }
+    let k = k.min(n - k);
+    // Each partial product is itself a binomial coefficient, so the
+    // division is always exact. Multiplying two `usize`s can't overflow a
+    // `u128`, so only a count that doesn't fit afterwards gives `None`.
+    (0..k).try_fold(1usize, |count, i| {
+        let count = count as u128 * (n - i) as u128 / (i + 1) as u128;
+        usize::try_from(count).ok()
+    })
+}
+
+/// Yields every ordered selection of `k` items from `items`
+pub fn permutations<T>(items: &[T], k: usize) -> Permutations<'_, T> {
+    Permutations {
+        items,
+        indices: (0..items.len()).collect(),
+        k,
+        done: k > items.len(),
+        remaining: permutation_count(items.len(), k),
+    }
+}
+
+/// Yields every unordered selection of `k` items from `items`, each in the
+/// order the items appear in the slice
+pub fn combinations<T>(items: &[T], k: usize) -> Combinations<'_, T> {
+    Combinations {
+        items,
+        indices: (0..k).collect(),
+        done: k > items.len(),
+        remaining: combination_count(items.len(), k),
+    }
+}
+
+/// Iterator returned by `permutations`
+#[derive(Debug, Clone)]
+pub struct Permutations<'a, T> {
+    items: &'a [T],
+    // A permutation of every position; the first `k` are the next result
+    // and the rest are kept in ascending order
+    indices: Vec<usize>,
+    k: usize,
+    done: bool,
+    // `None` when the count overflows a `usize`
+    remaining: Option<usize>,
+}
+
+impl<'a, T> Iterator for Permutations<'a, T> {
+    type Item = Vec<&'a T>;
+
+    fn next(&mut self) -> Option<Vec<&'a T>> {
+        if self.done {
+            return None;
+        }
+        let result = self.indices[..self.k]
+            .iter()
+            .map(|&index| &self.items[index])
+            .collect();
+
+        // Reversing the unused tail makes the whole array the last
+        // arrangement with this prefix, so the next full permutation is the
+        // next k-permutation
+        self.indices[self.k..].reverse();
+        self.done = !next_permutation(&mut self.indices);
+        self.remaining = self.remaining.map(|remaining| remaining - 1);
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.remaining {
+            Some(remaining) => (remaining, Some(remaining)),
+            None => (usize::MAX, None),
+        }
+    }
+
+    /// Panics if the count overflows a `usize`
+    fn count(self) -> usize {
+        self.remaining.expect("permutation count overflows usize")
+    }
+}
+
+/// Iterator returned by `combinations`
+#[derive(Debug, Clone)]
+pub struct Combinations<'a, T> {
+    items: &'a [T],
+    // Ascending positions of the next result
+    indices: Vec<usize>,
+    done: bool,
+    remaining: Option<usize>,
+}
+
+impl<'a, T> Iterator for Combinations<'a, T> {
+    type Item = Vec<&'a T>;
+
+    fn next(&mut self) -> Option<Vec<&'a T>> {
+        if self.done {
+            return None;
+        }
+        let result = self
+            .indices
+            .iter()
+            .map(|&index| &self.items[index])
+            .collect();
+
+        // Bump the rightmost position that still has room to move, then pack
+        // the ones after it directly behind it
+        let n = self.items.len();
+        let k = self.indices.len();
+        match (0..k).rev().find(|&i| self.indices[i] < n - k + i) {
+            Some(i) => {
+                self.indices[i] += 1;
+                for j in i + 1..k {
+                    self.indices[j] = self.indices[j - 1] + 1;
+                }
+            }
+            None => self.done = true,
+        }
+        self.remaining = self.remaining.map(|remaining| remaining - 1);
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.remaining {
+            Some(remaining) => (remaining, Some(remaining)),
+            None => (usize::MAX, None),
+        }
+    }
+
+    /// Panics if the count overflows a `usize`
+    fn count(self) -> usize {
+        self.remaining.expect("combination count overflows usize")
+    }
+}
+
+// Rearranges `values` into the next permutation in lexicographic order,
+// returning `false` if it was already the last
+fn next_permutation(values: &mut [usize]) -> bool {
+    let pivot = match (1..values.len()).rev().find(|&i| values[i - 1] < values[i]) {
+        Some(i) => i - 1,
+        None => return false,
+    };
+    let successor = (pivot + 1..values.len())
+        .rev()
+        .find(|&i| values[i] > values[pivot])
+        .expect("the pivot has a larger value after it");
+    values.swap(pivot, successor);
+    values[pivot + 1..].reverse();
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn combination_counts() {
+        assert_eq!(combination_count(5, 2), Some(10));
+        assert_eq!(combination_count(5, 0), Some(1));
+        assert_eq!(combination_count(5, 5), Some(1));
+        assert_eq!(combination_count(3, 4), Some(0));
+        assert_eq!(combination_count(0, 0), Some(1));
+    }
+
+    // The partial products of C(67, 33) pass u64::MAX even though the
+    // count itself doesn't
+    #[cfg(target_pointer_width = "64")]
+    #[test]
+    fn large_counts_that_fit_are_found() {
+        assert_eq!(combination_count(67, 33), Some(14_226_520_737_620_288_370));
+        assert_eq!(combination_count(68, 34), None);
+        assert_eq!(combination_count(usize::MAX, 1), Some(usize::MAX));
+        assert_eq!(
+            combination_count(usize::MAX, usize::MAX - 1),
+            Some(usize::MAX)
+        );
+        assert_eq!(combination_count(usize::MAX, 2), None);
+
+        let items: Vec<usize> = (0..67).collect();
+        let combinations = combinations(&items, 33);
+        assert_eq!(combinations.size_hint().0, 14_226_520_737_620_288_370);
+    }
+
+    #[test]
+    fn counts_match_enumeration() {
+        let items: Vec<u32> = (0..8).collect();
+        for k in 0..=9 {
+            assert_eq!(
+                Some(combinations(&items, k).count()),
+                combination_count(8, k),
+                "k = {}",
+                k
+            );
+            assert_eq!(
+                Some(combinations(&items, k).fold(0, |n, _| n + 1)),
+                combination_count(8, k)
+            );
+        }
+    }
+}
@@ -0,0 +1,200 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! An eight-byte stand-in for `SpreadsheetCell`
+//!
+//! A `SpreadsheetCell` is as large as its largest variant, the 24-byte
+//! `String`, so every integer or empty cell spends 24 bytes on at most
+//! eight bytes of value. A `CompactCell` is a tag byte and a 32-bit payload,
+//! a third of the size: integers are stored in the payload itself, while
+//! floats and text are stored in a `CellTables` that the payload indexes
+//! into. A float cell then costs 16 bytes in all, and a text cell 16 plus
+//! its text, which goes into one shared `TextArena` rather than a `String`
+//! of its own.
+//!
+//! A `CompactCell` only means something next to the `CellTables` it was
+//! made with, so the methods that read floats or text take the tables too.
+
+use crate::deep_size::DeepSize;
+use crate::spreadsheet::SpreadsheetCell;
+use crate::text_arena::{TextArena, TextRef};
+use std::convert::TryFrom;
+use std::mem;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Tag {
+    Empty,
+    Int,
+    Float,
+    Text,
+}
+
+/// A cell as a tag and a payload, with floats and text kept in `CellTables`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct CompactCell {
+    tag: Tag,
+    // The bits of an `Int`, or an index into the tables
+    payload: u32,
+}
+
+// The whole point of the type
+const _: () = assert!(mem::size_of::<CompactCell>() == 8);
+const _: () = assert!(mem::size_of::<CompactCell>() * 3 <= mem::size_of::<SpreadsheetCell>());
+
+impl CompactCell {
+    pub const EMPTY: CompactCell = CompactCell {
+        tag: Tag::Empty,
+        payload: 0,
+    };
+
+    pub fn int(int: i32) -> CompactCell {
+        CompactCell {
+            tag: Tag::Int,
+            payload: int as u32,
+        }
+    }
+
+    /// Stores `cell` compactly, putting any float or text into `tables`
+    ///
+    /// Panics if `tables` already holds `u32::MAX` floats or strings.
+    pub fn from_cell(cell: &SpreadsheetCell, tables: &mut CellTables) -> CompactCell {
+        match cell {
+            SpreadsheetCell::Int(int) => CompactCell::int(*int),
+            SpreadsheetCell::Float(float) => tables.push_float(*float),
+            SpreadsheetCell::Text(text) => tables.push_text(text),
+            SpreadsheetCell::Empty => CompactCell::EMPTY,
+        }
+    }
+
+    /// The `SpreadsheetCell` this stands for, given the tables it was made
+    /// with
+    ///
+    /// Panics if the cell points past the end of `tables`.
+    pub fn to_cell(self, tables: &CellTables) -> SpreadsheetCell {
+        match self.tag {
+            Tag::Empty => SpreadsheetCell::Empty,
+            Tag::Int => SpreadsheetCell::Int(self.payload as i32),
+            Tag::Float => SpreadsheetCell::Float(tables.floats[self.index()]),
+            Tag::Text => SpreadsheetCell::Text(tables.text_at(self.index()).to_string()),
+        }
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.tag == Tag::Empty
+    }
+
+    pub fn as_int(self) -> Option<i32> {
+        match self.tag {
+            Tag::Int => Some(self.payload as i32),
+            _ => None,
+        }
+    }
+
+    pub fn as_float(self, tables: &CellTables) -> Option<f64> {
+        match self.tag {
+            Tag::Float => tables.floats.get(self.index()).copied(),
+            _ => None,
+        }
+    }
+
+    pub fn as_text(self, tables: &CellTables) -> Option<&str> {
+        match self.tag {
+            Tag::Text => Some(tables.text_at(self.index())),
+            _ => None,
+        }
+    }
+
+    fn index(self) -> usize {
+        self.payload as usize
+    }
+}
+
+impl Default for CompactCell {
+    fn default() -> Self {
+        CompactCell::EMPTY
+    }
+}
+
+/// The floats and text that `CompactCell`s refer to
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CellTables {
+    floats: Vec<f64>,
+    texts: Vec<TextRef>,
+    arena: TextArena,
+}
+
+impl CellTables {
+    pub fn new() -> Self {
+        CellTables {
+            floats: Vec::new(),
+            texts: Vec::new(),
+            arena: TextArena::new(),
+        }
+    }
+
+    fn push_float(&mut self, float: f64) -> CompactCell {
+        let payload = next_index(self.floats.len());
+        self.floats.push(float);
+        CompactCell {
+            tag: Tag::Float,
+            payload,
+        }
+    }
+
+    fn push_text(&mut self, text: &str) -> CompactCell {
+        let payload = next_index(self.texts.len());
+        self.texts.push(self.arena.push(text));
+        CompactCell {
+            tag: Tag::Text,
+            payload,
+        }
+    }
+
+    fn text_at(&self, index: usize) -> &str {
+        self.arena.get(self.texts[index])
+    }
+
+    /// Number of floats stored
+    pub fn float_count(&self) -> usize {
+        self.floats.len()
+    }
+
+    /// Number of strings stored
+    pub fn text_count(&self) -> usize {
+        self.texts.len()
+    }
+}
+
+fn next_index(len: usize) -> u32 {
+    u32::try_from(len).expect("too many cells for a CellTables")
+}
+
+/// Compacts every cell of `rows`, returning the cells and their tables
+pub fn compact_rows<'a, I, R>(rows: I) -> (Vec<Vec<CompactCell>>, CellTables)
+where
+    I: IntoIterator<Item = R>,
+    R: IntoIterator<Item = &'a SpreadsheetCell>,
+{
+    let mut tables = CellTables::new();
+    let rows = rows
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|cell| CompactCell::from_cell(cell, &mut tables))
+                .collect()
+        })
+        .collect();
+    (rows, tables)
+}
+
+impl DeepSize for CompactCell {
+    fn heap_bytes(&self) -> usize {
+        0
+    }
+}
+
+impl DeepSize for CellTables {
+    fn heap_bytes(&self) -> usize {
+        self.floats.heap_bytes() + self.texts.heap_bytes() + self.arena.heap_bytes()
+    }
+}
@@ -0,0 +1,237 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Measured timings for choosing between map types
+//!
+//! `comparison` fills each map with the same keys and times inserting them,
+//! looking every one of them up and iterating over the whole map. The
+//! results come back as `Measurement` values rather than printed text, so
+//! they can be sorted, filtered or written out by the caller. The
+//! `containers` bench prints them as a table.
+//!
+//! The timings are wall-clock averages from a single run, so run them in
+//! release mode on a quiet machine, and compare numbers from the same run.
+
+use crate::probe_map::ProbeMap;
+use crate::random::{Rng, XorShift64};
+use crate::robin_hood_map::RobinHoodMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hint::black_box;
+use std::time::Instant;
+
+/// A map type covered by `comparison`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Container {
+    HashMap,
+    BTreeMap,
+    ProbeMap,
+    RobinHoodMap,
+}
+
+impl Container {
+    pub const ALL: [Container; 4] = [
+        Container::HashMap,
+        Container::BTreeMap,
+        Container::ProbeMap,
+        Container::RobinHoodMap,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Container::HashMap => "HashMap",
+            Container::BTreeMap => "BTreeMap",
+            Container::ProbeMap => "ProbeMap",
+            Container::RobinHoodMap => "RobinHoodMap",
+        }
+    }
+}
+
+impl fmt::Display for Container {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// How the `u64` keys fed to each map are spread out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum KeyDistribution {
+    /// `0, 1, 2, ...`
+    Sequential,
+    /// Pseudo-random keys over the whole `u64` range
+    Uniform,
+    /// Runs of 64 consecutive keys starting at pseudo-random points
+    Clustered,
+}
+
+impl KeyDistribution {
+    pub const ALL: [KeyDistribution; 3] = [
+        KeyDistribution::Sequential,
+        KeyDistribution::Uniform,
+        KeyDistribution::Clustered,
+    ];
+
+    /// Generates `count` keys, the same ones every time for the same `seed`
+    pub fn keys(self, count: usize, seed: u64) -> Vec<u64> {
+        let mut rng = XorShift64::new(seed);
+        match self {
+            KeyDistribution::Sequential => (0..count as u64).collect(),
+            KeyDistribution::Uniform => (0..count).map(|_| rng.next_u64()).collect(),
+            KeyDistribution::Clustered => {
+                let mut keys = Vec::with_capacity(count);
+                while keys.len() < count {
+                    let base = rng.next_u64() >> 8;
+                    let run = (count - keys.len()).min(CLUSTER_LEN);
+                    keys.extend(base..base + run as u64);
+                }
+                keys
+            }
+        }
+    }
+}
+
+const CLUSTER_LEN: usize = 64;
+
+impl fmt::Display for KeyDistribution {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            KeyDistribution::Sequential => "sequential",
+            KeyDistribution::Uniform => "uniform",
+            KeyDistribution::Clustered => "clustered",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What a `Measurement` timed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Operation {
+    /// Inserting every key into an empty map
+    Insert,
+    /// Looking up every key that was inserted
+    Lookup,
+    /// Visiting every entry once
+    Iterate,
+}
+
+impl Operation {
+    pub const ALL: [Operation; 3] = [Operation::Insert, Operation::Lookup, Operation::Iterate];
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Operation::Insert => "insert",
+            Operation::Lookup => "lookup",
+            Operation::Iterate => "iterate",
+        };
+        f.write_str(name)
+    }
+}
+
+/// The average time one operation took on one container
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Measurement {
+    pub container: Container,
+    pub distribution: KeyDistribution,
+    pub operation: Operation,
+    pub entries: usize,
+    /// Mean nanoseconds per key inserted, looked up or visited
+    pub nanos_per_op: f64,
+}
+
+impl fmt::Display for Measurement {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {} {} x{}: {:.1} ns",
+            self.container, self.distribution, self.operation, self.entries, self.nanos_per_op
+        )
+    }
+}
+
+/// Times every operation on every container for every key distribution,
+/// with `entries` keys each
+///
+/// Returns one `Measurement` per combination, grouped by distribution, then
+/// container, then operation. Panics if `entries` is zero.
+pub fn comparison(entries: usize) -> Vec<Measurement> {
+    assert!(entries > 0, "comparison needs at least one entry");
+    let mut measurements = Vec::new();
+    for &distribution in KeyDistribution::ALL.iter() {
+        let keys = distribution.keys(entries, SEED);
+        for &container in Container::ALL.iter() {
+            let timings = match container {
+                Container::HashMap => time_map::<HashMap<u64, u64>>(&keys),
+                Container::BTreeMap => time_map::<BTreeMap<u64, u64>>(&keys),
+                Container::ProbeMap => time_map::<ProbeMap<u64, u64>>(&keys),
+                Container::RobinHoodMap => time_map::<RobinHoodMap<u64, u64>>(&keys),
+            };
+            for (&operation, &nanos_per_op) in Operation::ALL.iter().zip(timings.iter()) {
+                measurements.push(Measurement {
+                    container,
+                    distribution,
+                    operation,
+                    entries,
+                    nanos_per_op,
+                });
+            }
+        }
+    }
+    measurements
+}
+
+const SEED: u64 = 0x5EED;
+
+// The operations `comparison` needs from each map
+trait TimedMap: Default {
+    fn insert(&mut self, key: u64, value: u64);
+    fn get(&self, key: &u64) -> Option<&u64>;
+    fn sum_values(&self) -> u64;
+}
+
+macro_rules! impl_timed_map {
+    ($($map:ident),+) => {
+        $(
+            impl TimedMap for $map<u64, u64> {
+                fn insert(&mut self, key: u64, value: u64) {
+                    $map::insert(self, key, value);
+                }
+
+                fn get(&self, key: &u64) -> Option<&u64> {
+                    $map::get(self, key)
+                }
+
+                fn sum_values(&self) -> u64 {
+                    self.iter().fold(0, |sum, (_, value)| sum.wrapping_add(*value))
+                }
+            }
+        )+
+    };
+}
+
+impl_timed_map!(HashMap, BTreeMap, ProbeMap, RobinHoodMap);
+
+// Nanoseconds per key for each of `Operation::ALL`, in order
+fn time_map<M: TimedMap>(keys: &[u64]) -> [f64; 3] {
+    let per_key = |start: Instant| start.elapsed().as_nanos() as f64 / keys.len() as f64;
+
+    let start = Instant::now();
+    let mut map = M::default();
+    for (value, &key) in keys.iter().enumerate() {
+        map.insert(key, value as u64);
+    }
+    let insert = per_key(start);
+
+    let start = Instant::now();
+    for key in keys {
+        black_box(map.get(key));
+    }
+    let lookup = per_key(start);
+
+    let start = Instant::now();
+    black_box(map.sum_values());
+    let iterate = per_key(start);
+
+    [insert, lookup, iterate]
+}
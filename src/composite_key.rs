@@ -0,0 +1,108 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A map keyed on tuples whose keys are normalized on the way in
+//!
+//! With keys like `(String, u32)`, a lookup for `("Alice", 3)` silently misses
+//! an entry stored as `("alice", 3)`. `CompositeKeyMap` runs every key through
+//! a `KeyNorm` on both insert and lookup, so equivalent keys always meet.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Rewrites a key into its canonical form
+pub trait KeyNorm<K> {
+    fn normalize(&self, key: K) -> K;
+}
+
+/// Leaves every key as it is
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Identity;
+
+impl<K> KeyNorm<K> for Identity {
+    fn normalize(&self, key: K) -> K {
+        key
+    }
+}
+
+/// Lowercases the string part of a `(String, T)` key
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LowercaseText;
+
+impl<T> KeyNorm<(String, T)> for LowercaseText {
+    fn normalize(&self, (text, rest): (String, T)) -> (String, T) {
+        (text.to_lowercase(), rest)
+    }
+}
+
+// Any plain function or closure over keys works as a normalizer too
+impl<K, F: Fn(K) -> K> KeyNorm<K> for F {
+    fn normalize(&self, key: K) -> K {
+        self(key)
+    }
+}
+
+/// A `HashMap` that normalizes every key with `N` before using it
+#[derive(Debug, Clone)]
+pub struct CompositeKeyMap<K, V, N = LowercaseText> {
+    map: HashMap<K, V>,
+    norm: N,
+}
+
+impl<K: Eq + Hash, V, N: KeyNorm<K> + Default> CompositeKeyMap<K, V, N> {
+    pub fn new() -> Self {
+        CompositeKeyMap::with_norm(N::default())
+    }
+}
+
+impl<K: Eq + Hash, V, N: KeyNorm<K> + Default> Default for CompositeKeyMap<K, V, N> {
+    fn default() -> Self {
+        CompositeKeyMap::new()
+    }
+}
+
+impl<K: Eq + Hash, V, N: KeyNorm<K>> CompositeKeyMap<K, V, N> {
+    /// Creates an empty map that normalizes keys with `norm`
+    pub fn with_norm(norm: N) -> Self {
+        CompositeKeyMap {
+            map: HashMap::new(),
+            norm,
+        }
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let key = self.norm.normalize(key);
+        self.map.insert(key, value)
+    }
+
+    pub fn get(&self, key: K) -> Option<&V> {
+        self.map.get(&self.norm.normalize(key))
+    }
+
+    pub fn get_mut(&mut self, key: K) -> Option<&mut V> {
+        let key = self.norm.normalize(key);
+        self.map.get_mut(&key)
+    }
+
+    pub fn contains_key(&self, key: K) -> bool {
+        self.map.contains_key(&self.norm.normalize(key))
+    }
+
+    pub fn remove(&mut self, key: K) -> Option<V> {
+        let key = self.norm.normalize(key);
+        self.map.remove(&key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterates over the entries, with keys in normalized form
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map.iter()
+    }
+}
@@ -0,0 +1,135 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A Count-Min Sketch for approximate frequency counting
+//!
+//! Counting events exactly needs an entry per distinct item, which stops
+//! fitting in memory once there are billions of them. A Count-Min Sketch keeps
+//! a fixed grid of counters instead: `depth` rows of `width` counters, with
+//! each item hashed to one counter per row. An estimate is the smallest of an
+//! item's counters, so it can overcount because of collisions but never
+//! undercounts.
+//!
+//! With `width = ceil(e / epsilon)` and `depth = ceil(ln(1 / delta))`, an
+//! estimate exceeds the true count by more than `epsilon * total()` with
+//! probability at most `delta`, which is what `with_error` computes.
+
+use crate::fast_hash::FastHasher;
+use std::error::Error;
+use std::f64::consts::E;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+
+/// Returned by `merge` when the two sketches have different dimensions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SketchMismatch;
+
+impl fmt::Display for SketchMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "sketches have different widths or depths")
+    }
+}
+
+impl Error for SketchMismatch {}
+
+/// An approximate counter with fixed memory use
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CountMinSketch {
+    // `depth` rows of `width` counters, stored row after row
+    counters: Vec<u64>,
+    width: usize,
+    depth: usize,
+    total: u64,
+}
+
+impl CountMinSketch {
+    /// Creates a sketch with `depth` rows of `width` counters, each raised to
+    /// at least one
+    pub fn new(width: usize, depth: usize) -> Self {
+        let (width, depth) = (width.max(1), depth.max(1));
+        CountMinSketch {
+            counters: vec![0; width * depth],
+            width,
+            depth,
+            total: 0,
+        }
+    }
+
+    /// Sizes a sketch so that estimates are within `epsilon * total()` of the
+    /// true count with probability at least `1 - delta`
+    ///
+    /// Panics if either argument is not strictly between 0 and 1.
+    pub fn with_error(epsilon: f64, delta: f64) -> Self {
+        assert!(
+            epsilon > 0.0 && epsilon < 1.0,
+            "epsilon must be between 0 and 1"
+        );
+        assert!(delta > 0.0 && delta < 1.0, "delta must be between 0 and 1");
+        let width = (E / epsilon).ceil() as usize;
+        let depth = (1.0 / delta).ln().ceil() as usize;
+        CountMinSketch::new(width, depth)
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// Sum of every count added so far
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// Counts one occurrence of `item`
+    pub fn add<T: Hash + ?Sized>(&mut self, item: &T) {
+        self.add_n(item, 1);
+    }
+
+    /// Counts `n` occurrences of `item`
+    pub fn add_n<T: Hash + ?Sized>(&mut self, item: &T, n: u64) {
+        for row in 0..self.depth {
+            let index = self.index(row, item);
+            self.counters[index] = self.counters[index].saturating_add(n);
+        }
+        self.total = self.total.saturating_add(n);
+    }
+
+    /// Returns an upper bound on how many times `item` was added
+    pub fn estimate<T: Hash + ?Sized>(&self, item: &T) -> u64 {
+        (0..self.depth)
+            .map(|row| self.counters[self.index(row, item)])
+            .min()
+            .unwrap_or(0)
+    }
+
+    /// Adds the counts from `other`, as if its items had been added here
+    pub fn merge(&mut self, other: &CountMinSketch) -> Result<(), SketchMismatch> {
+        if self.width != other.width || self.depth != other.depth {
+            return Err(SketchMismatch);
+        }
+        for (counter, other) in self.counters.iter_mut().zip(&other.counters) {
+            *counter = counter.saturating_add(*other);
+        }
+        self.total = self.total.saturating_add(other.total);
+        Ok(())
+    }
+
+    /// Resets every counter to zero
+    pub fn clear(&mut self) {
+        for counter in self.counters.iter_mut() {
+            *counter = 0;
+        }
+        self.total = 0;
+    }
+
+    // Each row salts the hash with its own number, giving independent hashes
+    fn index<T: Hash + ?Sized>(&self, row: usize, item: &T) -> usize {
+        let mut hasher = FastHasher::default();
+        hasher.write_usize(row);
+        item.hash(&mut hasher);
+        row * self.width + (hasher.finish() % self.width as u64) as usize
+    }
+}
@@ -0,0 +1,239 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A multiset that counts how many times each item has been seen, in the
+//! spirit of Python's `collections.Counter`
+//!
+//! Counts live in a `PrehashedMap`, so an update that has to both find and
+//! remove an item hashes it only once.
+
+use crate::deep_size::DeepSize;
+use crate::prehashed::PrehashedMap;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::iter::FromIterator;
+use std::ops::{Add, AddAssign, Sub, SubAssign};
+
+/// Counts occurrences of hashable items
+///
+/// Items whose count drops to zero are removed, so every stored count is at
+/// least one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Counter<T: Eq + Hash> {
+    counts: PrehashedMap<T, usize>,
+}
+
+impl<T: Eq + Hash> Counter<T> {
+    pub fn new() -> Self {
+        Counter {
+            counts: PrehashedMap::new(),
+        }
+    }
+
+    /// Counts one more occurrence of `item`
+    ///
+    /// Where `std::ops::Add` is in scope, `counter.add(item)` on an owned
+    /// counter means `counter + item` and won't compile, so call `add_n`
+    /// or `Counter::add(&mut counter, item)` there.
+    pub fn add(&mut self, item: T) {
+        self.add_n(item, 1);
+    }
+
+    /// Counts `n` more occurrences of `item`
+    pub fn add_n(&mut self, item: T, n: usize) {
+        if n > 0 {
+            *self.counts.get_or_insert_with(item, || 0) += n;
+        }
+    }
+
+    /// Counts one fewer occurrence of `item`, never going below zero
+    pub fn subtract(&mut self, item: &T) {
+        self.subtract_n(item, 1);
+    }
+
+    /// Counts `n` fewer occurrences of `item`, never going below zero
+    pub fn subtract_n(&mut self, item: &T, n: usize) {
+        let hash = self.counts.hash_key(item);
+        if let Some(count) = self.counts.get_mut_with_hash(hash, item) {
+            *count = count.saturating_sub(n);
+            if *count == 0 {
+                self.counts.remove_with_hash(hash, item);
+            }
+        }
+    }
+
+    /// Returns the count for `item`, which is zero if it was never seen
+    pub fn get(&self, item: &T) -> usize {
+        self.counts.get(item).copied().unwrap_or(0)
+    }
+
+    /// Forgets `item` entirely, returning its old count
+    pub fn remove(&mut self, item: &T) -> usize {
+        self.counts.remove(item).unwrap_or(0)
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.counts.contains_key(item)
+    }
+
+    /// Returns the number of distinct items
+    pub fn len(&self) -> usize {
+        self.counts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty()
+    }
+
+    /// Returns the sum of all counts
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Returns the `n` most common items with their counts, most common first
+    ///
+    /// Items with equal counts come out in arbitrary order.
+    pub fn most_common(&self, n: usize) -> Vec<(&T, usize)> {
+        let mut items: Vec<(&T, usize)> = self.iter().collect();
+        items.sort_by_key(|&(_, count)| Reverse(count));
+        items.truncate(n);
+        items
+    }
+
+    /// Iterates over `(item, count)` pairs in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (&T, usize)> {
+        self.counts.iter().map(|(item, count)| (item, *count))
+    }
+
+    pub fn clear(&mut self) {
+        self.counts.clear();
+    }
+
+    pub fn into_map(self) -> HashMap<T, usize> {
+        self.counts.into_iter().collect()
+    }
+}
+
+impl<T: Eq + Hash> Default for Counter<T> {
+    fn default() -> Self {
+        Counter::new()
+    }
+}
+
+impl<T: Eq + Hash + DeepSize> DeepSize for Counter<T> {
+    fn heap_bytes(&self) -> usize {
+        self.counts.heap_bytes()
+    }
+}
+
+impl<T: Eq + Hash> FromIterator<T> for Counter<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut counter = Counter::new();
+        counter.extend(iter);
+        counter
+    }
+}
+
+// Extending counts every item once more
+impl<T: Eq + Hash> Extend<T> for Counter<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.add(item);
+        }
+    }
+}
+
+// Adding one counter to another sums the counts of every item
+impl<T: Eq + Hash> Add for Counter<T> {
+    type Output = Counter<T>;
+
+    fn add(mut self, other: Counter<T>) -> Counter<T> {
+        self += other;
+        self
+    }
+}
+
+impl<T: Eq + Hash + Clone> Add<&Counter<T>> for Counter<T> {
+    type Output = Counter<T>;
+
+    fn add(mut self, other: &Counter<T>) -> Counter<T> {
+        self += other;
+        self
+    }
+}
+
+impl<T: Eq + Hash> AddAssign for Counter<T> {
+    fn add_assign(&mut self, other: Counter<T>) {
+        for (item, count) in other.counts {
+            self.add_n(item, count);
+        }
+    }
+}
+
+impl<T: Eq + Hash + Clone> AddAssign<&Counter<T>> for Counter<T> {
+    fn add_assign(&mut self, other: &Counter<T>) {
+        for (item, count) in other.counts.iter() {
+            self.add_n(item.clone(), *count);
+        }
+    }
+}
+
+// Subtracting keeps only the items that still have a positive count
+impl<T: Eq + Hash> Sub for Counter<T> {
+    type Output = Counter<T>;
+
+    fn sub(mut self, other: Counter<T>) -> Counter<T> {
+        self -= &other;
+        self
+    }
+}
+
+impl<T: Eq + Hash> Sub<&Counter<T>> for Counter<T> {
+    type Output = Counter<T>;
+
+    fn sub(mut self, other: &Counter<T>) -> Counter<T> {
+        self -= other;
+        self
+    }
+}
+
+impl<T: Eq + Hash> SubAssign for Counter<T> {
+    fn sub_assign(&mut self, other: Counter<T>) {
+        *self -= &other;
+    }
+}
+
+impl<T: Eq + Hash> SubAssign<&Counter<T>> for Counter<T> {
+    fn sub_assign(&mut self, other: &Counter<T>) {
+        for (item, count) in other.counts.iter() {
+            self.subtract_n(item, *count);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counter(items: &str) -> Counter<char> {
+        items.chars().collect()
+    }
+
+    #[test]
+    fn counters_add_and_subtract() {
+        let mut sum = counter("aab");
+        Counter::add(&mut sum, 'c');
+        let sum = sum + counter("abd");
+        assert_eq!(sum, counter("aaabbcd"));
+        let mut sum = sum - counter("aaaab");
+        assert_eq!(sum, counter("bcd"));
+        assert!(!sum.contains(&'a'));
+        let other = counter("dd");
+        assert_eq!(sum.clone() + &other - &other, sum);
+        sum += counter("b");
+        sum -= &counter("cd");
+        assert_eq!(sum, counter("bb"));
+        assert_eq!(sum.total(), 2);
+    }
+}
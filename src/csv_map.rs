@@ -0,0 +1,260 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Loading two columns of a CSV file into a `HashMap`
+//!
+//! The file's first row names the columns. One column supplies the keys and
+//! another the values, which are parsed into `SpreadsheetCell`s so numbers
+//! come out as numbers. Fields follow the usual CSV rules: they're separated
+//! by commas, and a field wrapped in double quotes may contain commas,
+//! newlines and doubled `""` quotes.
+
+#[cfg(feature = "bump")]
+use crate::bump::Bump;
+use crate::spreadsheet::SpreadsheetCell;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// What `load_csv_as_map` does when a key appears on more than one row
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DuplicateKeys {
+    KeepFirst,
+    KeepLast,
+    Error,
+}
+
+/// Why a CSV file couldn't be loaded
+#[derive(Debug)]
+pub enum CsvError {
+    Io(io::Error),
+    /// The file has no header row
+    Empty,
+    /// The header row has no column with this name
+    MissingColumn(String),
+    /// A quoted field was never closed
+    UnterminatedQuote {
+        line: usize,
+    },
+    /// A row ends before reaching a requested column
+    ShortRow {
+        line: usize,
+    },
+    /// A key repeated under `DuplicateKeys::Error`
+    DuplicateKey {
+        key: String,
+        line: usize,
+    },
+}
+
+impl fmt::Display for CsvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CsvError::Io(err) => write!(f, "could not read CSV file: {}", err),
+            CsvError::Empty => write!(f, "CSV file has no header row"),
+            CsvError::MissingColumn(name) => write!(f, "CSV file has no column '{}'", name),
+            CsvError::UnterminatedQuote { line } => {
+                write!(f, "quoted field starting on line {} is never closed", line)
+            }
+            CsvError::ShortRow { line } => write!(f, "row on line {} is missing columns", line),
+            CsvError::DuplicateKey { key, line } => {
+                write!(f, "key '{}' on line {} was already loaded", key, line)
+            }
+        }
+    }
+}
+
+impl Error for CsvError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CsvError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for CsvError {
+    fn from(err: io::Error) -> Self {
+        CsvError::Io(err)
+    }
+}
+
+/// Reads the CSV file at `path` into a map from each row's `key_column` to
+/// its `value_column`
+pub fn load_csv_as_map<P: AsRef<Path>>(
+    path: P,
+    key_column: &str,
+    value_column: &str,
+    duplicates: DuplicateKeys,
+) -> Result<HashMap<String, SpreadsheetCell>, CsvError> {
+    let text = fs::read_to_string(path)?;
+    csv_to_map(&text, key_column, value_column, duplicates)
+}
+
+/// Like `load_csv_as_map`, but parses CSV text that is already in memory
+pub fn csv_to_map(
+    text: &str,
+    key_column: &str,
+    value_column: &str,
+    duplicates: DuplicateKeys,
+) -> Result<HashMap<String, SpreadsheetCell>, CsvError> {
+    let mut rows = parse_csv(text)?.into_iter();
+    let (_, header) = rows.next().ok_or(CsvError::Empty)?;
+    let column = |name: &str| {
+        header
+            .iter()
+            .position(|field| field == name)
+            .ok_or_else(|| CsvError::MissingColumn(name.to_string()))
+    };
+    let key_index = column(key_column)?;
+    let value_index = column(value_column)?;
+
+    let mut map = HashMap::new();
+    for (line, mut row) in rows {
+        if row.len() <= key_index.max(value_index) {
+            return Err(CsvError::ShortRow { line });
+        }
+        let value = SpreadsheetCell::infer(&row[value_index]);
+        let key = row.swap_remove(key_index);
+
+        if map.contains_key(&key) {
+            match duplicates {
+                DuplicateKeys::KeepFirst => continue,
+                DuplicateKeys::KeepLast => {}
+                DuplicateKeys::Error => return Err(CsvError::DuplicateKey { key, line }),
+            }
+        }
+        map.insert(key, value);
+    }
+    Ok(map)
+}
+
+/// Splits CSV text into rows of fields, pairing each row with the line it
+/// starts on
+///
+/// Blank lines are skipped. Both `\n` and `\r\n` line endings are accepted.
+pub fn parse_csv(text: &str) -> Result<Vec<(usize, Vec<String>)>, CsvError> {
+    let mut rows = Vec::new();
+    split_csv(text, |line, row| {
+        rows.push((line, row.fields().map(str::to_string).collect()));
+    })?;
+    Ok(rows)
+}
+
+/// Like `parse_csv`, but puts the fields and rows in `bump`
+///
+/// Apart from the outer `Vec` and a few buffers reused for every row,
+/// nothing is allocated on the heap, so parsing a batch makes a handful of
+/// allocations rather than one per field.
+#[cfg(feature = "bump")]
+pub fn parse_csv_in<'b>(
+    text: &str,
+    bump: &'b Bump,
+) -> Result<Vec<(usize, &'b [&'b str])>, CsvError> {
+    let mut rows = Vec::new();
+    let mut fields = Vec::new();
+    split_csv(text, |line, row| {
+        fields.clear();
+        fields.extend(row.fields().map(|field| bump.alloc_str(field)));
+        rows.push((line, &*bump.alloc_slice_copy(&fields)));
+    })?;
+    Ok(rows)
+}
+
+// The fields of one row, one after another in a single buffer that is
+// reused for every row
+#[derive(Default)]
+struct RowBuffer {
+    text: String,
+    ends: Vec<usize>,
+}
+
+impl RowBuffer {
+    fn end_field(&mut self) {
+        self.ends.push(self.text.len());
+    }
+
+    fn fields(&self) -> impl Iterator<Item = &str> {
+        let starts = std::iter::once(0).chain(self.ends.iter().copied());
+        starts
+            .zip(&self.ends)
+            .map(move |(start, &end)| &self.text[start..end])
+    }
+
+    fn clear(&mut self) {
+        self.text.clear();
+        self.ends.clear();
+    }
+}
+
+// Calls `emit` with each row and the line it starts on
+fn split_csv<F>(text: &str, mut emit: F) -> Result<(), CsvError>
+where
+    F: FnMut(usize, &RowBuffer),
+{
+    let mut row = RowBuffer::default();
+    let mut in_quotes = false;
+    // Whether the current row has any content, so blank lines can be dropped
+    let mut started = false;
+    let mut line = 1;
+    let mut row_line = 1;
+    let mut quote_line = 1;
+
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    chars.next();
+                    row.text.push('"');
+                }
+                '"' => in_quotes = false,
+                '\n' => {
+                    line += 1;
+                    row.text.push(c);
+                }
+                _ => row.text.push(c),
+            }
+            continue;
+        }
+
+        match c {
+            '"' => {
+                in_quotes = true;
+                started = true;
+                quote_line = line;
+            }
+            ',' => {
+                row.end_field();
+                started = true;
+            }
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                if started {
+                    row.end_field();
+                    emit(row_line, &row);
+                    row.clear();
+                }
+                started = false;
+                line += 1;
+                row_line = line;
+            }
+            _ => {
+                row.text.push(c);
+                started = true;
+            }
+        }
+    }
+
+    if in_quotes {
+        return Err(CsvError::UnterminatedQuote { line: quote_line });
+    }
+    if started {
+        row.end_field();
+        emit(row_line, &row);
+    }
+    Ok(())
+}
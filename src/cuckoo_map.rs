@@ -0,0 +1,370 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A hash map using cuckoo hashing
+//!
+//! std's `HashMap` probes a run of nearby slots until it finds the key. Cuckoo
+//! hashing gives every key exactly two possible slots instead, one in each of
+//! two tables chosen by two independent hash functions, so a lookup reads at
+//! most two slots. Inserting into an occupied slot evicts its occupant, which
+//! moves to its other slot, possibly evicting someone in turn.
+//!
+//! Occasionally the evictions go round in a cycle and never settle. A chain of
+//! evictions that runs past `MAX_DISPLACEMENTS` is taken as a cycle, and the
+//! map picks new hash functions and rebuilds itself. `stats` reports how often
+//! that and ordinary displacement have happened.
+
+use crate::op_stats::{Op, OpCounters, OpStats};
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+
+/// How long an eviction chain may get before the map assumes it is a cycle
+pub const MAX_DISPLACEMENTS: usize = 64;
+
+// Above this fraction of used slots, cycles become common, so the map grows
+const MAX_LOAD: f64 = 0.5;
+
+const MIN_TABLE_LEN: usize = 4;
+
+/// Counters describing how much work a `CuckooMap` has done to place keys
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CuckooStats {
+    /// Entries evicted from their slot to make room for another
+    pub displacements: u64,
+    /// Times the map rebuilt itself, whether to grow or to escape a cycle
+    pub rehashes: u64,
+    /// Times an eviction chain passed `MAX_DISPLACEMENTS`
+    pub cycles: u64,
+    /// Longest eviction chain seen by a single insert
+    pub longest_chain: usize,
+}
+
+/// A hash map where every key lives in one of two slots
+#[derive(Debug, Clone)]
+pub struct CuckooMap<K, V> {
+    tables: [Vec<Option<(K, V)>>; 2],
+    hashers: [RandomState; 2],
+    len: usize,
+    stats: CuckooStats,
+    counters: OpCounters,
+}
+
+impl<K: Eq + Hash, V> CuckooMap<K, V> {
+    pub fn new() -> Self {
+        CuckooMap::with_capacity(0)
+    }
+
+    /// Creates a map that can hold `capacity` entries before growing
+    pub fn with_capacity(capacity: usize) -> Self {
+        let table_len = ((capacity as f64 / MAX_LOAD / 2.0).ceil() as usize).max(MIN_TABLE_LEN);
+        CuckooMap {
+            tables: [empty_table(table_len), empty_table(table_len)],
+            hashers: [RandomState::new(), RandomState::new()],
+            len: 0,
+            stats: CuckooStats::default(),
+            counters: OpCounters::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Total number of slots across both tables
+    pub fn slot_count(&self) -> usize {
+        self.tables[0].len() * 2
+    }
+
+    /// Fraction of slots currently holding an entry
+    pub fn load_factor(&self) -> f64 {
+        self.len as f64 / self.slot_count() as f64
+    }
+
+    pub fn stats(&self) -> CuckooStats {
+        self.stats
+    }
+
+    /// What the map has been asked to do, counted with the `instrument`
+    /// feature
+    pub fn op_stats(&self) -> OpStats {
+        self.counters.stats()
+    }
+
+    pub fn reset_op_stats(&mut self) {
+        self.counters.reset();
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.counters.record(Op::Insert);
+        if let Some(existing) = self.value_mut(&key) {
+            return Some(mem::replace(existing, value));
+        }
+
+        if (self.len + 1) as f64 > self.slot_count() as f64 * MAX_LOAD {
+            let table_len = self.tables[0].len() * 2;
+            self.rebuild(table_len, Vec::new());
+        }
+
+        if let Err(homeless) = self.place((key, value)) {
+            self.stats.cycles += 1;
+            let table_len = self.tables[0].len();
+            self.rebuild(table_len, vec![homeless]);
+        }
+        self.len += 1;
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counters.record(Op::Lookup);
+        (0..2).find_map(|table| match &self.tables[table][self.slot(table, key)] {
+            Some((k, v)) if k.borrow() == key => Some(v),
+            _ => None,
+        })
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counters.record(Op::Lookup);
+        self.value_mut(key)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counters.record(Op::Lookup);
+        self.find(key).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counters.record(Op::Removal);
+        let table = self.find(key)?;
+        let slot = self.slot(table, key);
+        self.len -= 1;
+        self.tables[table][slot].take().map(|(_, v)| v)
+    }
+
+    /// Iterates over every entry in slot order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.tables
+            .iter()
+            .flatten()
+            .filter_map(|slot| slot.as_ref().map(|(k, v)| (k, v)))
+    }
+
+    pub fn clear(&mut self) {
+        for table in self.tables.iter_mut() {
+            for slot in table.iter_mut() {
+                *slot = None;
+            }
+        }
+        self.len = 0;
+    }
+
+    fn value_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let table = self.find(key)?;
+        let slot = self.slot(table, key);
+        self.tables[table][slot].as_mut().map(|(_, v)| v)
+    }
+
+    fn slot<Q: Hash + ?Sized>(&self, table: usize, key: &Q) -> usize {
+        (self.hashers[table].hash_one(key) % self.tables[table].len() as u64) as usize
+    }
+
+    // Which table holds `key`, if either does
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        (0..2).find(|&table| {
+            self.tables[table][self.slot(table, key)]
+                .as_ref()
+                .is_some_and(|(k, _)| k.borrow() == key)
+        })
+    }
+
+    // Puts `entry` into one of its slots, evicting occupants as needed. If
+    // the chain of evictions runs too long, the entry left without a slot is
+    // handed back.
+    fn place(&mut self, mut entry: (K, V)) -> Result<(), (K, V)> {
+        for table in 0..2 {
+            let slot = self.slot(table, &entry.0);
+            if self.tables[table][slot].is_none() {
+                self.tables[table][slot] = Some(entry);
+                return Ok(());
+            }
+        }
+
+        let mut table = 0;
+        for chain in 1..=MAX_DISPLACEMENTS {
+            let slot = self.slot(table, &entry.0);
+            match self.tables[table][slot].replace(entry) {
+                None => return Ok(()),
+                Some(evicted) => {
+                    self.stats.displacements += 1;
+                    self.stats.longest_chain = self.stats.longest_chain.max(chain);
+                    entry = evicted;
+                    table ^= 1;
+                }
+            }
+        }
+        Err(entry)
+    }
+
+    // Reinserts every entry, plus `pending`, under fresh hash functions.
+    // Whenever that runs into a cycle too, the tables double and it tries
+    // again.
+    fn rebuild(&mut self, mut table_len: usize, mut pending: Vec<(K, V)>) {
+        loop {
+            self.stats.rehashes += 1;
+            self.counters.record(Op::Rehash);
+            if table_len != self.tables[0].len() {
+                self.counters.record(Op::Reallocation);
+            }
+            let [first, second] = mem::replace(
+                &mut self.tables,
+                [empty_table(table_len), empty_table(table_len)],
+            );
+            self.hashers = [RandomState::new(), RandomState::new()];
+            pending.extend(first.into_iter().chain(second).flatten());
+
+            let mut homeless = None;
+            while let Some(entry) = pending.pop() {
+                if let Err(entry) = self.place(entry) {
+                    homeless = Some(entry);
+                    break;
+                }
+            }
+            match homeless {
+                None => return,
+                Some(entry) => {
+                    self.stats.cycles += 1;
+                    pending.push(entry);
+                    table_len *= 2;
+                }
+            }
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> Default for CuckooMap<K, V> {
+    fn default() -> Self {
+        CuckooMap::new()
+    }
+}
+
+fn empty_table<K, V>(len: usize) -> Vec<Option<(K, V)>> {
+    (0..len).map(|_| None).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, XorShift64};
+    use std::collections::HashMap;
+
+    // Every entry must sit in the slot its own table's hash picks, or lookups
+    // reading just those two slots would miss it
+    fn assert_placed(map: &CuckooMap<u64, u64>) {
+        let mut stored = 0;
+        for (table, slots) in map.tables.iter().enumerate() {
+            for (index, slot) in slots.iter().enumerate() {
+                if let Some((key, _)) = slot {
+                    assert_eq!(map.slot(table, key), index, "key {}", key);
+                    stored += 1;
+                }
+            }
+        }
+        assert_eq!(stored, map.len());
+    }
+
+    #[test]
+    fn matches_a_model() {
+        let mut rng = XorShift64::new(7);
+        let mut map = CuckooMap::new();
+        let mut model = HashMap::new();
+        for step in 0..5000 {
+            let key = rng.below(1000);
+            if rng.below(3) == 0 {
+                assert_eq!(map.remove(&key), model.remove(&key));
+            } else {
+                assert_eq!(map.insert(key, step), model.insert(key, step));
+            }
+            assert!(map.load_factor() <= MAX_LOAD);
+        }
+        assert_placed(&map);
+        for (key, value) in &model {
+            assert_eq!(map.get(key), Some(value));
+        }
+        for key in 0..1000 {
+            assert_eq!(map.contains_key(&key), model.contains_key(&key));
+        }
+    }
+
+    #[test]
+    fn growing_displaces_entries() {
+        let mut map = CuckooMap::new();
+        for key in 0..2000 {
+            map.insert(key, key * 2);
+        }
+        let stats = map.stats();
+        assert!(stats.displacements > 0);
+        assert!(stats.rehashes > 0);
+        assert!(stats.longest_chain >= 1 && stats.longest_chain <= MAX_DISPLACEMENTS);
+        assert_placed(&map);
+        assert!((0..2000).all(|key| map.get(&key) == Some(&(key * 2))));
+    }
+
+    // What `insert` does with the entry a cycle leaves without a slot. The
+    // rebuild may run into a cycle of its own and rehash again.
+    #[test]
+    fn a_rebuild_places_the_homeless_entry() {
+        let mut map = CuckooMap::with_capacity(8);
+        for key in 0..8 {
+            map.insert(key, key);
+        }
+        let rehashes = map.stats().rehashes;
+        map.rebuild(map.tables[0].len(), vec![(100, 100)]);
+        map.len += 1;
+        assert!(map.stats().rehashes > rehashes);
+        assert_placed(&map);
+        assert_eq!(map.get(&100), Some(&100));
+        assert!((0..8).all(|key| map.get(&key) == Some(&key)));
+    }
+
+    #[test]
+    fn get_mut_and_clear() {
+        let mut map = CuckooMap::new();
+        map.insert("a", 1);
+        *map.get_mut("a").unwrap() += 1;
+        assert_eq!(map.insert("a", 5), Some(2));
+        assert_eq!(map.len(), 1);
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.get("a"), None);
+        assert_eq!(map.iter().count(), 0);
+    }
+}
@@ -0,0 +1,232 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! How much memory a value really takes, heap allocations included
+//!
+//! `mem::size_of` only counts a value's inline bytes, so a `Vec<String>` is
+//! always 24 bytes however much text it holds. `DeepSize` adds up the heap
+//! allocations a value owns as well: buffer capacity (not just length), the
+//! contents of every string, and the tables of hash maps.
+//!
+//! The figures for std's maps are estimates. Hash tables are sized from
+//! their reported capacity the way `capacity::MemoryReport` does it, and
+//! B-tree nodes are assumed to be two thirds full. Allocator overhead is not
+//! counted.
+//!
+//! `memory_report` breaks a `Spreadsheet` down by column.
+
+use crate::capacity;
+use crate::spreadsheet::{Spreadsheet, SpreadsheetCell};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::mem;
+
+/// Values that can report the heap memory they own
+pub trait DeepSize {
+    /// Bytes of heap memory owned by this value, not counting the value's
+    /// own inline bytes
+    fn heap_bytes(&self) -> usize;
+
+    /// Inline bytes plus `heap_bytes`
+    fn deep_size(&self) -> usize
+    where
+        Self: Sized,
+    {
+        mem::size_of::<Self>() + self.heap_bytes()
+    }
+}
+
+macro_rules! impl_deep_size_inline {
+    ($($ty:ty),+) => {
+        $(
+            impl DeepSize for $ty {
+                fn heap_bytes(&self) -> usize {
+                    0
+                }
+            }
+        )+
+    };
+}
+
+impl_deep_size_inline!(
+    bool,
+    char,
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    ()
+);
+
+impl DeepSize for String {
+    fn heap_bytes(&self) -> usize {
+        self.capacity()
+    }
+}
+
+impl<T: DeepSize> DeepSize for Option<T> {
+    fn heap_bytes(&self) -> usize {
+        self.as_ref().map_or(0, DeepSize::heap_bytes)
+    }
+}
+
+impl<T: DeepSize> DeepSize for Box<T> {
+    fn heap_bytes(&self) -> usize {
+        (**self).deep_size()
+    }
+}
+
+impl<A: DeepSize, B: DeepSize> DeepSize for (A, B) {
+    fn heap_bytes(&self) -> usize {
+        self.0.heap_bytes() + self.1.heap_bytes()
+    }
+}
+
+impl<A: DeepSize, B: DeepSize, C: DeepSize> DeepSize for (A, B, C) {
+    fn heap_bytes(&self) -> usize {
+        self.0.heap_bytes() + self.1.heap_bytes() + self.2.heap_bytes()
+    }
+}
+
+impl<T: DeepSize> DeepSize for Vec<T> {
+    fn heap_bytes(&self) -> usize {
+        self.capacity() * mem::size_of::<T>() + self.iter().map(T::heap_bytes).sum::<usize>()
+    }
+}
+
+impl<T: DeepSize> DeepSize for VecDeque<T> {
+    fn heap_bytes(&self) -> usize {
+        self.capacity() * mem::size_of::<T>() + self.iter().map(T::heap_bytes).sum::<usize>()
+    }
+}
+
+impl<K: DeepSize, V: DeepSize, S> DeepSize for HashMap<K, V, S> {
+    fn heap_bytes(&self) -> usize {
+        capacity::table_bytes(self.capacity(), mem::size_of::<(K, V)>())
+            + self
+                .iter()
+                .map(|(key, value)| key.heap_bytes() + value.heap_bytes())
+                .sum::<usize>()
+    }
+}
+
+impl<T: DeepSize, S> DeepSize for HashSet<T, S> {
+    fn heap_bytes(&self) -> usize {
+        capacity::table_bytes(self.capacity(), mem::size_of::<T>())
+            + self.iter().map(T::heap_bytes).sum::<usize>()
+    }
+}
+
+impl<K: DeepSize, V: DeepSize> DeepSize for BTreeMap<K, V> {
+    fn heap_bytes(&self) -> usize {
+        btree_bytes(self.len(), mem::size_of::<K>() + mem::size_of::<V>())
+            + self
+                .iter()
+                .map(|(key, value)| key.heap_bytes() + value.heap_bytes())
+                .sum::<usize>()
+    }
+}
+
+impl<T: DeepSize> DeepSize for BTreeSet<T> {
+    fn heap_bytes(&self) -> usize {
+        btree_bytes(self.len(), mem::size_of::<T>()) + self.iter().map(T::heap_bytes).sum::<usize>()
+    }
+}
+
+// std's B-tree nodes hold up to 11 entries, plus a parent pointer, indices
+// and lengths; child pointers on internal nodes are left out
+const BTREE_NODE_ENTRIES: usize = 11;
+const BTREE_NODE_HEADER: usize = 16;
+
+fn btree_bytes(len: usize, entry_size: usize) -> usize {
+    if len == 0 {
+        return 0;
+    }
+    let nodes = (len * 3).div_ceil(BTREE_NODE_ENTRIES * 2);
+    nodes * (BTREE_NODE_ENTRIES * entry_size + BTREE_NODE_HEADER)
+}
+
+/// Memory held by one column of a `Spreadsheet`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ColumnMemory {
+    pub column: usize,
+    /// Rows long enough to have a cell in this column
+    pub cells: usize,
+    /// Inline bytes of those cells
+    pub cell_bytes: usize,
+    /// Bytes of text owned by `Text` cells
+    pub text_bytes: usize,
+}
+
+impl ColumnMemory {
+    pub fn total_bytes(&self) -> usize {
+        self.cell_bytes + self.text_bytes
+    }
+}
+
+/// Where the memory of a `Spreadsheet` goes, from `memory_report`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SheetMemoryReport {
+    /// Everything the sheet holds, as `DeepSize::deep_size` counts it
+    pub total_bytes: usize,
+    /// Row headers plus unused room in row buffers
+    pub overhead_bytes: usize,
+    pub columns: Vec<ColumnMemory>,
+}
+
+impl fmt::Display for SheetMemoryReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "{} bytes in all, {} of them overhead",
+            self.total_bytes, self.overhead_bytes
+        )?;
+        for column in &self.columns {
+            writeln!(
+                f,
+                "  column {}: {} cells, {} bytes ({} of text)",
+                column.column,
+                column.cells,
+                column.total_bytes(),
+                column.text_bytes
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// Breaks down the memory held by `sheet` column by column
+pub fn memory_report(sheet: &Spreadsheet) -> SheetMemoryReport {
+    let mut columns: Vec<ColumnMemory> = (0..sheet.column_count())
+        .map(|column| ColumnMemory {
+            column,
+            cells: 0,
+            cell_bytes: 0,
+            text_bytes: 0,
+        })
+        .collect();
+    for (_, col, cell) in sheet.cells() {
+        let column = &mut columns[col];
+        column.cells += 1;
+        column.cell_bytes += mem::size_of::<SpreadsheetCell>();
+        column.text_bytes += cell.heap_bytes();
+    }
+
+    let total_bytes = sheet.deep_size();
+    let in_columns: usize = columns.iter().map(ColumnMemory::total_bytes).sum();
+    SheetMemoryReport {
+        total_bytes,
+        overhead_bytes: total_bytes - in_columns,
+        columns,
+    }
+}
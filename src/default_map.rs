@@ -0,0 +1,80 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A map that fills in missing values from a factory closure, mirroring
+//! Python's `collections.defaultdict`
+
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+/// A `HashMap` that creates a value with `factory` the first time a missing
+/// key is accessed mutably
+pub struct DefaultMap<K, V, F: Fn() -> V> {
+    map: HashMap<K, V>,
+    factory: F,
+}
+
+impl<K: Eq + Hash, V, F: Fn() -> V> DefaultMap<K, V, F> {
+    /// Creates an empty map that builds missing values with `factory`
+    pub fn new(factory: F) -> Self {
+        DefaultMap {
+            map: HashMap::new(),
+            factory,
+        }
+    }
+
+    /// Returns the value for `key`, inserting a fresh default if it is missing
+    pub fn get_mut(&mut self, key: K) -> &mut V {
+        let factory = &self.factory;
+        self.map.entry(key).or_insert_with(factory)
+    }
+
+    /// Looks up `key` without inserting anything
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.map.insert(key, value)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.map.remove(key)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    /// Iterates over the entries in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map.iter()
+    }
+
+    /// Builds a default value without touching the map
+    pub fn make_default(&self) -> V {
+        (self.factory)()
+    }
+
+    /// Gives up the factory and returns the plain `HashMap`
+    pub fn into_inner(self) -> HashMap<K, V> {
+        self.map
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, F: Fn() -> V> fmt::Debug for DefaultMap<K, V, F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DefaultMap")
+            .field("map", &self.map)
+            .finish_non_exhaustive()
+    }
+}
@@ -0,0 +1,507 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A hash set for small `Copy` keys, kept in one flat array
+//!
+//! `DenseSet` stores its keys inline in a single array of slots with linear
+//! probing, so checking membership reads one slot and usually the few right
+//! after it, all in the same cache line or the next. Slots come in groups of
+//! eight sharing one byte that marks which of them are in use, so a `u64`
+//! key costs nine bytes a slot rather than the sixteen of an `Option<u64>`,
+//! and more of the table stays in cache.
+//!
+//! That pays off while the whole table fits in cache, as for the few
+//! thousand ids a dedup pass over one batch tends to see. For much larger
+//! sets std's `HashSet`, with `FastHasher`, is faster: it can turn away
+//! most missing keys by reading only its small array of control bytes,
+//! where every probe here reads the slot itself. `benches/dense_set.rs`
+//! measures both.
+//!
+//! Removing a key shifts the keys after it back towards their home slots
+//! instead of leaving tombstones, so lookups stay short however many keys
+//! come and go. Keys are hashed with `FastHasher` unless another hasher is
+//! given, which like `FastHashSet` isn't safe for keys an attacker picks.
+
+use crate::deep_size::DeepSize;
+use crate::fast_hash::FastBuildHasher;
+use crate::op_stats::{Op, OpCounters, OpStats};
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
+use std::mem::{self, MaybeUninit};
+
+// Keys may fill at most three quarters of the slots
+const LOAD_NUMERATOR: usize = 3;
+const LOAD_DENOMINATOR: usize = 4;
+
+const LANES: usize = 8;
+
+const MIN_SLOTS: usize = LANES;
+
+// Eight slots and a bit for each saying whether it holds a key
+struct Group<T> {
+    occupied: u8,
+    keys: [MaybeUninit<T>; LANES],
+}
+
+// Derived impls would only ask for `T: Clone`, which `MaybeUninit` can't use
+impl<T: Copy> Clone for Group<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: Copy> Copy for Group<T> {}
+
+impl<T: Copy> Group<T> {
+    const EMPTY: Group<T> = Group {
+        occupied: 0,
+        keys: [MaybeUninit::uninit(); LANES],
+    };
+
+    fn get(&self, lane: usize) -> Option<T> {
+        if self.occupied & (1 << lane) == 0 {
+            return None;
+        }
+        // Safety: a lane's bit is only set once a key has been written
+        Some(unsafe { self.keys[lane].assume_init() })
+    }
+
+    fn set(&mut self, lane: usize, key: Option<T>) {
+        match key {
+            Some(key) => {
+                self.keys[lane] = MaybeUninit::new(key);
+                self.occupied |= 1 << lane;
+            }
+            None => self.occupied &= !(1 << lane),
+        }
+    }
+}
+
+/// An open-addressing set of `Copy` keys in one allocation
+pub struct DenseSet<T, S = FastBuildHasher> {
+    // Empty, or a power of two slots long
+    groups: Vec<Group<T>>,
+    len: usize,
+    hasher: S,
+    counters: OpCounters,
+}
+
+impl<T: Copy + Eq + Hash> DenseSet<T, FastBuildHasher> {
+    pub fn new() -> Self {
+        DenseSet::with_hasher(FastBuildHasher::default())
+    }
+
+    /// Creates a set that can hold `capacity` keys before growing
+    pub fn with_capacity(capacity: usize) -> Self {
+        DenseSet::with_capacity_and_hasher(capacity, FastBuildHasher::default())
+    }
+}
+
+impl<T, S> DenseSet<T, S> {
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Keys the set can hold before it grows
+    pub fn capacity(&self) -> usize {
+        self.slot_count() / LOAD_DENOMINATOR * LOAD_NUMERATOR
+    }
+
+    /// Number of slots in the backing array
+    pub fn slot_count(&self) -> usize {
+        self.groups.len() * LANES
+    }
+
+    /// What the set has been asked to do, counted with the `instrument`
+    /// feature
+    pub fn op_stats(&self) -> OpStats {
+        self.counters.stats()
+    }
+
+    pub fn reset_op_stats(&mut self) {
+        self.counters.reset();
+    }
+}
+
+impl<T: Copy + Eq + Hash, S: BuildHasher> DenseSet<T, S> {
+    /// Creates an empty set that hashes with `hasher`, allocating nothing
+    /// until the first insert
+    pub fn with_hasher(hasher: S) -> Self {
+        DenseSet {
+            groups: Vec::new(),
+            len: 0,
+            hasher,
+            counters: OpCounters::new(),
+        }
+    }
+
+    pub fn with_capacity_and_hasher(capacity: usize, hasher: S) -> Self {
+        let mut set = DenseSet::with_hasher(hasher);
+        if capacity > 0 {
+            set.groups = empty_groups(slots_for(capacity));
+        }
+        set
+    }
+
+    /// Adds `key`, returning whether it was new
+    pub fn insert(&mut self, key: T) -> bool {
+        self.counters.record(Op::Insert);
+        self.insert_uncounted(key)
+    }
+
+    fn insert_uncounted(&mut self, key: T) -> bool {
+        if self.len >= self.capacity() {
+            self.grow();
+        }
+        let mask = self.slot_count() - 1;
+        let mut index = self.home(&key);
+        loop {
+            match self.slot(index) {
+                Some(stored) if stored == key => return false,
+                Some(_) => index = (index + 1) & mask,
+                None => break,
+            }
+        }
+        self.set_slot(index, Some(key));
+        self.len += 1;
+        true
+    }
+
+    pub fn contains(&self, key: &T) -> bool {
+        self.counters.record(Op::Lookup);
+        self.find(key).is_some()
+    }
+
+    /// Removes `key`, returning whether it was there
+    pub fn remove(&mut self, key: &T) -> bool {
+        self.counters.record(Op::Removal);
+        let mut hole = match self.find(key) {
+            Some(index) => index,
+            None => return false,
+        };
+        self.set_slot(hole, None);
+        self.len -= 1;
+
+        // Walk the rest of the run, moving back every key whose probe
+        // passed through the hole, so no later lookup stops there early
+        let mask = self.slot_count() - 1;
+        let mut index = (hole + 1) & mask;
+        while let Some(stored) = self.slot(index) {
+            let home = self.home(&stored);
+            if index.wrapping_sub(home) & mask >= index.wrapping_sub(hole) & mask {
+                self.set_slot(hole, Some(stored));
+                self.set_slot(index, None);
+                hole = index;
+            }
+            index = (index + 1) & mask;
+        }
+        true
+    }
+
+    /// Makes room for `additional` more keys without growing
+    pub fn reserve(&mut self, additional: usize) {
+        let wanted = self.len.saturating_add(additional);
+        if wanted > self.capacity() {
+            self.rebuild(slots_for(wanted));
+        }
+    }
+
+    /// Iterates over the keys in slot order
+    pub fn iter(&self) -> impl Iterator<Item = T> + '_ {
+        (0..self.slot_count()).filter_map(move |index| self.slot(index))
+    }
+
+    /// Empties the set, keeping its allocation
+    pub fn clear(&mut self) {
+        for group in self.groups.iter_mut() {
+            group.occupied = 0;
+        }
+        self.len = 0;
+    }
+
+    fn home(&self, key: &T) -> usize {
+        (self.hasher.hash_one(key) as usize) & (self.slot_count() - 1)
+    }
+
+    fn slot(&self, index: usize) -> Option<T> {
+        self.groups[index / LANES].get(index % LANES)
+    }
+
+    fn set_slot(&mut self, index: usize, key: Option<T>) {
+        self.groups[index / LANES].set(index % LANES, key);
+    }
+
+    fn find(&self, key: &T) -> Option<usize> {
+        if self.groups.is_empty() {
+            return None;
+        }
+        let mask = self.slot_count() - 1;
+        let mut index = self.home(key);
+        loop {
+            match self.slot(index) {
+                Some(stored) if stored == *key => return Some(index),
+                Some(_) => index = (index + 1) & mask,
+                None => return None,
+            }
+        }
+    }
+
+    fn grow(&mut self) {
+        let slots = (self.slot_count() * 2).max(MIN_SLOTS);
+        self.rebuild(slots);
+    }
+
+    fn rebuild(&mut self, slots: usize) {
+        self.counters.record(Op::Reallocation);
+        let old = mem::replace(&mut self.groups, empty_groups(slots));
+        if !old.is_empty() {
+            self.counters.record(Op::Rehash);
+        }
+        self.len = 0;
+        for group in old {
+            for key in (0..LANES).filter_map(|lane| group.get(lane)) {
+                self.insert_uncounted(key);
+            }
+        }
+    }
+}
+
+fn empty_groups<T: Copy>(slots: usize) -> Vec<Group<T>> {
+    vec![Group::EMPTY; slots / LANES]
+}
+
+// The smallest power of two number of slots that holds `keys` within the
+// load limit
+fn slots_for(keys: usize) -> usize {
+    (keys.saturating_mul(LOAD_DENOMINATOR) / LOAD_NUMERATOR + 1)
+        .max(MIN_SLOTS)
+        .next_power_of_two()
+}
+
+impl<T: Copy, S: Clone> Clone for DenseSet<T, S> {
+    fn clone(&self) -> Self {
+        DenseSet {
+            groups: self.groups.clone(),
+            len: self.len,
+            hasher: self.hasher.clone(),
+            counters: self.counters.clone(),
+        }
+    }
+}
+
+impl<T: Copy + Eq + Hash> Default for DenseSet<T, FastBuildHasher> {
+    fn default() -> Self {
+        DenseSet::new()
+    }
+}
+
+impl<T: fmt::Debug + Copy + Eq + Hash, S: BuildHasher> fmt::Debug for DenseSet<T, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<T: Copy + Eq + Hash, S: BuildHasher> PartialEq for DenseSet<T, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().all(|key| other.contains(&key))
+    }
+}
+
+impl<T: Copy + Eq + Hash, S: BuildHasher> Eq for DenseSet<T, S> {}
+
+impl<T: Copy + Eq + Hash, S: BuildHasher + Default> FromIterator<T> for DenseSet<T, S> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = DenseSet::with_hasher(S::default());
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Copy + Eq + Hash, S: BuildHasher> Extend<T> for DenseSet<T, S> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let iter = iter.into_iter();
+        self.reserve(iter.size_hint().0);
+        for key in iter {
+            self.insert(key);
+        }
+    }
+}
+
+impl<T, S> DeepSize for DenseSet<T, S> {
+    fn heap_bytes(&self) -> usize {
+        self.groups.capacity() * mem::size_of::<Group<T>>()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, XorShift64};
+    use std::collections::HashSet;
+    use std::hash::Hasher;
+
+    // Hashes a `u64` key to itself, so a key's home slot is its value modulo
+    // the slot count
+    #[derive(Clone, Copy, Default)]
+    struct Identity;
+
+    struct IdentityHasher(u64);
+
+    impl Hasher for IdentityHasher {
+        fn write(&mut self, _: &[u8]) {
+            unreachable!("only u64 keys are hashed");
+        }
+
+        fn write_u64(&mut self, key: u64) {
+            self.0 = key;
+        }
+
+        fn finish(&self) -> u64 {
+            self.0
+        }
+    }
+
+    impl BuildHasher for Identity {
+        type Hasher = IdentityHasher;
+
+        fn build_hasher(&self) -> IdentityHasher {
+            IdentityHasher(0)
+        }
+    }
+
+    fn slots<S: BuildHasher>(set: &DenseSet<u64, S>) -> Vec<Option<u64>> {
+        (0..set.slot_count()).map(|index| set.slot(index)).collect()
+    }
+
+    // Every slot between a key's home and where it sits is full, so no
+    // lookup for it stops early at an empty one
+    fn assert_probe_runs<S: BuildHasher>(set: &DenseSet<u64, S>) {
+        let mask = set.slot_count() - 1;
+        let mut stored = 0;
+        for index in 0..set.slot_count() {
+            let key = match set.slot(index) {
+                Some(key) => key,
+                None => continue,
+            };
+            stored += 1;
+            let mut probe = set.home(&key);
+            while probe != index {
+                assert!(set.slot(probe).is_some(), "gap before key {}", key);
+                probe = (probe + 1) & mask;
+            }
+        }
+        assert_eq!(stored, set.len());
+    }
+
+    #[test]
+    fn removal_shifts_a_wrapped_run_back() {
+        let mut set = DenseSet::with_capacity_and_hasher(8, Identity);
+        assert_eq!(set.slot_count(), 16);
+        // 14, 30 and 46 all start at slot 14, and the run wraps onto 15 and
+        // 0, which start at slots 15 and 0
+        for &key in &[14, 30, 46, 15, 0] {
+            assert!(set.insert(key));
+        }
+        let mut expected = vec![None; 16];
+        expected[14] = Some(14);
+        expected[15] = Some(30);
+        expected[0] = Some(46);
+        expected[1] = Some(15);
+        expected[2] = Some(0);
+        assert_eq!(slots(&set), expected);
+
+        assert!(set.remove(&14));
+        expected[14] = Some(30);
+        expected[15] = Some(46);
+        expected[0] = Some(15);
+        expected[1] = Some(0);
+        expected[2] = None;
+        assert_eq!(slots(&set), expected);
+
+        // 31 goes on the end of the run, two slots past 0 and three past
+        // its own home, and moves back with the rest when 46 goes
+        assert!(set.insert(31));
+        assert_eq!(set.slot(2), Some(31));
+        assert!(set.remove(&46));
+        expected[15] = Some(15);
+        expected[0] = Some(0);
+        expected[1] = Some(31);
+        expected[2] = None;
+        assert_eq!(slots(&set), expected);
+        assert!(!set.remove(&46));
+        assert_probe_runs(&set);
+        for &key in &[30, 31, 15, 0] {
+            assert!(set.contains(&key));
+        }
+    }
+
+    #[test]
+    fn keys_already_home_stay_put() {
+        let mut set = DenseSet::with_capacity_and_hasher(8, Identity);
+        for &key in &[15, 31, 1, 2] {
+            set.insert(key);
+        }
+        // 31 wrapped onto slot 0, while 1 and 2 are at home
+        assert!(set.remove(&15));
+        let mut expected = vec![None; 16];
+        expected[15] = Some(31);
+        expected[1] = Some(1);
+        expected[2] = Some(2);
+        assert_eq!(slots(&set), expected);
+    }
+
+    // Keys crowd the last few slots and the first few, so most runs wrap
+    #[test]
+    fn matches_a_model_around_the_wrap() {
+        for seed in 1..=20 {
+            let mut rng = XorShift64::new(seed);
+            let mut set = DenseSet::with_capacity_and_hasher(8, Identity);
+            let mut model = HashSet::new();
+            // Homes 13, 14, 15, 0 and 1, and few enough keys that the 16
+            // slots never grow
+            let keys = [13, 29, 14, 30, 46, 15, 31, 0, 16, 32, 1];
+            for _ in 0..2000 {
+                let key = keys[rng.below(keys.len() as u64) as usize];
+                if rng.below(2) == 0 {
+                    assert_eq!(set.insert(key), model.insert(key));
+                } else {
+                    assert_eq!(set.remove(&key), model.remove(&key), "seed {}", seed);
+                }
+                assert_eq!(set.slot_count(), 16);
+                assert_probe_runs(&set);
+                for key in &keys {
+                    assert_eq!(set.contains(key), model.contains(key));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn matches_a_model_while_growing() {
+        let mut rng = XorShift64::new(7);
+        let mut set = DenseSet::new();
+        let mut model = HashSet::new();
+        for step in 0..20_000 {
+            let key = rng.below(2000);
+            if rng.below(3) == 0 {
+                assert_eq!(set.remove(&key), model.remove(&key));
+            } else {
+                assert_eq!(set.insert(key), model.insert(key));
+            }
+            if step % 500 == 0 {
+                assert_probe_runs(&set);
+            }
+        }
+        assert_eq!(set.len(), model.len());
+        let mut keys: Vec<u64> = set.iter().collect();
+        keys.sort_unstable();
+        let mut expected: Vec<u64> = model.into_iter().collect();
+        expected.sort_unstable();
+        assert_eq!(keys, expected);
+    }
+}
@@ -0,0 +1,215 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Iterating hash maps in a repeatable order
+//!
+//! A `HashMap` iterates in an arbitrary order that changes from run to run,
+//! which makes printed output awkward to compare in tests and diffs.
+//! `iter_sorted_by_key` sorts an existing map's entries once, and
+//! `DeterministicMap` keeps a sorted list of its keys alongside the map so
+//! every iteration comes out in key order.
+//!
+//! `iter_sorted_by_value` and `top_n_by_value` order entries by value
+//! instead, for questions like "which words are most frequent?".
+
+use std::borrow::Borrow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::hash_map::RandomState;
+use std::collections::BinaryHeap;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
+
+/// Returns the entries of `map` sorted by key
+pub fn iter_sorted_by_key<K: Ord, V, S>(map: &HashMap<K, V, S>) -> std::vec::IntoIter<(&K, &V)> {
+    let mut entries: Vec<(&K, &V)> = map.iter().collect();
+    entries.sort_unstable_by(|a, b| a.0.cmp(b.0));
+    entries.into_iter()
+}
+
+/// Returns the entries of `map` sorted by value, smallest first
+///
+/// Entries with equal values come out in the map's arbitrary order.
+pub fn iter_sorted_by_value<K, V: Ord, S>(map: &HashMap<K, V, S>) -> std::vec::IntoIter<(&K, &V)> {
+    let mut entries: Vec<(&K, &V)> = map.iter().collect();
+    entries.sort_unstable_by(|a, b| a.1.cmp(b.1));
+    entries.into_iter()
+}
+
+/// Returns the `n` entries of `map` with the largest values, largest first
+///
+/// Only `n` entries are held at a time, in a min-heap that drops its
+/// smallest entry whenever a larger one turns up, so this costs
+/// `O(len * log n)` rather than sorting the whole map. Which of several
+/// entries tied at the cut-off are kept is arbitrary.
+pub fn top_n_by_value<K, V: Ord, S>(map: &HashMap<K, V, S>, n: usize) -> Vec<(&K, &V)> {
+    let mut heap: BinaryHeap<Reverse<ByValue<'_, K, V>>> = BinaryHeap::with_capacity(n + 1);
+    for (key, value) in map {
+        if heap.len() < n {
+            heap.push(Reverse(ByValue(key, value)));
+        } else if heap
+            .peek()
+            .is_some_and(|Reverse(smallest)| value > smallest.1)
+        {
+            heap.pop();
+            heap.push(Reverse(ByValue(key, value)));
+        }
+    }
+    heap.into_sorted_vec()
+        .into_iter()
+        .map(|Reverse(ByValue(key, value))| (key, value))
+        .collect()
+}
+
+// An entry compared by its value alone
+struct ByValue<'a, K, V>(&'a K, &'a V);
+
+impl<K, V: Ord> Ord for ByValue<'_, K, V> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.1.cmp(other.1)
+    }
+}
+
+impl<K, V: Ord> PartialOrd for ByValue<'_, K, V> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<K, V: Ord> PartialEq for ByValue<'_, K, V> {
+    fn eq(&self, other: &Self) -> bool {
+        self.1 == other.1
+    }
+}
+
+impl<K, V: Ord> Eq for ByValue<'_, K, V> {}
+
+/// A `HashMap` that always iterates in key order
+///
+/// Lookups are hash lookups as usual. Inserting a new key or removing one
+/// also updates the sorted key list, which costs `O(n)` for the shift, so
+/// this suits maps that are read and printed far more than they change.
+#[derive(Debug, Clone)]
+pub struct DeterministicMap<K, V, S = RandomState> {
+    map: HashMap<K, V, S>,
+    keys: Vec<K>,
+}
+
+impl<K: Ord + Hash + Clone, V> DeterministicMap<K, V> {
+    pub fn new() -> Self {
+        DeterministicMap {
+            map: HashMap::new(),
+            keys: Vec::new(),
+        }
+    }
+}
+
+impl<K: Ord + Hash + Clone, V, S: BuildHasher> DeterministicMap<K, V, S> {
+    pub fn with_hasher(hasher: S) -> Self {
+        DeterministicMap {
+            map: HashMap::with_hasher(hasher),
+            keys: Vec::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(existing) = self.map.get_mut(&key) {
+            return Some(std::mem::replace(existing, value));
+        }
+        if let Err(index) = self.keys.binary_search(&key) {
+            self.keys.insert(index, key.clone());
+        }
+        self.map.insert(key, value)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + Hash + ?Sized,
+    {
+        self.map.get(key)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + Hash + ?Sized,
+    {
+        self.map.get_mut(key)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + Hash + ?Sized,
+    {
+        self.map.contains_key(key)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + Hash + ?Sized,
+    {
+        let value = self.map.remove(key)?;
+        if let Ok(index) = self.keys.binary_search_by(|k| k.borrow().cmp(key)) {
+            self.keys.remove(index);
+        }
+        Some(value)
+    }
+
+    /// Iterates over the entries in key order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.keys.iter().map(move |key| (key, &self.map[key]))
+    }
+
+    /// Iterates over the keys in order
+    pub fn keys(&self) -> std::slice::Iter<'_, K> {
+        self.keys.iter()
+    }
+
+    /// Iterates over the values in key order
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.keys.iter().map(move |key| &self.map[key])
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.keys.clear();
+    }
+
+    /// Gives back the underlying `HashMap`, dropping the key order
+    pub fn into_inner(self) -> HashMap<K, V, S> {
+        self.map
+    }
+}
+
+impl<K: Ord + Hash + Clone, V> Default for DeterministicMap<K, V> {
+    fn default() -> Self {
+        DeterministicMap::new()
+    }
+}
+
+impl<K: Ord + Hash + Clone, V, S: BuildHasher> From<HashMap<K, V, S>>
+    for DeterministicMap<K, V, S>
+{
+    fn from(map: HashMap<K, V, S>) -> Self {
+        let mut keys: Vec<K> = map.keys().cloned().collect();
+        keys.sort_unstable();
+        DeterministicMap { map, keys }
+    }
+}
+
+impl<K: Ord + Hash + Clone, V> FromIterator<(K, V)> for DeterministicMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        DeterministicMap::from(iter.into_iter().collect::<HashMap<K, V>>())
+    }
+}
@@ -0,0 +1,140 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Collecting iterators of `Result`s without stopping at the first error
+//!
+//! Collecting into `Result<Vec<T>, E>` gives up at the first `Err`, which is
+//! the wrong shape for jobs like validating an input file, where the user
+//! wants every bad line reported at once. These helpers consume the whole
+//! iterator and keep every error.
+//!
+//! `try_fold_collect` and `reduce_with_errors` are the "best effort with a
+//! report" versions of `fold` and `reduce`: they return whatever could be
+//! computed together with the errors met on the way, and an `ErrorPolicy`
+//! chooses whether the first error ends the run.
+
+/// Collects every `Ok` value, or if there were any errors, every error
+/// together with the position of the item that produced it
+pub fn try_collect_all<I, T, E>(iter: I) -> Result<Vec<T>, Vec<(usize, E)>>
+where
+    I: IntoIterator<Item = Result<T, E>>,
+{
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for (index, item) in iter.into_iter().enumerate() {
+        match item {
+            Ok(value) => values.push(value),
+            Err(error) => errors.push((index, error)),
+        }
+    }
+    if errors.is_empty() {
+        Ok(values)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Splits the `Ok` values from the errors, keeping the order within each
+pub fn partition_results<I, T, E>(iter: I) -> (Vec<T>, Vec<E>)
+where
+    I: IntoIterator<Item = Result<T, E>>,
+{
+    let mut values = Vec::new();
+    let mut errors = Vec::new();
+    for item in iter {
+        match item {
+            Ok(value) => values.push(value),
+            Err(error) => errors.push(error),
+        }
+    }
+    (values, errors)
+}
+
+/// What a fold does when it meets an error
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Record the error and stop, leaving the rest of the iterator unread
+    AbortOnFirst,
+    /// Record the error, skip the item and carry on
+    Continue,
+}
+
+/// The outcome of `try_fold_collect` or `reduce_with_errors`
+#[derive(Debug, Clone, PartialEq)]
+pub struct FoldReport<A, E> {
+    /// The result from every item that succeeded
+    pub value: A,
+    /// Each error with the position of the item that produced it
+    pub errors: Vec<(usize, E)>,
+    /// Whether the fold stopped early under `ErrorPolicy::AbortOnFirst`
+    pub aborted: bool,
+}
+
+impl<A, E> FoldReport<A, E> {
+    /// Whether every item was folded in without error
+    pub fn is_complete(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    /// The value if there were no errors, otherwise the errors
+    pub fn into_result(self) -> Result<A, Vec<(usize, E)>> {
+        if self.errors.is_empty() {
+            Ok(self.value)
+        } else {
+            Err(self.errors)
+        }
+    }
+}
+
+/// Folds every item into `init` with the fallible `step`
+///
+/// A `step` that fails should leave the accumulator as it found it, since
+/// the fold carries on from there.
+pub fn try_fold_collect<I, A, E, F>(
+    iter: I,
+    init: A,
+    policy: ErrorPolicy,
+    mut step: F,
+) -> FoldReport<A, E>
+where
+    I: IntoIterator,
+    F: FnMut(&mut A, I::Item) -> Result<(), E>,
+{
+    let mut report = FoldReport {
+        value: init,
+        errors: Vec::new(),
+        aborted: false,
+    };
+    for (index, item) in iter.into_iter().enumerate() {
+        if let Err(error) = step(&mut report.value, item) {
+            report.errors.push((index, error));
+            if policy == ErrorPolicy::AbortOnFirst {
+                report.aborted = true;
+                break;
+            }
+        }
+    }
+    report
+}
+
+/// Combines the `Ok` items with `combine`, collecting the `Err` items
+///
+/// The value is `None` when no item succeeded before the fold ended.
+pub fn reduce_with_errors<I, T, E, F>(
+    iter: I,
+    policy: ErrorPolicy,
+    mut combine: F,
+) -> FoldReport<Option<T>, E>
+where
+    I: IntoIterator<Item = Result<T, E>>,
+    F: FnMut(T, T) -> T,
+{
+    try_fold_collect(iter, None, policy, |acc: &mut Option<T>, item| {
+        let value = item?;
+        *acc = Some(match acc.take() {
+            Some(acc) => combine(acc, value),
+            None => value,
+        });
+        Ok(())
+    })
+}
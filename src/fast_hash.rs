@@ -0,0 +1,94 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A small, fast, non-cryptographic hasher in the style of FxHash
+//!
+//! `HashMap` uses SipHash by default, which protects against attackers who
+//! pick keys to force collisions. When the keys are trusted, that protection
+//! is wasted work, and a simpler hasher can be plugged in with
+//! `HashMap::with_hasher`. Never use `FastHasher` for keys that come from
+//! untrusted input.
+
+use std::collections::{HashMap, HashSet};
+use std::hash::{BuildHasherDefault, Hasher};
+
+// The multiplier used by rustc's FxHasher
+const SEED: u64 = 0x51_7c_c1_b7_27_22_0a_95;
+
+/// Mixes every written word into the state with a rotate, xor and multiply
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FastHasher {
+    hash: u64,
+}
+
+impl FastHasher {
+    // A plain multiply only carries a word's entropy upwards, so words that
+    // differ in their high bytes would end up sharing the low bits `HashMap`
+    // uses to pick a bucket. Folding the high half of the full 128-bit
+    // product back into the low half spreads every input bit over the result.
+    #[inline]
+    fn add_to_hash(&mut self, word: u64) {
+        let product = u128::from(self.hash.rotate_left(5) ^ word) * u128::from(SEED);
+        self.hash = (product as u64) ^ ((product >> 64) as u64);
+    }
+}
+
+impl Hasher for FastHasher {
+    #[inline]
+    fn write(&mut self, bytes: &[u8]) {
+        let mut chunks = bytes.chunks_exact(8);
+        for chunk in &mut chunks {
+            let mut word = [0; 8];
+            word.copy_from_slice(chunk);
+            self.add_to_hash(u64::from_le_bytes(word));
+        }
+
+        let rest = chunks.remainder();
+        if !rest.is_empty() {
+            let word = rest
+                .iter()
+                .rev()
+                .fold(0, |word, byte| (word << 8) | u64::from(*byte));
+            self.add_to_hash(word);
+        }
+    }
+
+    #[inline]
+    fn write_u8(&mut self, i: u8) {
+        self.add_to_hash(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u16(&mut self, i: u16) {
+        self.add_to_hash(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u32(&mut self, i: u32) {
+        self.add_to_hash(u64::from(i));
+    }
+
+    #[inline]
+    fn write_u64(&mut self, i: u64) {
+        self.add_to_hash(i);
+    }
+
+    #[inline]
+    fn write_usize(&mut self, i: usize) {
+        self.add_to_hash(i as u64);
+    }
+
+    #[inline]
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+/// Builds `FastHasher`s, for use with `HashMap::with_hasher`
+pub type FastBuildHasher = BuildHasherDefault<FastHasher>;
+
+/// A `HashMap` that hashes with `FastHasher`
+pub type FastHashMap<K, V> = HashMap<K, V, FastBuildHasher>;
+
+/// A `HashSet` that hashes with `FastHasher`
+pub type FastHashSet<T> = HashSet<T, FastBuildHasher>;
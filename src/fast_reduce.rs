@@ -0,0 +1,348 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Sums, extremes and dot products over numeric slices
+//!
+//! With the `simd` feature these keep eight independent running totals and
+//! walk the slice eight items at a time. Without the chain of dependencies a
+//! single total creates, the compiler can turn each step into a handful of
+//! vector instructions on stable Rust, without `std::simd`. Without the
+//! feature they are plain sequential loops, which are easier to step through
+//! in a debugger and give the same answers.
+//!
+//! Adding floats in a different order rounds differently, so with the
+//! feature a float sum or dot product can differ from the sequential one in
+//! the last few bits. Integer results are exact either way: `i32` values
+//! are summed as `i64`, which can't overflow before 2^32 values, and their
+//! products are summed as `i128`, which can't overflow at all.
+
+use std::ops::Add;
+
+/// Element types the reductions in this module accept
+pub trait Reducible: Copy + PartialOrd {
+    /// Type sums are accumulated and returned in
+    type Total: Copy + Add<Output = Self::Total>;
+
+    /// Type dot products are accumulated and returned in
+    type Product: Copy + Add<Output = Self::Product>;
+
+    const ZERO: Self::Total;
+
+    const ZERO_PRODUCT: Self::Product;
+
+    fn widen(self) -> Self::Total;
+
+    fn times(self, other: Self) -> Self::Product;
+
+    fn smaller(self, other: Self) -> Self;
+
+    fn larger(self, other: Self) -> Self;
+}
+
+impl Reducible for i32 {
+    type Total = i64;
+
+    type Product = i128;
+
+    const ZERO: i64 = 0;
+
+    const ZERO_PRODUCT: i128 = 0;
+
+    fn widen(self) -> i64 {
+        i64::from(self)
+    }
+
+    // Two products of `i32::MAX` already overflow an `i64`
+    fn times(self, other: i32) -> i128 {
+        i128::from(i64::from(self) * i64::from(other))
+    }
+
+    fn smaller(self, other: i32) -> i32 {
+        self.min(other)
+    }
+
+    fn larger(self, other: i32) -> i32 {
+        self.max(other)
+    }
+}
+
+// `f64::min` and `f64::max` return the other operand when one is NaN, so NaN
+// values are skipped rather than spreading into the result
+impl Reducible for f64 {
+    type Total = f64;
+
+    type Product = f64;
+
+    const ZERO: f64 = 0.0;
+
+    const ZERO_PRODUCT: f64 = 0.0;
+
+    fn widen(self) -> f64 {
+        self
+    }
+
+    fn times(self, other: f64) -> f64 {
+        self * other
+    }
+
+    fn smaller(self, other: f64) -> f64 {
+        self.min(other)
+    }
+
+    fn larger(self, other: f64) -> f64 {
+        self.max(other)
+    }
+}
+
+/// Adds up every value
+pub fn fast_sum<T: Reducible>(values: &[T]) -> T::Total {
+    imp::sum(values)
+}
+
+/// The smallest and largest values, or `None` for an empty slice
+///
+/// NaN values are ignored unless every value is NaN.
+pub fn fast_min_max<T: Reducible>(values: &[T]) -> Option<(T, T)> {
+    let (&first, _) = values.split_first()?;
+    Some(imp::min_max(values, first))
+}
+
+/// Sum of the products of the values at the same positions
+///
+/// Panics if the slices differ in length.
+pub fn fast_dot<T: Reducible>(left: &[T], right: &[T]) -> T::Product {
+    assert_eq!(
+        left.len(),
+        right.len(),
+        "dot product needs slices of the same length"
+    );
+    imp::dot(left, right)
+}
+
+#[cfg(feature = "simd")]
+mod imp {
+    use super::Reducible;
+
+    const LANES: usize = 8;
+
+    pub(super) fn sum<T: Reducible>(values: &[T]) -> T::Total {
+        let mut totals = [T::ZERO; LANES];
+        let chunks = values.chunks_exact(LANES);
+        let rest = chunks.remainder();
+        for chunk in chunks {
+            for (total, &value) in totals.iter_mut().zip(chunk) {
+                *total = *total + value.widen();
+            }
+        }
+        let mut total = combine::<T>(totals);
+        for &value in rest {
+            total = total + value.widen();
+        }
+        total
+    }
+
+    pub(super) fn min_max<T: Reducible>(values: &[T], first: T) -> (T, T) {
+        let mut mins = [first; LANES];
+        let mut maxes = [first; LANES];
+        let chunks = values.chunks_exact(LANES);
+        let rest = chunks.remainder();
+        for chunk in chunks {
+            for ((min, max), &value) in mins.iter_mut().zip(maxes.iter_mut()).zip(chunk) {
+                *min = min.smaller(value);
+                *max = max.larger(value);
+            }
+        }
+        let mut min = mins.iter().fold(first, |min, &lane| min.smaller(lane));
+        let mut max = maxes.iter().fold(first, |max, &lane| max.larger(lane));
+        for &value in rest {
+            min = min.smaller(value);
+            max = max.larger(value);
+        }
+        (min, max)
+    }
+
+    pub(super) fn dot<T: Reducible>(left: &[T], right: &[T]) -> T::Product {
+        let mut totals = [T::ZERO_PRODUCT; LANES];
+        let left_chunks = left.chunks_exact(LANES);
+        let right_chunks = right.chunks_exact(LANES);
+        let (left_rest, right_rest) = (left_chunks.remainder(), right_chunks.remainder());
+        for (left, right) in left_chunks.zip(right_chunks) {
+            for (total, (&l, &r)) in totals.iter_mut().zip(left.iter().zip(right)) {
+                *total = *total + l.times(r);
+            }
+        }
+        let mut total = totals
+            .iter()
+            .fold(T::ZERO_PRODUCT, |sum, &total| sum + total);
+        for (&l, &r) in left_rest.iter().zip(right_rest) {
+            total = total + l.times(r);
+        }
+        total
+    }
+
+    fn combine<T: Reducible>(totals: [T::Total; LANES]) -> T::Total {
+        totals.iter().fold(T::ZERO, |sum, &total| sum + total)
+    }
+}
+
+#[cfg(not(feature = "simd"))]
+mod imp {
+    use super::Reducible;
+
+    pub(super) fn sum<T: Reducible>(values: &[T]) -> T::Total {
+        values
+            .iter()
+            .fold(T::ZERO, |total, &value| total + value.widen())
+    }
+
+    pub(super) fn min_max<T: Reducible>(values: &[T], first: T) -> (T, T) {
+        values.iter().fold((first, first), |(min, max), &value| {
+            (min.smaller(value), max.larger(value))
+        })
+    }
+
+    pub(super) fn dot<T: Reducible>(left: &[T], right: &[T]) -> T::Product {
+        left.iter()
+            .zip(right)
+            .fold(T::ZERO_PRODUCT, |total, (&l, &r)| total + l.times(r))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, XorShift64};
+
+    fn naive_sum_i32(values: &[i32]) -> i64 {
+        let mut total = 0i64;
+        for &value in values {
+            total += i64::from(value);
+        }
+        total
+    }
+
+    fn naive_dot_i32(left: &[i32], right: &[i32]) -> i128 {
+        let mut total = 0i128;
+        for (&l, &r) in left.iter().zip(right) {
+            total += i128::from(l) * i128::from(r);
+        }
+        total
+    }
+
+    fn naive_min_max_i32(values: &[i32]) -> Option<(i32, i32)> {
+        let mut result = None;
+        for &value in values {
+            result = Some(match result {
+                None => (value, value),
+                Some((min, max)) => (value.min(min), value.max(max)),
+            });
+        }
+        result
+    }
+
+    // NaN is skipped, unless every value is NaN
+    fn naive_min_max_f64(values: &[f64]) -> Option<(f64, f64)> {
+        let first = *values.first()?;
+        let mut result: Option<(f64, f64)> = None;
+        for &value in values.iter().filter(|value| !value.is_nan()) {
+            result = Some(match result {
+                None => (value, value),
+                Some((min, max)) => (
+                    if value < min { value } else { min },
+                    if value > max { value } else { max },
+                ),
+            });
+        }
+        Some(result.unwrap_or((first, first)))
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a.is_nan() && b.is_nan()) || a == b || (a - b).abs() <= 1e-9 * a.abs().max(b.abs())
+    }
+
+    // Lengths around the lane count, so the remainder loops are exercised
+    const LENGTHS: &[usize] = &[0, 1, 7, 8, 9, 15, 16, 17, 63, 100, 1001];
+
+    #[test]
+    fn integers_match_naive_loops() {
+        let mut rng = XorShift64::new(1);
+        for &len in LENGTHS {
+            let left: Vec<i32> = (0..len).map(|_| rng.next_u64() as i32).collect();
+            let right: Vec<i32> = (0..len).map(|_| rng.next_u64() as i32).collect();
+            assert_eq!(fast_sum(&left), naive_sum_i32(&left), "len {}", len);
+            assert_eq!(fast_min_max(&left), naive_min_max_i32(&left), "len {}", len);
+            assert_eq!(
+                fast_dot(&left, &right),
+                naive_dot_i32(&left, &right),
+                "len {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn integer_extremes_do_not_overflow() {
+        for &len in LENGTHS {
+            let max = vec![i32::MAX; len];
+            let min = vec![i32::MIN; len];
+            assert_eq!(fast_sum(&max), i64::from(i32::MAX) * len as i64);
+            assert_eq!(fast_sum(&min), i64::from(i32::MIN) * len as i64);
+            assert_eq!(fast_dot(&max, &max), naive_dot_i32(&max, &max));
+            assert_eq!(fast_dot(&min, &min), naive_dot_i32(&min, &min));
+            assert_eq!(fast_dot(&min, &max), naive_dot_i32(&min, &max));
+        }
+        let max = [i32::MAX; 3];
+        assert_eq!(fast_dot(&max, &max), 13_835_058_042_397_261_827);
+    }
+
+    #[test]
+    fn floats_match_naive_loops() {
+        let mut rng = XorShift64::new(2);
+        for &len in LENGTHS {
+            let left: Vec<f64> = (0..len).map(|_| rng.next_f64() * 200.0 - 100.0).collect();
+            let right: Vec<f64> = (0..len).map(|_| rng.next_f64() * 200.0 - 100.0).collect();
+            let sum: f64 = left.iter().sum();
+            let dot: f64 = left.iter().zip(&right).map(|(l, r)| l * r).sum();
+            assert!(close(fast_sum(&left), sum), "len {}", len);
+            assert!(close(fast_dot(&left, &right), dot), "len {}", len);
+            assert_eq!(fast_min_max(&left), naive_min_max_f64(&left), "len {}", len);
+        }
+    }
+
+    #[test]
+    fn empty_slices() {
+        assert_eq!(fast_sum::<i32>(&[]), 0);
+        assert_eq!(fast_sum::<f64>(&[]), 0.0);
+        assert_eq!(fast_min_max::<i32>(&[]), None);
+        assert_eq!(fast_min_max::<f64>(&[]), None);
+        assert_eq!(fast_dot::<i32>(&[], &[]), 0);
+        assert_eq!(fast_dot::<f64>(&[], &[]), 0.0);
+    }
+
+    #[test]
+    fn nan_values() {
+        for &len in LENGTHS.iter().filter(|&&len| len > 0) {
+            for &at in &[0, len / 2, len - 1] {
+                let mut values: Vec<f64> = (0..len).map(|i| i as f64).collect();
+                values[at] = f64::NAN;
+                assert!(fast_sum(&values).is_nan());
+                assert!(fast_dot(&values, &values).is_nan());
+                if len > 1 {
+                    assert_eq!(fast_min_max(&values), naive_min_max_f64(&values));
+                    let min = if at == 0 { 1.0 } else { 0.0 };
+                    let max = if at == len - 1 { len - 2 } else { len - 1 } as f64;
+                    assert_eq!(fast_min_max(&values), Some((min, max)));
+                }
+            }
+            let nans = vec![f64::NAN; len];
+            let (min, max) = fast_min_max(&nans).unwrap();
+            assert!(min.is_nan() && max.is_nan());
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "same length")]
+    fn dot_of_different_lengths_panics() {
+        fast_dot(&[1, 2], &[1]);
+    }
+}
@@ -0,0 +1,136 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! An immutable map laid out as one sorted slice
+//!
+//! Entries are collected with a `FrozenMapBuilder` and then frozen into a
+//! `FrozenMap`, which can no longer change. Keeping every entry side by side
+//! in key order makes lookups a binary search over contiguous memory, which
+//! suits read-heavy workloads that are built once and queried many times.
+
+use std::borrow::Borrow;
+
+/// Collects key/value pairs for a `FrozenMap`
+#[derive(Debug, Clone)]
+pub struct FrozenMapBuilder<K, V> {
+    entries: Vec<(K, V)>,
+}
+
+impl<K: Ord, V> FrozenMapBuilder<K, V> {
+    pub fn new() -> Self {
+        FrozenMapBuilder {
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        FrozenMapBuilder {
+            entries: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Adds an entry; if a key is added more than once, the last value wins
+    pub fn insert(&mut self, key: K, value: V) -> &mut Self {
+        self.entries.push((key, value));
+        self
+    }
+
+    /// Returns the number of entries added so far, duplicates included
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Sorts the collected entries and produces the read-only map
+    pub fn freeze(self) -> FrozenMap<K, V> {
+        let mut entries = self.entries;
+
+        // The sort is stable, so duplicates stay in insertion order. Reversing
+        // puts the newest duplicate first, which is the one `dedup_by` keeps.
+        entries.sort_by(|a, b| a.0.cmp(&b.0));
+        entries.reverse();
+        entries.dedup_by(|a, b| a.0 == b.0);
+        entries.reverse();
+        entries.shrink_to_fit();
+
+        FrozenMap {
+            entries: entries.into_boxed_slice(),
+        }
+    }
+}
+
+impl<K: Ord, V> Default for FrozenMapBuilder<K, V> {
+    fn default() -> Self {
+        FrozenMapBuilder::new()
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for FrozenMapBuilder<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        self.entries.extend(iter);
+    }
+}
+
+/// A read-only map stored as a sorted slice of entries
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrozenMap<K, V> {
+    entries: Box<[(K, V)]>,
+}
+
+impl<K: Ord, V> FrozenMap<K, V> {
+    /// Finds the value for `key` with a binary search
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.position(key).map(|index| &self.entries[index].1)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.position(key).is_some()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Iterates over the entries in key order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &K> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &V> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    /// Returns the sorted entries as one contiguous slice
+    pub fn as_slice(&self) -> &[(K, V)] {
+        &self.entries
+    }
+
+    fn position<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.entries
+            .binary_search_by(|(probe, _)| probe.borrow().cmp(key))
+            .ok()
+    }
+}
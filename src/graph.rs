@@ -0,0 +1,201 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A graph of weighted nodes and edges stored as adjacency lists
+//!
+//! Nodes are kept in a `Vec` and named by the `NodeId` returned when they
+//! were added, and each node keeps a list of its outgoing edges and their
+//! weights. An undirected graph stores every edge in both endpoints' lists
+//! but counts it once. Traversals visit neighbours in the order their edges
+//! were added, so `bfs` and `dfs` give the same answer every run.
+
+use crate::union_find::UnionFind;
+use std::collections::VecDeque;
+use std::mem;
+
+/// Names a node in the `Graph` that created it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct NodeId(usize);
+
+impl NodeId {
+    /// The node's position in insertion order, starting from zero
+    pub fn index(self) -> usize {
+        self.0
+    }
+}
+
+/// A directed or undirected graph with node data `N` and edge weights `E`
+///
+/// Every method taking a `NodeId` panics if the node isn't in this graph.
+#[derive(Debug, Clone)]
+pub struct Graph<N, E> {
+    nodes: Vec<N>,
+    adjacency: Vec<Vec<(NodeId, E)>>,
+    edges: usize,
+    directed: bool,
+}
+
+impl<N, E> Graph<N, E> {
+    /// Creates an empty graph whose edges run one way
+    pub fn directed() -> Self {
+        Graph::with_direction(true)
+    }
+
+    /// Creates an empty graph whose edges run both ways
+    pub fn undirected() -> Self {
+        Graph::with_direction(false)
+    }
+
+    fn with_direction(directed: bool) -> Self {
+        Graph {
+            nodes: Vec::new(),
+            adjacency: Vec::new(),
+            edges: 0,
+            directed,
+        }
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    pub fn node_count(&self) -> usize {
+        self.nodes.len()
+    }
+
+    /// Number of edges, counting each undirected edge once
+    pub fn edge_count(&self) -> usize {
+        self.edges
+    }
+
+    pub fn add_node(&mut self, data: N) -> NodeId {
+        self.nodes.push(data);
+        self.adjacency.push(Vec::new());
+        NodeId(self.nodes.len() - 1)
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&N> {
+        self.nodes.get(id.0)
+    }
+
+    pub fn node_mut(&mut self, id: NodeId) -> Option<&mut N> {
+        self.nodes.get_mut(id.0)
+    }
+
+    /// Iterates over every node in the order they were added
+    pub fn nodes(&self) -> impl Iterator<Item = (NodeId, &N)> {
+        self.nodes
+            .iter()
+            .enumerate()
+            .map(|(index, data)| (NodeId(index), data))
+    }
+
+    /// Adds an edge from `from` to `to`, or replaces the weight of the edge
+    /// already there and returns the old weight
+    pub fn add_edge(&mut self, from: NodeId, to: NodeId, weight: E) -> Option<E>
+    where
+        E: Clone,
+    {
+        self.check(from);
+        self.check(to);
+        if !self.directed && from != to {
+            Self::set_weight(&mut self.adjacency[to.0], from, weight.clone());
+        }
+        let old = Self::set_weight(&mut self.adjacency[from.0], to, weight);
+        if old.is_none() {
+            self.edges += 1;
+        }
+        old
+    }
+
+    pub fn edge(&self, from: NodeId, to: NodeId) -> Option<&E> {
+        self.adjacency[from.0]
+            .iter()
+            .find(|(target, _)| *target == to)
+            .map(|(_, weight)| weight)
+    }
+
+    pub fn has_edge(&self, from: NodeId, to: NodeId) -> bool {
+        self.edge(from, to).is_some()
+    }
+
+    /// Iterates over the nodes reachable from `id` by one edge
+    pub fn neighbors(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        self.adjacency[id.0].iter().map(|(target, _)| *target)
+    }
+
+    /// Iterates over the edges leaving `id` as `(target, weight)`
+    pub fn edges_from(&self, id: NodeId) -> impl Iterator<Item = (NodeId, &E)> {
+        self.adjacency[id.0]
+            .iter()
+            .map(|(target, weight)| (*target, weight))
+    }
+
+    /// Returns the nodes reachable from `start` in breadth-first order
+    pub fn bfs(&self, start: NodeId) -> Vec<NodeId> {
+        self.check(start);
+        let mut seen = vec![false; self.nodes.len()];
+        let mut order = Vec::new();
+        let mut queue = VecDeque::new();
+        seen[start.0] = true;
+        queue.push_back(start);
+
+        while let Some(id) = queue.pop_front() {
+            order.push(id);
+            for next in self.neighbors(id) {
+                if !mem::replace(&mut seen[next.0], true) {
+                    queue.push_back(next);
+                }
+            }
+        }
+        order
+    }
+
+    /// Returns the nodes reachable from `start` in depth-first preorder
+    pub fn dfs(&self, start: NodeId) -> Vec<NodeId> {
+        self.check(start);
+        let mut seen = vec![false; self.nodes.len()];
+        let mut order = Vec::new();
+        let mut stack = vec![start];
+
+        while let Some(id) = stack.pop() {
+            if mem::replace(&mut seen[id.0], true) {
+                continue;
+            }
+            order.push(id);
+            // Pushed in reverse so the first neighbour is explored first
+            let unseen: Vec<NodeId> = self.neighbors(id).filter(|n| !seen[n.0]).collect();
+            stack.extend(unseen.into_iter().rev());
+        }
+        order
+    }
+
+    /// Groups the nodes into connected components, ignoring edge direction,
+    /// in order of each component's first node
+    pub fn connected_components(&self) -> Vec<Vec<NodeId>> {
+        let mut sets = UnionFind::new(self.nodes.len());
+        for (from, edges) in self.adjacency.iter().enumerate() {
+            for (to, _) in edges {
+                sets.union(from, to.0);
+            }
+        }
+        sets.groups()
+            .into_iter()
+            .map(|group| group.into_iter().map(NodeId).collect())
+            .collect()
+    }
+
+    fn check(&self, id: NodeId) {
+        assert!(id.0 < self.nodes.len(), "{:?} is not in this graph", id);
+    }
+
+    fn set_weight(edges: &mut Vec<(NodeId, E)>, to: NodeId, weight: E) -> Option<E> {
+        match edges.iter_mut().find(|(target, _)| *target == to) {
+            Some((_, existing)) => Some(mem::replace(existing, weight)),
+            None => {
+                edges.push((to, weight));
+                None
+            }
+        }
+    }
+}
@@ -0,0 +1,154 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A dense two-dimensional grid stored row by row in one `Vec`
+//!
+//! Cell `(row, col)` lives at index `row * cols + col`, so `Grid` does that
+//! index arithmetic in one place. `enumerate_2d` walks the cells in row-major
+//! order together with their coordinates, which replaces the nested
+//! `enumerate()` loops a `Vec<Vec<T>>` needs.
+
+use crate::deep_size::DeepSize;
+
+/// A `rows` by `cols` grid of `T`
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Grid<T> {
+    rows: usize,
+    cols: usize,
+    cells: Vec<T>,
+}
+
+impl<T> Grid<T> {
+    /// Creates a grid with every cell set to `value`
+    pub fn new(rows: usize, cols: usize, value: T) -> Self
+    where
+        T: Clone,
+    {
+        Grid {
+            rows,
+            cols,
+            cells: vec![value; rows * cols],
+        }
+    }
+
+    /// Creates a grid by calling `f` with the coordinates of every cell
+    pub fn from_fn<F>(rows: usize, cols: usize, mut f: F) -> Self
+    where
+        F: FnMut((usize, usize)) -> T,
+    {
+        let mut cells = Vec::with_capacity(rows * cols);
+        for row in 0..rows {
+            for col in 0..cols {
+                cells.push(f((row, col)));
+            }
+        }
+        Grid { rows, cols, cells }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        self.index_of(row, col).map(|index| &self.cells[index])
+    }
+
+    pub fn get_mut(&mut self, row: usize, col: usize) -> Option<&mut T> {
+        self.index_of(row, col)
+            .map(move |index| &mut self.cells[index])
+    }
+
+    /// The cells of one row, or `None` if `row` is out of range
+    pub fn row(&self, row: usize) -> Option<&[T]> {
+        if row >= self.rows {
+            return None;
+        }
+        Some(&self.cells[row * self.cols..(row + 1) * self.cols])
+    }
+
+    /// Iterates over every cell in row-major order with its coordinates
+    pub fn enumerate_2d(&self) -> impl Iterator<Item = ((usize, usize), &T)> {
+        let cols = self.cols;
+        self.cells
+            .iter()
+            .enumerate()
+            .map(move |(index, cell)| ((index / cols, index % cols), cell))
+    }
+
+    /// Like `enumerate_2d`, but with mutable access to each cell
+    pub fn enumerate_2d_mut(&mut self) -> impl Iterator<Item = ((usize, usize), &mut T)> {
+        let cols = self.cols;
+        self.cells
+            .iter_mut()
+            .enumerate()
+            .map(move |(index, cell)| ((index / cols, index % cols), cell))
+    }
+
+    /// Builds a grid of the same shape by calling `f` with each cell and its
+    /// coordinates
+    pub fn indexed_map<U, F>(&self, mut f: F) -> Grid<U>
+    where
+        F: FnMut((usize, usize), &T) -> U,
+    {
+        Grid {
+            rows: self.rows,
+            cols: self.cols,
+            cells: self
+                .enumerate_2d()
+                .map(|(coordinates, cell)| f(coordinates, cell))
+                .collect(),
+        }
+    }
+
+    /// The rows as slices, spread over several threads for large grids
+    #[cfg(feature = "parallel")]
+    pub fn par_rows(&self) -> crate::parallel::ParIter<impl Iterator<Item = &[T]> + Send>
+    where
+        T: Sync,
+    {
+        let (cols, cells) = (self.cols, &self.cells);
+        crate::parallel::par_ranges(self.rows, cells.len(), move |range| {
+            range.map(move |row| &cells[row * cols..(row + 1) * cols])
+        })
+    }
+
+    /// `enumerate_2d` spread over several threads for large grids, a run of
+    /// whole rows to each
+    #[cfg(feature = "parallel")]
+    pub fn par_cells(
+        &self,
+    ) -> crate::parallel::ParIter<impl Iterator<Item = ((usize, usize), &T)> + Send>
+    where
+        T: Sync,
+    {
+        let (cols, cells) = (self.cols, &self.cells);
+        crate::parallel::par_ranges(self.rows, cells.len(), move |range| {
+            let start = range.start * cols;
+            cells[start..range.end * cols]
+                .iter()
+                .enumerate()
+                .map(move |(offset, cell)| {
+                    let index = start + offset;
+                    ((index / cols, index % cols), cell)
+                })
+        })
+    }
+
+    fn index_of(&self, row: usize, col: usize) -> Option<usize> {
+        if row < self.rows && col < self.cols {
+            Some(row * self.cols + col)
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: DeepSize> DeepSize for Grid<T> {
+    fn heap_bytes(&self) -> usize {
+        self.cells.heap_bytes()
+    }
+}
@@ -0,0 +1,225 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Group the items of an iterator by a key into a `HashMap`
+//!
+//! Within each group, items keep the order in which the iterator produced
+//! them, so grouping the same input twice always gives the same groups. The
+//! `_with_hasher` variants build the result with a caller-chosen hasher.
+//!
+//! For input that is already sorted by key, `group_runs` finds the same
+//! groups lazily, one run of equal keys at a time, without holding more than
+//! the current group in memory.
+
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+
+/// Groups items by `key_fn`, keeping each item
+pub fn group_by<I, K, F>(iter: I, key_fn: F) -> HashMap<K, Vec<I::Item>>
+where
+    I: IntoIterator,
+    K: Eq + Hash,
+    F: FnMut(&I::Item) -> K,
+{
+    group_by_with_hasher(iter, key_fn, Default::default())
+}
+
+/// Groups items by `key_fn`, keeping `value_fn(item)` instead of each item
+pub fn group_map<I, K, V, F, G>(iter: I, key_fn: F, value_fn: G) -> HashMap<K, Vec<V>>
+where
+    I: IntoIterator,
+    K: Eq + Hash,
+    F: FnMut(&I::Item) -> K,
+    G: FnMut(I::Item) -> V,
+{
+    group_map_with_hasher(iter, key_fn, value_fn, Default::default())
+}
+
+/// Counts how many items fall into each group
+pub fn group_count<I, K, F>(iter: I, key_fn: F) -> HashMap<K, usize>
+where
+    I: IntoIterator,
+    K: Eq + Hash,
+    F: FnMut(&I::Item) -> K,
+{
+    group_count_with_hasher(iter, key_fn, Default::default())
+}
+
+/// Like `group_by`, but builds the result with `hasher`
+pub fn group_by_with_hasher<I, K, F, S>(
+    iter: I,
+    key_fn: F,
+    hasher: S,
+) -> HashMap<K, Vec<I::Item>, S>
+where
+    I: IntoIterator,
+    K: Eq + Hash,
+    F: FnMut(&I::Item) -> K,
+    S: BuildHasher,
+{
+    group_map_with_hasher(iter, key_fn, |item| item, hasher)
+}
+
+/// Like `group_map`, but builds the result with `hasher`
+pub fn group_map_with_hasher<I, K, V, F, G, S>(
+    iter: I,
+    mut key_fn: F,
+    mut value_fn: G,
+    hasher: S,
+) -> HashMap<K, Vec<V>, S>
+where
+    I: IntoIterator,
+    K: Eq + Hash,
+    F: FnMut(&I::Item) -> K,
+    G: FnMut(I::Item) -> V,
+    S: BuildHasher,
+{
+    let mut groups: HashMap<K, Vec<V>, S> = HashMap::with_hasher(hasher);
+    for item in iter {
+        let key = key_fn(&item);
+        groups.entry(key).or_default().push(value_fn(item));
+    }
+    groups
+}
+
+/// Like `group_count`, but builds the result with `hasher`
+pub fn group_count_with_hasher<I, K, F, S>(
+    iter: I,
+    mut key_fn: F,
+    hasher: S,
+) -> HashMap<K, usize, S>
+where
+    I: IntoIterator,
+    K: Eq + Hash,
+    F: FnMut(&I::Item) -> K,
+    S: BuildHasher,
+{
+    let mut counts = HashMap::with_hasher(hasher);
+    for item in iter {
+        *counts.entry(key_fn(&item)).or_insert(0) += 1;
+    }
+    counts
+}
+
+/// Like `group_by`, but puts the groups in `bump` instead of a `Vec` each
+///
+/// All the groups share one slice in the arena, so grouping makes a few
+/// allocations however many groups there are. Items are copied there, so
+/// they must be `Copy`, such as references or row numbers.
+#[cfg(feature = "bump")]
+pub fn group_by_in<I, K, F>(
+    iter: I,
+    mut key_fn: F,
+    bump: &crate::bump::Bump,
+) -> HashMap<K, &[I::Item]>
+where
+    I: IntoIterator,
+    I::Item: Copy,
+    K: Eq + Hash,
+    F: FnMut(&I::Item) -> K,
+{
+    // Number each group, and note every item's group and each group's size
+    let iter = iter.into_iter();
+    let mut numbers: HashMap<K, usize> = HashMap::new();
+    let mut sizes = Vec::new();
+    let mut items = Vec::with_capacity(iter.size_hint().0);
+    for item in iter {
+        let next = sizes.len();
+        let number = *numbers.entry(key_fn(&item)).or_insert(next);
+        if number == next {
+            sizes.push(0);
+        }
+        sizes[number] += 1;
+        items.push((number, item));
+    }
+    let first = match items.first() {
+        Some(&(_, item)) => item,
+        None => return HashMap::new(),
+    };
+
+    // Lay the groups out one after another, each in input order
+    let mut starts = Vec::with_capacity(sizes.len());
+    let mut end = 0;
+    for size in sizes {
+        starts.push(end);
+        end += size;
+    }
+    let all = bump.alloc_slice_fill_iter(std::iter::repeat_n(first, items.len()));
+    let mut next = starts.clone();
+    for (number, item) in items {
+        all[next[number]] = item;
+        next[number] += 1;
+    }
+
+    let all = &*all;
+    numbers
+        .into_iter()
+        .map(|(key, number)| (key, &all[starts[number]..next[number]]))
+        .collect()
+}
+
+/// Groups consecutive items with equal keys, yielding each run as
+/// `(key, items)` as soon as it ends
+///
+/// On input sorted by key this gives the same groups as `group_by`, in key
+/// order. On unsorted input, a key that appears in several separate runs is
+/// yielded once per run.
+pub fn group_runs<I, K, F>(iter: I, key_fn: F) -> GroupRuns<I::IntoIter, K, F>
+where
+    I: IntoIterator,
+    K: PartialEq,
+    F: FnMut(&I::Item) -> K,
+{
+    GroupRuns {
+        iter: iter.into_iter(),
+        key_fn,
+        pending: None,
+    }
+}
+
+/// Iterator returned by `group_runs`
+#[derive(Debug, Clone)]
+pub struct GroupRuns<I: Iterator, K, F> {
+    iter: I,
+    key_fn: F,
+    // The first item of the next run and its key, already pulled from `iter`
+    pending: Option<(K, I::Item)>,
+}
+
+impl<I, K, F> Iterator for GroupRuns<I, K, F>
+where
+    I: Iterator,
+    K: PartialEq,
+    F: FnMut(&I::Item) -> K,
+{
+    type Item = (K, Vec<I::Item>);
+
+    fn next(&mut self) -> Option<(K, Vec<I::Item>)> {
+        let (key, first) = match self.pending.take() {
+            Some(pending) => pending,
+            None => {
+                let item = self.iter.next()?;
+                ((self.key_fn)(&item), item)
+            }
+        };
+
+        let mut run = vec![first];
+        for item in self.iter.by_ref() {
+            let next_key = (self.key_fn)(&item);
+            if next_key != key {
+                self.pending = Some((next_key, item));
+                break;
+            }
+            run.push(item);
+        }
+        Some((key, run))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.pending.is_some() as usize;
+        let (lower, upper) = self.iter.size_hint();
+        // However many items are left, they might all share one key
+        let lower = (pending > 0 || lower > 0) as usize;
+        (lower, upper.and_then(|upper| upper.checked_add(pending)))
+    }
+}
@@ -0,0 +1,335 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Parsing INI and `.env` style configuration into nested maps
+//!
+//! The result maps each section name to that section's keys and values.
+//! Keys that appear before the first `[section]` header go in the section
+//! named `""`, which is all a `.env` file ever has. The parser accepts:
+//!
+//! - `[section]` headers and `key = value` lines, with an optional leading
+//!   `export` as in shell-sourced `.env` files
+//! - comments on their own line starting with `;` or `#`, after a section
+//!   header or a quoted value, and after an unquoted value when preceded by
+//!   whitespace
+//! - double-quoted values with `\n`, `\t`, `\"`, `\\` and `\$` escapes, and
+//!   single-quoted values taken literally
+//! - `${VAR}` in unquoted and double-quoted values, replaced by the
+//!   environment variable `VAR`; `$$` stands for a literal `$`
+//!
+//! A key repeated within a section keeps its last value.
+
+use std::collections::HashMap;
+use std::env;
+use std::error::Error;
+use std::fmt;
+
+/// Sections of `key = value` pairs, keyed by section name
+pub type IniSections = HashMap<String, HashMap<String, String>>;
+
+/// What went wrong on a line of configuration
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IniErrorKind {
+    /// A `[` header with no closing `]`
+    UnterminatedSection,
+    /// A line that is neither a header, a comment nor `key = value`
+    MissingEquals,
+    EmptyKey,
+    UnterminatedQuote,
+    /// A `${` with no closing `}`
+    UnterminatedVariable,
+    /// A `${VAR}` naming a variable that isn't set
+    UndefinedVariable(String),
+    /// Text after a closing quote or `]` that isn't a comment
+    TrailingCharacters,
+}
+
+/// A parse failure and the 1-based line it happened on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IniError {
+    pub line: usize,
+    pub kind: IniErrorKind,
+}
+
+impl fmt::Display for IniError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            IniErrorKind::UnterminatedSection => write!(f, "section header is missing ']'"),
+            IniErrorKind::MissingEquals => write!(f, "expected 'key = value'"),
+            IniErrorKind::EmptyKey => write!(f, "key is empty"),
+            IniErrorKind::UnterminatedQuote => write!(f, "quoted value is never closed"),
+            IniErrorKind::UnterminatedVariable => write!(f, "'${{' is missing its '}}'"),
+            IniErrorKind::UndefinedVariable(name) => {
+                write!(f, "environment variable '{}' is not set", name)
+            }
+            IniErrorKind::TrailingCharacters => {
+                write!(f, "unexpected characters after quoted value or header")
+            }
+        }
+    }
+}
+
+impl Error for IniError {}
+
+/// Parses `text`, reading `${VAR}` references from the process environment
+pub fn parse_ini(text: &str) -> Result<IniSections, IniError> {
+    parse_ini_with(text, |name| env::var(name).ok())
+}
+
+/// Parses `text`, resolving `${VAR}` references with `lookup` instead of the
+/// environment
+pub fn parse_ini_with<F>(text: &str, lookup: F) -> Result<IniSections, IniError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut sections = IniSections::new();
+    let mut section = String::new();
+
+    for (index, raw) in text.lines().enumerate() {
+        let line = index + 1;
+        let error = |kind| IniError { line, kind };
+        let trimmed = raw.trim();
+        if trimmed.is_empty() || trimmed.starts_with(';') || trimmed.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = trimmed.strip_prefix('[') {
+            let end = header
+                .find(']')
+                .ok_or_else(|| error(IniErrorKind::UnterminatedSection))?;
+            check_trailing(&header[end + 1..]).map_err(error)?;
+            section = header[..end].trim().to_string();
+            sections.entry(section.clone()).or_default();
+            continue;
+        }
+
+        let (key, value) = trimmed
+            .split_once('=')
+            .ok_or_else(|| error(IniErrorKind::MissingEquals))?;
+        // `export KEY=value` as in a shell-sourced `.env`, while a key that
+        // is just `export` stays a key
+        let key = match key.trim().strip_prefix("export ") {
+            Some(exported) if !exported.trim().is_empty() => exported.trim(),
+            _ => key.trim(),
+        };
+        if key.is_empty() {
+            return Err(error(IniErrorKind::EmptyKey));
+        }
+        let value = parse_value(value.trim(), &lookup).map_err(error)?;
+
+        sections
+            .entry(section.clone())
+            .or_default()
+            .insert(key.to_string(), value);
+    }
+    Ok(sections)
+}
+
+fn parse_value<F>(value: &str, lookup: &F) -> Result<String, IniErrorKind>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    if let Some(rest) = value.strip_prefix('\'') {
+        let end = rest.find('\'').ok_or(IniErrorKind::UnterminatedQuote)?;
+        check_trailing(&rest[end + 1..])?;
+        return Ok(rest[..end].to_string());
+    }
+
+    if let Some(rest) = value.strip_prefix('"') {
+        let mut unescaped = String::new();
+        let mut chars = rest.char_indices();
+        while let Some((index, c)) = chars.next() {
+            match c {
+                '"' => {
+                    check_trailing(&rest[index + 1..])?;
+                    return interpolate(&unescaped, lookup);
+                }
+                '\\' => match chars.next() {
+                    Some((_, 'n')) => unescaped.push('\n'),
+                    Some((_, 't')) => unescaped.push('\t'),
+                    // An escaped `$` must survive interpolation as a literal
+                    Some((_, '$')) => unescaped.push_str("$$"),
+                    Some((_, other)) => unescaped.push(other),
+                    None => return Err(IniErrorKind::UnterminatedQuote),
+                },
+                '$' => unescaped.push('$'),
+                _ => unescaped.push(c),
+            }
+        }
+        return Err(IniErrorKind::UnterminatedQuote);
+    }
+
+    interpolate(strip_inline_comment(value), lookup)
+}
+
+// Only whitespace or a comment may follow a closing quote
+fn check_trailing(rest: &str) -> Result<(), IniErrorKind> {
+    let rest = rest.trim_start();
+    if rest.is_empty() || rest.starts_with(';') || rest.starts_with('#') {
+        Ok(())
+    } else {
+        Err(IniErrorKind::TrailingCharacters)
+    }
+}
+
+// A `;` or `#` only starts a comment after whitespace, so values such as
+// `a#b` or URLs with fragments survive intact
+fn strip_inline_comment(value: &str) -> &str {
+    let mut previous_was_space = false;
+    for (index, c) in value.char_indices() {
+        if previous_was_space && (c == ';' || c == '#') {
+            return value[..index].trim_end();
+        }
+        previous_was_space = c.is_whitespace();
+    }
+    value
+}
+
+fn interpolate<F>(value: &str, lookup: &F) -> Result<String, IniErrorKind>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(dollar) = rest.find('$') {
+        result.push_str(&rest[..dollar]);
+        let after = &rest[dollar + 1..];
+        if let Some(after) = after.strip_prefix('$') {
+            result.push('$');
+            rest = after;
+        } else if let Some(after) = after.strip_prefix('{') {
+            let end = after.find('}').ok_or(IniErrorKind::UnterminatedVariable)?;
+            let name = &after[..end];
+            let replacement =
+                lookup(name).ok_or_else(|| IniErrorKind::UndefinedVariable(name.to_string()))?;
+            result.push_str(&replacement);
+            rest = &after[end + 1..];
+        } else {
+            result.push('$');
+            rest = after;
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Stands in for the environment, with only `HOME` and `EMPTY` set
+    fn lookup(name: &str) -> Option<String> {
+        match name {
+            "HOME" => Some("/home/greg".to_string()),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        }
+    }
+
+    fn parse(text: &str) -> Result<IniSections, IniError> {
+        parse_ini_with(text, lookup)
+    }
+
+    fn value(text: &str) -> String {
+        let sections = parse(&format!("key = {}", text)).unwrap();
+        sections[""]["key"].clone()
+    }
+
+    fn error(text: &str) -> IniError {
+        parse(text).unwrap_err()
+    }
+
+    #[test]
+    fn sections_and_keys() {
+        let sections = parse(
+            "top = 1\n\
+             ; a comment\n\
+             # another\n\
+             \n\
+             [server] ; main\n\
+             host = example.com\n\
+             port=8080\n\
+             [ empty ]\n\
+             [server]\n\
+             host = other.com",
+        )
+        .unwrap();
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[""]["top"], "1");
+        assert_eq!(sections["server"]["host"], "other.com");
+        assert_eq!(sections["server"]["port"], "8080");
+        assert!(sections["empty"].is_empty());
+    }
+
+    #[test]
+    fn export_is_stripped_only_before_a_key() {
+        let sections = parse("export PATH=/bin\nexport = yes\nexported = no").unwrap();
+        assert_eq!(sections[""]["PATH"], "/bin");
+        assert_eq!(sections[""]["export"], "yes");
+        assert_eq!(sections[""]["exported"], "no");
+        assert_eq!(parse("export =").unwrap()[""]["export"], "");
+    }
+
+    #[test]
+    fn unquoted_values() {
+        assert_eq!(value("plain text  "), "plain text");
+        assert_eq!(value("a#b"), "a#b");
+        assert_eq!(value("http://x/y;z"), "http://x/y;z");
+        assert_eq!(value("a # comment"), "a");
+        assert_eq!(value("a ; comment"), "a");
+        assert_eq!(value(""), "");
+        assert_eq!(value("${HOME}/bin"), "/home/greg/bin");
+        assert_eq!(value("x${EMPTY}y"), "xy");
+        assert_eq!(value("cost $$5 or $5"), "cost $5 or $5");
+    }
+
+    #[test]
+    fn double_quoted_values() {
+        assert_eq!(value(r#""  spaced # kept ""#), "  spaced # kept ");
+        assert_eq!(value(r#""a\nb\t\"c\" \\""#), "a\nb\t\"c\" \\");
+        assert_eq!(value(r#""${HOME}""#), "/home/greg");
+        assert_eq!(value(r#""\${HOME}""#), "${HOME}");
+        assert_eq!(value(r#""$$HOME""#), "$HOME");
+        assert_eq!(value(r#""\$$""#), "$$");
+        assert_eq!(value(r#""x" ; comment"#), "x");
+    }
+
+    #[test]
+    fn single_quoted_values_are_literal() {
+        assert_eq!(value("'${HOME} \\n'"), "${HOME} \\n");
+        assert_eq!(value("'$$'"), "$$");
+        assert_eq!(value("'\\$'"), "\\$");
+        assert_eq!(value("'x' # comment"), "x");
+    }
+
+    #[test]
+    fn each_error_kind() {
+        let cases = [
+            ("[server", IniErrorKind::UnterminatedSection),
+            ("[server] extra", IniErrorKind::TrailingCharacters),
+            ("just words", IniErrorKind::MissingEquals),
+            ("= value", IniErrorKind::EmptyKey),
+            ("key = \"open", IniErrorKind::UnterminatedQuote),
+            ("key = 'open", IniErrorKind::UnterminatedQuote),
+            ("key = \"ends in \\", IniErrorKind::UnterminatedQuote),
+            ("key = ${HOME", IniErrorKind::UnterminatedVariable),
+            (
+                "key = ${MISSING}",
+                IniErrorKind::UndefinedVariable("MISSING".to_string()),
+            ),
+            ("key = \"x\" y", IniErrorKind::TrailingCharacters),
+            ("key = 'x'y", IniErrorKind::TrailingCharacters),
+        ];
+        for (text, kind) in cases.iter() {
+            assert_eq!(error(text).kind, *kind, "{}", text);
+        }
+    }
+
+    #[test]
+    fn errors_report_their_line() {
+        let err = error("a = 1\n\n[ok]\nbroken");
+        assert_eq!(err.line, 4);
+        assert_eq!(err.to_string(), "line 4: expected 'key = value'");
+    }
+}
@@ -0,0 +1,111 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Merging several iterators by taking turns
+//!
+//! `interleave` alternates between two iterators and `round_robin` cycles
+//! through any number of them, one item from each in turn. A source that runs
+//! out simply drops out of the rotation while the rest carry on, so every
+//! item from every source is yielded exactly once.
+
+/// Alternates between `a` and `b`, starting with `a`, and finishes whichever
+/// is longer once the other runs out
+pub fn interleave<A, B>(a: A, b: B) -> Interleave<A::IntoIter, B::IntoIter>
+where
+    A: IntoIterator,
+    B: IntoIterator<Item = A::Item>,
+{
+    Interleave {
+        a: a.into_iter(),
+        b: b.into_iter(),
+        a_next: true,
+    }
+}
+
+/// Takes one item from each source in turn, skipping sources that have run
+/// out, until all of them have
+pub fn round_robin<I: Iterator>(sources: Vec<I>) -> RoundRobin<I> {
+    RoundRobin { sources, next: 0 }
+}
+
+/// Iterator returned by `interleave`
+#[derive(Debug, Clone)]
+pub struct Interleave<A, B> {
+    a: A,
+    b: B,
+    a_next: bool,
+}
+
+impl<A, B> Iterator for Interleave<A, B>
+where
+    A: Iterator,
+    B: Iterator<Item = A::Item>,
+{
+    type Item = A::Item;
+
+    fn next(&mut self) -> Option<A::Item> {
+        self.a_next = !self.a_next;
+        if self.a_next {
+            self.b.next().or_else(|| self.a.next())
+        } else {
+            self.a.next().or_else(|| self.b.next())
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (a_lower, a_upper) = self.a.size_hint();
+        let (b_lower, b_upper) = self.b.size_hint();
+        let upper = match (a_upper, b_upper) {
+            (Some(a), Some(b)) => a.checked_add(b),
+            _ => None,
+        };
+        (a_lower.saturating_add(b_lower), upper)
+    }
+}
+
+/// Iterator returned by `round_robin`
+#[derive(Debug, Clone)]
+pub struct RoundRobin<I> {
+    // Only the sources that haven't run out yet
+    sources: Vec<I>,
+    // The source whose turn it is
+    next: usize,
+}
+
+impl<I: Iterator> Iterator for RoundRobin<I> {
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        while !self.sources.is_empty() {
+            if self.next >= self.sources.len() {
+                self.next = 0;
+            }
+            match self.sources[self.next].next() {
+                Some(item) => {
+                    self.next += 1;
+                    return Some(item);
+                }
+                // The following source slides into this slot, so its turn
+                // comes next without moving `next`
+                None => {
+                    self.sources.remove(self.next);
+                }
+            }
+        }
+        None
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.sources.iter().map(Iterator::size_hint).fold(
+            (0, Some(0)),
+            |(lower, upper), (source_lower, source_upper)| {
+                (
+                    lower.saturating_add(source_lower),
+                    upper
+                        .zip(source_upper)
+                        .and_then(|(upper, source)| upper.checked_add(source)),
+                )
+            },
+        )
+    }
+}
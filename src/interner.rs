@@ -0,0 +1,90 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Storing each distinct string once
+//!
+//! An `Interner` hands out a `TextRef` for every string passed to `intern`,
+//! the same one each time the same string comes back, and keeps the text in
+//! a `TextArena`. A column of a few dozen category names repeated over
+//! millions of rows then costs a few dozen strings plus one small `TextRef`
+//! per row.
+//!
+//! Every distinct string is held twice, once in the arena and once as the
+//! key it's looked up by, so interning only pays off for repeated values.
+
+use crate::deep_size::DeepSize;
+use crate::text_arena::{TextArena, TextRef};
+use std::collections::HashMap;
+
+/// Deduplicating storage for strings
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Interner {
+    arena: TextArena,
+    ids: HashMap<String, TextRef>,
+    // Calls to `intern`, repeats included
+    interned: usize,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Interner {
+            arena: TextArena::new(),
+            ids: HashMap::new(),
+            interned: 0,
+        }
+    }
+
+    /// Returns the `TextRef` of `text`, storing it first if it's new
+    ///
+    /// Panics if the arena fills up, as `TextArena::push` does.
+    pub fn intern(&mut self, text: &str) -> TextRef {
+        self.interned += 1;
+        if let Some(&id) = self.ids.get(text) {
+            return id;
+        }
+        let id = self.arena.push(text);
+        self.ids.insert(text.to_string(), id);
+        id
+    }
+
+    /// The string behind `text`, which must have come from this interner
+    pub fn get(&self, text: TextRef) -> &str {
+        self.arena.get(text)
+    }
+
+    /// Number of distinct strings stored
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ids.is_empty()
+    }
+
+    /// Number of strings interned, counting every repeat
+    pub fn interned(&self) -> usize {
+        self.interned
+    }
+
+    /// Strings interned per string stored, or 1.0 before anything has been
+    /// interned
+    ///
+    /// A column of 50 categories over a million rows comes out at 20,000.
+    pub fn dedup_ratio(&self) -> f64 {
+        if self.ids.is_empty() {
+            1.0
+        } else {
+            self.interned as f64 / self.ids.len() as f64
+        }
+    }
+
+    pub fn arena(&self) -> &TextArena {
+        &self.arena
+    }
+}
+
+impl DeepSize for Interner {
+    fn heap_bytes(&self) -> usize {
+        self.arena.heap_bytes() + self.ids.heap_bytes()
+    }
+}
@@ -0,0 +1,336 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A map keyed by half-open ranges `start..end`
+//!
+//! Typical uses are labelling IP address blocks or time windows. The map can
+//! either allow intervals to overlap, in which case a point may fall into
+//! several of them, or reject any interval that overlaps one already stored.
+//!
+//! Intervals are kept sorted by start alone. When overlaps are rejected,
+//! only the last interval starting at or before a point can reach it, so
+//! lookups and `overlapping` take O(log n + k) for k results. When they are
+//! allowed, any earlier interval might be long enough to reach it, so
+//! `get`, `get_all` and `overlapping` check every interval starting before
+//! the point or range, which is O(n) on a large map.
+
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::ops::{Bound, Range};
+
+/// Whether an `IntervalMap` accepts overlapping intervals
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlapPolicy {
+    Allow,
+    Reject,
+}
+
+/// Why an interval could not be inserted
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IntervalError<K> {
+    /// The interval's start was not before its end
+    Empty,
+    /// The map rejects overlaps and the interval overlapped `existing`
+    Overlap { existing: Range<K> },
+}
+
+impl<K: fmt::Debug> fmt::Display for IntervalError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntervalError::Empty => write!(f, "interval is empty"),
+            IntervalError::Overlap { existing } => {
+                write!(f, "interval overlaps existing interval {:?}", existing)
+            }
+        }
+    }
+}
+
+impl<K: fmt::Debug> Error for IntervalError<K> {}
+
+/// A map from half-open intervals to values
+#[derive(Debug, Clone)]
+pub struct IntervalMap<K, V> {
+    // Start -> every interval beginning there, as `(end, value)`
+    intervals: BTreeMap<K, Vec<(K, V)>>,
+    policy: OverlapPolicy,
+    len: usize,
+}
+
+impl<K: Ord + Clone, V> IntervalMap<K, V> {
+    pub fn new(policy: OverlapPolicy) -> Self {
+        IntervalMap {
+            intervals: BTreeMap::new(),
+            policy,
+            len: 0,
+        }
+    }
+
+    pub fn policy(&self) -> OverlapPolicy {
+        self.policy
+    }
+
+    /// Number of stored intervals
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Maps every point in `range` to `value`
+    pub fn insert(&mut self, range: Range<K>, value: V) -> Result<(), IntervalError<K>> {
+        if range.start >= range.end {
+            return Err(IntervalError::Empty);
+        }
+
+        if self.policy == OverlapPolicy::Reject {
+            // Stored intervals are disjoint, so only the last one starting
+            // before `range.end` can reach into `range`
+            let before_end = self.intervals.range(..range.end.clone()).next_back();
+            if let Some((start, bucket)) = before_end {
+                let (end, _) = &bucket[0];
+                if *end > range.start {
+                    return Err(IntervalError::Overlap {
+                        existing: start.clone()..end.clone(),
+                    });
+                }
+            }
+        }
+
+        self.intervals
+            .entry(range.start)
+            .or_default()
+            .push((range.end, value));
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Returns the value of the earliest-starting interval containing `point`
+    pub fn get(&self, point: &K) -> Option<&V> {
+        self.get_entry(point).map(|(_, value)| value)
+    }
+
+    /// Like `get`, but also returns the interval that matched
+    pub fn get_entry(&self, point: &K) -> Option<(Range<&K>, &V)> {
+        if self.policy == OverlapPolicy::Reject {
+            return self
+                .intervals
+                .range(..=point)
+                .next_back()
+                .and_then(|(start, bucket)| {
+                    let (end, value) = &bucket[0];
+                    if point < end {
+                        Some((start..end, value))
+                    } else {
+                        None
+                    }
+                });
+        }
+
+        self.containing(point).next()
+    }
+
+    /// Returns every interval containing `point`, earliest start first
+    pub fn get_all(&self, point: &K) -> Vec<(Range<&K>, &V)> {
+        self.containing(point).collect()
+    }
+
+    /// Returns every interval that shares at least one point with `range`
+    pub fn overlapping(&self, range: Range<K>) -> Vec<(Range<&K>, &V)> {
+        if range.start >= range.end {
+            return Vec::new();
+        }
+
+        self.intervals
+            .range((self.scan_from(&range.start), Bound::Excluded(&range.end)))
+            .flat_map(|(start, bucket)| bucket.iter().map(move |(end, value)| (start..end, value)))
+            .filter(|(interval, _)| *interval.end > range.start)
+            .collect()
+    }
+
+    /// Removes the interval exactly matching `range`, returning its value
+    ///
+    /// If the same interval was inserted more than once, the oldest copy is
+    /// removed first.
+    pub fn remove(&mut self, range: &Range<K>) -> Option<V> {
+        let bucket = self.intervals.get_mut(&range.start)?;
+        let index = bucket.iter().position(|(end, _)| *end == range.end)?;
+        let (_, value) = bucket.remove(index);
+        if bucket.is_empty() {
+            self.intervals.remove(&range.start);
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Iterates over every interval in order of its start
+    pub fn iter(&self) -> impl Iterator<Item = (Range<&K>, &V)> {
+        self.intervals
+            .iter()
+            .flat_map(|(start, bucket)| bucket.iter().map(move |(end, value)| (start..end, value)))
+    }
+
+    pub fn clear(&mut self) {
+        self.intervals.clear();
+        self.len = 0;
+    }
+
+    fn containing<'a, 'b>(
+        &'a self,
+        point: &'b K,
+    ) -> impl Iterator<Item = (Range<&'a K>, &'a V)> + 'b
+    where
+        'a: 'b,
+    {
+        self.intervals
+            .range((self.scan_from(point), Bound::Included(point)))
+            .flat_map(|(start, bucket)| bucket.iter().map(move |(end, value)| (start..end, value)))
+            .filter(move |(interval, _)| point < interval.end)
+    }
+
+    // The earliest start an interval reaching `point` could have. Disjoint
+    // intervals can't reach past the next one's start, but overlapping ones
+    // could start anywhere before it.
+    fn scan_from(&self, point: &K) -> Bound<&K> {
+        match self.policy {
+            OverlapPolicy::Reject => self
+                .intervals
+                .range(..=point)
+                .next_back()
+                .map_or(Bound::Unbounded, |(start, _)| Bound::Included(start)),
+            OverlapPolicy::Allow => Bound::Unbounded,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, XorShift64};
+
+    type Model = Vec<(Range<u64>, usize)>;
+
+    // What every query should give, worked out by checking each interval.
+    // The sort is stable, so intervals sharing a start stay in the order
+    // they were inserted, as the map keeps them.
+    fn expected_containing(model: &Model, point: u64) -> Vec<(Range<u64>, usize)> {
+        let mut found: Model = model
+            .iter()
+            .filter(|(range, _)| range.contains(&point))
+            .cloned()
+            .collect();
+        found.sort_by_key(|(range, _)| range.start);
+        found
+    }
+
+    fn expected_overlapping(model: &Model, query: &Range<u64>) -> Vec<(Range<u64>, usize)> {
+        let mut found: Model = model
+            .iter()
+            .filter(|(range, _)| {
+                query.start < query.end && range.start < query.end && query.start < range.end
+            })
+            .cloned()
+            .collect();
+        found.sort_by_key(|(range, _)| range.start);
+        found
+    }
+
+    fn owned(found: Vec<(Range<&u64>, &usize)>) -> Model {
+        found
+            .into_iter()
+            .map(|(range, &value)| (*range.start..*range.end, value))
+            .collect()
+    }
+
+    fn check_against_model(policy: OverlapPolicy, seed: u64) {
+        let mut rng = XorShift64::new(seed);
+        let mut map = IntervalMap::new(policy);
+        let mut model: Model = Vec::new();
+        for value in 0..300 {
+            let start = rng.below(200);
+            let range = start..start + 1 + rng.below(20);
+            let overlaps = model
+                .iter()
+                .any(|(stored, _)| stored.start < range.end && range.start < stored.end);
+            match map.insert(range.clone(), value) {
+                Ok(()) => model.push((range, value)),
+                Err(IntervalError::Overlap { existing }) => {
+                    assert_eq!(policy, OverlapPolicy::Reject);
+                    assert!(overlaps);
+                    assert!(existing.start < range.end && range.start < existing.end);
+                }
+                Err(IntervalError::Empty) => unreachable!("the range isn't empty"),
+            }
+            if rng.below(4) == 0 && !model.is_empty() {
+                // The map removes the oldest copy of a repeated interval
+                let range = model[rng.below(model.len() as u64) as usize].0.clone();
+                let oldest = model.iter().position(|(stored, _)| *stored == range);
+                let (_, value) = model.remove(oldest.unwrap());
+                assert_eq!(map.remove(&range), Some(value));
+            }
+        }
+        assert_eq!(map.len(), model.len());
+
+        for point in 0..230 {
+            let expected = expected_containing(&model, point);
+            assert_eq!(owned(map.get_all(&point)), expected, "point {}", point);
+            assert_eq!(
+                map.get_entry(&point).map(|(range, _)| *range.start),
+                expected.first().map(|(range, _)| range.start)
+            );
+        }
+        for _ in 0..200 {
+            let start = rng.below(230);
+            let query = start..start + rng.below(30);
+            assert_eq!(
+                owned(map.overlapping(query.clone())),
+                expected_overlapping(&model, &query),
+                "query {:?}",
+                query
+            );
+        }
+    }
+
+    #[test]
+    fn overlapping_intervals_match_a_model() {
+        for seed in 1..=10 {
+            check_against_model(OverlapPolicy::Allow, seed);
+        }
+    }
+
+    #[test]
+    fn disjoint_intervals_match_a_model() {
+        for seed in 1..=10 {
+            check_against_model(OverlapPolicy::Reject, seed);
+        }
+    }
+
+    #[test]
+    fn a_long_interval_reaches_past_later_ones() {
+        let mut map = IntervalMap::new(OverlapPolicy::Allow);
+        map.insert(0..100, "long").unwrap();
+        map.insert(10..20, "short").unwrap();
+        map.insert(30..40, "other").unwrap();
+        assert_eq!(map.get(&35), Some(&"long"));
+        let found: Vec<&&str> = map.get_all(&35).into_iter().map(|(_, v)| v).collect();
+        assert_eq!(found, [&"long", &"other"]);
+        assert_eq!(map.overlapping(50..60).len(), 1);
+    }
+
+    #[test]
+    fn rejects_empty_and_overlapping_intervals() {
+        let mut map = IntervalMap::new(OverlapPolicy::Reject);
+        map.insert(10..20, 1).unwrap();
+        assert_eq!(map.insert(5..5, 2), Err(IntervalError::Empty));
+        assert_eq!(
+            map.insert(19..25, 2),
+            Err(IntervalError::Overlap { existing: 10..20 })
+        );
+        map.insert(20..25, 2).unwrap();
+        assert_eq!(map.get(&20), Some(&2));
+        assert_eq!(map.get(&25), None);
+        assert!(map.overlapping(12..12).is_empty());
+    }
+}
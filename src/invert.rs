@@ -0,0 +1,86 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Swap the keys and values of a `HashMap`
+//!
+//! Several keys can share a value, so a naive inversion quietly loses keys.
+//! `invert` keeps that behaviour but says so, `invert_grouped` keeps every
+//! key, and `invert_strict` refuses to invert when values are not unique.
+//! The inverted map uses the same kind of hasher as the input.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+
+/// Two keys of the map being inverted shared the same value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvertCollision<K, V> {
+    pub value: V,
+    pub first_key: K,
+    pub second_key: K,
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Display for InvertCollision<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "keys {:?} and {:?} both map to {:?}",
+            self.first_key, self.second_key, self.value
+        )
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> Error for InvertCollision<K, V> {}
+
+/// Inverts `map`, keeping an arbitrary one of the keys that share a value
+///
+/// Use `invert_grouped` or `invert_strict` when values may repeat.
+pub fn invert<K, V, S>(map: HashMap<K, V, S>) -> HashMap<V, K, S>
+where
+    V: Eq + Hash,
+    S: BuildHasher + Default,
+{
+    map.into_iter().map(|(key, value)| (value, key)).collect()
+}
+
+/// Inverts `map`, collecting every key that shares a value
+pub fn invert_grouped<K, V, S>(map: HashMap<K, V, S>) -> HashMap<V, Vec<K>, S>
+where
+    V: Eq + Hash,
+    S: BuildHasher + Default,
+{
+    let mut inverted: HashMap<V, Vec<K>, S> = HashMap::default();
+    for (key, value) in map {
+        inverted.entry(value).or_default().push(key);
+    }
+    inverted
+}
+
+/// Inverts `map`, failing on the first value that appears more than once
+pub fn invert_strict<K, V, S>(
+    map: HashMap<K, V, S>,
+) -> Result<HashMap<V, K, S>, InvertCollision<K, V>>
+where
+    V: Eq + Hash,
+    S: BuildHasher + Default,
+{
+    let mut inverted = HashMap::with_capacity_and_hasher(map.len(), S::default());
+    for (key, value) in map {
+        match inverted.entry(value) {
+            Entry::Vacant(entry) => {
+                entry.insert(key);
+            }
+            Entry::Occupied(entry) => {
+                let (value, first_key) = entry.remove_entry();
+                return Err(InvertCollision {
+                    value,
+                    first_key,
+                    second_key: key,
+                });
+            }
+        }
+    }
+    Ok(inverted)
+}
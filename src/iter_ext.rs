@@ -0,0 +1,366 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Iterator adapters that std doesn't provide
+//!
+//! Bringing `IterExt` into scope adds its methods to every iterator. Each
+//! adapter is a lazy struct like the ones in `std::iter`, pulling from the
+//! underlying iterator only as far as it has to and reporting an accurate
+//! `size_hint`.
+
+use crate::batched::{self, Batched};
+use crate::grouping::{self, GroupRuns};
+use crate::interleave::{self, Interleave};
+use crate::windowed::{self, Windowed};
+use crate::zip_longest::{self, ZipLongest};
+use std::collections::{HashSet, VecDeque};
+use std::fmt;
+use std::hash::Hash;
+
+/// Extra adapter methods for every `Iterator`
+pub trait IterExt: Iterator + Sized {
+    /// Collapses each run of equal consecutive items into its first item
+    fn dedup_consecutive(self) -> DedupConsecutive<Self>
+    where
+        Self::Item: PartialEq,
+    {
+        DedupConsecutive {
+            iter: self,
+            pending: None,
+        }
+    }
+
+    /// Groups consecutive items with the same key into `Vec`s, like
+    /// `grouping::group_runs` without the keys
+    ///
+    /// Items with equal keys that aren't next to each other end up in
+    /// separate chunks, so sort first to group by key across the whole
+    /// iterator.
+    fn chunk_by<K, F>(self, key_fn: F) -> ChunkBy<Self, K, F>
+    where
+        K: PartialEq,
+        F: FnMut(&Self::Item) -> K,
+    {
+        ChunkBy {
+            runs: grouping::group_runs(self, key_fn),
+        }
+    }
+
+    /// Drops every item equal to one already yielded, keeping the rest in
+    /// their original order
+    fn unique(self) -> Unique<Self>
+    where
+        Self::Item: Eq + Hash + Clone,
+    {
+        Unique {
+            iter: self,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Drops every item whose key matches one already yielded, keeping the
+    /// rest in their original order
+    fn unique_by<K, F>(self, key_fn: F) -> UniqueBy<Self, K, F>
+    where
+        K: Eq + Hash,
+        F: FnMut(&Self::Item) -> K,
+    {
+        UniqueBy {
+            iter: self,
+            key_fn,
+            seen: HashSet::new(),
+        }
+    }
+
+    /// Pairs every item with where it falls in the iteration
+    fn with_positions(self) -> WithPositions<Self> {
+        WithPositions {
+            iter: self,
+            next: None,
+            started: false,
+        }
+    }
+
+    /// Allows looking any number of items ahead without consuming them
+    fn multi_peek(self) -> MultiPeek<Self> {
+        MultiPeek {
+            iter: self,
+            buffer: VecDeque::new(),
+            cursor: 0,
+        }
+    }
+
+    /// Pairs up items with `other` until both have run out; see
+    /// `zip_longest::zip_longest`
+    fn zip_longest<J: IntoIterator>(self, other: J) -> ZipLongest<Self, J::IntoIter> {
+        zip_longest::zip_longest(self, other)
+    }
+
+    /// Yields items in `Vec`s of `size`, the last possibly shorter; see
+    /// `batched::batched`
+    fn batched(self, size: usize) -> Batched<Self> {
+        batched::batched(self, size)
+    }
+
+    /// Alternates items with `other`; see `interleave::interleave`
+    fn interleave<J>(self, other: J) -> Interleave<Self, J::IntoIter>
+    where
+        J: IntoIterator<Item = Self::Item>,
+    {
+        interleave::interleave(self, other)
+    }
+
+    /// Yields windows of `size` items starting every `step` items; see
+    /// `windowed::windowed`
+    fn windowed(self, size: usize, step: usize) -> Windowed<Self>
+    where
+        Self::Item: Clone,
+    {
+        windowed::windowed(self, size, step)
+    }
+}
+
+impl<I: Iterator> IterExt for I {}
+
+/// Iterator returned by `IterExt::dedup_consecutive`
+#[derive(Debug, Clone)]
+pub struct DedupConsecutive<I: Iterator> {
+    iter: I,
+    // The first item of the next run, already pulled from `iter`
+    pending: Option<I::Item>,
+}
+
+impl<I> Iterator for DedupConsecutive<I>
+where
+    I: Iterator,
+    I::Item: PartialEq,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let current = self.pending.take().or_else(|| self.iter.next())?;
+        self.pending = self.iter.by_ref().find(|item| *item != current);
+        Some(current)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.pending.is_some() as usize;
+        let (lower, upper) = self.iter.size_hint();
+        // However many items are left, they might all be duplicates
+        let lower = (pending > 0 || lower > 0) as usize;
+        (lower, upper.and_then(|upper| upper.checked_add(pending)))
+    }
+}
+
+/// Iterator returned by `IterExt::chunk_by`
+pub struct ChunkBy<I: Iterator, K, F> {
+    runs: GroupRuns<I, K, F>,
+}
+
+// Derives would only bound `I`, `K` and `F`, not the buffered `I::Item`
+impl<I: Iterator, K, F> Clone for ChunkBy<I, K, F>
+where
+    GroupRuns<I, K, F>: Clone,
+{
+    fn clone(&self) -> Self {
+        ChunkBy {
+            runs: self.runs.clone(),
+        }
+    }
+}
+
+impl<I: Iterator, K, F> fmt::Debug for ChunkBy<I, K, F>
+where
+    GroupRuns<I, K, F>: fmt::Debug,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkBy").field("runs", &self.runs).finish()
+    }
+}
+
+impl<I, K, F> Iterator for ChunkBy<I, K, F>
+where
+    I: Iterator,
+    K: PartialEq,
+    F: FnMut(&I::Item) -> K,
+{
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Vec<I::Item>> {
+        self.runs.next().map(|(_, chunk)| chunk)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.runs.size_hint()
+    }
+}
+
+/// Iterator returned by `IterExt::unique`
+#[derive(Debug, Clone)]
+pub struct Unique<I: Iterator> {
+    iter: I,
+    seen: HashSet<I::Item>,
+}
+
+impl<I> Iterator for Unique<I>
+where
+    I: Iterator,
+    I::Item: Eq + Hash + Clone,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let seen = &mut self.seen;
+        self.iter.by_ref().find(|item| seen.insert(item.clone()))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        unique_size_hint(&self.iter, self.seen.is_empty())
+    }
+}
+
+/// Iterator returned by `IterExt::unique_by`
+#[derive(Debug, Clone)]
+pub struct UniqueBy<I, K, F> {
+    iter: I,
+    key_fn: F,
+    seen: HashSet<K>,
+}
+
+impl<I, K, F> Iterator for UniqueBy<I, K, F>
+where
+    I: Iterator,
+    K: Eq + Hash,
+    F: FnMut(&I::Item) -> K,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let (seen, key_fn) = (&mut self.seen, &mut self.key_fn);
+        self.iter.by_ref().find(|item| seen.insert(key_fn(item)))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        unique_size_hint(&self.iter, self.seen.is_empty())
+    }
+}
+
+// Before anything has been yielded the first item is certain to be new;
+// after that every remaining item might be a repeat
+fn unique_size_hint<I: Iterator>(iter: &I, nothing_seen: bool) -> (usize, Option<usize>) {
+    let (lower, upper) = iter.size_hint();
+    ((nothing_seen && lower > 0) as usize, upper)
+}
+
+/// Where an item falls in the iteration, as reported by
+/// `IterExt::with_positions`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Position {
+    First,
+    Middle,
+    Last,
+    /// The only item, which is both first and last
+    Only,
+}
+
+/// Iterator returned by `IterExt::with_positions`
+#[derive(Debug, Clone)]
+pub struct WithPositions<I: Iterator> {
+    iter: I,
+    // The item after the one last returned, pulled early to tell whether
+    // that one was last
+    next: Option<I::Item>,
+    started: bool,
+}
+
+impl<I: Iterator> Iterator for WithPositions<I> {
+    type Item = (Position, I::Item);
+
+    fn next(&mut self) -> Option<(Position, I::Item)> {
+        let item = if self.started {
+            self.next.take()?
+        } else {
+            self.iter.next()?
+        };
+        self.next = self.iter.next();
+        let is_last = self.next.is_none();
+        let position = match (self.started, is_last) {
+            (false, true) => Position::Only,
+            (false, false) => Position::First,
+            (true, true) => Position::Last,
+            (true, false) => Position::Middle,
+        };
+        self.started = true;
+        Some((position, item))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let next = self.next.is_some() as usize;
+        let (lower, upper) = self.iter.size_hint();
+        (
+            lower.saturating_add(next),
+            upper.and_then(|upper| upper.checked_add(next)),
+        )
+    }
+}
+
+impl<I: ExactSizeIterator> ExactSizeIterator for WithPositions<I> {}
+
+/// Iterator returned by `IterExt::multi_peek`
+///
+/// Items that have been peeked at are buffered until `next` returns them, and
+/// the underlying iterator is never read further ahead than the furthest
+/// peek.
+#[derive(Debug, Clone)]
+pub struct MultiPeek<I: Iterator> {
+    iter: I,
+    buffer: VecDeque<I::Item>,
+    // Where the next `peek` looks, counted from the next item
+    cursor: usize,
+}
+
+impl<I: Iterator> MultiPeek<I> {
+    /// Returns the item `n` places ahead, where 0 is the item `next` would
+    /// return, without moving the `peek` cursor
+    pub fn peek_nth(&mut self, n: usize) -> Option<&I::Item> {
+        while self.buffer.len() <= n {
+            self.buffer.push_back(self.iter.next()?);
+        }
+        self.buffer.get(n)
+    }
+
+    /// Returns the item at the cursor and moves the cursor one further, so
+    /// repeated calls walk forward through the upcoming items
+    pub fn peek(&mut self) -> Option<&I::Item> {
+        let n = self.cursor;
+        self.peek_nth(n)?;
+        self.cursor += 1;
+        self.buffer.get(n)
+    }
+
+    /// Moves the `peek` cursor back to the next item
+    pub fn reset_peek(&mut self) {
+        self.cursor = 0;
+    }
+}
+
+impl<I: Iterator> Iterator for MultiPeek<I> {
+    type Item = I::Item;
+
+    /// Returns the next item and resets the `peek` cursor
+    fn next(&mut self) -> Option<I::Item> {
+        self.cursor = 0;
+        self.buffer.pop_front().or_else(|| self.iter.next())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let buffered = self.buffer.len();
+        let (lower, upper) = self.iter.size_hint();
+        (
+            lower.saturating_add(buffered),
+            upper.and_then(|upper| upper.checked_add(buffered)),
+        )
+    }
+}
+
+impl<I: ExactSizeIterator> ExactSizeIterator for MultiPeek<I> {}
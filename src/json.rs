@@ -0,0 +1,782 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A JSON value type with its own parser and printer
+//!
+//! JSON maps directly onto the collections in this crate: arrays become
+//! `Vec`s and objects become `BTreeMap`s, so a parsed document is just nested
+//! collections that can be walked with the usual methods. Objects use a
+//! `BTreeMap` so printing the same value always gives the same text.
+//!
+//! [`reformat`] reads a document with the same parser but keeps the members
+//! of each object in the order they were written, and each number as it was
+//! written, for tools that tidy JSON up without changing it.
+//!
+//! Numbers are stored as `f64`, as in JavaScript, so integers beyond 2^53
+//! lose precision, and a literal too large for an `f64`, such as `1e400`, is
+//! rejected rather than read as infinity. They are printed with the fewest
+//! digits that read back as the same `f64`, switching to an exponent for
+//! very large and very small magnitudes.
+
+use std::collections::{BTreeMap, HashMap};
+use std::error::Error;
+use std::fmt::{self, Write};
+use std::str::FromStr;
+
+/// The members of a JSON object, sorted by key
+pub type JsonObject = BTreeMap<String, JsonValue>;
+
+// Deeper nesting than this is rejected rather than risking a stack overflow
+const MAX_DEPTH: usize = 128;
+
+/// Any JSON value
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonValue {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<JsonValue>),
+    Object(JsonObject),
+}
+
+/// What the parser ran into
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JsonErrorKind {
+    UnexpectedEnd,
+    /// A character that can't start or continue a value here
+    UnexpectedChar(char),
+    InvalidNumber,
+    /// A backslash escape JSON doesn't define, or a malformed `\u` escape
+    InvalidEscape,
+    /// A raw control character inside a string
+    ControlCharInString,
+    TooDeep,
+    /// Non-whitespace after the end of the top-level value
+    TrailingCharacters,
+}
+
+/// A parse failure and the 1-based line and column where it happened
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    pub kind: JsonErrorKind,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}, column {}: ", self.line, self.column)?;
+        match &self.kind {
+            JsonErrorKind::UnexpectedEnd => write!(f, "unexpected end of input"),
+            JsonErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {:?}", c),
+            JsonErrorKind::InvalidNumber => write!(f, "invalid number"),
+            JsonErrorKind::InvalidEscape => write!(f, "invalid escape sequence"),
+            JsonErrorKind::ControlCharInString => write!(f, "control character in string"),
+            JsonErrorKind::TooDeep => write!(f, "nesting deeper than {} levels", MAX_DEPTH),
+            JsonErrorKind::TrailingCharacters => write!(f, "unexpected text after value"),
+        }
+    }
+}
+
+impl Error for JsonError {}
+
+/// How [`reformat`] lays a document out
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JsonLayout {
+    /// All on one line, with no spaces, like `Display`
+    Compact,
+    /// Across several lines, like `to_string_pretty`
+    Pretty,
+}
+
+/// Parses `text` and writes it back out in `layout`, keeping the members of
+/// each object in the order they were written
+///
+/// If an object repeats a key, the last value wins but stays where the key
+/// first appeared. Numbers are copied out exactly as written, so they keep
+/// their precision even where an `f64` couldn't hold them.
+pub fn reformat(text: &str, layout: JsonLayout) -> Result<String, JsonError> {
+    let node: Node = parse_document(text)?;
+    let indent = match layout {
+        JsonLayout::Compact => None,
+        JsonLayout::Pretty => Some(0),
+    };
+    let mut out = String::new();
+    write_node(&mut out, &node, indent).expect("writing to a String never fails");
+    Ok(out)
+}
+
+impl JsonValue {
+    /// Parses a complete JSON document
+    pub fn parse(text: &str) -> Result<JsonValue, JsonError> {
+        parse_document(text)
+    }
+
+    /// Formats the value across several lines, indenting each level by two
+    /// spaces
+    ///
+    /// `Display` gives the compact single-line form instead.
+    pub fn to_string_pretty(&self) -> String {
+        let mut out = String::new();
+        write_value(&mut out, self, Some(0)).expect("writing to a String never fails");
+        out
+    }
+
+    pub fn is_null(&self) -> bool {
+        *self == JsonValue::Null
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            JsonValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            JsonValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    /// The number, if it is whole and fits in an `i64`
+    pub fn as_i64(&self) -> Option<i64> {
+        let n = self.as_f64()?;
+        // i64::MAX as f64 rounds up to 2^63, which doesn't itself fit
+        if n.fract() == 0.0 && n >= i64::MIN as f64 && n < i64::MAX as f64 {
+            Some(n as i64)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            JsonValue::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_array(&self) -> Option<&Vec<JsonValue>> {
+        match self {
+            JsonValue::Array(items) => Some(items),
+            _ => None,
+        }
+    }
+
+    pub fn as_object(&self) -> Option<&JsonObject> {
+        match self {
+            JsonValue::Object(members) => Some(members),
+            _ => None,
+        }
+    }
+
+    /// Looks up `key` if this value is an object
+    pub fn get(&self, key: &str) -> Option<&JsonValue> {
+        self.as_object()?.get(key)
+    }
+
+    /// Looks up each key of `path` in turn through nested objects, e.g.
+    /// `&["server", "port"]`
+    ///
+    /// An empty path gives back this value.
+    pub fn get_at(&self, path: &[&str]) -> Option<&JsonValue> {
+        path.iter().try_fold(self, |value, key| value.get(key))
+    }
+
+    /// The string at `path`, or `None` if it is missing or not a string
+    pub fn get_str_at(&self, path: &[&str]) -> Option<&str> {
+        self.get_at(path)?.as_str()
+    }
+
+    /// The boolean at `path`, or `None` if it is missing or not a boolean
+    pub fn get_bool_at(&self, path: &[&str]) -> Option<bool> {
+        self.get_at(path)?.as_bool()
+    }
+
+    /// The number at `path`, or `None` if it is missing or not a number
+    pub fn get_f64_at(&self, path: &[&str]) -> Option<f64> {
+        self.get_at(path)?.as_f64()
+    }
+
+    /// The whole number at `path`, or `None` if it is missing, not a number,
+    /// or doesn't fit in an `i64`
+    pub fn get_i64_at(&self, path: &[&str]) -> Option<i64> {
+        self.get_at(path)?.as_i64()
+    }
+
+    /// The array at `path`, or `None` if it is missing or not an array
+    pub fn get_array_at(&self, path: &[&str]) -> Option<&Vec<JsonValue>> {
+        self.get_at(path)?.as_array()
+    }
+}
+
+impl FromStr for JsonValue {
+    type Err = JsonError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        JsonValue::parse(text)
+    }
+}
+
+impl fmt::Display for JsonValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_value(f, self, None)
+    }
+}
+
+impl From<bool> for JsonValue {
+    fn from(b: bool) -> Self {
+        JsonValue::Bool(b)
+    }
+}
+
+impl From<f64> for JsonValue {
+    fn from(n: f64) -> Self {
+        JsonValue::Number(n)
+    }
+}
+
+impl From<i32> for JsonValue {
+    fn from(n: i32) -> Self {
+        JsonValue::Number(f64::from(n))
+    }
+}
+
+impl From<&str> for JsonValue {
+    fn from(s: &str) -> Self {
+        JsonValue::String(s.to_string())
+    }
+}
+
+impl From<String> for JsonValue {
+    fn from(s: String) -> Self {
+        JsonValue::String(s)
+    }
+}
+
+impl From<Vec<JsonValue>> for JsonValue {
+    fn from(items: Vec<JsonValue>) -> Self {
+        JsonValue::Array(items)
+    }
+}
+
+impl From<JsonObject> for JsonValue {
+    fn from(members: JsonObject) -> Self {
+        JsonValue::Object(members)
+    }
+}
+
+// What the parser builds, so the same parser can give either sorted
+// `JsonValue`s or the ordered `Node`s behind `reformat`
+trait Tree: Sized {
+    /// Anything but a number, an array or an object
+    fn scalar(value: JsonValue) -> Self;
+    /// A number as written, already checked against JSON's grammar, or
+    /// `None` if this tree can't hold it
+    fn number(text: &str) -> Option<Self>;
+    fn array(items: Vec<Self>) -> Self;
+    /// The members in the order they were written, repeated keys included
+    fn object(members: Vec<(String, Self)>) -> Self;
+}
+
+impl Tree for JsonValue {
+    fn scalar(value: JsonValue) -> Self {
+        value
+    }
+
+    fn number(text: &str) -> Option<Self> {
+        // Too large for an `f64`, which would otherwise round it to infinity
+        text.parse()
+            .ok()
+            .filter(|n: &f64| n.is_finite())
+            .map(JsonValue::Number)
+    }
+
+    fn array(items: Vec<Self>) -> Self {
+        JsonValue::Array(items)
+    }
+
+    fn object(members: Vec<(String, Self)>) -> Self {
+        JsonValue::Object(members.into_iter().collect())
+    }
+}
+
+// A JSON value whose objects keep their members in document order
+enum Node {
+    Scalar(JsonValue),
+    /// The text of a number, which going through an `f64` could round
+    Number(String),
+    Array(Vec<Node>),
+    Object(Vec<(String, Node)>),
+}
+
+impl Tree for Node {
+    fn scalar(value: JsonValue) -> Self {
+        Node::Scalar(value)
+    }
+
+    fn number(text: &str) -> Option<Self> {
+        Some(Node::Number(text.to_string()))
+    }
+
+    fn array(items: Vec<Self>) -> Self {
+        Node::Array(items)
+    }
+
+    fn object(members: Vec<(String, Self)>) -> Self {
+        let mut positions: HashMap<String, usize> = HashMap::with_capacity(members.len());
+        let mut unique: Vec<(String, Node)> = Vec::with_capacity(members.len());
+        for (key, value) in members {
+            match positions.get(&key) {
+                Some(&position) => unique[position].1 = value,
+                None => {
+                    positions.insert(key.clone(), unique.len());
+                    unique.push((key, value));
+                }
+            }
+        }
+        Node::Object(unique)
+    }
+}
+
+fn parse_document<T: Tree>(text: &str) -> Result<T, JsonError> {
+    let mut parser = Parser { text, pos: 0 };
+    parser.skip_whitespace();
+    let value = parser.value(0)?;
+    parser.skip_whitespace();
+    if parser.pos < text.len() {
+        return Err(parser.error(JsonErrorKind::TrailingCharacters));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    // Byte offset of the next unread character
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(' ') | Some('\t') | Some('\n') | Some('\r') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn error(&self, kind: JsonErrorKind) -> JsonError {
+        let before = &self.text[..self.pos];
+        let line = before.matches('\n').count() + 1;
+        let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+        JsonError {
+            kind,
+            line,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+
+    // The error for whatever is at the current position
+    fn unexpected(&self) -> JsonError {
+        match self.peek() {
+            Some(c) => self.error(JsonErrorKind::UnexpectedChar(c)),
+            None => self.error(JsonErrorKind::UnexpectedEnd),
+        }
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), JsonError> {
+        if self.peek() == Some(expected) {
+            self.pos += expected.len_utf8();
+            Ok(())
+        } else {
+            Err(self.unexpected())
+        }
+    }
+
+    fn value<T: Tree>(&mut self, depth: usize) -> Result<T, JsonError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error(JsonErrorKind::TooDeep));
+        }
+        let scalar = match self.peek() {
+            Some('n') => self.literal("null", JsonValue::Null),
+            Some('t') => self.literal("true", JsonValue::Bool(true)),
+            Some('f') => self.literal("false", JsonValue::Bool(false)),
+            Some('"') => self.string().map(JsonValue::String),
+            Some('[') => return self.array(depth),
+            Some('{') => return self.object(depth),
+            Some('-') | Some('0'..='9') => return self.number(),
+            _ => Err(self.unexpected()),
+        };
+        scalar.map(T::scalar)
+    }
+
+    fn literal(&mut self, word: &str, value: JsonValue) -> Result<JsonValue, JsonError> {
+        for expected in word.chars() {
+            self.expect(expected)?;
+        }
+        Ok(value)
+    }
+
+    fn number<T: Tree>(&mut self) -> Result<T, JsonError> {
+        let start = self.pos;
+        let invalid = |parser: &Parser| parser.error(JsonErrorKind::InvalidNumber);
+
+        if self.peek() == Some('-') {
+            self.pos += 1;
+        }
+        match self.peek() {
+            Some('0') => self.pos += 1,
+            Some('1'..='9') => self.digits(),
+            _ => return Err(invalid(self)),
+        }
+        if self.peek() == Some('.') {
+            self.pos += 1;
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(invalid(self));
+            }
+            self.digits();
+        }
+        if let Some('e') | Some('E') = self.peek() {
+            self.pos += 1;
+            if let Some('+') | Some('-') = self.peek() {
+                self.pos += 1;
+            }
+            if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+                return Err(invalid(self));
+            }
+            self.digits();
+        }
+
+        T::number(&self.text[start..self.pos]).ok_or_else(|| {
+            self.pos = start;
+            invalid(self)
+        })
+    }
+
+    fn digits(&mut self) {
+        while self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            self.pos += 1;
+        }
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.expect('"')?;
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None => return Err(self.error(JsonErrorKind::UnexpectedEnd)),
+                Some('"') => return Ok(s),
+                Some('\\') => {
+                    let escaped = match self.bump() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.unicode_escape()?,
+                        _ => return Err(self.error(JsonErrorKind::InvalidEscape)),
+                    };
+                    s.push(escaped);
+                }
+                Some(c) if c < ' ' => return Err(self.error(JsonErrorKind::ControlCharInString)),
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    // Characters outside the Basic Multilingual Plane are written as a pair
+    // of `\u` escapes for the two UTF-16 surrogates
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let first = self.hex4()?;
+        let code = if (0xD800..0xDC00).contains(&first) {
+            if self.bump() != Some('\\') || self.bump() != Some('u') {
+                return Err(self.error(JsonErrorKind::InvalidEscape));
+            }
+            let second = self.hex4()?;
+            if !(0xDC00..0xE000).contains(&second) {
+                return Err(self.error(JsonErrorKind::InvalidEscape));
+            }
+            0x10000 + ((first - 0xD800) << 10) + (second - 0xDC00)
+        } else {
+            first
+        };
+        std::char::from_u32(code).ok_or_else(|| self.error(JsonErrorKind::InvalidEscape))
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .text
+            .get(self.pos..self.pos + 4)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error(JsonErrorKind::InvalidEscape))?;
+        self.pos += 4;
+        Ok(u32::from_str_radix(digits, 16).expect("digits were checked to be hex"))
+    }
+
+    fn array<T: Tree>(&mut self, depth: usize) -> Result<T, JsonError> {
+        self.expect('[')?;
+        let mut items = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some(']') {
+            self.pos += 1;
+            return Ok(T::array(items));
+        }
+        loop {
+            self.skip_whitespace();
+            items.push(self.value(depth + 1)?);
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(T::array(items));
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn object<T: Tree>(&mut self, depth: usize) -> Result<T, JsonError> {
+        self.expect('{')?;
+        let mut members = Vec::new();
+        self.skip_whitespace();
+        if self.peek() == Some('}') {
+            self.pos += 1;
+            return Ok(T::object(members));
+        }
+        loop {
+            self.skip_whitespace();
+            let key = self.string()?;
+            self.skip_whitespace();
+            self.expect(':')?;
+            self.skip_whitespace();
+            let value = self.value(depth + 1)?;
+            members.push((key, value));
+            self.skip_whitespace();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some('}') => {
+                    self.pos += 1;
+                    return Ok(T::object(members));
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+}
+
+// Writes compact JSON when `indent` is `None`, or pretty JSON starting at
+// that many levels of indentation
+fn write_value<W: Write>(out: &mut W, value: &JsonValue, indent: Option<usize>) -> fmt::Result {
+    match value {
+        JsonValue::Null => out.write_str("null"),
+        JsonValue::Bool(b) => write!(out, "{}", b),
+        JsonValue::Number(n) => write_number(out, *n),
+        JsonValue::String(s) => write_string(out, s),
+        JsonValue::Array(items) => {
+            write_container(out, '[', ']', items, indent, |out, item, indent| {
+                write_value(out, item, indent)
+            })
+        }
+        JsonValue::Object(members) => write_container(
+            out,
+            '{',
+            '}',
+            members,
+            indent,
+            |out, (key, value), indent| {
+                write_string(out, key)?;
+                out.write_str(if indent.is_some() { ": " } else { ":" })?;
+                write_value(out, value, indent)
+            },
+        ),
+    }
+}
+
+// Both `Display` and `LowerExp` give the shortest digits that round-trip,
+// but `Display` never uses an exponent, which would write 1e300 out in full
+fn write_number<W: Write>(out: &mut W, n: f64) -> fmt::Result {
+    if !n.is_finite() {
+        // JSON has no way to write NaN or infinity, which only a value built
+        // in code can hold
+        out.write_str("null")
+    } else if n == 0.0 || (1e-6..1e21).contains(&n.abs()) {
+        write!(out, "{}", n)
+    } else {
+        write!(out, "{:e}", n)
+    }
+}
+
+fn write_node<W: Write>(out: &mut W, node: &Node, indent: Option<usize>) -> fmt::Result {
+    match node {
+        Node::Scalar(value) => write_value(out, value, indent),
+        Node::Number(text) => out.write_str(text),
+        Node::Array(items) => write_container(out, '[', ']', items, indent, write_node),
+        Node::Object(members) => write_container(
+            out,
+            '{',
+            '}',
+            members,
+            indent,
+            |out, (key, value), indent| {
+                write_string(out, key)?;
+                out.write_str(if indent.is_some() { ": " } else { ":" })?;
+                write_node(out, value, indent)
+            },
+        ),
+    }
+}
+
+fn write_container<W, I, F>(
+    out: &mut W,
+    open: char,
+    close: char,
+    items: I,
+    indent: Option<usize>,
+    mut write_item: F,
+) -> fmt::Result
+where
+    W: Write,
+    I: IntoIterator,
+    F: FnMut(&mut W, I::Item, Option<usize>) -> fmt::Result,
+{
+    out.write_char(open)?;
+    let inner = indent.map(|level| level + 1);
+    let mut empty = true;
+    for (index, item) in items.into_iter().enumerate() {
+        if index > 0 {
+            out.write_char(',')?;
+        }
+        if let Some(level) = inner {
+            write_newline(out, level)?;
+        }
+        write_item(out, item, inner)?;
+        empty = false;
+    }
+    if let (Some(level), false) = (indent, empty) {
+        write_newline(out, level)?;
+    }
+    out.write_char(close)
+}
+
+fn write_newline<W: Write>(out: &mut W, level: usize) -> fmt::Result {
+    out.write_char('\n')?;
+    for _ in 0..level {
+        out.write_str("  ")?;
+    }
+    Ok(())
+}
+
+fn write_string<W: Write>(out: &mut W, s: &str) -> fmt::Result {
+    out.write_char('"')?;
+    for c in s.chars() {
+        match c {
+            '"' => out.write_str("\\\"")?,
+            '\\' => out.write_str("\\\\")?,
+            '\n' => out.write_str("\\n")?,
+            '\r' => out.write_str("\\r")?,
+            '\t' => out.write_str("\\t")?,
+            c if c < ' ' => write!(out, "\\u{:04x}", c as u32)?,
+            c => out.write_char(c)?,
+        }
+    }
+    out.write_char('"')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn printed(n: f64) -> String {
+        JsonValue::Number(n).to_string()
+    }
+
+    #[test]
+    fn numbers_print_in_their_shortest_form() {
+        assert_eq!(printed(0.0), "0");
+        assert_eq!(printed(-0.0), "-0");
+        assert_eq!(printed(100.0), "100");
+        assert_eq!(printed(0.1), "0.1");
+        assert_eq!(printed(-2.5), "-2.5");
+        assert_eq!(printed(9007199254740992.0), "9007199254740992");
+        assert_eq!(printed(1e300), "1e300");
+        assert_eq!(printed(-1.5e-10), "-1.5e-10");
+        assert_eq!(printed(1e21), "1e21");
+        assert_eq!(printed(f64::NAN), "null");
+        assert_eq!(printed(f64::NEG_INFINITY), "null");
+    }
+
+    #[test]
+    fn printed_numbers_read_back_the_same() {
+        let values = [
+            f64::MAX,
+            f64::MIN_POSITIVE,
+            5e-324,
+            0.1 + 0.2,
+            1e-7,
+            123456789.125,
+            -1e20,
+            std::f64::consts::PI,
+        ];
+        for &n in &values {
+            let text = printed(n);
+            assert!(text.len() <= 25, "{} printed as {}", n, text);
+            assert_eq!(JsonValue::parse(&text), Ok(JsonValue::Number(n)));
+        }
+    }
+
+    #[test]
+    fn out_of_range_literals_are_rejected() {
+        for text in &["1e400", "-1e400", "[1, 2e999]"] {
+            let err = JsonValue::parse(text).unwrap_err();
+            assert_eq!(err.kind, JsonErrorKind::InvalidNumber, "{}", text);
+        }
+        let err = JsonValue::parse("{\"a\": 1e400}").unwrap_err();
+        assert_eq!((err.line, err.column), (1, 7));
+        // Underflow is only a loss of precision, like any other rounding
+        assert_eq!(JsonValue::parse("1e-400"), Ok(JsonValue::Number(0.0)));
+    }
+
+    #[test]
+    fn reformat_keeps_numbers_as_written() {
+        let text = r#"{"b": 1e400, "a": [9007199254740993, -0.10, 1E2, 0]}"#;
+        assert_eq!(
+            reformat(text, JsonLayout::Compact),
+            Ok(r#"{"b":1e400,"a":[9007199254740993,-0.10,1E2,0]}"#.to_string())
+        );
+        assert_eq!(
+            reformat("[1.50]", JsonLayout::Pretty),
+            Ok("[\n  1.50\n]".to_string())
+        );
+    }
+
+    #[test]
+    fn reformat_still_checks_number_grammar() {
+        for text in &["[01]", "[1.]", "[-]", "[1e]", "[.5]"] {
+            let err = reformat(text, JsonLayout::Compact).unwrap_err();
+            assert!(
+                matches!(
+                    err.kind,
+                    JsonErrorKind::InvalidNumber | JsonErrorKind::UnexpectedChar(_)
+                ),
+                "{}: {:?}",
+                text,
+                err
+            );
+        }
+    }
+}
@@ -0,0 +1,206 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A scoreboard that keeps its entries ranked
+//!
+//! Scores live in a `RankSet` of `(score, key)` pairs so the entries are
+//! always sorted best first, next to a `HashMap` from each key to its current
+//! score so an update can find and remove the old position. Higher scores
+//! rank first and ties are broken by key, smallest first. Ranks start at 1.
+//!
+//! Setting or removing a score and `rank_of` are O(log n), since the
+//! `RankSet` counts the entries ahead of a position without walking them.
+//! `top(n)` and `around(key, n)` add O(n log n) for the entries they return.
+
+use crate::rank_set::RankSet;
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// An entry reported by `around`: its rank, its key and its score
+pub type Ranked<'a, K> = (usize, &'a K, i64);
+
+/// Keys ranked by score, highest first
+#[derive(Debug, Clone)]
+pub struct Leaderboard<K> {
+    ranking: RankSet<(Reverse<i64>, K)>,
+    scores: HashMap<K, i64>,
+}
+
+impl<K: Ord + Hash + Clone> Leaderboard<K> {
+    pub fn new() -> Self {
+        Leaderboard {
+            ranking: RankSet::new(),
+            scores: HashMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.scores.is_empty()
+    }
+
+    /// Sets the score for `key`, returning its previous score
+    pub fn set_score(&mut self, key: K, score: i64) -> Option<i64> {
+        let old = self.scores.insert(key.clone(), score);
+        if let Some(old) = old {
+            self.ranking.remove(&(Reverse(old), key.clone()));
+        }
+        self.ranking.insert((Reverse(score), key));
+        old
+    }
+
+    /// Adds `delta` to the score for `key`, starting from zero, and returns
+    /// the new score
+    pub fn add_score(&mut self, key: K, delta: i64) -> i64 {
+        let score = self.score(&key).unwrap_or(0).saturating_add(delta);
+        self.set_score(key, score);
+        score
+    }
+
+    pub fn score(&self, key: &K) -> Option<i64> {
+        self.scores.get(key).copied()
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<i64> {
+        let score = self.scores.remove(key)?;
+        self.ranking.remove(&(Reverse(score), key.clone()));
+        Some(score)
+    }
+
+    /// Returns the 1-based rank of `key`
+    pub fn rank_of(&self, key: &K) -> Option<usize> {
+        let score = *self.scores.get(key)?;
+        Some(self.ranking.rank(&(Reverse(score), key.clone())) + 1)
+    }
+
+    /// Returns the best `n` entries, best first
+    pub fn top(&self, n: usize) -> Vec<(&K, i64)> {
+        self.iter().take(n).collect()
+    }
+
+    /// Returns `key` together with up to `n` entries ranked directly above
+    /// and below it, in rank order
+    pub fn around(&self, key: &K, n: usize) -> Vec<Ranked<'_, K>> {
+        let score = match self.scores.get(key) {
+            Some(&score) => score,
+            None => return Vec::new(),
+        };
+        let rank = self.ranking.rank(&(Reverse(score), key.clone())) + 1;
+        let first = rank.saturating_sub(n).max(1);
+        let last = rank.saturating_add(n).min(self.len());
+        (first..=last)
+            .filter_map(|rank| self.ranking.select(rank - 1))
+            .zip(first..)
+            .map(|((Reverse(score), key), rank)| (rank, key, *score))
+            .collect()
+    }
+
+    /// Iterates over every entry, best first
+    pub fn iter(&self) -> impl Iterator<Item = (&K, i64)> {
+        self.ranking
+            .iter()
+            .map(|(Reverse(score), key)| (key, *score))
+    }
+
+    pub fn clear(&mut self) {
+        self.ranking.clear();
+        self.scores.clear();
+    }
+}
+
+impl<K: Ord + Hash + Clone> Default for Leaderboard<K> {
+    fn default() -> Self {
+        Leaderboard::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn board(entries: &[(&'static str, i64)]) -> Leaderboard<&'static str> {
+        let mut board = Leaderboard::new();
+        for &(key, score) in entries {
+            board.set_score(key, score);
+        }
+        board
+    }
+
+    #[test]
+    fn ties_are_broken_by_key() {
+        let board = board(&[("carol", 5), ("bob", 7), ("alice", 5), ("dave", 1)]);
+        assert_eq!(
+            board.top(4),
+            vec![(&"bob", 7), (&"alice", 5), (&"carol", 5), (&"dave", 1)]
+        );
+        assert_eq!(board.rank_of(&"bob"), Some(1));
+        assert_eq!(board.rank_of(&"alice"), Some(2));
+        assert_eq!(board.rank_of(&"carol"), Some(3));
+        assert_eq!(board.rank_of(&"dave"), Some(4));
+        assert_eq!(board.rank_of(&"erin"), None);
+    }
+
+    #[test]
+    fn updating_a_score_moves_the_key() {
+        let mut board = board(&[("a", 10), ("b", 20), ("c", 30)]);
+        assert_eq!(board.set_score("a", 40), Some(10));
+        assert_eq!(board.len(), 3);
+        assert_eq!(board.top(3), vec![(&"a", 40), (&"c", 30), (&"b", 20)]);
+        assert_eq!(board.add_score("b", 15), 35);
+        assert_eq!(board.rank_of(&"b"), Some(2));
+        assert_eq!(board.rank_of(&"c"), Some(3));
+        assert_eq!(board.remove(&"a"), Some(40));
+        assert_eq!(board.rank_of(&"b"), Some(1));
+        assert_eq!(board.remove(&"a"), None);
+        assert_eq!(board.len(), 2);
+    }
+
+    #[test]
+    fn top_with_more_than_len() {
+        let board = board(&[("a", 1), ("b", 2)]);
+        assert_eq!(board.top(10), vec![(&"b", 2), (&"a", 1)]);
+        assert_eq!(board.top(0), vec![]);
+        assert!(Leaderboard::<&str>::new().top(3).is_empty());
+    }
+
+    #[test]
+    fn around_at_both_ends() {
+        let board = board(&[("a", 50), ("b", 40), ("c", 30), ("d", 20), ("e", 10)]);
+        assert_eq!(
+            board.around(&"a", 2),
+            vec![(1, &"a", 50), (2, &"b", 40), (3, &"c", 30)]
+        );
+        assert_eq!(
+            board.around(&"e", 2),
+            vec![(3, &"c", 30), (4, &"d", 20), (5, &"e", 10)]
+        );
+        assert_eq!(
+            board.around(&"c", 1),
+            vec![(2, &"b", 40), (3, &"c", 30), (4, &"d", 20)]
+        );
+        assert_eq!(board.around(&"c", 0), vec![(3, &"c", 30)]);
+        assert_eq!(board.around(&"c", usize::MAX).len(), 5);
+        assert!(board.around(&"z", 2).is_empty());
+    }
+
+    #[test]
+    fn ranks_match_a_sorted_copy() {
+        let mut board = Leaderboard::new();
+        for i in 0..200u64 {
+            let score = (i * 7919 % 61) as i64;
+            board.set_score(i % 150, score);
+        }
+        let mut sorted: Vec<(Reverse<i64>, u64)> = board
+            .iter()
+            .map(|(&key, score)| (Reverse(score), key))
+            .collect();
+        sorted.sort();
+        for (index, (_, key)) in sorted.iter().enumerate() {
+            assert_eq!(board.rank_of(key), Some(index + 1));
+        }
+    }
+}
@@ -0,0 +1,349 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A least-frequently-used cache
+//!
+//! Every entry remembers how many times it has been used. When the cache is
+//! full, the entry with the lowest use count is evicted, and ties between
+//! equally used entries go to whichever was touched longest ago.
+
+use crate::op_stats::{Op, OpCounters, OpStats};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::Hash;
+
+#[derive(Debug, Clone)]
+struct Entry<V> {
+    value: V,
+    freq: usize,
+    tick: u64,
+}
+
+/// A fixed-capacity cache that evicts the least-frequently-used entry
+#[derive(Debug, Clone)]
+pub struct LfuCache<K, V> {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<K, Entry<V>>,
+    // Frequency -> (last-use tick -> key), so the first key of the
+    // first bucket is always the next one to be evicted
+    buckets: BTreeMap<usize, BTreeMap<u64, K>>,
+    counters: OpCounters,
+}
+
+impl<K: Clone + Eq + Hash, V> LfuCache<K, V> {
+    /// Creates an empty cache holding at most `capacity` entries
+    pub fn new(capacity: usize) -> Self {
+        LfuCache {
+            capacity,
+            tick: 0,
+            entries: HashMap::with_capacity(capacity),
+            buckets: BTreeMap::new(),
+            counters: OpCounters::new(),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// What the cache has been asked to do, counted with the `instrument`
+    /// feature
+    pub fn op_stats(&self) -> OpStats {
+        self.counters.stats()
+    }
+
+    pub fn reset_op_stats(&mut self) {
+        self.counters.reset();
+    }
+
+    /// Returns true if `key` is cached, without counting as a use
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.counters.record(Op::Lookup);
+        self.entries.contains_key(key)
+    }
+
+    /// Looks up `key` and counts the lookup as a use
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.counters.record(Op::Lookup);
+        self.touch(key)?;
+        self.entries.get(key).map(|entry| &entry.value)
+    }
+
+    /// Like `get`, but hands back a mutable reference
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.counters.record(Op::Lookup);
+        self.touch(key)?;
+        self.entries.get_mut(key).map(|entry| &mut entry.value)
+    }
+
+    /// Looks up `key` without counting the lookup as a use
+    pub fn peek(&self, key: &K) -> Option<&V> {
+        self.counters.record(Op::Lookup);
+        self.entries.get(key).map(|entry| &entry.value)
+    }
+
+    /// Inserts or updates `key`, counting it as a use
+    ///
+    /// Returns the entry that had to be evicted to make room, if any. A cache
+    /// with zero capacity hands the new entry straight back.
+    pub fn put(&mut self, key: K, value: V) -> Option<(K, V)> {
+        self.counters.record(Op::Insert);
+        if self.capacity == 0 {
+            return Some((key, value));
+        }
+
+        if let Some(entry) = self.entries.get_mut(&key) {
+            entry.value = value;
+            self.touch(&key);
+            return None;
+        }
+
+        let evicted = if self.entries.len() >= self.capacity {
+            self.evict()
+        } else {
+            None
+        };
+
+        self.tick += 1;
+        self.buckets
+            .entry(1)
+            .or_default()
+            .insert(self.tick, key.clone());
+        self.entries.insert(
+            key,
+            Entry {
+                value,
+                freq: 1,
+                tick: self.tick,
+            },
+        );
+
+        evicted
+    }
+
+    /// Removes `key` from the cache, returning its value
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.counters.record(Op::Removal);
+        let entry = self.entries.remove(key)?;
+        Self::unlink(&mut self.buckets, entry.freq, entry.tick);
+        Some(entry.value)
+    }
+
+    /// Returns how many times `key` has been used since it was inserted
+    pub fn frequency(&self, key: &K) -> Option<usize> {
+        self.entries.get(key).map(|entry| entry.freq)
+    }
+
+    /// Returns `(frequency, number of entries)` pairs, lowest frequency first
+    pub fn frequency_histogram(&self) -> Vec<(usize, usize)> {
+        self.buckets
+            .iter()
+            .map(|(freq, bucket)| (*freq, bucket.len()))
+            .collect()
+    }
+
+    /// Returns the key that would be evicted by the next insertion
+    pub fn eviction_candidate(&self) -> Option<&K> {
+        self.buckets
+            .values()
+            .next()
+            .and_then(|bucket| bucket.values().next())
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.buckets.clear();
+    }
+
+    // Moves `key` up one frequency bucket and marks it as the most recent
+    fn touch(&mut self, key: &K) -> Option<()> {
+        let entry = self.entries.get_mut(key)?;
+        let key = Self::unlink(&mut self.buckets, entry.freq, entry.tick);
+
+        self.tick += 1;
+        entry.freq += 1;
+        entry.tick = self.tick;
+        self.buckets
+            .entry(entry.freq)
+            .or_default()
+            .insert(entry.tick, key);
+
+        Some(())
+    }
+
+    fn evict(&mut self) -> Option<(K, V)> {
+        let mut lowest = self.buckets.first_entry()?;
+        let (_, key) = lowest.get_mut().pop_first()?;
+        if lowest.get().is_empty() {
+            lowest.remove();
+        }
+
+        let entry = self.entries.remove(&key)?;
+        self.counters.record(Op::Eviction);
+        Some((key, entry.value))
+    }
+
+    fn unlink(buckets: &mut BTreeMap<usize, BTreeMap<u64, K>>, freq: usize, tick: u64) -> K {
+        let bucket = buckets
+            .get_mut(&freq)
+            .expect("every entry has a frequency bucket");
+        let key = bucket
+            .remove(&tick)
+            .expect("every entry is listed in its bucket");
+        if bucket.is_empty() {
+            buckets.remove(&freq);
+        }
+        key
+    }
+}
+
+// There is no `FromIterator`, since a cache can't be built without a capacity
+//
+// Extending puts each entry in turn, so once the cache is full every new key
+// evicts the least-used entry as `put` would. The evicted entries are
+// dropped, and with more distinct keys than `capacity` the earlier ones in
+// the batch are evicted by the later ones.
+impl<K: Clone + Eq + Hash, V> Extend<(K, V)> for LfuCache<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.put(key, value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, XorShift64};
+
+    #[test]
+    fn evicts_the_least_used_entry() {
+        let mut cache = LfuCache::new(3);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        cache.put("c", 3);
+        cache.get(&"a");
+        cache.get(&"a");
+        cache.get(&"c");
+        // Frequencies are a: 3, b: 1, c: 2
+        assert_eq!(cache.eviction_candidate(), Some(&"b"));
+        assert_eq!(cache.put("d", 4), Some(("b", 2)));
+        assert_eq!(cache.frequency_histogram(), [(1, 1), (2, 1), (3, 1)]);
+        // d is now the only entry used once
+        assert_eq!(cache.put("e", 5), Some(("d", 4)));
+        assert_eq!(cache.len(), 3);
+        assert_eq!(cache.frequency(&"a"), Some(3));
+    }
+
+    #[test]
+    fn ties_go_to_the_least_recently_used() {
+        let mut cache = LfuCache::new(3);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        cache.put("c", 3);
+        assert_eq!(cache.put("d", 4), Some(("a", 1)));
+
+        // b, c and d all have frequency 2 after this, with b touched last
+        cache.get(&"c");
+        cache.get(&"d");
+        *cache.get_mut(&"b").unwrap() += 10;
+        assert_eq!(cache.eviction_candidate(), Some(&"c"));
+        assert_eq!(cache.put("e", 5), Some(("c", 3)));
+        // Updating a value counts as a use too
+        cache.put("d", 40);
+        assert_eq!(cache.frequency(&"d"), Some(3));
+        assert_eq!(cache.put("f", 6), Some(("e", 5)));
+        assert_eq!(cache.put("g", 7), Some(("f", 6)));
+        assert_eq!(cache.peek(&"b"), Some(&12));
+    }
+
+    #[test]
+    fn peeking_is_not_a_use() {
+        let mut cache = LfuCache::new(2);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        assert_eq!(cache.peek(&"a"), Some(&1));
+        assert!(cache.contains_key(&"a"));
+        assert_eq!(cache.frequency(&"a"), Some(1));
+        assert_eq!(cache.put("c", 3), Some(("a", 1)));
+    }
+
+    #[test]
+    fn removal_and_zero_capacity() {
+        let mut cache = LfuCache::new(2);
+        cache.put("a", 1);
+        cache.put("b", 2);
+        assert_eq!(cache.remove(&"a"), Some(1));
+        assert_eq!(cache.remove(&"a"), None);
+        assert_eq!(cache.put("c", 3), None);
+        assert_eq!(cache.frequency_histogram(), [(1, 2)]);
+
+        let mut empty = LfuCache::new(0);
+        assert_eq!(empty.put("a", 1), Some(("a", 1)));
+        assert!(empty.is_empty());
+        assert_eq!(empty.eviction_candidate(), None);
+    }
+
+    // Evicts by scanning for the lowest (frequency, last use) pair
+    #[test]
+    fn matches_a_scanning_model() {
+        for seed in 1..=10 {
+            let mut rng = XorShift64::new(seed);
+            let mut cache = LfuCache::new(8);
+            // (key, value, frequency, last use)
+            let mut model: Vec<(u64, u64, usize, u64)> = Vec::new();
+            for tick in 0..2000 {
+                let key = rng.below(20);
+                let position = model.iter().position(|entry| entry.0 == key);
+                match rng.below(3) {
+                    0 | 1 => {
+                        let expected = match position {
+                            Some(index) => {
+                                let entry = &mut model[index];
+                                entry.2 += 1;
+                                entry.3 = tick;
+                                Some(entry.1)
+                            }
+                            None => None,
+                        };
+                        assert_eq!(cache.get(&key).copied(), expected);
+                    }
+                    _ => {
+                        let value = rng.below(1000);
+                        let expected = match position {
+                            Some(index) => {
+                                model[index] = (key, value, model[index].2 + 1, tick);
+                                None
+                            }
+                            None => {
+                                let evicted = if model.len() == 8 {
+                                    let (index, _) = model
+                                        .iter()
+                                        .enumerate()
+                                        .min_by_key(|(_, entry)| (entry.2, entry.3))
+                                        .unwrap();
+                                    let (key, value, _, _) = model.remove(index);
+                                    Some((key, value))
+                                } else {
+                                    None
+                                };
+                                model.push((key, value, 1, tick));
+                                evicted
+                            }
+                        };
+                        assert_eq!(cache.put(key, value), expected, "seed {}", seed);
+                    }
+                }
+                assert_eq!(cache.len(), model.len());
+            }
+        }
+    }
+}
@@ -0,0 +1,90 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+// Collection types and utilities that build on the walkthroughs in
+// `src/bin/collections/{vectors,strings,maps}.rs`
+
+pub mod aggregates;
+pub mod batched;
+pub mod bit_set;
+pub mod bloom;
+#[cfg(feature = "bump")]
+pub mod bump;
+pub mod cache;
+pub mod capacity;
+pub mod columnar;
+pub mod combinatorics;
+pub mod compact_cell;
+pub mod comparison;
+pub mod composite_key;
+pub mod count_min;
+pub mod counter;
+pub mod csv_map;
+pub mod cuckoo_map;
+pub mod deep_size;
+pub mod default_map;
+pub mod dense_set;
+pub mod deterministic_map;
+pub mod fallible;
+pub mod fast_hash;
+pub mod fast_reduce;
+pub mod frozen_map;
+pub mod graph;
+pub mod grid;
+pub mod grouping;
+pub mod ini;
+pub mod interleave;
+pub mod interner;
+pub mod interval_map;
+pub mod invert;
+pub mod iter_ext;
+pub mod json;
+pub mod leaderboard;
+pub mod lfu_cache;
+pub mod map2;
+pub mod map_diff;
+pub mod map_stats;
+pub mod merge;
+pub mod merge_sorted;
+#[cfg(all(feature = "mmap", unix))]
+pub mod mmap_vec;
+pub mod nested;
+pub mod online_stats;
+pub mod op_stats;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod persistent_map;
+pub mod prefix_map;
+pub mod prehashed;
+pub mod priority_map;
+pub mod probe_map;
+pub mod product;
+pub mod random;
+pub mod range_map;
+pub mod rank_set;
+pub mod recalc;
+pub mod reducers;
+pub mod robin_hood_map;
+pub mod sampling;
+pub mod sequences;
+pub mod sets;
+pub mod sharded_map;
+pub mod shortest_path;
+pub mod snapshot;
+pub mod sorted_map;
+pub mod sparse_matrix;
+pub mod spreadsheet;
+pub mod steal_deque;
+pub mod text_arena;
+pub mod text_windows;
+pub mod time_series;
+pub mod toml;
+pub mod ttl_map;
+pub mod union_find;
+pub mod upsert;
+pub mod watched_map;
+pub mod weak_map;
+pub mod windowed;
+pub mod word_count;
+pub mod workload;
+pub mod zip_longest;
@@ -0,0 +1,139 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A map keyed by two keys, stored as a map of maps
+//!
+//! `HashMap<K1, HashMap<K2, V>>` is the natural shape for data such as
+//! "scores by player, then by level", but using it directly means creating
+//! inner maps by hand on insert and leaving empty ones behind on removal.
+//! `Map2` does both, and can iterate either one outer key's entries or every
+//! entry as `(K1, K2, V)` triples.
+
+use std::collections::hash_map::{self, HashMap};
+use std::hash::Hash;
+use std::iter::FromIterator;
+
+/// A two-level map from `(K1, K2)` to `V`
+#[derive(Debug, Clone)]
+pub struct Map2<K1, K2, V> {
+    outer: HashMap<K1, HashMap<K2, V>>,
+    // Entries across every inner map
+    len: usize,
+}
+
+impl<K1: Eq + Hash, K2: Eq + Hash, V> Map2<K1, K2, V> {
+    pub fn new() -> Self {
+        Map2 {
+            outer: HashMap::new(),
+            len: 0,
+        }
+    }
+
+    /// Number of `(K1, K2)` entries in total
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of distinct outer keys
+    pub fn outer_len(&self) -> usize {
+        self.outer.len()
+    }
+
+    pub fn insert(&mut self, k1: K1, k2: K2, value: V) -> Option<V> {
+        let old = self.outer.entry(k1).or_default().insert(k2, value);
+        if old.is_none() {
+            self.len += 1;
+        }
+        old
+    }
+
+    pub fn get(&self, k1: &K1, k2: &K2) -> Option<&V> {
+        self.outer.get(k1)?.get(k2)
+    }
+
+    pub fn get_mut(&mut self, k1: &K1, k2: &K2) -> Option<&mut V> {
+        self.outer.get_mut(k1)?.get_mut(k2)
+    }
+
+    pub fn contains(&self, k1: &K1, k2: &K2) -> bool {
+        self.get(k1, k2).is_some()
+    }
+
+    pub fn contains_outer(&self, k1: &K1) -> bool {
+        self.outer.contains_key(k1)
+    }
+
+    /// Removes one entry, dropping the inner map for `k1` if it empties
+    pub fn remove(&mut self, k1: &K1, k2: &K2) -> Option<V> {
+        let inner = self.outer.get_mut(k1)?;
+        let value = inner.remove(k2)?;
+        if inner.is_empty() {
+            self.outer.remove(k1);
+        }
+        self.len -= 1;
+        Some(value)
+    }
+
+    /// Removes every entry under `k1`, returning them as a map
+    pub fn remove_outer(&mut self, k1: &K1) -> Option<HashMap<K2, V>> {
+        let inner = self.outer.remove(k1)?;
+        self.len -= inner.len();
+        Some(inner)
+    }
+
+    /// The inner map for `k1`, which is never empty
+    pub fn inner(&self, k1: &K1) -> Option<&HashMap<K2, V>> {
+        self.outer.get(k1)
+    }
+
+    /// Iterates over the entries under `k1`
+    pub fn iter_outer(&self, k1: &K1) -> impl Iterator<Item = (&K2, &V)> {
+        self.outer.get(k1).into_iter().flatten()
+    }
+
+    pub fn outer_keys(&self) -> hash_map::Keys<'_, K1, HashMap<K2, V>> {
+        self.outer.keys()
+    }
+
+    /// Iterates over every entry as `(k1, k2, value)`
+    pub fn iter(&self) -> impl Iterator<Item = (&K1, &K2, &V)> {
+        self.outer
+            .iter()
+            .flat_map(|(k1, inner)| inner.iter().map(move |(k2, value)| (k1, k2, value)))
+    }
+
+    pub fn clear(&mut self) {
+        self.outer.clear();
+        self.len = 0;
+    }
+
+    pub fn into_inner(self) -> HashMap<K1, HashMap<K2, V>> {
+        self.outer
+    }
+}
+
+impl<K1: Eq + Hash, K2: Eq + Hash, V> Default for Map2<K1, K2, V> {
+    fn default() -> Self {
+        Map2::new()
+    }
+}
+
+impl<K1: Eq + Hash, K2: Eq + Hash, V> FromIterator<(K1, K2, V)> for Map2<K1, K2, V> {
+    fn from_iter<I: IntoIterator<Item = (K1, K2, V)>>(iter: I) -> Self {
+        let mut map = Map2::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K1: Eq + Hash, K2: Eq + Hash, V> Extend<(K1, K2, V)> for Map2<K1, K2, V> {
+    fn extend<I: IntoIterator<Item = (K1, K2, V)>>(&mut self, iter: I) {
+        for (k1, k2, value) in iter {
+            self.insert(k1, k2, value);
+        }
+    }
+}
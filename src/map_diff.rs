@@ -0,0 +1,81 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A structural diff between two `HashMap`s
+//!
+//! `map_diff(&old, &new)` reports which keys were added, which were removed
+//! and which now hold a different value. Applying that diff to `old` turns it
+//! into `new`.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+
+/// The differences between an old and a new version of a map
+#[derive(Debug, Clone)]
+pub struct MapDiff<K, V, S = RandomState> {
+    /// Keys only present in the new map, with their values
+    pub added: HashMap<K, V, S>,
+    /// Keys only present in the old map, with their old values
+    pub removed: HashMap<K, V, S>,
+    /// Keys present in both maps with different values, as `(old, new)`
+    pub changed: HashMap<K, (V, V), S>,
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> MapDiff<K, V, S> {
+    /// Returns true if the two maps were equal
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+
+    /// Returns the total number of added, removed and changed keys
+    pub fn len(&self) -> usize {
+        self.added.len() + self.removed.len() + self.changed.len()
+    }
+
+    /// Patches `map` so that it matches the new side of the diff
+    pub fn apply<T: BuildHasher>(self, map: &mut HashMap<K, V, T>) {
+        for key in self.removed.keys() {
+            map.remove(key);
+        }
+        for (key, (_, new)) in self.changed {
+            map.insert(key, new);
+        }
+        map.extend(self.added);
+    }
+}
+
+/// Compares `old` against `new`
+pub fn map_diff<K, V, S>(old: &HashMap<K, V, S>, new: &HashMap<K, V, S>) -> MapDiff<K, V, S>
+where
+    K: Eq + Hash + Clone,
+    V: PartialEq + Clone,
+    S: BuildHasher + Default,
+{
+    let mut diff = MapDiff {
+        added: HashMap::default(),
+        removed: HashMap::default(),
+        changed: HashMap::default(),
+    };
+
+    for (key, old_value) in old {
+        match new.get(key) {
+            None => {
+                diff.removed.insert(key.clone(), old_value.clone());
+            }
+            Some(new_value) if new_value != old_value => {
+                diff.changed
+                    .insert(key.clone(), (old_value.clone(), new_value.clone()));
+            }
+            Some(_) => {}
+        }
+    }
+
+    for (key, new_value) in new {
+        if !old.contains_key(key) {
+            diff.added.insert(key.clone(), new_value.clone());
+        }
+    }
+
+    diff
+}
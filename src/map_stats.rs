@@ -0,0 +1,139 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Measuring how well a hasher spreads a particular set of keys
+//!
+//! A hash map is only fast when its keys land in different buckets. Some key
+//! sets, paired with some hashers, pile into a few buckets instead, and every
+//! lookup then has to wade through the pile. `MapStats` hashes a key set into
+//! a simulated table of buckets, counts how full each bucket gets, and
+//! compares the number of collisions with what a perfectly random hash would
+//! produce, so a pathological combination stands out.
+//!
+//! Buckets are picked from the low bits of the hash, as std's map does.
+
+use std::fmt::Write;
+use std::hash::{BuildHasher, Hash};
+
+// Widest bar `render_histogram` draws
+const BAR_WIDTH: usize = 50;
+
+/// How a key set spread across a table of buckets
+#[derive(Debug, Clone, PartialEq)]
+pub struct MapStats {
+    pub keys: usize,
+    pub buckets: usize,
+    /// Buckets holding at least one key
+    pub used_buckets: usize,
+    /// Keys that landed in an already occupied bucket
+    pub collisions: usize,
+    /// Collisions a uniformly random hash would produce on average
+    pub expected_collisions: f64,
+    /// Most keys sharing a single bucket
+    pub longest_chain: usize,
+    /// A bucket holding `longest_chain` keys, if there were any keys
+    pub worst_bucket: Option<usize>,
+    /// Element `n` is the number of buckets holding exactly `n` keys
+    pub histogram: Vec<usize>,
+}
+
+impl MapStats {
+    /// Hashes `keys` into as many buckets as std's map would allocate for
+    /// them
+    pub fn analyze<'a, K, I, S>(keys: I, hasher: &S) -> MapStats
+    where
+        K: Hash + 'a,
+        I: IntoIterator<Item = &'a K>,
+        S: BuildHasher,
+    {
+        let hashes: Vec<u64> = keys.into_iter().map(|key| hasher.hash_one(key)).collect();
+        let buckets = (hashes.len() * 8 / 7).max(1).next_power_of_two();
+        MapStats::from_hashes(&hashes, buckets)
+    }
+
+    /// Hashes `keys` into `buckets` buckets, rounded up to a power of two
+    pub fn analyze_with_buckets<'a, K, I, S>(keys: I, hasher: &S, buckets: usize) -> MapStats
+    where
+        K: Hash + 'a,
+        I: IntoIterator<Item = &'a K>,
+        S: BuildHasher,
+    {
+        let hashes: Vec<u64> = keys.into_iter().map(|key| hasher.hash_one(key)).collect();
+        MapStats::from_hashes(&hashes, buckets.max(1).next_power_of_two())
+    }
+
+    fn from_hashes(hashes: &[u64], buckets: usize) -> MapStats {
+        let mut occupancy = vec![0usize; buckets];
+        for &hash in hashes {
+            occupancy[(hash as usize) & (buckets - 1)] += 1;
+        }
+
+        let longest_chain = occupancy.iter().copied().max().unwrap_or(0);
+        let mut histogram = vec![0; longest_chain + 1];
+        for &count in &occupancy {
+            histogram[count] += 1;
+        }
+        let used_buckets = buckets - histogram[0];
+
+        // Each bucket stays empty with probability (1 - 1/m)^n, and every key
+        // beyond the first in a bucket is a collision
+        let (n, m) = (hashes.len() as f64, buckets as f64);
+        let expected_used = m * (1.0 - (1.0 - 1.0 / m).powf(n));
+
+        MapStats {
+            keys: hashes.len(),
+            buckets,
+            used_buckets,
+            collisions: hashes.len() - used_buckets,
+            expected_collisions: n - expected_used,
+            longest_chain,
+            worst_bucket: occupancy
+                .iter()
+                .position(|&count| count == longest_chain && count > 0),
+            histogram,
+        }
+    }
+
+    /// How many times more collisions there were than a random hash would
+    /// give; values well above 1 point to a poor hasher for these keys
+    pub fn collision_ratio(&self) -> f64 {
+        if self.expected_collisions == 0.0 {
+            if self.collisions == 0 {
+                1.0
+            } else {
+                f64::INFINITY
+            }
+        } else {
+            self.collisions as f64 / self.expected_collisions
+        }
+    }
+
+    /// Draws the occupancy histogram as one bar per chain length, skipping
+    /// lengths no bucket has
+    pub fn render_histogram(&self) -> String {
+        let tallest = self.histogram.iter().copied().max().unwrap_or(0).max(1);
+        let mut out = String::new();
+        for (chain, &count) in self.histogram.iter().enumerate() {
+            if count == 0 {
+                continue;
+            }
+            let bar = (count * BAR_WIDTH).div_ceil(tallest);
+            writeln!(
+                out,
+                "{:>3} keys | {:<width$} {}",
+                chain,
+                "#".repeat(bar),
+                count,
+                width = BAR_WIDTH
+            )
+            .expect("writing to a String never fails");
+        }
+        writeln!(
+            out,
+            "{} keys in {} buckets: {} collisions (random hash: {:.1}), longest chain {}",
+            self.keys, self.buckets, self.collisions, self.expected_collisions, self.longest_chain
+        )
+        .expect("writing to a String never fails");
+        out
+    }
+}
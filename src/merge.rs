@@ -0,0 +1,116 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Merge two `HashMap`s, deciding what happens when both have the same key
+//!
+//! The motivating case is layered configuration, where a user's settings are
+//! merged over the defaults and conflicts need an explicit rule.
+
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+
+/// What to do when a key is present in both maps
+pub enum MergeStrategy<V> {
+    /// Keep the value from the map being merged into
+    KeepLeft,
+    /// Keep the value from the map being merged in
+    KeepRight,
+    /// Combine both values, left first
+    Combine(fn(V, V) -> V),
+    /// Refuse to merge
+    Error,
+}
+
+// Written out by hand because deriving would require `V: Clone`
+impl<V> Clone for MergeStrategy<V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<V> Copy for MergeStrategy<V> {}
+
+impl<V> fmt::Debug for MergeStrategy<V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeStrategy::KeepLeft => write!(f, "KeepLeft"),
+            MergeStrategy::KeepRight => write!(f, "KeepRight"),
+            MergeStrategy::Combine(_) => write!(f, "Combine(..)"),
+            MergeStrategy::Error => write!(f, "Error"),
+        }
+    }
+}
+
+/// Both maps held `key` and the strategy was `MergeStrategy::Error`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MergeConflict<K> {
+    pub key: K,
+}
+
+impl<K: fmt::Debug> fmt::Display for MergeConflict<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "key {:?} is present in both maps", self.key)
+    }
+}
+
+impl<K: fmt::Debug> Error for MergeConflict<K> {}
+
+/// Merges `right` over `left` and returns the result, which keeps the
+/// hasher of `left`
+pub fn merge<K, V, S, T>(
+    mut left: HashMap<K, V, S>,
+    right: HashMap<K, V, T>,
+    strategy: MergeStrategy<V>,
+) -> Result<HashMap<K, V, S>, MergeConflict<K>>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    merge_into(&mut left, right, strategy)?;
+    Ok(left)
+}
+
+/// Merges `right` into `left` in place
+///
+/// With `MergeStrategy::Error`, conflicts are checked before anything is
+/// moved, so `left` is untouched when a conflict is reported.
+pub fn merge_into<K, V, S, T>(
+    left: &mut HashMap<K, V, S>,
+    right: HashMap<K, V, T>,
+    strategy: MergeStrategy<V>,
+) -> Result<(), MergeConflict<K>>
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+{
+    match strategy {
+        MergeStrategy::KeepLeft => {
+            for (key, value) in right {
+                left.entry(key).or_insert(value);
+            }
+        }
+        MergeStrategy::KeepRight => {
+            left.extend(right);
+        }
+        MergeStrategy::Combine(combine) => {
+            for (key, value) in right {
+                let merged = match left.remove(&key) {
+                    Some(existing) => combine(existing, value),
+                    None => value,
+                };
+                left.insert(key, merged);
+            }
+        }
+        MergeStrategy::Error => {
+            let mut entries: Vec<(K, V)> = right.into_iter().collect();
+            if let Some(index) = entries.iter().position(|(key, _)| left.contains_key(key)) {
+                let (key, _) = entries.swap_remove(index);
+                return Err(MergeConflict { key });
+            }
+            left.extend(entries);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,181 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Merging iterators that are already sorted
+//!
+//! `merge_sorted` combines any number of sorted sources into one sorted
+//! stream by keeping only the head of each source in a small binary heap, so
+//! memory stays proportional to the number of sources however long they are.
+//! `merge_join_by` walks two sorted streams side by side and pairs up the
+//! items that compare equal, like a merge join in a database.
+
+use crate::zip_longest::EitherOrBoth;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::iter::Fuse;
+
+/// Merges sorted `sources` into one sorted iterator
+///
+/// Equal items come out in the order of the sources they came from. If a
+/// source isn't sorted the output isn't either, but every item still comes
+/// out exactly once.
+pub fn merge_sorted<I>(sources: Vec<I>) -> MergeSorted<I::IntoIter>
+where
+    I: IntoIterator,
+    I::Item: Ord,
+{
+    let mut sources: Vec<I::IntoIter> = sources.into_iter().map(IntoIterator::into_iter).collect();
+    let heads = sources
+        .iter_mut()
+        .enumerate()
+        .filter_map(|(source, iter)| iter.next().map(|item| Head { item, source }))
+        .collect();
+    MergeSorted { sources, heads }
+}
+
+/// Walks two streams sorted in the order `cmp` describes, pairing items that
+/// compare equal and passing the rest through on their own side
+///
+/// Each item is matched at most once, so with repeated keys on both sides
+/// the repeats are paired off in order rather than cross-multiplied.
+pub fn merge_join_by<A, B, F>(left: A, right: B, cmp: F) -> MergeJoinBy<A::IntoIter, B::IntoIter, F>
+where
+    A: IntoIterator,
+    B: IntoIterator,
+    F: FnMut(&A::Item, &B::Item) -> Ordering,
+{
+    MergeJoinBy {
+        left: left.into_iter().fuse(),
+        right: right.into_iter().fuse(),
+        left_next: None,
+        right_next: None,
+        cmp,
+    }
+}
+
+// The next item of one source, ordered so the smallest sits at the top of a
+// max-heap and ties go to the earliest source
+#[derive(Debug, Clone)]
+struct Head<T> {
+    item: T,
+    source: usize,
+}
+
+impl<T: Ord> Ord for Head<T> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .item
+            .cmp(&self.item)
+            .then_with(|| other.source.cmp(&self.source))
+    }
+}
+
+impl<T: Ord> PartialOrd for Head<T> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<T: Ord> PartialEq for Head<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<T: Ord> Eq for Head<T> {}
+
+/// Iterator returned by `merge_sorted`
+#[derive(Debug, Clone)]
+pub struct MergeSorted<I: Iterator> {
+    sources: Vec<I>,
+    heads: BinaryHeap<Head<I::Item>>,
+}
+
+impl<I> Iterator for MergeSorted<I>
+where
+    I: Iterator,
+    I::Item: Ord,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let Head { item, source } = self.heads.pop()?;
+        if let Some(next) = self.sources[source].next() {
+            self.heads.push(Head { item: next, source });
+        }
+        Some(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let heads = self.heads.len();
+        self.sources.iter().map(Iterator::size_hint).fold(
+            (heads, Some(heads)),
+            |(lower, upper), (source_lower, source_upper)| {
+                (
+                    lower.saturating_add(source_lower),
+                    upper
+                        .zip(source_upper)
+                        .and_then(|(upper, source)| upper.checked_add(source)),
+                )
+            },
+        )
+    }
+}
+
+/// Iterator returned by `merge_join_by`
+#[derive(Debug, Clone)]
+pub struct MergeJoinBy<A: Iterator, B: Iterator, F> {
+    left: Fuse<A>,
+    right: Fuse<B>,
+    // Items pulled from each side but not yet yielded
+    left_next: Option<A::Item>,
+    right_next: Option<B::Item>,
+    cmp: F,
+}
+
+impl<A, B, F> Iterator for MergeJoinBy<A, B, F>
+where
+    A: Iterator,
+    B: Iterator,
+    F: FnMut(&A::Item, &B::Item) -> Ordering,
+{
+    type Item = EitherOrBoth<A::Item, B::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let left = self.left_next.take().or_else(|| self.left.next());
+        let right = self.right_next.take().or_else(|| self.right.next());
+        match (left, right) {
+            (Some(left), Some(right)) => match (self.cmp)(&left, &right) {
+                Ordering::Less => {
+                    self.right_next = Some(right);
+                    Some(EitherOrBoth::Left(left))
+                }
+                Ordering::Greater => {
+                    self.left_next = Some(left);
+                    Some(EitherOrBoth::Right(right))
+                }
+                Ordering::Equal => Some(EitherOrBoth::Both(left, right)),
+            },
+            (Some(left), None) => Some(EitherOrBoth::Left(left)),
+            (None, Some(right)) => Some(EitherOrBoth::Right(right)),
+            (None, None) => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (left_lower, left_upper) = self.left.size_hint();
+        let (right_lower, right_upper) = self.right.size_hint();
+        let left_pending = self.left_next.is_some() as usize;
+        let right_pending = self.right_next.is_some() as usize;
+        let left_lower = left_lower.saturating_add(left_pending);
+        let right_lower = right_lower.saturating_add(right_pending);
+        // Every step uses at least one item, and at most one from each side
+        let upper = match (left_upper, right_upper) {
+            (Some(left), Some(right)) => left
+                .checked_add(right)
+                .and_then(|upper| upper.checked_add(left_pending + right_pending)),
+            _ => None,
+        };
+        (left_lower.max(right_lower), upper)
+    }
+}
@@ -0,0 +1,288 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A read-only view of a file of fixed-size records, mapped into memory
+//!
+//! Reading a multi-gigabyte column of numbers into a `Vec` copies the whole
+//! file into the heap, while the operating system is caching the same bytes
+//! in its page cache anyway. `MmapVec` maps the file into the address space
+//! instead and reads the records straight out of the page cache, so only the
+//! pages actually touched are ever loaded, and they're shared with every
+//! other process reading the file.
+//!
+//! Records are read in the machine's native byte order, with no header: a
+//! file of `n` records of type `T` is exactly `n * size_of::<T>()` bytes.
+//! Only types implementing `Pod`, whose every bit pattern is a valid value,
+//! can be read this way. Mapping uses the C library's `mmap`, so this module
+//! is only built on Unix.
+
+use std::convert::TryFrom;
+use std::fmt;
+use std::fs::File;
+use std::io;
+use std::mem;
+use std::ops::Deref;
+use std::os::raw::{c_int, c_void};
+use std::os::unix::io::AsRawFd;
+use std::path::Path;
+use std::ptr::{self, NonNull};
+use std::slice;
+
+const PROT_READ: c_int = 1;
+const MAP_PRIVATE: c_int = 2;
+
+extern "C" {
+    // `off_t` is a C `long` on Linux and 64 bits on the BSDs and macOS,
+    // which taken together is an `isize` on every platform with a 64-bit
+    // address space and on 32-bit Linux
+    fn mmap(
+        addr: *mut c_void,
+        len: usize,
+        prot: c_int,
+        flags: c_int,
+        fd: c_int,
+        offset: isize,
+    ) -> *mut c_void;
+    fn munmap(addr: *mut c_void, len: usize) -> c_int;
+}
+
+/// Plain data types that any bit pattern of the right size is a valid
+/// value of
+///
+/// # Safety
+///
+/// Implementors must be `Copy`, have no padding bytes, and accept every bit
+/// pattern, which rules out `bool`, `char`, references and most enums.
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! impl_pod {
+    ($($ty:ty),+) => {
+        $(
+            unsafe impl Pod for $ty {}
+        )+
+    };
+}
+
+impl_pod!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize, f32, f64);
+
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
+
+/// A file of `T` records mapped read-only into memory
+pub struct MmapVec<T: Pod> {
+    // Start of the mapping, or dangling for an empty file, which can't be
+    // mapped
+    data: NonNull<T>,
+    len: usize,
+}
+
+impl<T: Pod> MmapVec<T> {
+    /// Maps the file at `path` as a sequence of `T` records
+    ///
+    /// Fails with `InvalidData` if the file's length isn't a whole number
+    /// of records. Panics if `T` is zero-sized.
+    ///
+    /// # Safety
+    ///
+    /// Nothing may write to or truncate the file while the `MmapVec` is
+    /// alive. Changes would show up in what the view reads, breaking the
+    /// guarantee that a `&[T]` doesn't change underneath it, and reading a
+    /// part that has been truncated away kills the process with `SIGBUS`.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let record = mem::size_of::<T>();
+        assert!(record > 0, "can't map zero-sized records");
+        let file = File::open(path)?;
+        let bytes = file.metadata()?.len();
+        if bytes % record as u64 != 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "file length is not a whole number of records",
+            ));
+        }
+        let bytes = usize::try_from(bytes)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "file too large to map"))?;
+        if bytes == 0 {
+            return Ok(MmapVec {
+                data: NonNull::dangling(),
+                len: 0,
+            });
+        }
+
+        // The mapping stays valid after the file is closed. It's page
+        // aligned, which satisfies the alignment of any `Pod` type.
+        let addr = mmap(
+            ptr::null_mut(),
+            bytes,
+            PROT_READ,
+            MAP_PRIVATE,
+            file.as_raw_fd(),
+            0,
+        );
+        if addr as isize == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(MmapVec {
+            data: NonNull::new_unchecked(addr as *mut T),
+            len: bytes / record,
+        })
+    }
+
+    /// Number of records
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The record at `index`, or `None` past the end
+    pub fn get(&self, index: usize) -> Option<&T> {
+        self.as_slice().get(index)
+    }
+
+    pub fn as_slice(&self) -> &[T] {
+        // Safety: `data` points at `len` initialised records, valid for any
+        // bit pattern, that live as long as the mapping and that `open`'s
+        // caller promised not to change
+        unsafe { slice::from_raw_parts(self.data.as_ptr(), self.len) }
+    }
+
+    pub fn iter(&self) -> slice::Iter<'_, T> {
+        self.as_slice().iter()
+    }
+}
+
+impl<T: Pod> Deref for MmapVec<T> {
+    type Target = [T];
+
+    fn deref(&self) -> &[T] {
+        self.as_slice()
+    }
+}
+
+impl<'a, T: Pod> IntoIterator for &'a MmapVec<T> {
+    type Item = &'a T;
+    type IntoIter = slice::Iter<'a, T>;
+
+    fn into_iter(self) -> slice::Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<T: Pod> Drop for MmapVec<T> {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // Safety: the mapping was made by `open` with this length and
+            // nothing borrowed from it can outlive `self`
+            unsafe {
+                munmap(
+                    self.data.as_ptr() as *mut c_void,
+                    self.len * mem::size_of::<T>(),
+                );
+            }
+        }
+    }
+}
+
+impl<T: Pod + fmt::Debug> fmt::Debug for MmapVec<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// Safety: the mapping is read-only, so sharing or sending it is no different
+// from sharing or sending a `&[T]`
+unsafe impl<T: Pod + Sync> Send for MmapVec<T> {}
+unsafe impl<T: Pod + Sync> Sync for MmapVec<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::thread;
+
+    // A file in the temporary directory holding `bytes`, removed on drop
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new(name: &str, bytes: &[u8]) -> Scratch {
+            let path = env::temp_dir().join(format!("mmap-vec-{}-{}", std::process::id(), name));
+            fs::write(&path, bytes).unwrap();
+            Scratch(path)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn reads_u32_records() {
+        let values: Vec<u32> = (0..5000).map(|n| n * 7 + 1).collect();
+        let bytes: Vec<u8> = values.iter().flat_map(|n| n.to_ne_bytes()).collect();
+        let file = Scratch::new("u32", &bytes);
+        // Safety: nothing else touches the scratch file
+        let records: MmapVec<u32> = unsafe { MmapVec::open(&file.0) }.unwrap();
+        assert_eq!(records.len(), 5000);
+        assert!(!records.is_empty());
+        assert_eq!(records.get(0), Some(&1));
+        assert_eq!(records.get(4999), Some(&(4999 * 7 + 1)));
+        assert_eq!(records.get(5000), None);
+        assert!(records.iter().eq(values.iter()));
+        assert_eq!(&records[..], &values[..]);
+        assert_eq!((&records).into_iter().count(), 5000);
+
+        // Another thread can read it too
+        let sum: u64 = thread::scope(|scope| {
+            scope
+                .spawn(|| records.iter().map(|&n| u64::from(n)).sum())
+                .join()
+                .unwrap()
+        });
+        assert_eq!(sum, values.iter().map(|&n| u64::from(n)).sum::<u64>());
+    }
+
+    #[test]
+    fn reads_byte_array_records() {
+        let file = Scratch::new("triples", b"abcdefghi");
+        // Safety: nothing else touches the scratch file
+        let records: MmapVec<[u8; 3]> = unsafe { MmapVec::open(&file.0) }.unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records.get(1), Some(b"def"));
+        assert_eq!(records.get(3), None);
+        assert_eq!(format!("{:?}", records.get(2).unwrap()), "[103, 104, 105]");
+    }
+
+    #[test]
+    fn an_empty_file_is_an_empty_view() {
+        let file = Scratch::new("empty", b"");
+        // Safety: nothing else touches the scratch file
+        let records: MmapVec<u64> = unsafe { MmapVec::open(&file.0) }.unwrap();
+        assert!(records.is_empty());
+        assert_eq!(records.get(0), None);
+        assert_eq!(records.iter().count(), 0);
+        assert_eq!(format!("{:?}", records), "[]");
+    }
+
+    #[test]
+    fn partial_records_are_invalid_data() {
+        let file = Scratch::new("partial", b"abcdefg");
+        // Safety: nothing else touches the scratch file
+        let err = unsafe { MmapVec::<[u8; 3]>::open(&file.0) }.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = unsafe { MmapVec::<u32>::open(&file.0) }.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn a_missing_file_is_not_found() {
+        let path = env::temp_dir().join(format!("mmap-vec-{}-missing", std::process::id()));
+        // Safety: the file doesn't exist
+        let err = unsafe { MmapVec::<u8>::open(&path) }.unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,159 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Path-based access into maps of maps
+//!
+//! Config-style data is often a tree of string-keyed maps. Rather than
+//! chaining `.get().and_then()` through every level, these helpers take the
+//! whole path at once, e.g. `get_in(&config, &["server", "http", "port"])`.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::BuildHasher;
+
+/// A node in a tree of string-keyed maps, each hashed with an `S`
+#[derive(Debug, Clone)]
+pub enum Nested<V, S = RandomState> {
+    Value(V),
+    Map(HashMap<String, Nested<V, S>, S>),
+}
+
+/// The top level of a tree of nested maps
+pub type NestedMap<V, S = RandomState> = HashMap<String, Nested<V, S>, S>;
+
+impl<V: PartialEq, S: BuildHasher> PartialEq for Nested<V, S> {
+    fn eq(&self, other: &Self) -> bool {
+        match (self, other) {
+            (Nested::Value(a), Nested::Value(b)) => a == b,
+            (Nested::Map(a), Nested::Map(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+impl<V, S: BuildHasher + Default> Nested<V, S> {
+    pub fn map() -> Self {
+        Nested::Map(HashMap::default())
+    }
+}
+
+impl<V, S> Nested<V, S> {
+    pub fn as_value(&self) -> Option<&V> {
+        match self {
+            Nested::Value(value) => Some(value),
+            Nested::Map(_) => None,
+        }
+    }
+
+    pub fn as_map(&self) -> Option<&NestedMap<V, S>> {
+        match self {
+            Nested::Map(map) => Some(map),
+            Nested::Value(_) => None,
+        }
+    }
+}
+
+/// Why a path could not be followed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError {
+    /// The path had no segments
+    EmptyPath,
+    /// The segment at `depth` holds a value, but the path keeps going
+    NotAMap { depth: usize },
+    /// The path ends at a map where a value was expected
+    NotAValue,
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::EmptyPath => write!(f, "path is empty"),
+            PathError::NotAMap { depth } => {
+                write!(f, "segment {} holds a value, not a map", depth)
+            }
+            PathError::NotAValue => write!(f, "path ends at a map, not a value"),
+        }
+    }
+}
+
+impl Error for PathError {}
+
+/// Returns the node at `path`, which may be a value or a whole sub-map
+pub fn get_node_in<'a, V, S>(map: &'a NestedMap<V, S>, path: &[&str]) -> Option<&'a Nested<V, S>>
+where
+    S: BuildHasher,
+{
+    let (last, parents) = path.split_last()?;
+    let mut current = map;
+    for segment in parents {
+        current = current.get(*segment)?.as_map()?;
+    }
+    current.get(*last)
+}
+
+/// Returns the value at `path`, or `None` if any level is missing
+pub fn get_in<'a, V, S>(map: &'a NestedMap<V, S>, path: &[&str]) -> Option<&'a V>
+where
+    S: BuildHasher,
+{
+    get_node_in(map, path)?.as_value()
+}
+
+/// Stores `value` at `path`, creating intermediate maps as needed
+///
+/// Returns whatever node was previously stored at `path`.
+pub fn set_in<V, S>(
+    map: &mut NestedMap<V, S>,
+    path: &[&str],
+    value: V,
+) -> Result<Option<Nested<V, S>>, PathError>
+where
+    S: BuildHasher + Default,
+{
+    let (last, parents) = path.split_last().ok_or(PathError::EmptyPath)?;
+    let parent = descend_mut(map, parents)?;
+    Ok(parent.insert(last.to_string(), Nested::Value(value)))
+}
+
+/// Replaces the value at `path` with `f(old)`, creating intermediate maps as
+/// needed; `old` is `None` when nothing was stored there yet
+pub fn update_in<V, S, F>(map: &mut NestedMap<V, S>, path: &[&str], f: F) -> Result<(), PathError>
+where
+    S: BuildHasher + Default,
+    F: FnOnce(Option<V>) -> V,
+{
+    let (last, parents) = path.split_last().ok_or(PathError::EmptyPath)?;
+    let parent = descend_mut(map, parents)?;
+    if let Some(Nested::Map(_)) = parent.get(*last) {
+        return Err(PathError::NotAValue);
+    }
+
+    let old = parent.remove(*last).and_then(|node| match node {
+        Nested::Value(value) => Some(value),
+        Nested::Map(_) => None,
+    });
+    parent.insert(last.to_string(), Nested::Value(f(old)));
+    Ok(())
+}
+
+// Walks down `path`, creating empty maps for any missing segments. Nothing is
+// created when an error is returned, since a value can only be found in a
+// part of the tree that already existed.
+fn descend_mut<'a, V, S>(
+    mut map: &'a mut NestedMap<V, S>,
+    path: &[&str],
+) -> Result<&'a mut NestedMap<V, S>, PathError>
+where
+    S: BuildHasher + Default,
+{
+    for (depth, segment) in path.iter().enumerate() {
+        let node = map.entry(segment.to_string()).or_insert_with(Nested::map);
+        map = match node {
+            Nested::Map(inner) => inner,
+            Nested::Value(_) => return Err(PathError::NotAMap { depth }),
+        };
+    }
+    Ok(map)
+}
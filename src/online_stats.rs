@@ -0,0 +1,134 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Summary statistics computed in a single pass
+//!
+//! `OnlineStats` folds values in one at a time and never stores them, so it
+//! can summarise a stream of any length in constant memory. The mean and
+//! variance are kept with Welford's algorithm, which stays accurate where the
+//! textbook "sum of squares minus square of sum" formula loses precision.
+//! Two summaries of separate chunks can be combined with `merge`, so the
+//! chunks can be processed in parallel.
+
+use std::iter::FromIterator;
+
+/// Count, extremes, mean and variance of the values seen so far
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OnlineStats {
+    count: u64,
+    mean: f64,
+    // Sum of squared differences from the current mean
+    m2: f64,
+    min: f64,
+    max: f64,
+}
+
+impl OnlineStats {
+    pub fn new() -> Self {
+        OnlineStats {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+
+    pub fn push(&mut self, value: f64) {
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+    }
+
+    /// Folds in the summary of another set of values, as if every value
+    /// behind `other` had been pushed here
+    pub fn merge(&mut self, other: &OnlineStats) {
+        if other.count == 0 {
+            return;
+        }
+        if self.count == 0 {
+            *self = *other;
+            return;
+        }
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        let weight = other.count as f64 / count as f64;
+        self.mean += delta * weight;
+        self.m2 += other.m2 + delta * delta * self.count as f64 * weight;
+        self.count = count;
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    pub fn min(&self) -> Option<f64> {
+        self.value_if_any(self.min)
+    }
+
+    pub fn max(&self) -> Option<f64> {
+        self.value_if_any(self.max)
+    }
+
+    pub fn mean(&self) -> Option<f64> {
+        self.value_if_any(self.mean)
+    }
+
+    /// Variance of the values themselves, dividing by `n`
+    pub fn variance(&self) -> Option<f64> {
+        self.value_if_any(self.m2 / self.count as f64)
+    }
+
+    /// Unbiased estimate of the variance of the population the values were
+    /// drawn from, dividing by `n - 1`; needs at least two values
+    pub fn sample_variance(&self) -> Option<f64> {
+        if self.count < 2 {
+            return None;
+        }
+        Some(self.m2 / (self.count - 1) as f64)
+    }
+
+    /// Square root of `variance`
+    pub fn std_dev(&self) -> Option<f64> {
+        self.variance().map(f64::sqrt)
+    }
+
+    fn value_if_any(&self, value: f64) -> Option<f64> {
+        if self.count == 0 {
+            None
+        } else {
+            Some(value)
+        }
+    }
+}
+
+impl Default for OnlineStats {
+    fn default() -> Self {
+        OnlineStats::new()
+    }
+}
+
+impl Extend<f64> for OnlineStats {
+    fn extend<I: IntoIterator<Item = f64>>(&mut self, iter: I) {
+        for value in iter {
+            self.push(value);
+        }
+    }
+}
+
+impl FromIterator<f64> for OnlineStats {
+    fn from_iter<I: IntoIterator<Item = f64>>(iter: I) -> Self {
+        let mut stats = OnlineStats::new();
+        stats.extend(iter);
+        stats
+    }
+}
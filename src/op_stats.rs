@@ -0,0 +1,156 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Counting what each collection is asked to do, behind the `instrument`
+//! feature
+//!
+//! With the feature on, every `RobinHoodMap`, `ProbeMap`, `CuckooMap`,
+//! `DenseSet`, `PrehashedMap` and `LfuCache` counts its own inserts,
+//! lookups, removals, rehashes, evictions and reallocations, which its
+//! `op_stats` method returns and `reset_op_stats` sets back to zero. Lookups
+//! are counted through `&self`, so two threads reading one collection at
+//! the same moment may lose a count between them; the counters are plain
+//! loads and stores rather than locked increments to keep them cheap.
+//!
+//! With the feature off the counters are empty, every count compiles away
+//! and `op_stats` always returns zeros, so code reading the stats builds
+//! either way.
+//!
+//! A rehash is the entries being placed into a rebuilt table, and a
+//! reallocation is the table changing size. A table growing does both; the
+//! first allocation of an empty one is only a reallocation, while
+//! `ProbeMap::rehash`, or a `CuckooMap` escaping a cycle, is only a rehash.
+
+use std::fmt;
+#[cfg(feature = "instrument")]
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+
+/// How many operations of each kind a collection has carried out
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct OpStats {
+    pub inserts: u64,
+    pub lookups: u64,
+    pub removals: u64,
+    pub rehashes: u64,
+    /// Entries a cache dropped to make room
+    pub evictions: u64,
+    pub reallocations: u64,
+}
+
+impl fmt::Display for OpStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} inserts, {} lookups, {} removals, {} rehashes, {} evictions, {} reallocations",
+            self.inserts,
+            self.lookups,
+            self.removals,
+            self.rehashes,
+            self.evictions,
+            self.reallocations
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum Op {
+    Insert,
+    Lookup,
+    Removal,
+    Rehash,
+    Eviction,
+    Reallocation,
+}
+
+#[cfg(feature = "instrument")]
+const OPS: usize = 6;
+
+/// The counters a collection keeps its `OpStats` in
+///
+/// A clone starts again from zero, since it's a different collection.
+#[derive(Default)]
+pub(crate) struct OpCounters {
+    #[cfg(feature = "instrument")]
+    counts: [AtomicU64; OPS],
+    // Capacity as last seen by `observe_capacity`
+    #[cfg(feature = "instrument")]
+    capacity: AtomicUsize,
+}
+
+impl OpCounters {
+    pub(crate) fn new() -> Self {
+        OpCounters::default()
+    }
+
+    #[inline]
+    pub(crate) fn record(&self, op: Op) {
+        #[cfg(feature = "instrument")]
+        {
+            let count = &self.counts[op as usize];
+            count.store(count.load(Ordering::Relaxed) + 1, Ordering::Relaxed);
+        }
+        #[cfg(not(feature = "instrument"))]
+        let _ = op;
+    }
+
+    /// Records a reallocation if `capacity` differs from what was last
+    /// observed, and a rehash too unless the collection was unallocated
+    #[inline]
+    pub(crate) fn observe_capacity(&self, capacity: usize) {
+        #[cfg(feature = "instrument")]
+        {
+            let previous = self.capacity.load(Ordering::Relaxed);
+            if capacity != previous {
+                self.capacity.store(capacity, Ordering::Relaxed);
+                self.record(Op::Reallocation);
+                if previous > 0 {
+                    self.record(Op::Rehash);
+                }
+            }
+        }
+        #[cfg(not(feature = "instrument"))]
+        let _ = capacity;
+    }
+
+    pub(crate) fn stats(&self) -> OpStats {
+        #[cfg(feature = "instrument")]
+        {
+            let count = |op: Op| self.counts[op as usize].load(Ordering::Relaxed);
+            OpStats {
+                inserts: count(Op::Insert),
+                lookups: count(Op::Lookup),
+                removals: count(Op::Removal),
+                rehashes: count(Op::Rehash),
+                evictions: count(Op::Eviction),
+                reallocations: count(Op::Reallocation),
+            }
+        }
+        #[cfg(not(feature = "instrument"))]
+        OpStats::default()
+    }
+
+    /// Sets every count back to zero, keeping the capacity last observed
+    pub(crate) fn reset(&mut self) {
+        #[cfg(feature = "instrument")]
+        for count in self.counts.iter_mut() {
+            *count.get_mut() = 0;
+        }
+    }
+}
+
+impl Clone for OpCounters {
+    fn clone(&self) -> Self {
+        let counters = OpCounters::new();
+        #[cfg(feature = "instrument")]
+        counters
+            .capacity
+            .store(self.capacity.load(Ordering::Relaxed), Ordering::Relaxed);
+        counters
+    }
+}
+
+impl fmt::Debug for OpCounters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.stats(), f)
+    }
+}
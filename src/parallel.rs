@@ -0,0 +1,430 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Data-parallel helpers for large slices, behind the `parallel` feature
+//!
+//! The input is split into one contiguous chunk per available core and each
+//! chunk is processed on a scoped thread, so the closures can borrow from the
+//! caller without `Arc`. Results are stitched back together in input order.
+//! Spawning threads costs far more than a cheap closure call, so inputs
+//! shorter than `SEQUENTIAL_THRESHOLD` are processed on the calling thread.
+//! A panic in any closure is re-raised on the calling thread.
+//!
+//! The sorts split the vector into one run per thread, sort the runs in
+//! parallel and then merge neighbouring pairs of runs in parallel until one
+//! is left. They are stable, like `slice::sort`.
+//!
+//! `par_group_by` and `par_counter` split their input the same way, build a
+//! map per thread and then fold the maps together, so each key is hashed
+//! into a map that only its own thread touches.
+//!
+//! `ParIter`, which `Spreadsheet::par_rows` and `Grid::par_cells` and their
+//! siblings return, is a small stand-in for a rayon parallel iterator: one
+//! ordinary iterator per thread, with `map` and `filter` applied to each and
+//! `for_each`, `count` or `collect` running them all at once.
+
+use crate::counter::Counter;
+use crate::grouping;
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::iter::FromIterator;
+use std::mem;
+use std::ops::Range;
+use std::panic;
+use std::sync::Arc;
+use std::thread::{self, ScopedJoinHandle};
+
+/// Inputs shorter than this are processed sequentially
+pub const SEQUENTIAL_THRESHOLD: usize = 16 * 1024;
+
+/// Applies `f` to every item, in parallel for large inputs, returning the
+/// results in input order
+pub fn par_map_vec<T, U, F>(items: &[T], f: F) -> Vec<U>
+where
+    T: Sync,
+    U: Send,
+    F: Fn(&T) -> U + Sync,
+{
+    concat(run_chunks(items, |chunk| {
+        chunk.iter().map(&f).collect::<Vec<U>>()
+    }))
+}
+
+/// Clones out the items matching `predicate`, in parallel for large inputs,
+/// keeping their input order
+pub fn par_filter_vec<T, F>(items: &[T], predicate: F) -> Vec<T>
+where
+    T: Sync + Send + Clone,
+    F: Fn(&T) -> bool + Sync,
+{
+    concat(run_chunks(items, |chunk| {
+        chunk
+            .iter()
+            .filter(|item| predicate(item))
+            .cloned()
+            .collect::<Vec<T>>()
+    }))
+}
+
+/// Sorts `items` in place, in parallel for large inputs
+pub fn par_sort_vec<T: Ord + Send>(items: &mut Vec<T>) {
+    par_sort_by(items, T::cmp);
+}
+
+/// Sorts `items` in place by the key `key` extracts, in parallel for large
+/// inputs
+///
+/// `key` is called on every comparison, so it should be cheap.
+pub fn par_sort_by_key<T, K, F>(items: &mut Vec<T>, key: F)
+where
+    T: Send,
+    K: Ord,
+    F: Fn(&T) -> K + Sync,
+{
+    par_sort_by(items, |a, b| key(a).cmp(&key(b)));
+}
+
+/// Sorts `items` in place with the comparator `compare`, in parallel for
+/// large inputs
+pub fn par_sort_by<T, F>(items: &mut Vec<T>, compare: F)
+where
+    T: Send,
+    F: Fn(&T, &T) -> Ordering + Sync,
+{
+    let threads = thread_count();
+    if items.len() < SEQUENTIAL_THRESHOLD || threads == 1 {
+        items.sort_by(compare);
+        return;
+    }
+
+    let mut runs = split_runs(mem::take(items), threads);
+    let compare = &compare;
+    thread::scope(|scope| {
+        let handles: Vec<_> = runs
+            .iter_mut()
+            .map(|run| scope.spawn(move || run.sort_by(compare)))
+            .collect();
+        handles.into_iter().for_each(join);
+    });
+
+    while runs.len() > 1 {
+        runs = thread::scope(|scope| {
+            let mut pairs = Vec::with_capacity(runs.len().div_ceil(2));
+            let mut drained = runs.into_iter();
+            while let Some(left) = drained.next() {
+                match drained.next() {
+                    Some(right) => {
+                        pairs.push(scope.spawn(move || merge_runs(left, right, compare)))
+                    }
+                    None => pairs.push(scope.spawn(move || left)),
+                }
+            }
+            pairs.into_iter().map(join).collect()
+        });
+    }
+    *items = runs.pop().unwrap_or_default();
+}
+
+/// Groups items by `key_fn` like `grouping::group_by`, building the groups
+/// on several threads for large inputs
+///
+/// The items are collected into one `Vec` and split into a run per thread.
+/// Each group keeps its items in input order.
+pub fn par_group_by<I, K, F>(iter: I, key_fn: F) -> HashMap<K, Vec<I::Item>>
+where
+    I: IntoIterator,
+    I::Item: Send,
+    K: Eq + Hash + Send,
+    F: Fn(&I::Item) -> K + Sync,
+{
+    let partials = map_runs(iter.into_iter().collect(), |run| {
+        grouping::group_by(run, &key_fn)
+    });
+    let mut partials = partials.into_iter();
+    let mut groups = partials.next().unwrap_or_default();
+    // Later runs hold later items, so appending keeps input order
+    for partial in partials {
+        for (key, items) in partial {
+            groups.entry(key).or_default().extend(items);
+        }
+    }
+    groups
+}
+
+/// Counts how often each item occurs, counting on several threads for large
+/// inputs
+pub fn par_counter<I>(iter: I) -> Counter<I::Item>
+where
+    I: IntoIterator,
+    I::Item: Eq + Hash + Send,
+{
+    let partials = map_runs(iter.into_iter().collect(), |run| {
+        run.into_iter().collect::<Counter<_>>()
+    });
+    let mut partials = partials.into_iter();
+    let mut counts = partials.next().unwrap_or_default();
+    for partial in partials {
+        counts += partial;
+    }
+    counts
+}
+
+// Splits `items` into one run per thread and runs `work` on each, returning
+// the results in run order, or runs `work` once on a short input
+fn map_runs<T, R, F>(items: Vec<T>, work: F) -> Vec<R>
+where
+    T: Send,
+    R: Send,
+    F: Fn(Vec<T>) -> R + Sync,
+{
+    let threads = thread_count();
+    if items.len() < SEQUENTIAL_THRESHOLD || threads == 1 {
+        return vec![work(items)];
+    }
+
+    let work = &work;
+    thread::scope(|scope| {
+        let handles: Vec<_> = split_runs(items, threads)
+            .into_iter()
+            .map(|run| scope.spawn(move || work(run)))
+            .collect();
+        handles.into_iter().map(join).collect()
+    })
+}
+
+// Cuts `items` into `runs` pieces of nearly equal length, in order
+fn split_runs<T>(mut items: Vec<T>, runs: usize) -> Vec<Vec<T>> {
+    let run_len = items.len().div_ceil(runs).max(1);
+    let mut pieces = Vec::with_capacity(runs);
+    while items.len() > run_len {
+        let tail = items.split_off(run_len);
+        pieces.push(items);
+        items = tail;
+    }
+    pieces.push(items);
+    pieces
+}
+
+/// Items split into one sequential iterator per thread
+///
+/// Adapters are lazy and run on the worker threads; nothing happens until
+/// `for_each`, `count` or `collect` is called. `collect` keeps the order a
+/// sequential iterator would have.
+pub struct ParIter<I> {
+    parts: Vec<I>,
+}
+
+impl<I> ParIter<I>
+where
+    I: Iterator + Send,
+{
+    /// Applies `f` to every item
+    pub fn map<U, F>(self, f: F) -> ParIter<MapPart<I, F>>
+    where
+        F: Fn(I::Item) -> U + Send + Sync,
+    {
+        let f = Arc::new(f);
+        ParIter {
+            parts: self
+                .parts
+                .into_iter()
+                .map(|inner| MapPart {
+                    inner,
+                    f: Arc::clone(&f),
+                })
+                .collect(),
+        }
+    }
+
+    /// Keeps the items matching `predicate`
+    pub fn filter<F>(self, predicate: F) -> ParIter<FilterPart<I, F>>
+    where
+        F: Fn(&I::Item) -> bool + Send + Sync,
+    {
+        let predicate = Arc::new(predicate);
+        ParIter {
+            parts: self
+                .parts
+                .into_iter()
+                .map(|inner| FilterPart {
+                    inner,
+                    predicate: Arc::clone(&predicate),
+                })
+                .collect(),
+        }
+    }
+
+    /// Calls `f` on every item, in no particular order
+    pub fn for_each<F>(self, f: F)
+    where
+        F: Fn(I::Item) + Sync,
+    {
+        self.run(|part| part.for_each(&f));
+    }
+
+    pub fn count(self) -> usize {
+        self.run(Iterator::count).into_iter().sum()
+    }
+
+    /// Collects every item, in order
+    pub fn collect<C>(self) -> C
+    where
+        I::Item: Send,
+        C: FromIterator<I::Item>,
+    {
+        self.run(Iterator::collect::<Vec<_>>)
+            .into_iter()
+            .flatten()
+            .collect()
+    }
+
+    // Runs `work` on every part, on the calling thread if there's only one
+    fn run<R, F>(self, work: F) -> Vec<R>
+    where
+        R: Send,
+        F: Fn(I) -> R + Sync,
+    {
+        if self.parts.len() == 1 {
+            return self.parts.into_iter().map(work).collect();
+        }
+        let work = &work;
+        thread::scope(|scope| {
+            let handles: Vec<_> = self
+                .parts
+                .into_iter()
+                .map(|part| scope.spawn(move || work(part)))
+                .collect();
+            handles.into_iter().map(join).collect()
+        })
+    }
+}
+
+/// One thread's part of `ParIter::map`
+pub struct MapPart<I, F> {
+    inner: I,
+    f: Arc<F>,
+}
+
+impl<I, U, F> Iterator for MapPart<I, F>
+where
+    I: Iterator,
+    F: Fn(I::Item) -> U,
+{
+    type Item = U;
+
+    fn next(&mut self) -> Option<U> {
+        self.inner.next().map(&*self.f)
+    }
+}
+
+/// One thread's part of `ParIter::filter`
+pub struct FilterPart<I, F> {
+    inner: I,
+    predicate: Arc<F>,
+}
+
+impl<I, F> Iterator for FilterPart<I, F>
+where
+    I: Iterator,
+    F: Fn(&I::Item) -> bool,
+{
+    type Item = I::Item;
+
+    fn next(&mut self) -> Option<I::Item> {
+        let predicate = &*self.predicate;
+        self.inner.find(|item| predicate(item))
+    }
+}
+
+/// Builds a `ParIter` over the indices `0..len`, cutting them into one range
+/// per thread and calling `part` to turn each range into an iterator
+///
+/// `work` is roughly how many items the ranges hold between them. A single
+/// part covers everything when that is too few to be worth splitting.
+pub(crate) fn par_ranges<I, F>(len: usize, work: usize, mut part: F) -> ParIter<I>
+where
+    F: FnMut(Range<usize>) -> I,
+{
+    let threads = thread_count();
+    if work < SEQUENTIAL_THRESHOLD || threads == 1 || len < 2 {
+        return ParIter {
+            parts: vec![part(0..len)],
+        };
+    }
+    let range_len = len.div_ceil(threads);
+    ParIter {
+        parts: (0..len)
+            .step_by(range_len)
+            .map(|start| start..(start + range_len).min(len))
+            .map(part)
+            .collect(),
+    }
+}
+
+// Merges two sorted runs, taking from `left` on ties to keep the sort stable
+fn merge_runs<T, F>(left: Vec<T>, right: Vec<T>, compare: &F) -> Vec<T>
+where
+    F: Fn(&T, &T) -> Ordering,
+{
+    let mut merged = Vec::with_capacity(left.len() + right.len());
+    let mut left = left.into_iter().peekable();
+    let mut right = right.into_iter().peekable();
+    while let (Some(l), Some(r)) = (left.peek(), right.peek()) {
+        if compare(r, l) == Ordering::Less {
+            merged.extend(right.next());
+        } else {
+            merged.extend(left.next());
+        }
+    }
+    merged.extend(left);
+    merged.extend(right);
+    merged
+}
+
+/// Splits `items` into one chunk per thread and runs `work` on each,
+/// returning the per-chunk results in order
+///
+/// Runs `work` once over the whole slice when it is too short to be worth
+/// splitting.
+pub(crate) fn run_chunks<T, R, F>(items: &[T], work: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&[T]) -> R + Sync,
+{
+    let threads = thread_count();
+    if items.len() < SEQUENTIAL_THRESHOLD || threads == 1 {
+        return vec![work(items)];
+    }
+
+    let chunk_len = items.len().div_ceil(threads);
+    let work = &work;
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks(chunk_len)
+            .map(|chunk| scope.spawn(move || work(chunk)))
+            .collect();
+        handles.into_iter().map(join).collect()
+    })
+}
+
+fn thread_count() -> usize {
+    thread::available_parallelism().map_or(1, |count| count.get())
+}
+
+// Waits for a scoped thread, re-raising its panic on this thread
+fn join<R>(handle: ScopedJoinHandle<'_, R>) -> R {
+    handle
+        .join()
+        .unwrap_or_else(|payload| panic::resume_unwind(payload))
+}
+
+fn concat<U>(parts: Vec<Vec<U>>) -> Vec<U> {
+    let mut parts = parts.into_iter();
+    let mut all = parts.next().unwrap_or_default();
+    for part in parts {
+        all.extend(part);
+    }
+    all
+}
@@ -0,0 +1,565 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A persistent hash map built as a hash array mapped trie (HAMT)
+//!
+//! `insert` and `remove` never change a map in place. They return a new map
+//! that shares every untouched node with the old one, so only the nodes on
+//! the path to the changed key are copied. Cloning is just bumping a
+//! reference count, which makes keeping many historical versions of a large
+//! map cheap.
+//!
+//! Each level of the trie consumes five bits of the key's hash and holds up
+//! to 32 children, stored densely alongside a bitmap saying which of the 32
+//! slots are in use.
+
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+use std::iter::FromIterator;
+use std::sync::Arc;
+
+const BITS_PER_LEVEL: u32 = 5;
+const LEVEL_MASK: u64 = (1 << BITS_PER_LEVEL) - 1;
+
+#[derive(Debug)]
+enum Node<K, V> {
+    Branch {
+        bitmap: u32,
+        children: Vec<Arc<Node<K, V>>>,
+    },
+    Leaf {
+        hash: u64,
+        key: K,
+        value: V,
+    },
+    // Distinct keys whose full 64-bit hashes are equal
+    Collision {
+        hash: u64,
+        entries: Vec<(K, V)>,
+    },
+}
+
+// What happened to a subtree when a key was removed from it
+enum Removal<K, V> {
+    NotFound,
+    Removed(Option<Arc<Node<K, V>>>),
+}
+
+fn slot(hash: u64, shift: u32) -> u32 {
+    ((hash >> shift) & LEVEL_MASK) as u32
+}
+
+// Where the child for `bit` sits in a dense children vector
+fn dense_index(bitmap: u32, bit: u32) -> usize {
+    (bitmap & (bit - 1)).count_ones() as usize
+}
+
+impl<K: Clone + Eq, V: Clone> Node<K, V> {
+    fn get<Q>(&self, hash: u64, mut shift: u32, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        let mut node = self;
+        loop {
+            match node {
+                Node::Branch { bitmap, children } => {
+                    let bit = 1 << slot(hash, shift);
+                    if bitmap & bit == 0 {
+                        return None;
+                    }
+                    node = &children[dense_index(*bitmap, bit)];
+                    shift += BITS_PER_LEVEL;
+                }
+                Node::Leaf {
+                    hash: leaf_hash,
+                    key: leaf_key,
+                    value,
+                } => {
+                    return if *leaf_hash == hash && leaf_key.borrow() == key {
+                        Some(value)
+                    } else {
+                        None
+                    };
+                }
+                Node::Collision {
+                    hash: bucket_hash,
+                    entries,
+                } => {
+                    if *bucket_hash != hash {
+                        return None;
+                    }
+                    return entries
+                        .iter()
+                        .find(|(entry_key, _)| entry_key.borrow() == key)
+                        .map(|(_, value)| value);
+                }
+            }
+        }
+    }
+
+    // Returns the new subtree and whether a new key was added, as opposed to
+    // an existing key being given a new value
+    fn insert(node: &Arc<Self>, hash: u64, shift: u32, key: K, value: V) -> (Arc<Self>, bool) {
+        match &**node {
+            Node::Branch { bitmap, children } => {
+                let bit = 1 << slot(hash, shift);
+                let index = dense_index(*bitmap, bit);
+                let mut children = children.clone();
+                let added = if bitmap & bit == 0 {
+                    children.insert(index, Arc::new(Node::Leaf { hash, key, value }));
+                    true
+                } else {
+                    let (child, added) =
+                        Node::insert(&children[index], hash, shift + BITS_PER_LEVEL, key, value);
+                    children[index] = child;
+                    added
+                };
+                let branch = Node::Branch {
+                    bitmap: bitmap | bit,
+                    children,
+                };
+                (Arc::new(branch), added)
+            }
+            Node::Leaf {
+                hash: leaf_hash,
+                key: leaf_key,
+                value: leaf_value,
+            } => {
+                if *leaf_hash != hash {
+                    let leaf = Arc::new(Node::Leaf { hash, key, value });
+                    let branch = Node::split(node.clone(), *leaf_hash, leaf, hash, shift);
+                    (Arc::new(branch), true)
+                } else if *leaf_key == key {
+                    (Arc::new(Node::Leaf { hash, key, value }), false)
+                } else {
+                    let entries = vec![(leaf_key.clone(), leaf_value.clone()), (key, value)];
+                    (Arc::new(Node::Collision { hash, entries }), true)
+                }
+            }
+            Node::Collision {
+                hash: bucket_hash,
+                entries,
+            } => {
+                if *bucket_hash != hash {
+                    let leaf = Arc::new(Node::Leaf { hash, key, value });
+                    let branch = Node::split(node.clone(), *bucket_hash, leaf, hash, shift);
+                    return (Arc::new(branch), true);
+                }
+
+                let mut entries = entries.clone();
+                let added = match entries.iter_mut().find(|(entry_key, _)| *entry_key == key) {
+                    Some(entry) => {
+                        entry.1 = value;
+                        false
+                    }
+                    None => {
+                        entries.push((key, value));
+                        true
+                    }
+                };
+                (Arc::new(Node::Collision { hash, entries }), added)
+            }
+        }
+    }
+
+    // Builds the branches needed to tell apart two nodes whose hashes differ
+    fn split(a: Arc<Self>, a_hash: u64, b: Arc<Self>, b_hash: u64, shift: u32) -> Self {
+        let a_slot = slot(a_hash, shift);
+        let b_slot = slot(b_hash, shift);
+
+        if a_slot == b_slot {
+            let child = Node::split(a, a_hash, b, b_hash, shift + BITS_PER_LEVEL);
+            return Node::Branch {
+                bitmap: 1 << a_slot,
+                children: vec![Arc::new(child)],
+            };
+        }
+
+        let children = if a_slot < b_slot {
+            vec![a, b]
+        } else {
+            vec![b, a]
+        };
+        Node::Branch {
+            bitmap: (1 << a_slot) | (1 << b_slot),
+            children,
+        }
+    }
+
+    fn remove<Q>(node: &Arc<Self>, hash: u64, shift: u32, key: &Q) -> Removal<K, V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        match &**node {
+            Node::Branch { bitmap, children } => {
+                let bit = 1 << slot(hash, shift);
+                if bitmap & bit == 0 {
+                    return Removal::NotFound;
+                }
+                let index = dense_index(*bitmap, bit);
+                let replacement =
+                    match Node::remove(&children[index], hash, shift + BITS_PER_LEVEL, key) {
+                        Removal::NotFound => return Removal::NotFound,
+                        Removal::Removed(replacement) => replacement,
+                    };
+
+                let mut children = children.clone();
+                let mut bitmap = *bitmap;
+                match replacement {
+                    Some(child) => children[index] = child,
+                    None => {
+                        children.remove(index);
+                        bitmap &= !bit;
+                    }
+                }
+
+                // A branch left holding a single leaf is replaced by that leaf,
+                // which keeps the trie as shallow as the remaining keys allow
+                match children.len() {
+                    0 => Removal::Removed(None),
+                    1 if !matches!(*children[0], Node::Branch { .. }) => {
+                        Removal::Removed(children.pop())
+                    }
+                    _ => Removal::Removed(Some(Arc::new(Node::Branch { bitmap, children }))),
+                }
+            }
+            Node::Leaf {
+                hash: leaf_hash,
+                key: leaf_key,
+                ..
+            } => {
+                if *leaf_hash == hash && leaf_key.borrow() == key {
+                    Removal::Removed(None)
+                } else {
+                    Removal::NotFound
+                }
+            }
+            Node::Collision {
+                hash: bucket_hash,
+                entries,
+            } => {
+                let position = entries
+                    .iter()
+                    .position(|(entry_key, _)| entry_key.borrow() == key);
+                let position = match position {
+                    Some(position) if *bucket_hash == hash => position,
+                    _ => return Removal::NotFound,
+                };
+
+                let mut entries = entries.clone();
+                entries.remove(position);
+                if entries.len() == 1 {
+                    let (key, value) = entries.pop().expect("one entry is left");
+                    Removal::Removed(Some(Arc::new(Node::Leaf { hash, key, value })))
+                } else {
+                    Removal::Removed(Some(Arc::new(Node::Collision { hash, entries })))
+                }
+            }
+        }
+    }
+}
+
+/// A persistent hash map whose updates return new versions sharing structure
+/// with the old ones
+pub struct PHashMap<K, V, S = RandomState> {
+    root: Option<Arc<Node<K, V>>>,
+    len: usize,
+    hasher: S,
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> PHashMap<K, V> {
+    pub fn new() -> Self {
+        PHashMap::with_hasher(RandomState::new())
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: Clone, S: BuildHasher + Clone> PHashMap<K, V, S> {
+    /// Creates an empty map; every version derived from it shares `hasher`
+    pub fn with_hasher(hasher: S) -> Self {
+        PHashMap {
+            root: None,
+            len: 0,
+            hasher,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let hash = self.hasher.hash_one(key);
+        self.root.as_ref()?.get(hash, 0, key)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// Returns a new map with `key` set to `value`, leaving `self` unchanged
+    pub fn insert(&self, key: K, value: V) -> Self {
+        let hash = self.hasher.hash_one(&key);
+        let (root, added) = match &self.root {
+            Some(root) => Node::insert(root, hash, 0, key, value),
+            None => (Arc::new(Node::Leaf { hash, key, value }), true),
+        };
+
+        PHashMap {
+            root: Some(root),
+            len: self.len + added as usize,
+            hasher: self.hasher.clone(),
+        }
+    }
+
+    /// Returns a new map without `key`, leaving `self` unchanged
+    pub fn remove<Q>(&self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let hash = self.hasher.hash_one(key);
+        let removal = match &self.root {
+            Some(root) => Node::remove(root, hash, 0, key),
+            None => Removal::NotFound,
+        };
+
+        match removal {
+            Removal::NotFound => self.clone(),
+            Removal::Removed(root) => PHashMap {
+                root,
+                len: self.len - 1,
+                hasher: self.hasher.clone(),
+            },
+        }
+    }
+
+    /// Returns true if both maps are the very same version, which is cheaper
+    /// than comparing entries
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        match (&self.root, &other.root) {
+            (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    /// Iterates over the entries in hash order
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            stack: self.root.iter().map(|root| &**root).collect(),
+            collision: [].iter(),
+        }
+    }
+}
+
+// Cloning only copies the root pointer, so every clone is O(1)
+impl<K, V, S: Clone> Clone for PHashMap<K, V, S> {
+    fn clone(&self) -> Self {
+        PHashMap {
+            root: self.root.clone(),
+            len: self.len,
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> Default for PHashMap<K, V> {
+    fn default() -> Self {
+        PHashMap::new()
+    }
+}
+
+impl<K, V, S> fmt::Debug for PHashMap<K, V, S>
+where
+    K: Clone + Eq + Hash + fmt::Debug,
+    V: Clone + fmt::Debug,
+    S: BuildHasher + Clone,
+{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K: Clone + Eq + Hash, V: Clone> FromIterator<(K, V)> for PHashMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(PHashMap::new(), |map, (key, value)| map.insert(key, value))
+    }
+}
+
+/// Iterator over the entries of a `PHashMap`
+pub struct Iter<'a, K, V> {
+    stack: Vec<&'a Node<K, V>>,
+    collision: std::slice::Iter<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((key, value)) = self.collision.next() {
+                return Some((key, value));
+            }
+
+            match self.stack.pop()? {
+                Node::Branch { children, .. } => {
+                    self.stack
+                        .extend(children.iter().rev().map(|child| &**child));
+                }
+                Node::Leaf { key, value, .. } => return Some((key, value)),
+                Node::Collision { entries, .. } => self.collision = entries.iter(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, XorShift64};
+    use std::collections::HashMap;
+    use std::hash::Hasher;
+
+    // Hashes a `u64` key with a chosen function, to force collisions and
+    // long shared hash prefixes
+    #[derive(Clone, Copy)]
+    struct Rigged(fn(u64) -> u64);
+
+    struct RiggedHasher(fn(u64) -> u64, u64);
+
+    impl Hasher for RiggedHasher {
+        fn write(&mut self, _: &[u8]) {
+            unreachable!("only u64 keys are hashed");
+        }
+
+        fn write_u64(&mut self, key: u64) {
+            self.1 = (self.0)(key);
+        }
+
+        fn finish(&self) -> u64 {
+            self.1
+        }
+    }
+
+    impl BuildHasher for Rigged {
+        type Hasher = RiggedHasher;
+
+        fn build_hasher(&self) -> RiggedHasher {
+            RiggedHasher(self.0, 0)
+        }
+    }
+
+    fn assert_matches<S: BuildHasher + Clone>(
+        map: &PHashMap<u64, u64, S>,
+        model: &HashMap<u64, u64>,
+    ) {
+        assert_eq!(map.len(), model.len());
+        for (key, value) in model {
+            assert_eq!(map.get(key), Some(value), "key {}", key);
+        }
+        let mut entries: Vec<(u64, u64)> = map.iter().map(|(&k, &v)| (k, v)).collect();
+        entries.sort_unstable();
+        let mut expected: Vec<(u64, u64)> = model.iter().map(|(&k, &v)| (k, v)).collect();
+        expected.sort_unstable();
+        assert_eq!(entries, expected);
+    }
+
+    // Random inserts and removes, checking every version kept along the way
+    // still holds exactly what it held when it was made
+    fn check_against_model(hasher: Rigged, seed: u64) {
+        let mut rng = XorShift64::new(seed);
+        let mut map = PHashMap::with_hasher(hasher);
+        let mut model = HashMap::new();
+        let mut versions = Vec::new();
+        for step in 0..2000 {
+            let key = rng.below(300);
+            if rng.below(3) == 0 {
+                let removed = map.remove(&key);
+                assert_eq!(removed.ptr_eq(&map), !model.contains_key(&key));
+                model.remove(&key);
+                map = removed;
+                assert!(!map.contains_key(&key));
+            } else {
+                map = map.insert(key, step);
+                model.insert(key, step);
+            }
+            if step % 100 == 0 {
+                versions.push((map.clone(), model.clone()));
+            }
+        }
+        assert_matches(&map, &model);
+        for (version, model) in &versions {
+            assert_matches(version, model);
+        }
+        for key in 0..300 {
+            map = map.remove(&key);
+        }
+        assert!(map.is_empty());
+        assert!(map.root.is_none());
+        assert_eq!(map.iter().count(), 0);
+    }
+
+    #[test]
+    fn matches_a_hash_map() {
+        check_against_model(Rigged(|key| key.wrapping_mul(0x9E37_79B9_7F4A_7C15)), 1);
+    }
+
+    #[test]
+    fn full_hash_collisions() {
+        check_against_model(Rigged(|key| key % 7), 2);
+    }
+
+    #[test]
+    fn hashes_that_differ_only_in_their_top_bits() {
+        check_against_model(Rigged(|key| key << 55), 3);
+    }
+
+    #[test]
+    fn versions_are_independent() {
+        let empty: PHashMap<&str, i32> = PHashMap::new();
+        let one = empty.insert("a", 1);
+        let two = one.insert("b", 2);
+        let replaced = two.insert("a", 10);
+        let removed = replaced.remove("b");
+        assert!(empty.is_empty());
+        assert_eq!((one.get("a"), one.get("b")), (Some(&1), None));
+        assert_eq!((two.get("a"), two.get("b")), (Some(&1), Some(&2)));
+        assert_eq!(replaced.len(), 2);
+        assert_eq!(replaced.get("a"), Some(&10));
+        assert_eq!(removed.len(), 1);
+        assert_eq!(removed.get("a"), Some(&10));
+        assert!(two.clone().ptr_eq(&two));
+        assert!(!two.ptr_eq(&replaced));
+        assert!(empty.remove("a").ptr_eq(&empty));
+    }
+
+    #[test]
+    fn removing_a_collision_leaves_a_leaf() {
+        let map = PHashMap::with_hasher(Rigged(|_| 42))
+            .insert(1u64, 1)
+            .insert(2, 2)
+            .insert(3, 3);
+        assert!(matches!(map.root.as_deref(), Some(Node::Collision { .. })));
+        let map = map.remove(&1).remove(&2);
+        assert!(matches!(map.root.as_deref(), Some(Node::Leaf { .. })));
+        assert_eq!(map.get(&3), Some(&3));
+        assert_eq!(map.get(&1), None);
+    }
+}
@@ -0,0 +1,315 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A string-keyed map backed by a trie
+//!
+//! `PrefixMap` offers the same basic surface as `HashMap<String, V>`, so it
+//! can replace one directly, but it can also answer "which keys start with
+//! this?" without scanning every key. Each node records how many values live
+//! beneath it, which makes `count_prefix` proportional to the prefix length.
+
+use std::collections::{BTreeMap, HashMap};
+use std::iter::FromIterator;
+
+#[derive(Debug, Clone)]
+struct Node<V> {
+    value: Option<V>,
+    children: BTreeMap<char, Node<V>>,
+    // Values stored in this node and everything below it
+    count: usize,
+}
+
+impl<V> Node<V> {
+    fn new() -> Self {
+        Node {
+            value: None,
+            children: BTreeMap::new(),
+            count: 0,
+        }
+    }
+}
+
+/// A map from strings to values supporting prefix queries
+#[derive(Debug, Clone)]
+pub struct PrefixMap<V> {
+    root: Node<V>,
+}
+
+impl<V> PrefixMap<V> {
+    pub fn new() -> Self {
+        PrefixMap { root: Node::new() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.root.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.count == 0
+    }
+
+    /// Inserts `value` under `key`, returning the value it replaced
+    pub fn insert(&mut self, key: &str, value: V) -> Option<V> {
+        // Walk once to see whether the key exists, so the counts along the
+        // path are only bumped for genuinely new keys
+        let is_new = self.get(key).is_none();
+        let mut node = &mut self.root;
+        for c in key.chars() {
+            if is_new {
+                node.count += 1;
+            }
+            node = node.children.entry(c).or_insert_with(Node::new);
+        }
+        if is_new {
+            node.count += 1;
+        }
+        node.value.replace(value)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&V> {
+        self.find(key)?.value.as_ref()
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut V> {
+        let mut node = &mut self.root;
+        for c in key.chars() {
+            node = node.children.get_mut(&c)?;
+        }
+        node.value.as_mut()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.get(key).is_some()
+    }
+
+    /// Removes `key`, pruning any nodes left without values beneath them
+    pub fn remove(&mut self, key: &str) -> Option<V> {
+        let chars: Vec<char> = key.chars().collect();
+        remove_from(&mut self.root, &chars)
+    }
+
+    /// Iterates over every entry in lexicographic key order
+    pub fn iter(&self) -> Iter<'_, V> {
+        self.iter_prefix("")
+    }
+
+    /// Iterates over the entries whose keys start with `prefix`, in
+    /// lexicographic key order
+    pub fn iter_prefix(&self, prefix: &str) -> Iter<'_, V> {
+        let stack = match self.find(prefix) {
+            Some(node) => vec![(prefix.to_string(), node)],
+            None => Vec::new(),
+        };
+        Iter { stack }
+    }
+
+    /// Counts the keys that start with `prefix`
+    pub fn count_prefix(&self, prefix: &str) -> usize {
+        self.find(prefix).map_or(0, |node| node.count)
+    }
+
+    pub fn clear(&mut self) {
+        self.root = Node::new();
+    }
+
+    fn find(&self, key: &str) -> Option<&Node<V>> {
+        let mut node = &self.root;
+        for c in key.chars() {
+            node = node.children.get(&c)?;
+        }
+        Some(node)
+    }
+}
+
+fn remove_from<V>(node: &mut Node<V>, key: &[char]) -> Option<V> {
+    let removed = match key.split_first() {
+        None => node.value.take(),
+        Some((c, rest)) => {
+            let child = node.children.get_mut(c)?;
+            let removed = remove_from(child, rest);
+            if child.count == 0 {
+                node.children.remove(c);
+            }
+            removed
+        }
+    };
+    if removed.is_some() {
+        node.count -= 1;
+    }
+    removed
+}
+
+impl<V> Default for PrefixMap<V> {
+    fn default() -> Self {
+        PrefixMap::new()
+    }
+}
+
+impl<V> From<HashMap<String, V>> for PrefixMap<V> {
+    fn from(map: HashMap<String, V>) -> Self {
+        map.into_iter().collect()
+    }
+}
+
+impl<V> FromIterator<(String, V)> for PrefixMap<V> {
+    fn from_iter<I: IntoIterator<Item = (String, V)>>(iter: I) -> Self {
+        let mut map = PrefixMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<V> Extend<(String, V)> for PrefixMap<V> {
+    fn extend<I: IntoIterator<Item = (String, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(&key, value);
+        }
+    }
+}
+
+/// Iterator over a `PrefixMap`'s entries, rebuilding each key as it goes
+pub struct Iter<'a, V> {
+    // Nodes still to visit, with the key spelled out on the way to each
+    stack: Vec<(String, &'a Node<V>)>,
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = (String, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some((key, node)) = self.stack.pop() {
+            // Children go on in reverse so the smallest is visited next
+            for (c, child) in node.children.iter().rev() {
+                let mut child_key = key.clone();
+                child_key.push(*c);
+                self.stack.push((child_key, child));
+            }
+            if let Some(value) = &node.value {
+                return Some((key, value));
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, XorShift64};
+
+    // Every node's count is its own value plus its children's counts, and
+    // no node without values beneath it is left behind
+    fn check_counts<V>(node: &Node<V>) -> usize {
+        let below: usize = node.children.values().map(check_counts).sum();
+        for child in node.children.values() {
+            assert!(child.count > 0, "unpruned node");
+        }
+        assert_eq!(node.count, below + node.value.is_some() as usize);
+        node.count
+    }
+
+    fn keys<V>(iter: Iter<'_, V>) -> Vec<String> {
+        iter.map(|(key, _)| key).collect()
+    }
+
+    #[test]
+    fn counts_follow_inserts_replacements_and_removals() {
+        let mut map = PrefixMap::new();
+        assert_eq!(map.insert("car", 1), None);
+        assert_eq!(map.insert("cart", 2), None);
+        assert_eq!(map.insert("cat", 3), None);
+        assert_eq!(map.insert("dog", 4), None);
+        check_counts(&map.root);
+        assert_eq!(map.count_prefix(""), 4);
+        assert_eq!(map.count_prefix("ca"), 3);
+        assert_eq!(map.count_prefix("car"), 2);
+        assert_eq!(map.count_prefix("cart"), 1);
+        assert_eq!(map.count_prefix("carts"), 0);
+        assert_eq!(map.count_prefix("x"), 0);
+
+        // Replacing a value leaves every count alone
+        assert_eq!(map.insert("car", 10), Some(1));
+        check_counts(&map.root);
+        assert_eq!(map.len(), 4);
+        assert_eq!(map.count_prefix("car"), 2);
+
+        assert_eq!(map.remove("cat"), Some(3));
+        assert_eq!(map.remove("cat"), None);
+        // "ca" is only a path to other keys, not a key itself
+        assert_eq!(map.remove("ca"), None);
+        check_counts(&map.root);
+        assert_eq!(map.count_prefix("ca"), 2);
+        assert!(map.find("cat").is_none());
+        assert_eq!(map.len(), 3);
+    }
+
+    #[test]
+    fn removing_a_prefix_key_keeps_the_longer_one() {
+        let mut map = PrefixMap::new();
+        map.insert("car", 1);
+        map.insert("cart", 2);
+        assert_eq!(map.remove("car"), Some(1));
+        check_counts(&map.root);
+        assert_eq!(map.get("car"), None);
+        assert_eq!(map.get("cart"), Some(&2));
+        assert_eq!(map.count_prefix("car"), 1);
+
+        // With the last key gone the whole branch is pruned
+        assert_eq!(map.remove("cart"), Some(2));
+        assert!(map.root.children.is_empty());
+        assert!(map.is_empty());
+
+        map.insert("", 0);
+        map.insert("a", 1);
+        assert_eq!(map.remove(""), Some(0));
+        check_counts(&map.root);
+        assert_eq!(map.get("a"), Some(&1));
+    }
+
+    #[test]
+    fn iterates_in_key_order() {
+        let map: PrefixMap<usize> = ["b", "ab", "a", "abc", "ac", "", "é", "aa"]
+            .iter()
+            .enumerate()
+            .map(|(i, key)| (key.to_string(), i))
+            .collect();
+        assert_eq!(
+            keys(map.iter()),
+            ["", "a", "aa", "ab", "abc", "ac", "b", "é"]
+        );
+        assert_eq!(keys(map.iter_prefix("a")), ["a", "aa", "ab", "abc", "ac"]);
+        assert_eq!(keys(map.iter_prefix("ab")), ["ab", "abc"]);
+        assert_eq!(keys(map.iter_prefix("abcd")), Vec::<String>::new());
+        assert_eq!(map.iter_prefix("ab").next(), Some(("ab".to_string(), &1)));
+    }
+
+    #[test]
+    fn matches_a_sorted_model() {
+        let mut rng = XorShift64::new(3);
+        let mut map = PrefixMap::new();
+        let mut model = BTreeMap::new();
+        // Short keys over a small alphabet, so most keys prefix others
+        let mut key = || -> String {
+            (0..rng.below(5))
+                .map(|_| (b'a' + rng.below(3) as u8) as char)
+                .collect()
+        };
+        for step in 0..5000 {
+            let key = key();
+            if step % 3 == 0 {
+                assert_eq!(map.remove(&key), model.remove(&key));
+            } else {
+                assert_eq!(map.insert(&key, step), model.insert(key.clone(), step));
+            }
+            assert_eq!(check_counts(&map.root), model.len());
+            let expected = model
+                .keys()
+                .filter(|stored: &&String| stored.starts_with(key.as_str()))
+                .count();
+            assert_eq!(map.count_prefix(&key), expected, "prefix {:?}", key);
+        }
+        let entries: Vec<(String, usize)> = map.iter().map(|(k, &v)| (k, v)).collect();
+        let expected: Vec<(String, usize)> = model.into_iter().collect();
+        assert_eq!(entries, expected);
+    }
+}
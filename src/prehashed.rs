@@ -0,0 +1,384 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A `HashMap` that lets one hash of a key serve several operations
+//!
+//! `HashMap` hashes the key again on every call, so looking a key up and
+//! then removing or inserting it hashes it twice. The raw-entry API that
+//! avoids this in std was never stabilised. A `PrehashedMap` stores every key
+//! next to its hash and builds its table with a hasher that passes that hash
+//! straight through, so a hash computed once with `hash_key` can be handed to
+//! any number of the `_with_hash` methods.
+//!
+//! Passing a hash that didn't come from `hash_key` for the same key on the
+//! same map won't cause undefined behaviour, but the key won't be found.
+//! Keys with expensive hashes, such as long strings, gain the most; each
+//! entry costs eight more bytes for its hash.
+
+use crate::deep_size::DeepSize;
+use crate::op_stats::{Op, OpCounters, OpStats};
+use std::borrow::Borrow;
+use std::collections::hash_map::{self, RandomState};
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{BuildHasher, BuildHasherDefault, Hash, Hasher};
+use std::iter::FromIterator;
+
+/// A key stored together with its hash
+#[derive(Debug, Clone, Copy)]
+pub struct Hashed<K> {
+    hash: u64,
+    key: K,
+}
+
+impl<K> Hashed<K> {
+    pub fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+}
+
+impl<K> Hash for Hashed<K> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(self.hash);
+    }
+}
+
+impl<K: PartialEq> PartialEq for Hashed<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.hash == other.hash && self.key == other.key
+    }
+}
+
+impl<K: Eq> Eq for Hashed<K> {}
+
+impl<K: DeepSize> DeepSize for Hashed<K> {
+    fn heap_bytes(&self) -> usize {
+        self.key.heap_bytes()
+    }
+}
+
+/// A hash and a key to look up, either borrowed or stored in a `Hashed`
+///
+/// Stored keys borrow as `dyn Probe`, which is what lets a `PrehashedMap` be
+/// searched with a `&Q` the keys borrow as, without building an owned key.
+pub trait Probe<Q: ?Sized> {
+    fn hash(&self) -> u64;
+    fn key(&self) -> &Q;
+}
+
+impl<K: Borrow<Q>, Q: ?Sized> Probe<Q> for Hashed<K> {
+    fn hash(&self) -> u64 {
+        self.hash
+    }
+
+    fn key(&self) -> &Q {
+        self.key.borrow()
+    }
+}
+
+impl<Q: ?Sized> Probe<Q> for (u64, &Q) {
+    fn hash(&self) -> u64 {
+        self.0
+    }
+
+    fn key(&self) -> &Q {
+        self.1
+    }
+}
+
+impl<'a, K: Borrow<Q> + 'a, Q: ?Sized + 'a> Borrow<dyn Probe<Q> + 'a> for Hashed<K> {
+    fn borrow(&self) -> &(dyn Probe<Q> + 'a) {
+        self
+    }
+}
+
+impl<Q: ?Sized> Hash for dyn Probe<Q> + '_ {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        state.write_u64(Probe::hash(self));
+    }
+}
+
+impl<Q: ?Sized + PartialEq> PartialEq for dyn Probe<Q> + '_ {
+    fn eq(&self, other: &Self) -> bool {
+        Probe::hash(self) == Probe::hash(other) && self.key() == other.key()
+    }
+}
+
+impl<Q: ?Sized + Eq> Eq for dyn Probe<Q> + '_ {}
+
+/// Hands the hash written by a `Hashed` key to the table unchanged
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PassThroughHasher {
+    hash: u64,
+}
+
+impl Hasher for PassThroughHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        // Only `write_u64` is used by `Hashed`, but stay a valid hasher
+        for byte in bytes {
+            self.hash = self.hash.rotate_left(8) ^ u64::from(*byte);
+        }
+    }
+
+    fn write_u64(&mut self, hash: u64) {
+        self.hash = hash;
+    }
+
+    fn finish(&self) -> u64 {
+        self.hash
+    }
+}
+
+type Table<K, V> = HashMap<Hashed<K>, V, BuildHasherDefault<PassThroughHasher>>;
+
+/// A hash map whose operations can reuse a hash computed by `hash_key`
+#[derive(Clone)]
+pub struct PrehashedMap<K, V, S = RandomState> {
+    map: Table<K, V>,
+    hasher: S,
+    counters: OpCounters,
+}
+
+impl<K: Eq + Hash, V> PrehashedMap<K, V, RandomState> {
+    pub fn new() -> Self {
+        PrehashedMap::with_hasher(RandomState::new())
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> PrehashedMap<K, V, S> {
+    /// An empty map hashing its keys with `hasher`
+    pub fn with_hasher(hasher: S) -> Self {
+        PrehashedMap {
+            map: HashMap::default(),
+            hasher,
+            counters: OpCounters::new(),
+        }
+    }
+
+    /// The hash this map uses for `key`, to pass to the `_with_hash` methods
+    pub fn hash_key<Q: Hash + ?Sized>(&self, key: &Q) -> u64 {
+        self.hasher.hash_one(key)
+    }
+
+    pub fn get_with_hash<Q>(&self, hash: u64, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        self.counters.record(Op::Lookup);
+        self.map.get(&(hash, key) as &dyn Probe<Q>)
+    }
+
+    pub fn get_mut_with_hash<Q>(&mut self, hash: u64, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        self.counters.record(Op::Lookup);
+        self.map.get_mut(&(hash, key) as &dyn Probe<Q>)
+    }
+
+    /// Returns the value of `key`, inserting `default()` first if it's
+    /// missing, all with the one hash
+    ///
+    /// Counts as a lookup if `key` is there and an insert if it isn't.
+    pub fn get_or_insert_with_hash<F>(&mut self, hash: u64, key: K, default: F) -> &mut V
+    where
+        F: FnOnce() -> V,
+    {
+        self.observe_growth();
+        match self.map.entry(Hashed { hash, key }) {
+            hash_map::Entry::Occupied(entry) => {
+                self.counters.record(Op::Lookup);
+                entry.into_mut()
+            }
+            hash_map::Entry::Vacant(entry) => {
+                self.counters.record(Op::Insert);
+                entry.insert(default())
+            }
+        }
+    }
+
+    pub fn insert_with_hash(&mut self, hash: u64, key: K, value: V) -> Option<V> {
+        self.observe_growth();
+        self.counters.record(Op::Insert);
+        self.map.insert(Hashed { hash, key }, value)
+    }
+
+    pub fn remove_with_hash<Q>(&mut self, hash: u64, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + ?Sized,
+    {
+        self.counters.record(Op::Removal);
+        self.map.remove(&(hash, key) as &dyn Probe<Q>)
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get_with_hash(self.hash_key(key), key)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get_mut_with_hash(self.hash_key(key), key)
+    }
+
+    pub fn get_or_insert_with<F>(&mut self, key: K, default: F) -> &mut V
+    where
+        F: FnOnce() -> V,
+    {
+        self.get_or_insert_with_hash(self.hash_key(&key), key, default)
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_with_hash(self.hash_key(&key), key, value)
+    }
+
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.remove_with_hash(self.hash_key(key), key)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+}
+
+impl<K, V, S> PrehashedMap<K, V, S> {
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.map.capacity()
+    }
+
+    /// What the map has been asked to do, counted with the `instrument`
+    /// feature
+    pub fn op_stats(&self) -> OpStats {
+        self.observe_growth();
+        self.counters.stats()
+    }
+
+    pub fn reset_op_stats(&mut self) {
+        self.counters.reset();
+    }
+
+    /// Iterates over `(key, value)` pairs in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.map.iter().map(|(key, value)| (&key.key, value))
+    }
+
+    /// Iterates over the values in arbitrary order
+    pub fn values(&self) -> impl Iterator<Item = &V> + '_ {
+        self.map.values()
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+    }
+
+    // Counts the table growing, which only an insert can make it do. The
+    // entry an insert returns borrows the table, so the growth is seen at
+    // the start of the next insert, or when the stats are read.
+    fn observe_growth(&self) {
+        self.counters.observe_capacity(self.map.capacity());
+    }
+}
+
+impl<K: Eq + Hash, V> Default for PrehashedMap<K, V, RandomState> {
+    fn default() -> Self {
+        PrehashedMap::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug, S> fmt::Debug for PrehashedMap<K, V, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+// Two maps can hash differently, so every key is looked up afresh
+impl<K: Eq + Hash, V: PartialEq, S: BuildHasher> PartialEq for PrehashedMap<K, V, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K: Eq + Hash, V: Eq, S: BuildHasher> Eq for PrehashedMap<K, V, S> {}
+
+impl<K: DeepSize, V: DeepSize, S> DeepSize for PrehashedMap<K, V, S> {
+    fn heap_bytes(&self) -> usize {
+        self.map.heap_bytes()
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher + Default> FromIterator<(K, V)> for PrehashedMap<K, V, S> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = PrehashedMap::with_hasher(S::default());
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Eq + Hash, V, S: BuildHasher> Extend<(K, V)> for PrehashedMap<K, V, S> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+// Iterating by value drops the stored hashes
+impl<K, V, S> IntoIterator for PrehashedMap<K, V, S> {
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> IntoIter<K, V> {
+        IntoIter {
+            inner: self.map.into_iter(),
+        }
+    }
+}
+
+/// Iterator returned by `PrehashedMap::into_iter`
+pub struct IntoIter<K, V> {
+    inner: hash_map::IntoIter<Hashed<K>, V>,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<(K, V)> {
+        self.inner.next().map(|(key, value)| (key.key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
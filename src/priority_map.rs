@@ -0,0 +1,273 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A min-priority queue whose entries can be found and re-prioritised by key
+//!
+//! `BinaryHeap` can't change an item's priority once it's queued, which is
+//! exactly what Dijkstra's algorithm and most schedulers need to do. A
+//! `PriorityMap` is a binary heap that also remembers where each key sits in
+//! the heap, so it can move a key after its priority changes instead of
+//! searching for it.
+//!
+//! | Operation                    | Cost       |
+//! |------------------------------|------------|
+//! | `push`, `pop_min`, `remove`  | O(log n)   |
+//! | `change_priority`            | O(log n)   |
+//! | `peek_min`, `contains_key`   | O(1)       |
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::mem;
+
+/// A binary min-heap of keys ordered by priority, indexed by key
+#[derive(Debug, Clone)]
+pub struct PriorityMap<K, P> {
+    heap: Vec<(K, P)>,
+    // Where each key currently sits in `heap`
+    positions: HashMap<K, usize>,
+}
+
+impl<K: Clone + Eq + Hash, P: Ord> PriorityMap<K, P> {
+    pub fn new() -> Self {
+        PriorityMap {
+            heap: Vec::new(),
+            positions: HashMap::new(),
+        }
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        PriorityMap {
+            heap: Vec::with_capacity(capacity),
+            positions: HashMap::with_capacity(capacity),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    /// Queues `key` with `priority`, or changes its priority if it is
+    /// already queued, returning the old priority
+    pub fn push(&mut self, key: K, priority: P) -> Option<P> {
+        if self.positions.contains_key(&key) {
+            return self.change_priority(&key, priority);
+        }
+        let index = self.heap.len();
+        self.positions.insert(key.clone(), index);
+        self.heap.push((key, priority));
+        self.sift_up(index);
+        None
+    }
+
+    /// Returns the key with the smallest priority without removing it
+    pub fn peek_min(&self) -> Option<(&K, &P)> {
+        self.heap.first().map(|(key, priority)| (key, priority))
+    }
+
+    /// Removes and returns the key with the smallest priority
+    pub fn pop_min(&mut self) -> Option<(K, P)> {
+        self.remove_at(0)
+    }
+
+    /// Sets the priority of a queued key, returning the old priority, or
+    /// `None` without queueing anything if the key is absent
+    pub fn change_priority(&mut self, key: &K, priority: P) -> Option<P> {
+        let index = *self.positions.get(key)?;
+        let old = mem::replace(&mut self.heap[index].1, priority);
+        if self.heap[index].1 < old {
+            self.sift_up(index);
+        } else {
+            self.sift_down(index);
+        }
+        Some(old)
+    }
+
+    pub fn priority(&self, key: &K) -> Option<&P> {
+        self.positions.get(key).map(|&index| &self.heap[index].1)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.positions.contains_key(key)
+    }
+
+    /// Removes `key` from the queue, returning its priority
+    pub fn remove(&mut self, key: &K) -> Option<P> {
+        let index = *self.positions.get(key)?;
+        self.remove_at(index).map(|(_, priority)| priority)
+    }
+
+    /// Iterates over the queued keys in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &P)> {
+        self.heap.iter().map(|(key, priority)| (key, priority))
+    }
+
+    pub fn clear(&mut self) {
+        self.heap.clear();
+        self.positions.clear();
+    }
+
+    fn remove_at(&mut self, index: usize) -> Option<(K, P)> {
+        if index >= self.heap.len() {
+            return None;
+        }
+        let last = self.heap.len() - 1;
+        self.swap(index, last);
+        let (key, priority) = self.heap.pop()?;
+        self.positions.remove(&key);
+
+        // The entry moved into the gap came from the bottom of the heap, but
+        // it can still belong higher up when the gap was in another subtree
+        if index < self.heap.len() {
+            self.sift_up(index);
+            self.sift_down(index);
+        }
+        Some((key, priority))
+    }
+
+    fn sift_up(&mut self, mut index: usize) {
+        while index > 0 {
+            let parent = (index - 1) / 2;
+            if self.heap[index].1 >= self.heap[parent].1 {
+                break;
+            }
+            self.swap(index, parent);
+            index = parent;
+        }
+    }
+
+    fn sift_down(&mut self, mut index: usize) {
+        loop {
+            let left = 2 * index + 1;
+            let right = left + 1;
+            let mut smallest = index;
+            if left < self.heap.len() && self.heap[left].1 < self.heap[smallest].1 {
+                smallest = left;
+            }
+            if right < self.heap.len() && self.heap[right].1 < self.heap[smallest].1 {
+                smallest = right;
+            }
+            if smallest == index {
+                break;
+            }
+            self.swap(index, smallest);
+            index = smallest;
+        }
+    }
+
+    // Swaps two heap slots and keeps the position index in step
+    fn swap(&mut self, a: usize, b: usize) {
+        self.heap.swap(a, b);
+        for &index in &[a, b] {
+            if let Some(position) = self.positions.get_mut(&self.heap[index].0) {
+                *position = index;
+            }
+        }
+    }
+}
+
+impl<K: Clone + Eq + Hash, P: Ord> Default for PriorityMap<K, P> {
+    fn default() -> Self {
+        PriorityMap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, XorShift64};
+    use std::collections::BTreeSet;
+
+    // Every key's recorded position points back at it, and each entry is no
+    // smaller than its parent
+    fn check<K: Clone + Eq + Hash + std::fmt::Debug, P: Ord>(map: &PriorityMap<K, P>) {
+        assert_eq!(map.positions.len(), map.heap.len());
+        for (index, (key, priority)) in map.heap.iter().enumerate() {
+            assert_eq!(map.positions.get(key), Some(&index), "{:?}", key);
+            if index > 0 {
+                assert!(map.heap[(index - 1) / 2].1 <= *priority);
+            }
+        }
+    }
+
+    #[test]
+    fn pops_in_priority_order() {
+        let mut map = PriorityMap::new();
+        for (key, priority) in [("c", 3), ("a", 1), ("d", 4), ("b", 2)].iter() {
+            assert_eq!(map.push(*key, *priority), None);
+        }
+        assert_eq!(map.peek_min(), Some((&"a", &1)));
+        assert_eq!(map.push("d", 0), Some(4));
+        assert_eq!(map.change_priority(&"a", 5), Some(1));
+        assert_eq!(map.change_priority(&"z", 5), None);
+        assert!(!map.contains_key(&"z"));
+        assert_eq!(map.remove(&"c"), Some(3));
+        check(&map);
+        let order: Vec<_> = std::iter::from_fn(|| map.pop_min()).collect();
+        assert_eq!(order, [("d", 0), ("b", 2), ("a", 5)]);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn matches_a_sorted_model() {
+        for seed in 1..=20 {
+            let mut rng = XorShift64::new(seed);
+            let mut map = PriorityMap::new();
+            // (priority, key), with a key's priority looked up in `model`
+            let mut sorted = BTreeSet::new();
+            let mut model = HashMap::new();
+            for _ in 0..2000 {
+                let key = rng.below(64);
+                // A small range so that equal priorities are common
+                let priority = rng.below(32);
+                match rng.below(5) {
+                    0 | 1 => {
+                        let old = model.insert(key, priority);
+                        if let Some(old) = old {
+                            sorted.remove(&(old, key));
+                        }
+                        sorted.insert((priority, key));
+                        assert_eq!(map.push(key, priority), old);
+                    }
+                    2 => {
+                        let old = model.get(&key).copied();
+                        if let Some(old) = old {
+                            sorted.remove(&(old, key));
+                            sorted.insert((priority, key));
+                            model.insert(key, priority);
+                        }
+                        assert_eq!(map.change_priority(&key, priority), old);
+                    }
+                    3 => {
+                        let old = model.remove(&key);
+                        if let Some(old) = old {
+                            sorted.remove(&(old, key));
+                        }
+                        assert_eq!(map.remove(&key), old);
+                    }
+                    _ => match map.pop_min() {
+                        // Ties may come out in any order
+                        Some((key, priority)) => {
+                            assert_eq!(sorted.iter().next().map(|&(p, _)| p), Some(priority));
+                            assert!(sorted.remove(&(priority, key)));
+                            model.remove(&key);
+                        }
+                        None => assert!(sorted.is_empty()),
+                    },
+                }
+                check(&map);
+                assert_eq!(map.len(), model.len());
+                assert_eq!(
+                    map.peek_min().map(|(_, &p)| p),
+                    sorted.iter().next().map(|&(p, _)| p)
+                );
+                for (key, priority) in &model {
+                    assert_eq!(map.priority(key), Some(priority), "seed {}", seed);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,407 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! An open-addressing hash map with linear probing, built to be inspected
+//!
+//! Every entry lives directly in one flat array of slots. A key starts at the
+//! slot its hash picks and, if that is taken, walks forward one slot at a time
+//! until it finds room. Removing an entry can't simply empty its slot, since
+//! that would cut the walk short for keys stored further along, so it leaves a
+//! tombstone that lookups step over and inserts may reuse.
+//!
+//! std's `HashMap` hides all of this. `ProbeMap` exposes the load factor, the
+//! longest walk and the tombstone count, so the effect of a high load or many
+//! deletions can be measured directly.
+
+use crate::op_stats::{Op, OpCounters, OpStats};
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+
+// Live entries plus tombstones may fill at most this fraction of the slots
+const MAX_LOAD: f64 = 0.75;
+
+const MIN_SLOTS: usize = 8;
+
+#[derive(Debug, Clone)]
+enum Slot<K, V> {
+    Empty,
+    Tombstone,
+    Full(K, V),
+}
+
+/// A linear-probing hash map that reports on its own layout
+#[derive(Debug, Clone)]
+pub struct ProbeMap<K, V> {
+    slots: Vec<Slot<K, V>>,
+    hasher: RandomState,
+    len: usize,
+    tombstones: usize,
+    counters: OpCounters,
+}
+
+impl<K: Eq + Hash, V> ProbeMap<K, V> {
+    pub fn new() -> Self {
+        ProbeMap::with_capacity(0)
+    }
+
+    /// Creates a map that can hold `capacity` entries before growing
+    pub fn with_capacity(capacity: usize) -> Self {
+        let slots = ((capacity as f64 / MAX_LOAD).ceil() as usize)
+            .max(MIN_SLOTS)
+            .next_power_of_two();
+        ProbeMap {
+            slots: empty_slots(slots),
+            hasher: RandomState::new(),
+            len: 0,
+            tombstones: 0,
+            counters: OpCounters::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of slots in the backing array
+    pub fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Fraction of slots holding a live entry
+    pub fn load_factor(&self) -> f64 {
+        self.len as f64 / self.slots.len() as f64
+    }
+
+    /// Number of slots left behind by removed entries
+    pub fn tombstone_count(&self) -> usize {
+        self.tombstones
+    }
+
+    /// The furthest any entry sits from the slot its hash picked, counting
+    /// its home slot as a probe length of one, or zero for an empty map
+    pub fn max_probe_length(&self) -> usize {
+        self.slots
+            .iter()
+            .enumerate()
+            .filter_map(|(index, slot)| match slot {
+                Slot::Full(key, _) => Some(self.distance(self.home(key), index) + 1),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// What the map has been asked to do, counted with the `instrument`
+    /// feature
+    pub fn op_stats(&self) -> OpStats {
+        self.counters.stats()
+    }
+
+    pub fn reset_op_stats(&mut self) {
+        self.counters.reset();
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.counters.record(Op::Insert);
+        self.insert_uncounted(key, value)
+    }
+
+    fn insert_uncounted(&mut self, key: K, value: V) -> Option<V> {
+        if (self.len + self.tombstones + 1) as f64 > self.slots.len() as f64 * MAX_LOAD {
+            self.grow();
+        }
+
+        let mask = self.slots.len() - 1;
+        let mut index = self.home(&key);
+        let mut reusable = None;
+        loop {
+            match &mut self.slots[index] {
+                Slot::Full(k, v) if *k == key => return Some(mem::replace(v, value)),
+                Slot::Full(..) => {}
+                Slot::Tombstone => {
+                    reusable.get_or_insert(index);
+                }
+                Slot::Empty => break,
+            }
+            index = (index + 1) & mask;
+        }
+
+        // The key isn't stored, so the first tombstone passed on the way is
+        // the closest free slot to its home
+        if let Some(tombstone) = reusable {
+            index = tombstone;
+            self.tombstones -= 1;
+        }
+        self.slots[index] = Slot::Full(key, value);
+        self.len += 1;
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counters.record(Op::Lookup);
+        match &self.slots[self.find(key)?] {
+            Slot::Full(_, value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counters.record(Op::Lookup);
+        let index = self.find(key)?;
+        match &mut self.slots[index] {
+            Slot::Full(_, value) => Some(value),
+            _ => None,
+        }
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counters.record(Op::Lookup);
+        self.find(key).is_some()
+    }
+
+    /// Removes `key`, leaving a tombstone in its slot
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counters.record(Op::Removal);
+        let index = self.find(key)?;
+        match mem::replace(&mut self.slots[index], Slot::Tombstone) {
+            Slot::Full(_, value) => {
+                self.len -= 1;
+                self.tombstones += 1;
+                Some(value)
+            }
+            _ => unreachable!("`find` only returns full slots"),
+        }
+    }
+
+    /// Rebuilds the slot array at its current size, clearing every tombstone
+    /// and moving entries as close to their home slots as they can get
+    pub fn rehash(&mut self) {
+        let slots = self.slots.len();
+        self.rebuild(slots);
+    }
+
+    /// Iterates over every entry in slot order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.slots.iter().filter_map(|slot| match slot {
+            Slot::Full(key, value) => Some((key, value)),
+            _ => None,
+        })
+    }
+
+    pub fn clear(&mut self) {
+        for slot in self.slots.iter_mut() {
+            *slot = Slot::Empty;
+        }
+        self.len = 0;
+        self.tombstones = 0;
+    }
+
+    fn home<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        (self.hasher.hash_one(key) as usize) & (self.slots.len() - 1)
+    }
+
+    // How many steps forward, wrapping around, `index` is from `home`
+    fn distance(&self, home: usize, index: usize) -> usize {
+        index.wrapping_sub(home) & (self.slots.len() - 1)
+    }
+
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let mask = self.slots.len() - 1;
+        let mut index = self.home(key);
+        loop {
+            match &self.slots[index] {
+                Slot::Full(k, _) if k.borrow() == key => return Some(index),
+                Slot::Empty => return None,
+                _ => index = (index + 1) & mask,
+            }
+        }
+    }
+
+    // A map mostly full of tombstones only needs cleaning; one full of live
+    // entries needs more room
+    fn grow(&mut self) {
+        let mut slots = self.slots.len();
+        if (self.len + 1) as f64 > slots as f64 * MAX_LOAD / 2.0 {
+            slots *= 2;
+        }
+        self.rebuild(slots);
+    }
+
+    fn rebuild(&mut self, slots: usize) {
+        self.counters.record(Op::Rehash);
+        if slots != self.slots.len() {
+            self.counters.record(Op::Reallocation);
+        }
+        let old = mem::replace(&mut self.slots, empty_slots(slots));
+        self.len = 0;
+        self.tombstones = 0;
+        for slot in old {
+            if let Slot::Full(key, value) = slot {
+                self.insert_uncounted(key, value);
+            }
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> Default for ProbeMap<K, V> {
+    fn default() -> Self {
+        ProbeMap::new()
+    }
+}
+
+fn empty_slots<K, V>(len: usize) -> Vec<Slot<K, V>> {
+    (0..len).map(|_| Slot::Empty).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, XorShift64};
+    use std::collections::HashMap;
+
+    // The counts must agree with the slots, and no entry may have an empty
+    // slot between it and its home, or lookups would stop short of it
+    fn assert_consistent(map: &ProbeMap<u64, u64>) {
+        let full = map.iter().count();
+        let tombstones = map
+            .slots
+            .iter()
+            .filter(|slot| matches!(slot, Slot::Tombstone))
+            .count();
+        assert_eq!(full, map.len());
+        assert_eq!(tombstones, map.tombstone_count());
+        let mask = map.slots.len() - 1;
+        for (index, slot) in map.slots.iter().enumerate() {
+            if let Slot::Full(key, _) = slot {
+                let mut walk = map.home(key);
+                while walk != index {
+                    assert!(!matches!(map.slots[walk], Slot::Empty), "key {}", key);
+                    walk = (walk + 1) & mask;
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn matches_a_model() {
+        let mut rng = XorShift64::new(11);
+        let mut map = ProbeMap::new();
+        let mut model = HashMap::new();
+        for step in 0..5000 {
+            let key = rng.below(500);
+            if rng.below(2) == 0 {
+                assert_eq!(map.remove(&key), model.remove(&key));
+            } else {
+                assert_eq!(map.insert(key, step), model.insert(key, step));
+            }
+            let used = map.len() + map.tombstone_count();
+            assert!(used as f64 <= map.slot_count() as f64 * MAX_LOAD);
+            if step % 250 == 0 {
+                assert_consistent(&map);
+            }
+        }
+        assert_consistent(&map);
+        for key in 0..500 {
+            assert_eq!(map.get(&key), model.get(&key));
+        }
+    }
+
+    #[test]
+    fn removing_leaves_a_tombstone_that_insert_reuses() {
+        let mut map = ProbeMap::with_capacity(4);
+        for key in 0..4 {
+            map.insert(key, key);
+        }
+        assert_eq!(map.remove(&2), Some(2));
+        assert_eq!(map.remove(&2), None);
+        assert_eq!(map.tombstone_count(), 1);
+        assert_eq!(map.len(), 3);
+        assert!((0..4)
+            .filter(|&key| key != 2)
+            .all(|key| map.get(&key) == Some(&key)));
+
+        // Key 2 walks past its old slot again, so it lands on the tombstone
+        let slots = map.slot_count();
+        map.insert(2, 20);
+        assert_eq!(map.tombstone_count(), 0);
+        assert_eq!(map.slot_count(), slots);
+        assert_eq!(map.get(&2), Some(&20));
+        assert_consistent(&map);
+    }
+
+    #[test]
+    fn rehash_clears_tombstones() {
+        let mut map = ProbeMap::with_capacity(100);
+        for key in 0..100 {
+            map.insert(key, key);
+        }
+        for key in (0..100).step_by(2) {
+            map.remove(&key);
+        }
+        assert_eq!(map.tombstone_count(), 50);
+        let slots = map.slot_count();
+        let longest = map.max_probe_length();
+        map.rehash();
+        assert_eq!(map.tombstone_count(), 0);
+        assert_eq!(map.slot_count(), slots);
+        assert_eq!(map.len(), 50);
+        assert!(map.max_probe_length() <= longest);
+        assert_consistent(&map);
+        assert!((1..100).step_by(2).all(|key| map.get(&key) == Some(&key)));
+    }
+
+    // Churn that never raises the live count fills the table with
+    // tombstones, which a rebuild at the same size clears
+    #[test]
+    fn tombstones_alone_do_not_grow_the_table() {
+        let mut map = ProbeMap::with_capacity(10);
+        let slots = map.slot_count();
+        for key in 0..1000 {
+            map.insert(key, key);
+            map.remove(&key);
+        }
+        assert!(map.is_empty());
+        assert_eq!(map.slot_count(), slots);
+        assert_consistent(&map);
+    }
+
+    #[test]
+    fn layout_statistics() {
+        let mut map: ProbeMap<u64, u64> = ProbeMap::new();
+        assert_eq!(map.max_probe_length(), 0);
+        assert_eq!(map.load_factor(), 0.0);
+        map.insert(1, 1);
+        assert_eq!(map.max_probe_length(), 1);
+        assert_eq!(map.load_factor(), 1.0 / map.slot_count() as f64);
+        map.clear();
+        assert!(map.is_empty());
+        assert_eq!(map.tombstone_count(), 0);
+        assert_eq!(map.get(&1), None);
+    }
+}
@@ -0,0 +1,106 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Lazy cartesian products
+//!
+//! `cartesian_product(a, b)` yields every `(x, y)` pair with `x` from `a` and
+//! `y` from `b`, in order with the last position varying fastest. Nothing is
+//! collected up front: the second iterator is cloned and replayed for each
+//! item of the first, so it needs to be cheap to clone, as ranges and slice
+//! iterators are. The `product!` macro extends this to three or more
+//! iterators with flat tuples, which is handy for parameter grids.
+
+/// Yields every pair of an item from `a` and an item from `b`
+pub fn cartesian_product<A, B>(a: A, b: B) -> Product<A::IntoIter, B::IntoIter>
+where
+    A: IntoIterator,
+    A::Item: Clone,
+    B: IntoIterator,
+    B::IntoIter: Clone,
+{
+    let mut a = a.into_iter();
+    let b = b.into_iter();
+    Product {
+        current: a.next(),
+        a,
+        b: b.clone(),
+        b_start: b,
+    }
+}
+
+/// Yields every combination of one item from each iterator as a flat tuple,
+/// for two to six iterators
+///
+/// Every iterator but the first must be `Clone` and every item but the last
+/// must be `Clone`, as for `cartesian_product`.
+#[macro_export]
+macro_rules! product {
+    ($a:expr, $b:expr $(,)?) => {
+        $crate::product::cartesian_product($a, $b)
+    };
+    ($a:expr, $b:expr, $c:expr $(,)?) => {
+        $crate::product::cartesian_product($crate::product!($a, $b), $c)
+            .map(|((a, b), c)| (a, b, c))
+    };
+    ($a:expr, $b:expr, $c:expr, $d:expr $(,)?) => {
+        $crate::product::cartesian_product($crate::product!($a, $b, $c), $d)
+            .map(|((a, b, c), d)| (a, b, c, d))
+    };
+    ($a:expr, $b:expr, $c:expr, $d:expr, $e:expr $(,)?) => {
+        $crate::product::cartesian_product($crate::product!($a, $b, $c, $d), $e)
+            .map(|((a, b, c, d), e)| (a, b, c, d, e))
+    };
+    ($a:expr, $b:expr, $c:expr, $d:expr, $e:expr, $f:expr $(,)?) => {
+        $crate::product::cartesian_product($crate::product!($a, $b, $c, $d, $e), $f)
+            .map(|((a, b, c, d, e), f)| (a, b, c, d, e, f))
+    };
+}
+
+/// Iterator returned by `cartesian_product`
+#[derive(Debug, Clone)]
+pub struct Product<A: Iterator, B> {
+    a: A,
+    // The item of `a` currently being paired, `None` once `a` is exhausted
+    current: Option<A::Item>,
+    b: B,
+    // A fresh copy of `b` to replay for the next item of `a`
+    b_start: B,
+}
+
+impl<A, B> Iterator for Product<A, B>
+where
+    A: Iterator,
+    A::Item: Clone,
+    B: Iterator + Clone,
+{
+    type Item = (A::Item, B::Item);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let x = self.current.as_ref()?;
+            if let Some(y) = self.b.next() {
+                return Some((x.clone(), y));
+            }
+            self.current = self.a.next();
+            self.b = self.b_start.clone();
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.current.is_none() {
+            return (0, Some(0));
+        }
+        let (a_lower, a_upper) = self.a.size_hint();
+        let (b_lower, b_upper) = self.b.size_hint();
+        let (start_lower, start_upper) = self.b_start.size_hint();
+
+        let lower = a_lower.saturating_mul(start_lower).saturating_add(b_lower);
+        let upper = match (a_upper, b_upper, start_upper) {
+            (Some(a), Some(b), Some(start)) => {
+                a.checked_mul(start).and_then(|rest| rest.checked_add(b))
+            }
+            _ => None,
+        };
+        (lower, upper)
+    }
+}
@@ -0,0 +1,83 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A small source of pseudo-random numbers
+//!
+//! The crate has no dependencies, so anything that needs randomness takes
+//! an `Rng`, a minimal trait that a caller can implement over whatever
+//! generator they already use. `XorShift64` is a fast, seedable
+//! implementation for the common case. It's fine for sampling and shuffling
+//! but is not cryptographically secure.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A generator of uniformly distributed 64-bit numbers
+pub trait Rng {
+    fn next_u64(&mut self) -> u64;
+
+    /// Returns a number in `0..bound`, every value equally likely
+    ///
+    /// Panics if `bound` is zero.
+    fn below(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "bound must be positive");
+        // Reject the top sliver of the range that doesn't divide evenly by
+        // `bound`, which would otherwise favour the smaller results
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next_u64();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+
+    /// Returns a float in `0.0..1.0`
+    fn next_f64(&mut self) -> f64 {
+        // The top 53 bits fill an `f64` mantissa exactly
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+}
+
+impl<R: Rng + ?Sized> Rng for &mut R {
+    fn next_u64(&mut self) -> u64 {
+        (**self).next_u64()
+    }
+}
+
+/// Marsaglia's xorshift64* generator
+#[derive(Debug, Clone)]
+pub struct XorShift64 {
+    state: u64,
+}
+
+impl XorShift64 {
+    /// Creates a generator that always produces the same sequence for the
+    /// same `seed`
+    pub fn new(seed: u64) -> Self {
+        // An all-zero state would only ever produce zeros
+        XorShift64 {
+            state: if seed == 0 {
+                0x9E37_79B9_7F4A_7C15
+            } else {
+                seed
+            },
+        }
+    }
+
+    /// Creates a generator seeded from the system clock
+    pub fn from_time() -> Self {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+        XorShift64::new(nanos)
+    }
+}
+
+impl Rng for XorShift64 {
+    fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+}
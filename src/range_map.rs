@@ -0,0 +1,267 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A map from half-open ranges to values that keeps its ranges coalesced
+//!
+//! The stored ranges never overlap. Inserting a range overwrites whatever it
+//! covers, splitting any range that sticks out on either side, and then merges
+//! with neighbours that touch it and hold an equal value. The result is always
+//! the smallest set of ranges describing the mapping.
+
+use std::collections::BTreeMap;
+use std::ops::Range;
+
+/// A coalescing map from half-open ranges to values
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RangeMap<K, V> {
+    // Start -> (end, value), with no two ranges overlapping
+    ranges: BTreeMap<K, (K, V)>,
+}
+
+impl<K: Ord + Clone, V: Eq + Clone> RangeMap<K, V> {
+    pub fn new() -> Self {
+        RangeMap {
+            ranges: BTreeMap::new(),
+        }
+    }
+
+    /// Number of stored ranges after coalescing
+    pub fn len(&self) -> usize {
+        self.ranges.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ranges.is_empty()
+    }
+
+    /// Maps every point in `range` to `value`; empty ranges are ignored
+    pub fn insert(&mut self, range: Range<K>, value: V) {
+        if range.start >= range.end {
+            return;
+        }
+        self.carve(&range);
+
+        let Range { mut start, mut end } = range;
+
+        let touching_left = self
+            .ranges
+            .range(..start.clone())
+            .next_back()
+            .filter(|(_, (left_end, left_value))| *left_end == start && *left_value == value)
+            .map(|(left_start, _)| left_start.clone());
+        if let Some(left_start) = touching_left {
+            self.ranges.remove(&left_start);
+            start = left_start;
+        }
+
+        let touching_right = self
+            .ranges
+            .get(&end)
+            .filter(|(_, right_value)| *right_value == value)
+            .map(|(right_end, _)| right_end.clone());
+        if let Some(right_end) = touching_right {
+            self.ranges.remove(&end);
+            end = right_end;
+        }
+
+        self.ranges.insert(start, (end, value));
+    }
+
+    /// Unmaps every point in `range`, splitting ranges that stick out
+    pub fn remove(&mut self, range: Range<K>) {
+        if range.start < range.end {
+            self.carve(&range);
+        }
+    }
+
+    pub fn get(&self, point: &K) -> Option<&V> {
+        self.get_range(point).map(|(_, value)| value)
+    }
+
+    /// Returns the stored range containing `point` along with its value
+    pub fn get_range(&self, point: &K) -> Option<(Range<&K>, &V)> {
+        let (start, (end, value)) = self.ranges.range(..=point).next_back()?;
+        if point < end {
+            Some((start..end, value))
+        } else {
+            None
+        }
+    }
+
+    pub fn contains(&self, point: &K) -> bool {
+        self.get_range(point).is_some()
+    }
+
+    /// Iterates over the stored ranges in order
+    pub fn iter(&self) -> impl Iterator<Item = (Range<&K>, &V)> {
+        self.ranges
+            .iter()
+            .map(|(start, (end, value))| (start..end, value))
+    }
+
+    pub fn clear(&mut self) {
+        self.ranges.clear();
+    }
+
+    // Clears `range` out of the map, trimming ranges that straddle its edges
+    fn carve(&mut self, range: &Range<K>) {
+        // A range starting before `range` may reach into it, and possibly
+        // out the other side
+        let mut tail = None;
+        if let Some((_, (left_end, left_value))) =
+            self.ranges.range_mut(..range.start.clone()).next_back()
+        {
+            if *left_end > range.start {
+                if *left_end > range.end {
+                    tail = Some((range.end.clone(), (left_end.clone(), left_value.clone())));
+                }
+                *left_end = range.start.clone();
+            }
+        }
+
+        // Ranges starting inside `range` are dropped, keeping any part that
+        // runs past its end
+        let inside: Vec<K> = self
+            .ranges
+            .range(range.clone())
+            .map(|(start, _)| start.clone())
+            .collect();
+        for start in inside {
+            let (end, value) = self.ranges.remove(&start).expect("key was just listed");
+            if end > range.end {
+                tail = Some((range.end.clone(), (end, value)));
+            }
+        }
+
+        if let Some((start, rest)) = tail {
+            self.ranges.insert(start, rest);
+        }
+    }
+}
+
+impl<K: Ord + Clone, V: Eq + Clone> Default for RangeMap<K, V> {
+    fn default() -> Self {
+        RangeMap::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, XorShift64};
+
+    const POINTS: u32 = 60;
+
+    // The stored ranges must be non-empty, in order, non-overlapping and
+    // never touch a neighbour holding the same value
+    fn assert_coalesced(map: &RangeMap<u32, u8>) {
+        let ranges: Vec<(Range<u32>, u8)> = map
+            .iter()
+            .map(|(range, &value)| (*range.start..*range.end, value))
+            .collect();
+        for (range, _) in &ranges {
+            assert!(range.start < range.end, "empty range {:?}", range);
+        }
+        for pair in ranges.windows(2) {
+            let ((left, left_value), (right, right_value)) = (&pair[0], &pair[1]);
+            assert!(left.end <= right.start, "{:?} overlaps {:?}", left, right);
+            assert!(
+                left.end < right.start || left_value != right_value,
+                "{:?} and {:?} should have merged",
+                left,
+                right
+            );
+        }
+    }
+
+    #[test]
+    fn matches_a_model_and_stays_coalesced() {
+        for seed in 1..=30 {
+            let mut rng = XorShift64::new(seed);
+            let mut map = RangeMap::new();
+            let mut model = [None; POINTS as usize];
+            for _ in 0..200 {
+                let a = rng.below(u64::from(POINTS) + 1) as u32;
+                let b = rng.below(u64::from(POINTS) + 1) as u32;
+                let range = a.min(b)..a.max(b);
+                if rng.below(4) == 0 {
+                    map.remove(range.clone());
+                    for point in range {
+                        model[point as usize] = None;
+                    }
+                } else {
+                    let value = rng.below(3) as u8;
+                    map.insert(range.clone(), value);
+                    for point in range {
+                        model[point as usize] = Some(value);
+                    }
+                }
+                assert_coalesced(&map);
+                for point in 0..POINTS {
+                    assert_eq!(map.get(&point).copied(), model[point as usize]);
+                }
+                assert_eq!(map.get(&POINTS), None);
+            }
+        }
+    }
+
+    #[test]
+    fn equal_neighbours_merge() {
+        let mut map = RangeMap::new();
+        map.insert(0..5, "a");
+        map.insert(10..15, "a");
+        map.insert(5..10, "a");
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&0..&15, &"a")]);
+
+        // Overlapping also merges, from either side
+        map.insert(12..20, "a");
+        map.insert(-3..2, "a");
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&-3..&20, &"a")]);
+    }
+
+    #[test]
+    fn a_different_value_splits() {
+        let mut map = RangeMap::new();
+        map.insert(0..10, "a");
+        map.insert(3..6, "b");
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            vec![(&0..&3, &"a"), (&3..&6, &"b"), (&6..&10, &"a")]
+        );
+        assert_eq!(map.get_range(&4), Some((&3..&6, &"b")));
+
+        // Writing the old value back heals the split
+        map.insert(3..6, "a");
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn remove_splits_and_trims() {
+        let mut map = RangeMap::new();
+        map.insert(0..10, 1);
+        map.remove(4..6);
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            vec![(&0..&4, &1), (&6..&10, &1)]
+        );
+        map.remove(8..20);
+        map.remove(-5..1);
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            vec![(&1..&4, &1), (&6..&8, &1)]
+        );
+        assert!(!map.contains(&0) && !map.contains(&5) && !map.contains(&8));
+    }
+
+    #[test]
+    fn empty_ranges_are_ignored() {
+        let mut map = RangeMap::new();
+        map.insert(5..5, 1);
+        #[allow(clippy::reversed_empty_ranges)]
+        map.insert(7..3, 1);
+        assert!(map.is_empty());
+        map.insert(0..10, 1);
+        map.remove(5..5);
+        assert_eq!(map.len(), 1);
+    }
+}
@@ -0,0 +1,461 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A sorted set that can answer "how many are smaller?" and "which is k-th?"
+//!
+//! `BTreeSet` keeps its elements in order but can only find the k-th element
+//! or the rank of an element by walking to it. A `RankSet` is an AVL tree in
+//! which every node also records the size of its subtree, so `rank`, `select`
+//! and the percentile queries built on them follow a single root-to-leaf path.
+//!
+//! | Operation                      | Cost     |
+//! |--------------------------------|----------|
+//! | `insert`, `remove`, `contains` | O(log n) |
+//! | `rank`, `select`, `quantile`   | O(log n) |
+
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::iter::FromIterator;
+
+type Link<T> = Option<Box<Node<T>>>;
+
+#[derive(Debug, Clone)]
+struct Node<T> {
+    value: T,
+    left: Link<T>,
+    right: Link<T>,
+    height: usize,
+    // Nodes in the subtree rooted here, this one included
+    size: usize,
+}
+
+impl<T> Node<T> {
+    fn leaf(value: T) -> Box<Self> {
+        Box::new(Node {
+            value,
+            left: None,
+            right: None,
+            height: 1,
+            size: 1,
+        })
+    }
+
+    fn update(&mut self) {
+        self.height = 1 + height(&self.left).max(height(&self.right));
+        self.size = 1 + size(&self.left) + size(&self.right);
+    }
+}
+
+fn height<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.height)
+}
+
+fn size<T>(link: &Link<T>) -> usize {
+    link.as_ref().map_or(0, |node| node.size)
+}
+
+fn rotate_right<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut left = node.left.take().expect("rotation needs a left child");
+    node.left = left.right.take();
+    node.update();
+    left.right = Some(node);
+    left.update();
+    left
+}
+
+fn rotate_left<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    let mut right = node.right.take().expect("rotation needs a right child");
+    node.right = right.left.take();
+    node.update();
+    right.left = Some(node);
+    right.update();
+    right
+}
+
+// Restores the AVL invariant at `node` after one of its subtrees changed
+// height by at most one
+fn rebalance<T>(mut node: Box<Node<T>>) -> Box<Node<T>> {
+    node.update();
+    let left = height(&node.left);
+    let right = height(&node.right);
+    if left > right + 1 {
+        let child = node.left.take().expect("taller side has a child");
+        node.left = Some(if height(&child.left) < height(&child.right) {
+            rotate_left(child)
+        } else {
+            child
+        });
+        rotate_right(node)
+    } else if right > left + 1 {
+        let child = node.right.take().expect("taller side has a child");
+        node.right = Some(if height(&child.right) < height(&child.left) {
+            rotate_right(child)
+        } else {
+            child
+        });
+        rotate_left(node)
+    } else {
+        node
+    }
+}
+
+fn insert<T: Ord>(link: Link<T>, value: T) -> (Box<Node<T>>, bool) {
+    let mut node = match link {
+        Some(node) => node,
+        None => return (Node::leaf(value), true),
+    };
+    let inserted = match value.cmp(&node.value) {
+        Ordering::Less => {
+            let (left, inserted) = insert(node.left.take(), value);
+            node.left = Some(left);
+            inserted
+        }
+        Ordering::Greater => {
+            let (right, inserted) = insert(node.right.take(), value);
+            node.right = Some(right);
+            inserted
+        }
+        Ordering::Equal => false,
+    };
+    (rebalance(node), inserted)
+}
+
+fn remove<T, Q>(link: Link<T>, value: &Q) -> (Link<T>, Option<T>)
+where
+    T: Borrow<Q>,
+    Q: Ord + ?Sized,
+{
+    let mut node = match link {
+        Some(node) => node,
+        None => return (None, None),
+    };
+    match value.cmp(node.value.borrow()) {
+        Ordering::Less => {
+            let (left, removed) = remove(node.left.take(), value);
+            node.left = left;
+            (Some(rebalance(node)), removed)
+        }
+        Ordering::Greater => {
+            let (right, removed) = remove(node.right.take(), value);
+            node.right = right;
+            (Some(rebalance(node)), removed)
+        }
+        Ordering::Equal => {
+            let Node {
+                value, left, right, ..
+            } = *node;
+            let replacement = match (left, right) {
+                (None, child) | (child, None) => child,
+                (Some(left), Some(right)) => {
+                    // Replace the node with its in-order successor
+                    let (right, successor) = remove_min(right);
+                    let mut node = Node::leaf(successor);
+                    node.left = Some(left);
+                    node.right = right;
+                    Some(rebalance(node))
+                }
+            };
+            (replacement, Some(value))
+        }
+    }
+}
+
+fn remove_min<T>(mut node: Box<Node<T>>) -> (Link<T>, T) {
+    match node.left.take() {
+        Some(left) => {
+            let (left, min) = remove_min(left);
+            node.left = left;
+            (Some(rebalance(node)), min)
+        }
+        None => {
+            let Node { value, right, .. } = *node;
+            (right, value)
+        }
+    }
+}
+
+/// A sorted set with O(log n) rank and select
+#[derive(Debug, Clone)]
+pub struct RankSet<T> {
+    root: Link<T>,
+}
+
+impl<T: Ord> RankSet<T> {
+    pub fn new() -> Self {
+        RankSet { root: None }
+    }
+
+    pub fn len(&self) -> usize {
+        size(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    /// Adds `value`, returning `false` if it was already present
+    pub fn insert(&mut self, value: T) -> bool {
+        let (root, inserted) = insert(self.root.take(), value);
+        self.root = Some(root);
+        inserted
+    }
+
+    /// Removes `value`, returning it if it was present
+    pub fn remove<Q>(&mut self, value: &Q) -> Option<T>
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let (root, removed) = remove(self.root.take(), value);
+        self.root = root;
+        removed
+    }
+
+    pub fn contains<Q>(&self, value: &Q) -> bool
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut current = &self.root;
+        while let Some(node) = current {
+            current = match value.cmp(node.value.borrow()) {
+                Ordering::Less => &node.left,
+                Ordering::Greater => &node.right,
+                Ordering::Equal => return true,
+            };
+        }
+        false
+    }
+
+    /// Number of elements less than `value`, whether or not `value` itself
+    /// is in the set
+    pub fn rank<Q>(&self, value: &Q) -> usize
+    where
+        T: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut rank = 0;
+        let mut current = &self.root;
+        while let Some(node) = current {
+            match value.cmp(node.value.borrow()) {
+                Ordering::Less => current = &node.left,
+                Ordering::Greater => {
+                    rank += size(&node.left) + 1;
+                    current = &node.right;
+                }
+                Ordering::Equal => return rank + size(&node.left),
+            }
+        }
+        rank
+    }
+
+    /// Returns the `k`-th smallest element, counting from zero
+    pub fn select(&self, mut k: usize) -> Option<&T> {
+        let mut current = &self.root;
+        while let Some(node) = current {
+            let left = size(&node.left);
+            match k.cmp(&left) {
+                Ordering::Less => current = &node.left,
+                Ordering::Equal => return Some(&node.value),
+                Ordering::Greater => {
+                    k -= left + 1;
+                    current = &node.right;
+                }
+            }
+        }
+        None
+    }
+
+    /// Returns the element at quantile `q` by the nearest-rank method, so
+    /// `quantile(0.5)` is the median and `quantile(0.99)` the 99th percentile
+    ///
+    /// Panics unless `q` is between 0 and 1.
+    pub fn quantile(&self, q: f64) -> Option<&T> {
+        assert!((0.0..=1.0).contains(&q), "quantile must be within 0..=1");
+        if self.is_empty() {
+            return None;
+        }
+        let rank = (q * self.len() as f64).ceil() as usize;
+        self.select(rank.saturating_sub(1))
+    }
+
+    pub fn first(&self) -> Option<&T> {
+        self.select(0)
+    }
+
+    pub fn last(&self) -> Option<&T> {
+        self.len().checked_sub(1).and_then(|k| self.select(k))
+    }
+
+    /// Iterates over the elements in ascending order
+    pub fn iter(&self) -> Iter<'_, T> {
+        let mut iter = Iter {
+            stack: Vec::new(),
+            remaining: self.len(),
+        };
+        iter.push_left(&self.root);
+        iter
+    }
+
+    pub fn clear(&mut self) {
+        self.root = None;
+    }
+}
+
+impl<T: Ord> Default for RankSet<T> {
+    fn default() -> Self {
+        RankSet::new()
+    }
+}
+
+impl<T: Ord> FromIterator<T> for RankSet<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut set = RankSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<T: Ord> Extend<T> for RankSet<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+/// In-order iterator returned by `RankSet::iter`
+pub struct Iter<'a, T> {
+    // Nodes whose left subtree has been visited but which haven't been
+    // yielded yet, deepest on top
+    stack: Vec<&'a Node<T>>,
+    remaining: usize,
+}
+
+impl<'a, T> Iter<'a, T> {
+    fn push_left(&mut self, mut link: &'a Link<T>) {
+        while let Some(node) = link {
+            self.stack.push(node);
+            link = &node.left;
+        }
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        let node = self.stack.pop()?;
+        self.push_left(&node.right);
+        self.remaining -= 1;
+        Some(&node.value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<'a, T> ExactSizeIterator for Iter<'a, T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, XorShift64};
+
+    // Checks heights, sizes, ordering and balance below `link`, returning its
+    // height
+    fn assert_avl(link: &Link<u64>, low: Option<u64>, high: Option<u64>) -> usize {
+        let node = match link {
+            Some(node) => node,
+            None => return 0,
+        };
+        assert!(low.is_none_or(|low| node.value > low));
+        assert!(high.is_none_or(|high| node.value < high));
+        let left = assert_avl(&node.left, low, Some(node.value));
+        let right = assert_avl(&node.right, Some(node.value), high);
+        assert!(left.abs_diff(right) <= 1, "unbalanced at {}", node.value);
+        assert_eq!(node.height, 1 + left.max(right));
+        assert_eq!(node.size, 1 + size(&node.left) + size(&node.right));
+        node.height
+    }
+
+    #[test]
+    fn matches_a_sorted_vec() {
+        for seed in 1..=10 {
+            let mut rng = XorShift64::new(seed);
+            let mut set = RankSet::new();
+            let mut model: Vec<u64> = Vec::new();
+            for _ in 0..1000 {
+                let value = rng.below(300);
+                let position = model.binary_search(&value);
+                if rng.below(3) == 0 {
+                    let expected = position.ok().map(|index| model.remove(index));
+                    assert_eq!(set.remove(&value), expected);
+                } else {
+                    assert_eq!(set.insert(value), position.is_err());
+                    if let Err(index) = position {
+                        model.insert(index, value);
+                    }
+                }
+                assert_eq!(set.len(), model.len());
+            }
+            assert_avl(&set.root, None, None);
+            assert!(set.iter().eq(model.iter()));
+            for value in 0..300 {
+                let rank = model.partition_point(|&v| v < value);
+                assert_eq!(set.rank(&value), rank);
+                assert_eq!(set.contains(&value), model.binary_search(&value).is_ok());
+            }
+            for k in 0..=model.len() {
+                assert_eq!(set.select(k), model.get(k));
+            }
+        }
+    }
+
+    // Sorted input is the worst case for an unbalanced tree
+    #[test]
+    fn sorted_inserts_stay_balanced() {
+        let mut set: RankSet<u64> = (0..1024).collect();
+        let height = assert_avl(&set.root, None, None);
+        assert!(height <= 11, "height {}", height);
+        for value in (0..1024).step_by(2) {
+            set.remove(&value);
+        }
+        assert_avl(&set.root, None, None);
+        assert_eq!(set.first(), Some(&1));
+        assert_eq!(set.last(), Some(&1023));
+    }
+
+    #[test]
+    fn quantiles_use_the_nearest_rank() {
+        let set: RankSet<u64> = (1..=100).collect();
+        assert_eq!(set.quantile(0.0), Some(&1));
+        assert_eq!(set.quantile(0.5), Some(&50));
+        assert_eq!(set.quantile(0.99), Some(&99));
+        assert_eq!(set.quantile(1.0), Some(&100));
+        assert_eq!(RankSet::<u64>::new().quantile(0.5), None);
+    }
+
+    #[test]
+    #[should_panic(expected = "quantile must be within 0..=1")]
+    fn quantile_out_of_range_panics() {
+        let set: RankSet<u64> = (1..=3).collect();
+        set.quantile(1.5);
+    }
+
+    #[test]
+    fn empty_set() {
+        let mut set: RankSet<u64> = RankSet::new();
+        assert!(set.is_empty());
+        assert_eq!(set.first(), None);
+        assert_eq!(set.last(), None);
+        assert_eq!(set.select(0), None);
+        assert_eq!(set.rank(&5), 0);
+        assert_eq!(set.remove(&5), None);
+        set.insert(5);
+        set.clear();
+        assert!(set.is_empty());
+        assert_eq!(set.iter().len(), 0);
+    }
+}
@@ -0,0 +1,417 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Cached cell values that are recomputed only when their inputs change
+//!
+//! A `Recalc` holds input cells, set directly, and formula cells, computed
+//! by a closure from the values of other cells. Every formula's result is
+//! cached. Changing a cell marks the formulas that depend on it, directly or
+//! through other formulas, as dirty, and `evaluate_dirty_only` recomputes
+//! just those, each after its inputs. Editing one input of a large sheet
+//! therefore costs work proportional to what actually depends on it.
+//!
+//! Formula cells are plain closures, so any expression language can compile
+//! its formulas down to them.
+
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+type Compute<V> = Box<dyn Fn(&[&V]) -> V>;
+
+struct Formula<K, V> {
+    inputs: Vec<K>,
+    compute: Compute<V>,
+}
+
+struct Cell<K, V> {
+    value: Option<V>,
+    formula: Option<Formula<K, V>>,
+}
+
+/// `set_formula` would have made a cell depend on its own value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CycleError<K> {
+    pub cell: K,
+}
+
+impl<K: fmt::Debug> fmt::Display for CycleError<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "formula for {:?} would depend on itself", self.cell)
+    }
+}
+
+impl<K: fmt::Debug> Error for CycleError<K> {}
+
+/// Input and formula cells with memoized results and dirty tracking
+pub struct Recalc<K, V> {
+    cells: HashMap<K, Cell<K, V>>,
+    // Cell -> formulas that read it
+    dependents: HashMap<K, Vec<K>>,
+    dirty: HashSet<K>,
+}
+
+impl<K: Clone + Eq + Hash, V> Recalc<K, V> {
+    pub fn new() -> Self {
+        Recalc {
+            cells: HashMap::new(),
+            dependents: HashMap::new(),
+            dirty: HashSet::new(),
+        }
+    }
+
+    /// Sets an input cell, replacing any formula it had, and marks every
+    /// formula depending on it dirty
+    pub fn set_input(&mut self, key: K, value: V) {
+        self.detach_formula(&key);
+        self.cell_mut(key.clone()).value = Some(value);
+        self.dirty.remove(&key);
+        self.mark_dependents_dirty(&key);
+    }
+
+    /// Makes `key` a formula cell computed by `compute` from the values of
+    /// `inputs`, in that order
+    ///
+    /// The cell starts out dirty, as does everything depending on it. While
+    /// any input has no value the formula isn't called and the cell has no
+    /// value either. Fails, leaving the cell as it was, if `key` would end up
+    /// among its own inputs.
+    pub fn set_formula<F>(
+        &mut self,
+        key: K,
+        inputs: Vec<K>,
+        compute: F,
+    ) -> Result<(), CycleError<K>>
+    where
+        F: Fn(&[&V]) -> V + 'static,
+    {
+        if self.reaches(&inputs, &key) {
+            return Err(CycleError { cell: key });
+        }
+        self.detach_formula(&key);
+        for input in &inputs {
+            self.dependents
+                .entry(input.clone())
+                .or_default()
+                .push(key.clone());
+        }
+        let cell = self.cell_mut(key.clone());
+        cell.formula = Some(Formula {
+            inputs,
+            compute: Box::new(compute),
+        });
+        self.mark_dirty(key.clone());
+        self.mark_dependents_dirty(&key);
+        Ok(())
+    }
+
+    /// The cached value of `key`, which is stale if `is_dirty` says so
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.cells.get(key)?.value.as_ref()
+    }
+
+    pub fn is_dirty(&self, key: &K) -> bool {
+        self.dirty.contains(key)
+    }
+
+    /// Number of formula cells waiting to be recomputed
+    pub fn dirty_count(&self) -> usize {
+        self.dirty.len()
+    }
+
+    /// Recomputes every dirty formula, each after its inputs, returning how
+    /// many were evaluated
+    pub fn evaluate_dirty_only(&mut self) -> usize {
+        let order = self.dirty_in_input_order();
+        for key in &order {
+            let value = {
+                let formula = match self.cells.get(key).and_then(|cell| cell.formula.as_ref()) {
+                    Some(formula) => formula,
+                    None => continue,
+                };
+                let inputs: Option<Vec<&V>> =
+                    formula.inputs.iter().map(|input| self.get(input)).collect();
+                inputs.map(|inputs| (formula.compute)(&inputs))
+            };
+            self.cell_mut(key.clone()).value = value;
+        }
+        self.dirty.clear();
+        order.len()
+    }
+
+    /// Marks every formula dirty and recomputes them all
+    pub fn evaluate_all(&mut self) -> usize {
+        let formulas: Vec<K> = self
+            .cells
+            .iter()
+            .filter(|(_, cell)| cell.formula.is_some())
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in formulas {
+            self.mark_dirty(key);
+        }
+        self.evaluate_dirty_only()
+    }
+
+    fn cell_mut(&mut self, key: K) -> &mut Cell<K, V> {
+        self.cells.entry(key).or_insert_with(|| Cell {
+            value: None,
+            formula: None,
+        })
+    }
+
+    fn mark_dirty(&mut self, key: K) {
+        self.dirty.insert(key);
+    }
+
+    // Stops at formulas already dirty, whose dependents must be dirty too
+    fn mark_dependents_dirty(&mut self, key: &K) {
+        let mut pending: Vec<K> = self.dependents.get(key).cloned().unwrap_or_default();
+        while let Some(dependent) = pending.pop() {
+            if self.dirty.contains(&dependent) {
+                continue;
+            }
+            if let Some(next) = self.dependents.get(&dependent) {
+                pending.extend(next.iter().cloned());
+            }
+            self.mark_dirty(dependent);
+        }
+    }
+
+    // Drops the formula of `key`, if any, and its entries in `dependents`
+    fn detach_formula(&mut self, key: &K) {
+        let formula = match self.cells.get_mut(key).and_then(|cell| cell.formula.take()) {
+            Some(formula) => formula,
+            None => return,
+        };
+        for input in &formula.inputs {
+            if let Some(readers) = self.dependents.get_mut(input) {
+                readers.retain(|reader| reader != key);
+            }
+        }
+    }
+
+    // Whether `target` is one of `from` or among their formulas' inputs
+    fn reaches(&self, from: &[K], target: &K) -> bool {
+        let mut seen = HashSet::new();
+        let mut pending: Vec<&K> = from.iter().collect();
+        while let Some(key) = pending.pop() {
+            if key == target {
+                return true;
+            }
+            if !seen.insert(key) {
+                continue;
+            }
+            if let Some(formula) = self.cells.get(key).and_then(|cell| cell.formula.as_ref()) {
+                pending.extend(formula.inputs.iter());
+            }
+        }
+        false
+    }
+
+    // The dirty formulas ordered so that each comes after the dirty
+    // formulas it reads
+    fn dirty_in_input_order(&self) -> Vec<K> {
+        let mut order = Vec::with_capacity(self.dirty.len());
+        let mut done: HashSet<&K> = HashSet::new();
+        for start in &self.dirty {
+            if done.contains(start) {
+                continue;
+            }
+            // Depth-first, emitting a key once all of its inputs are done
+            let mut stack = vec![(start, false)];
+            while let Some((key, inputs_done)) = stack.pop() {
+                if done.contains(key) {
+                    continue;
+                }
+                if inputs_done {
+                    done.insert(key);
+                    order.push(key.clone());
+                    continue;
+                }
+                stack.push((key, true));
+                if let Some(formula) = self.cells.get(key).and_then(|cell| cell.formula.as_ref()) {
+                    for input in &formula.inputs {
+                        if self.dirty.contains(input) && !done.contains(input) {
+                            stack.push((input, false));
+                        }
+                    }
+                }
+            }
+        }
+        order
+    }
+}
+
+impl<K: Clone + Eq + Hash, V> Default for Recalc<K, V> {
+    fn default() -> Self {
+        Recalc::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for Recalc<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(self.cells.iter().map(|(key, cell)| (key, &cell.value)))
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, XorShift64};
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Log = Rc<RefCell<Vec<&'static str>>>;
+
+    // `key = inputs summed + offset`, noting in `log` each time it runs
+    fn sum_formula(
+        sheet: &mut Recalc<&'static str, i64>,
+        log: &Log,
+        key: &'static str,
+        inputs: Vec<&'static str>,
+        offset: i64,
+    ) -> Result<(), CycleError<&'static str>> {
+        let log = Rc::clone(log);
+        sheet.set_formula(key, inputs, move |values| {
+            log.borrow_mut().push(key);
+            values.iter().copied().sum::<i64>() + offset
+        })
+    }
+
+    // a -> c -> d -> f and b -> e -> f
+    fn chain() -> (Recalc<&'static str, i64>, Log) {
+        let mut sheet = Recalc::new();
+        let log = Log::default();
+        sheet.set_input("a", 1);
+        sheet.set_input("b", 2);
+        sum_formula(&mut sheet, &log, "f", vec!["d", "e"], 0).unwrap();
+        sum_formula(&mut sheet, &log, "d", vec!["c"], 100).unwrap();
+        sum_formula(&mut sheet, &log, "c", vec!["a"], 10).unwrap();
+        sum_formula(&mut sheet, &log, "e", vec!["b"], 1000).unwrap();
+        assert_eq!(sheet.evaluate_dirty_only(), 4);
+        log.borrow_mut().clear();
+        (sheet, log)
+    }
+
+    #[test]
+    fn only_the_affected_chain_is_recomputed_in_order() {
+        let (mut sheet, log) = chain();
+        assert_eq!(sheet.get(&"f"), Some(&(111 + 1002)));
+
+        sheet.set_input("a", 5);
+        assert_eq!(sheet.dirty_count(), 3);
+        assert!(sheet.is_dirty(&"f") && !sheet.is_dirty(&"e"));
+        assert_eq!(sheet.get(&"c"), Some(&11), "stale until evaluated");
+        assert_eq!(sheet.evaluate_dirty_only(), 3);
+        assert_eq!(*log.borrow(), ["c", "d", "f"]);
+        assert_eq!(sheet.get(&"f"), Some(&(115 + 1002)));
+        assert_eq!(sheet.dirty_count(), 0);
+
+        log.borrow_mut().clear();
+        sheet.set_input("b", 0);
+        sheet.evaluate_dirty_only();
+        assert_eq!(*log.borrow(), ["e", "f"]);
+        assert_eq!(sheet.evaluate_dirty_only(), 0);
+    }
+
+    #[test]
+    fn a_formula_waits_for_all_its_inputs() {
+        let mut sheet = Recalc::new();
+        let log = Log::default();
+        sheet.set_input("a", 1);
+        sum_formula(&mut sheet, &log, "sum", vec!["a", "missing"], 0).unwrap();
+        sheet.evaluate_dirty_only();
+        assert_eq!(sheet.get(&"sum"), None);
+        assert!(log.borrow().is_empty());
+        sheet.set_input("missing", 2);
+        sheet.evaluate_dirty_only();
+        assert_eq!(sheet.get(&"sum"), Some(&3));
+    }
+
+    #[test]
+    fn cycles_are_rejected_and_change_nothing() {
+        let (mut sheet, log) = chain();
+        assert_eq!(
+            sum_formula(&mut sheet, &log, "a", vec!["a"], 0),
+            Err(CycleError { cell: "a" })
+        );
+        // Indirect, through c and d, onto an input and onto a formula
+        assert_eq!(
+            sum_formula(&mut sheet, &log, "a", vec!["b", "d"], 0),
+            Err(CycleError { cell: "a" })
+        );
+        assert_eq!(
+            sum_formula(&mut sheet, &log, "c", vec!["f"], 0),
+            Err(CycleError { cell: "c" })
+        );
+        assert_eq!(sheet.dirty_count(), 0);
+        assert_eq!(sheet.get(&"a"), Some(&1));
+
+        // Both cells still behave as before, and `a` is still an input
+        sheet.set_input("a", 2);
+        sheet.evaluate_dirty_only();
+        assert_eq!(*log.borrow(), ["c", "d", "f"]);
+        assert_eq!(sheet.get(&"c"), Some(&12));
+        assert_eq!(sheet.evaluate_all(), 4);
+        assert_eq!(sheet.get(&"f"), Some(&(112 + 1002)));
+    }
+
+    #[test]
+    fn replacing_a_formula_drops_its_old_inputs() {
+        let (mut sheet, log) = chain();
+        sum_formula(&mut sheet, &log, "c", vec!["b"], 0).unwrap();
+        sheet.evaluate_dirty_only();
+        log.borrow_mut().clear();
+        sheet.set_input("a", 50);
+        assert_eq!(sheet.evaluate_dirty_only(), 0);
+        assert!(log.borrow().is_empty());
+        // Without the old edge, c can now read a formula that read it
+        sum_formula(&mut sheet, &log, "a", vec!["f"], 0).unwrap();
+    }
+
+    // Random sheets where each formula reads earlier cells, so there are
+    // no cycles, edited at random
+    #[test]
+    fn evaluate_all_agrees_with_dirty_only() {
+        const INPUTS: usize = 5;
+        const CELLS: usize = 30;
+        for seed in 1..=10 {
+            let mut rng = XorShift64::new(seed);
+            let mut sheet: Recalc<usize, i64> = Recalc::new();
+            for key in 0..INPUTS {
+                sheet.set_input(key, key as i64);
+            }
+            for key in INPUTS..CELLS {
+                let inputs = (0..1 + rng.below(3))
+                    .map(|_| rng.below(key as u64) as usize)
+                    .collect();
+                let offset = rng.below(10) as i64;
+                sheet
+                    .set_formula(key, inputs, move |values| {
+                        values
+                            .iter()
+                            .fold(offset, |sum, &&v| sum.wrapping_mul(3) ^ v)
+                    })
+                    .unwrap();
+            }
+            for _ in 0..50 {
+                let key = rng.below(INPUTS as u64) as usize;
+                sheet.set_input(key, rng.below(1000) as i64);
+                if rng.below(2) == 0 {
+                    continue;
+                }
+                sheet.evaluate_dirty_only();
+                let incremental: Vec<Option<i64>> =
+                    (0..CELLS).map(|key| sheet.get(&key).copied()).collect();
+                assert_eq!(sheet.evaluate_all(), CELLS - INPUTS);
+                let full: Vec<Option<i64>> =
+                    (0..CELLS).map(|key| sheet.get(&key).copied()).collect();
+                assert_eq!(incremental, full, "seed {}", seed);
+            }
+        }
+    }
+}
@@ -0,0 +1,263 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Aggregations that can share a single pass over an iterator
+//!
+//! Each `Reducer` folds items into some running state and produces a result
+//! at the end. Reducers compose as tuples: a tuple of reducers is itself a
+//! reducer that feeds every item to each member and returns a tuple of their
+//! results. `MultiReducer` runs such a bundle over an iterator, so the sum,
+//! maximum and top ten of a column all come out of one read of the data.
+
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashSet};
+use std::hash::Hash;
+use std::ops::Add;
+
+/// Folds items of type `T` into a result
+pub trait Reducer<T> {
+    type Output;
+
+    fn update(&mut self, item: &T);
+
+    fn finish(self) -> Self::Output;
+}
+
+/// Adds up every item, starting from `T::default()`
+#[derive(Debug, Clone, Default)]
+pub struct Sum<T> {
+    total: T,
+}
+
+impl<T: Default> Sum<T> {
+    pub fn new() -> Self {
+        Sum {
+            total: T::default(),
+        }
+    }
+}
+
+impl<T: Copy + Add<Output = T>> Reducer<T> for Sum<T> {
+    type Output = T;
+
+    fn update(&mut self, item: &T) {
+        self.total = self.total + *item;
+    }
+
+    fn finish(self) -> T {
+        self.total
+    }
+}
+
+/// Keeps the smallest item, or `None` if there were none
+#[derive(Debug, Clone)]
+pub struct Min<T> {
+    min: Option<T>,
+}
+
+impl<T> Min<T> {
+    pub fn new() -> Self {
+        Min { min: None }
+    }
+}
+
+impl<T> Default for Min<T> {
+    fn default() -> Self {
+        Min::new()
+    }
+}
+
+impl<T: Ord + Clone> Reducer<T> for Min<T> {
+    type Output = Option<T>;
+
+    fn update(&mut self, item: &T) {
+        if self.min.as_ref().is_none_or(|min| item < min) {
+            self.min = Some(item.clone());
+        }
+    }
+
+    fn finish(self) -> Option<T> {
+        self.min
+    }
+}
+
+/// Keeps the largest item, or `None` if there were none
+#[derive(Debug, Clone)]
+pub struct Max<T> {
+    max: Option<T>,
+}
+
+impl<T> Max<T> {
+    pub fn new() -> Self {
+        Max { max: None }
+    }
+}
+
+impl<T> Default for Max<T> {
+    fn default() -> Self {
+        Max::new()
+    }
+}
+
+impl<T: Ord + Clone> Reducer<T> for Max<T> {
+    type Output = Option<T>;
+
+    fn update(&mut self, item: &T) {
+        if self.max.as_ref().is_none_or(|max| item > max) {
+            self.max = Some(item.clone());
+        }
+    }
+
+    fn finish(self) -> Option<T> {
+        self.max
+    }
+}
+
+/// Counts the distinct items, holding one copy of each
+#[derive(Debug, Clone)]
+pub struct CountDistinct<T> {
+    seen: HashSet<T>,
+}
+
+impl<T: Eq + Hash> CountDistinct<T> {
+    pub fn new() -> Self {
+        CountDistinct {
+            seen: HashSet::new(),
+        }
+    }
+}
+
+impl<T: Eq + Hash> Default for CountDistinct<T> {
+    fn default() -> Self {
+        CountDistinct::new()
+    }
+}
+
+impl<T: Eq + Hash + Clone> Reducer<T> for CountDistinct<T> {
+    type Output = usize;
+
+    fn update(&mut self, item: &T) {
+        if !self.seen.contains(item) {
+            self.seen.insert(item.clone());
+        }
+    }
+
+    fn finish(self) -> usize {
+        self.seen.len()
+    }
+}
+
+/// Keeps the `k` largest items, returned largest first
+///
+/// Holds at most `k` items at a time in a min-heap, so the smallest of the
+/// current top `k` can be dropped in O(log k) when a larger item arrives.
+#[derive(Debug, Clone)]
+pub struct TopK<T> {
+    k: usize,
+    heap: BinaryHeap<Reverse<T>>,
+}
+
+impl<T: Ord> TopK<T> {
+    pub fn new(k: usize) -> Self {
+        TopK {
+            k,
+            heap: BinaryHeap::with_capacity(k + 1),
+        }
+    }
+}
+
+impl<T: Ord + Clone> Reducer<T> for TopK<T> {
+    type Output = Vec<T>;
+
+    fn update(&mut self, item: &T) {
+        if self.heap.len() < self.k {
+            self.heap.push(Reverse(item.clone()));
+        } else if self
+            .heap
+            .peek()
+            .is_some_and(|Reverse(smallest)| item > smallest)
+        {
+            self.heap.pop();
+            self.heap.push(Reverse(item.clone()));
+        }
+    }
+
+    fn finish(self) -> Vec<T> {
+        // Ascending order of `Reverse` is descending order of the items
+        self.heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(item)| item)
+            .collect()
+    }
+}
+
+macro_rules! impl_reducer_for_tuples {
+    ($(($($name:ident),+)),+) => {
+        $(
+            #[allow(non_snake_case)]
+            impl<T, $($name: Reducer<T>),+> Reducer<T> for ($($name,)+) {
+                type Output = ($($name::Output,)+);
+
+                fn update(&mut self, item: &T) {
+                    let ($($name,)+) = self;
+                    $($name.update(item);)+
+                }
+
+                fn finish(self) -> Self::Output {
+                    let ($($name,)+) = self;
+                    ($($name.finish(),)+)
+                }
+            }
+        )+
+    };
+}
+
+impl_reducer_for_tuples!(
+    (A),
+    (A, B),
+    (A, B, C),
+    (A, B, C, D),
+    (A, B, C, D, E),
+    (A, B, C, D, E, F)
+);
+
+/// Runs a reducer, typically a tuple of them, over one pass of an iterator
+#[derive(Debug, Clone)]
+pub struct MultiReducer<R> {
+    reducers: R,
+}
+
+impl<R> MultiReducer<R> {
+    pub fn new(reducers: R) -> Self {
+        MultiReducer { reducers }
+    }
+
+    /// Feeds every item to the reducers and returns their results
+    pub fn run<T, I>(self, iter: I) -> R::Output
+    where
+        R: Reducer<T>,
+        I: IntoIterator<Item = T>,
+    {
+        let mut reducers = self.reducers;
+        for item in iter {
+            reducers.update(&item);
+        }
+        reducers.finish()
+    }
+
+    /// Like `run` over fallible items, stopping at the first error
+    pub fn try_run<T, E, I>(self, iter: I) -> Result<R::Output, E>
+    where
+        R: Reducer<T>,
+        I: IntoIterator<Item = Result<T, E>>,
+    {
+        let reducers = iter
+            .into_iter()
+            .try_fold(self.reducers, |mut reducers, item| {
+                reducers.update(&item?);
+                Ok(reducers)
+            })?;
+        Ok(reducers.finish())
+    }
+}
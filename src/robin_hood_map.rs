@@ -0,0 +1,386 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! An open-addressing hash map using Robin Hood hashing
+//!
+//! Like `ProbeMap`, entries live in one flat array and walk forward from
+//! their home slot until they find room. The difference is what happens on
+//! the way: an entry that has already walked further than the occupant of a
+//! slot takes that slot, and the occupant carries on walking instead. Taking
+//! from the "rich" entries close to home and giving to the "poor" ones far
+//! from it keeps every probe distance close to the average, so even a very
+//! full table has no long walks.
+//!
+//! Removal shifts the following entries back one slot rather than leaving a
+//! tombstone, so the table never fills up with dead slots.
+//! `probe_distance_histogram` shows how evenly the distances are spread.
+
+use crate::op_stats::{Op, OpCounters, OpStats};
+use std::borrow::Borrow;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hash};
+use std::mem;
+
+// Robin Hood tables stay well behaved at a higher load than plain probing
+const MAX_LOAD: f64 = 0.875;
+
+const MIN_SLOTS: usize = 8;
+
+#[derive(Debug, Clone)]
+struct Entry<K, V> {
+    key: K,
+    value: V,
+    // Steps from the slot this key's hash picked
+    distance: usize,
+}
+
+/// A linear-probing hash map that rebalances probe distances on insert
+#[derive(Debug, Clone)]
+pub struct RobinHoodMap<K, V> {
+    slots: Vec<Option<Entry<K, V>>>,
+    hasher: RandomState,
+    len: usize,
+    counters: OpCounters,
+}
+
+impl<K: Eq + Hash, V> RobinHoodMap<K, V> {
+    pub fn new() -> Self {
+        RobinHoodMap::with_capacity(0)
+    }
+
+    /// Creates a map that can hold `capacity` entries before growing
+    pub fn with_capacity(capacity: usize) -> Self {
+        let slots = ((capacity as f64 / MAX_LOAD).ceil() as usize)
+            .max(MIN_SLOTS)
+            .next_power_of_two();
+        RobinHoodMap {
+            slots: empty_slots(slots),
+            hasher: RandomState::new(),
+            len: 0,
+            counters: OpCounters::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Number of slots in the backing array
+    pub fn slot_count(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Fraction of slots holding an entry
+    pub fn load_factor(&self) -> f64 {
+        self.len as f64 / self.slots.len() as f64
+    }
+
+    /// The largest distance any entry sits from its home slot
+    pub fn max_probe_distance(&self) -> usize {
+        self.slots
+            .iter()
+            .flatten()
+            .map(|entry| entry.distance)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Counts entries by how far they sit from their home slot: element `d`
+    /// of the result is the number of entries at distance `d`
+    pub fn probe_distance_histogram(&self) -> Vec<usize> {
+        let mut histogram = Vec::new();
+        for entry in self.slots.iter().flatten() {
+            if entry.distance >= histogram.len() {
+                histogram.resize(entry.distance + 1, 0);
+            }
+            histogram[entry.distance] += 1;
+        }
+        histogram
+    }
+
+    /// What the map has been asked to do, counted with the `instrument`
+    /// feature
+    pub fn op_stats(&self) -> OpStats {
+        self.counters.stats()
+    }
+
+    pub fn reset_op_stats(&mut self) {
+        self.counters.reset();
+    }
+
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.counters.record(Op::Insert);
+        if let Some(existing) = self.value_mut(&key) {
+            return Some(mem::replace(existing, value));
+        }
+        if (self.len + 1) as f64 > self.slots.len() as f64 * MAX_LOAD {
+            self.grow();
+        }
+        self.place(key, value);
+        None
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counters.record(Op::Lookup);
+        let index = self.find(key)?;
+        self.slots[index].as_ref().map(|entry| &entry.value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counters.record(Op::Lookup);
+        self.value_mut(key)
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counters.record(Op::Lookup);
+        self.find(key).is_some()
+    }
+
+    /// Removes `key`, shifting the entries after it back towards home
+    pub fn remove<Q>(&mut self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.counters.record(Op::Removal);
+        let mask = self.slots.len() - 1;
+        let mut index = self.find(key)?;
+        let removed = self.slots[index].take();
+
+        loop {
+            let next = (index + 1) & mask;
+            match self.slots[next].take() {
+                Some(mut entry) if entry.distance > 0 => {
+                    entry.distance -= 1;
+                    self.slots[index] = Some(entry);
+                    index = next;
+                }
+                other => {
+                    self.slots[next] = other;
+                    break;
+                }
+            }
+        }
+
+        self.len -= 1;
+        removed.map(|entry| entry.value)
+    }
+
+    /// Iterates over every entry in slot order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.slots
+            .iter()
+            .flatten()
+            .map(|entry| (&entry.key, &entry.value))
+    }
+
+    pub fn clear(&mut self) {
+        for slot in self.slots.iter_mut() {
+            *slot = None;
+        }
+        self.len = 0;
+    }
+
+    fn value_mut<Q>(&mut self, key: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let index = self.find(key)?;
+        self.slots[index].as_mut().map(|entry| &mut entry.value)
+    }
+
+    // Stores a key that isn't in the map yet, which must have room for it
+    fn place(&mut self, key: K, value: V) {
+        let mask = self.slots.len() - 1;
+        let mut index = self.home(&key);
+        let mut carried = Entry {
+            key,
+            value,
+            distance: 0,
+        };
+        loop {
+            match &mut self.slots[index] {
+                None => {
+                    self.slots[index] = Some(carried);
+                    break;
+                }
+                Some(resident) if resident.distance < carried.distance => {
+                    mem::swap(resident, &mut carried);
+                }
+                Some(_) => {}
+            }
+            index = (index + 1) & mask;
+            carried.distance += 1;
+        }
+        self.len += 1;
+    }
+
+    fn home<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        (self.hasher.hash_one(key) as usize) & (self.slots.len() - 1)
+    }
+
+    // Walks until the key turns up, or until it reaches an entry closer to
+    // home than the key would be, which the key would have displaced
+    fn find<Q>(&self, key: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let mask = self.slots.len() - 1;
+        let mut index = self.home(key);
+        let mut distance = 0;
+        loop {
+            match &self.slots[index] {
+                Some(entry) if entry.key.borrow() == key => return Some(index),
+                Some(entry) if entry.distance >= distance => {}
+                _ => return None,
+            }
+            index = (index + 1) & mask;
+            distance += 1;
+        }
+    }
+
+    fn grow(&mut self) {
+        self.counters.record(Op::Rehash);
+        self.counters.record(Op::Reallocation);
+        let slots = self.slots.len() * 2;
+        let old = mem::replace(&mut self.slots, empty_slots(slots));
+        self.len = 0;
+        for entry in old.into_iter().flatten() {
+            self.place(entry.key, entry.value);
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> Default for RobinHoodMap<K, V> {
+    fn default() -> Self {
+        RobinHoodMap::new()
+    }
+}
+
+fn empty_slots<K, V>(len: usize) -> Vec<Option<Entry<K, V>>> {
+    (0..len).map(|_| None).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, XorShift64};
+    use std::collections::HashMap;
+
+    // Each stored distance must be the real one, an entry may be at most one
+    // step further from home than the entry before it, and an entry after an
+    // empty slot must be at home. Backward-shift deletion has to keep all
+    // three.
+    fn assert_invariants(map: &RobinHoodMap<u64, u64>) {
+        let mask = map.slots.len() - 1;
+        let mut stored = 0;
+        for (index, slot) in map.slots.iter().enumerate() {
+            let entry = match slot {
+                Some(entry) => entry,
+                None => continue,
+            };
+            stored += 1;
+            let home = map.home(&entry.key);
+            assert_eq!(
+                index.wrapping_sub(home) & mask,
+                entry.distance,
+                "key {}",
+                entry.key
+            );
+            let before = match &map.slots[index.wrapping_sub(1) & mask] {
+                Some(before) => before.distance + 1,
+                None => 0,
+            };
+            assert!(entry.distance <= before, "key {}", entry.key);
+        }
+        assert_eq!(stored, map.len());
+    }
+
+    #[test]
+    fn matches_a_model() {
+        let mut rng = XorShift64::new(13);
+        let mut map = RobinHoodMap::new();
+        let mut model = HashMap::new();
+        for step in 0..5000 {
+            let key = rng.below(500);
+            if rng.below(2) == 0 {
+                assert_eq!(map.remove(&key), model.remove(&key));
+            } else {
+                assert_eq!(map.insert(key, step), model.insert(key, step));
+            }
+            assert!(map.load_factor() <= MAX_LOAD);
+            if step % 100 == 0 {
+                assert_invariants(&map);
+            }
+        }
+        assert_invariants(&map);
+        for key in 0..500 {
+            assert_eq!(map.get(&key), model.get(&key));
+        }
+    }
+
+    // A nearly full table has long runs, so removals shift many entries
+    #[test]
+    fn removal_shifts_entries_back() {
+        let mut map = RobinHoodMap::with_capacity(200);
+        let slots = map.slot_count();
+        for key in 0..200 {
+            map.insert(key, key);
+        }
+        assert_eq!(map.slot_count(), slots);
+        let before = map.max_probe_distance();
+        for key in (0..200).step_by(3) {
+            assert_eq!(map.remove(&key), Some(key));
+            assert_invariants(&map);
+        }
+        assert!(map.max_probe_distance() <= before);
+        for key in 0..200 {
+            let expected = if key % 3 == 0 { None } else { Some(&key) };
+            assert_eq!(map.get(&key), expected);
+        }
+    }
+
+    #[test]
+    fn histogram_counts_every_entry() {
+        let mut map = RobinHoodMap::new();
+        assert!(map.probe_distance_histogram().is_empty());
+        for key in 0..1000 {
+            map.insert(key, key);
+        }
+        let histogram = map.probe_distance_histogram();
+        assert_eq!(histogram.iter().sum::<usize>(), 1000);
+        assert_eq!(histogram.len(), map.max_probe_distance() + 1);
+        assert!(histogram[0] > 0);
+    }
+
+    #[test]
+    fn get_mut_and_clear() {
+        let mut map = RobinHoodMap::new();
+        map.insert("a", 1);
+        *map.get_mut("a").unwrap() += 1;
+        assert_eq!(map.insert("a", 5), Some(2));
+        assert_eq!(map.len(), 1);
+        map.clear();
+        assert!(map.is_empty());
+        assert!(!map.contains_key("a"));
+        assert_eq!(map.max_probe_distance(), 0);
+    }
+}
@@ -0,0 +1,36 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Drawing a uniform sample from a stream of unknown length
+//!
+//! `reservoir_sample` keeps a reservoir of `k` items and gives every later
+//! item a `k / seen` chance of replacing one of them (Vitter's algorithm R),
+//! so after one pass each item has had the same chance of being kept while
+//! only `k` items were ever held in memory.
+
+use crate::random::Rng;
+
+/// Returns `k` items chosen uniformly at random from `iter`, or every item
+/// if there are fewer than `k`
+///
+/// The sample is not in any particular order.
+pub fn reservoir_sample<I, R>(iter: I, k: usize, rng: &mut R) -> Vec<I::Item>
+where
+    I: IntoIterator,
+    R: Rng + ?Sized,
+{
+    let mut iter = iter.into_iter();
+    let mut reservoir: Vec<I::Item> = iter.by_ref().take(k).collect();
+    if reservoir.len() < k {
+        return reservoir;
+    }
+
+    for (index, item) in iter.enumerate() {
+        let seen = (k + index + 1) as u64;
+        let slot = rng.below(seen) as usize;
+        if slot < k {
+            reservoir[slot] = item;
+        }
+    }
+    reservoir
+}
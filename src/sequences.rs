@@ -0,0 +1,145 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Infinite-looking iterators over classic integer sequences
+//!
+//! Each sequence is computed lazily, one term per `next`, and ends with
+//! `None` instead of overflowing once its next term doesn't fit in the item
+//! type. `Fibonacci` uses `u128` so it runs for 187 terms, up to F(186),
+//! before that happens.
+
+use std::collections::HashMap;
+
+/// The Fibonacci numbers 0, 1, 1, 2, 3, 5, ...
+#[derive(Debug, Clone)]
+pub struct Fibonacci {
+    // `None` once the next term has overflowed
+    current: Option<u128>,
+    next: Option<u128>,
+}
+
+impl Fibonacci {
+    pub fn new() -> Self {
+        Fibonacci {
+            current: Some(0),
+            next: Some(1),
+        }
+    }
+}
+
+impl Default for Fibonacci {
+    fn default() -> Self {
+        Fibonacci::new()
+    }
+}
+
+impl Iterator for Fibonacci {
+    type Item = u128;
+
+    fn next(&mut self) -> Option<u128> {
+        let current = self.current?;
+        let following = self.next.and_then(|next| current.checked_add(next));
+        self.current = self.next;
+        self.next = following;
+        Some(current)
+    }
+}
+
+/// The primes 2, 3, 5, 7, 11, ... from an incremental sieve of Eratosthenes
+///
+/// Rather than sieving a fixed range up front, each prime found is
+/// scheduled to cross off its next multiple, so memory grows with the number
+/// of primes produced rather than with a sieve limit.
+#[derive(Debug, Clone)]
+pub struct Primes {
+    candidate: u64,
+    // Upcoming composite -> the primes that will cross it off
+    composites: HashMap<u64, Vec<u64>>,
+    done: bool,
+}
+
+impl Primes {
+    pub fn new() -> Self {
+        Primes {
+            candidate: 2,
+            composites: HashMap::new(),
+            done: false,
+        }
+    }
+
+    // Moves `candidate` on, or ends the sequence at the top of `u64`
+    fn advance(&mut self) {
+        match self.candidate.checked_add(1) {
+            Some(next) => self.candidate = next,
+            None => self.done = true,
+        }
+    }
+}
+
+impl Default for Primes {
+    fn default() -> Self {
+        Primes::new()
+    }
+}
+
+impl Iterator for Primes {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        while !self.done {
+            let candidate = self.candidate;
+            self.advance();
+            match self.composites.remove(&candidate) {
+                Some(factors) => {
+                    for prime in factors {
+                        if let Some(multiple) = candidate.checked_add(prime) {
+                            self.composites.entry(multiple).or_default().push(prime);
+                        }
+                    }
+                }
+                None => {
+                    // Smaller multiples of this prime have smaller factors
+                    // too, so crossing off starts at its square
+                    if let Some(square) = candidate.checked_mul(candidate) {
+                        self.composites.entry(square).or_default().push(candidate);
+                    }
+                    return Some(candidate);
+                }
+            }
+        }
+        None
+    }
+}
+
+/// The Collatz sequence from a starting number down to 1, halving even
+/// terms and taking odd terms to `3n + 1`
+///
+/// A start of 0 yields nothing, since the sequence would never reach 1.
+#[derive(Debug, Clone)]
+pub struct Collatz {
+    next: Option<u64>,
+}
+
+impl Collatz {
+    pub fn new(start: u64) -> Self {
+        Collatz {
+            next: Some(start).filter(|&start| start > 0),
+        }
+    }
+}
+
+impl Iterator for Collatz {
+    type Item = u64;
+
+    fn next(&mut self) -> Option<u64> {
+        let current = self.next?;
+        self.next = match current {
+            1 => None,
+            even if even % 2 == 0 => Some(even / 2),
+            odd => odd
+                .checked_mul(3)
+                .and_then(|tripled| tripled.checked_add(1)),
+        };
+        Some(current)
+    }
+}
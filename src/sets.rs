@@ -0,0 +1,144 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Set algebra over any number of `HashSet`s
+//!
+//! `HashSet` has `union`, `intersection` and friends for two sets at a time.
+//! These functions extend them to whole collections of sets, and add a few
+//! questions std doesn't answer: whether some sets partition another, and
+//! what every subset of a set is.
+
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+use std::hash::{BuildHasher, Hash};
+
+/// The most elements `power_set` accepts, which already means a million
+/// subsets
+pub const MAX_POWER_SET_ELEMENTS: usize = 20;
+
+/// Returned by `power_set` for a set with more than
+/// `MAX_POWER_SET_ELEMENTS` elements
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PowerSetTooLarge {
+    pub elements: usize,
+}
+
+impl fmt::Display for PowerSetTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "power set of {} elements would have 2^{} subsets, more than the limit of 2^{}",
+            self.elements, self.elements, MAX_POWER_SET_ELEMENTS
+        )
+    }
+}
+
+impl Error for PowerSetTooLarge {}
+
+/// Returns every element that is in at least one of `sets`
+pub fn union_all<'a, T, S, I>(sets: I) -> HashSet<T, S>
+where
+    T: Eq + Hash + Clone + 'a,
+    S: BuildHasher + Default + 'a,
+    I: IntoIterator<Item = &'a HashSet<T, S>>,
+{
+    let mut union = HashSet::default();
+    for set in sets {
+        union.extend(set.iter().cloned());
+    }
+    union
+}
+
+/// Returns the elements that are in every one of `sets`
+///
+/// The smallest set is the starting point, so the work is bounded by its
+/// size. With no sets at all the result is empty.
+pub fn intersection_all<'a, T, S, I>(sets: I) -> HashSet<T, S>
+where
+    T: Eq + Hash + Clone + 'a,
+    S: BuildHasher + Default + 'a,
+    I: IntoIterator<Item = &'a HashSet<T, S>>,
+{
+    let sets: Vec<&HashSet<T, S>> = sets.into_iter().collect();
+    let smallest = match sets.iter().min_by_key(|set| set.len()) {
+        Some(smallest) => *smallest,
+        None => return HashSet::default(),
+    };
+    smallest
+        .iter()
+        .filter(|item| sets.iter().all(|set| set.contains(*item)))
+        .cloned()
+        .collect()
+}
+
+/// Returns the elements that are in an odd number of `sets`
+///
+/// For two sets these are the elements in exactly one of them, the usual
+/// symmetric difference. Defining it by odd membership keeps it the same
+/// whichever order the sets are combined in.
+pub fn symmetric_difference<'a, T, S, I>(sets: I) -> HashSet<T, S>
+where
+    T: Eq + Hash + Clone + 'a,
+    S: BuildHasher + Default + 'a,
+    I: IntoIterator<Item = &'a HashSet<T, S>>,
+{
+    let mut odd = HashSet::default();
+    for set in sets {
+        for item in set {
+            if !odd.remove(item) {
+                odd.insert(item.clone());
+            }
+        }
+    }
+    odd
+}
+
+/// Checks whether `parts` partition `whole`: every part is non-empty, no two
+/// parts share an element, and together they cover exactly `whole`
+pub fn is_partition<'a, T, S, I>(parts: I, whole: &HashSet<T, S>) -> bool
+where
+    T: Eq + Hash + 'a,
+    S: BuildHasher + 'a,
+    I: IntoIterator<Item = &'a HashSet<T, S>>,
+{
+    let mut covered = 0;
+    for part in parts {
+        if part.is_empty() || !part.is_subset(whole) {
+            return false;
+        }
+        covered += part.len();
+    }
+    // Every part lies inside `whole`, so their sizes only add up to the size
+    // of `whole` when none of them overlap and nothing is left uncovered
+    covered == whole.len()
+}
+
+/// Returns every subset of `set`, from the empty set up to `set` itself
+///
+/// A set of `n` elements has `2^n` subsets, so sets larger than
+/// `MAX_POWER_SET_ELEMENTS` are refused.
+pub fn power_set<T, S>(set: &HashSet<T, S>) -> Result<Vec<HashSet<T, S>>, PowerSetTooLarge>
+where
+    T: Eq + Hash + Clone,
+    S: BuildHasher + Default,
+{
+    if set.len() > MAX_POWER_SET_ELEMENTS {
+        return Err(PowerSetTooLarge {
+            elements: set.len(),
+        });
+    }
+
+    let items: Vec<&T> = set.iter().collect();
+    // Bit `i` of each mask says whether `items[i]` is in that subset
+    Ok((0u32..1 << items.len())
+        .map(|mask| {
+            items
+                .iter()
+                .enumerate()
+                .filter(|(index, _)| mask & (1 << index) != 0)
+                .map(|(_, item)| (*item).clone())
+                .collect()
+        })
+        .collect())
+}
@@ -0,0 +1,132 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A concurrent `HashMap` split into independently locked shards
+//!
+//! One `RwLock` around a whole map serializes every writer. Splitting the
+//! keys across several smaller maps, each behind its own lock, lets threads
+//! that touch different shards run in parallel. The trade-off is that any
+//! operation over the whole map, such as `len`, has to take every lock.
+
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+/// How many shards `ShardedMap::new` creates
+pub const DEFAULT_SHARDS: usize = 16;
+
+/// A `HashMap` spread across `RwLock`-protected shards, shareable between
+/// threads behind an `Arc`
+#[derive(Debug)]
+pub struct ShardedMap<K, V> {
+    shards: Vec<RwLock<HashMap<K, V>>>,
+    hasher: RandomState,
+}
+
+impl<K: Eq + Hash, V> ShardedMap<K, V> {
+    pub fn new() -> Self {
+        ShardedMap::with_shards(DEFAULT_SHARDS)
+    }
+
+    /// Creates a map with `count` shards, or one shard if `count` is zero
+    pub fn with_shards(count: usize) -> Self {
+        ShardedMap {
+            shards: (0..count.max(1))
+                .map(|_| RwLock::new(HashMap::new()))
+                .collect(),
+            hasher: RandomState::new(),
+        }
+    }
+
+    pub fn shard_count(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn insert(&self, key: K, value: V) -> Option<V> {
+        self.write_shard(&key).insert(key, value)
+    }
+
+    /// Returns a copy of the value, since no reference can outlive the lock
+    pub fn get_cloned(&self, key: &K) -> Option<V>
+    where
+        V: Clone,
+    {
+        self.read_shard(key).get(key).cloned()
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.read_shard(key).contains_key(key)
+    }
+
+    /// Runs `f` on the value for `key` while holding its shard's write lock,
+    /// returning false if the key is missing
+    pub fn update<F: FnOnce(&mut V)>(&self, key: &K, f: F) -> bool {
+        match self.write_shard(key).get_mut(key) {
+            Some(value) => {
+                f(value);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&self, key: &K) -> Option<V> {
+        self.write_shard(key).remove(key)
+    }
+
+    /// Counts every entry
+    ///
+    /// All shards are read-locked together before counting, so the result is
+    /// a consistent snapshot rather than a sum of counts taken at different
+    /// moments.
+    pub fn len(&self) -> usize {
+        let guards: Vec<_> = self.shards.iter().map(read_lock).collect();
+        guards.iter().map(|shard| shard.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of entries in each shard, to check the spread
+    pub fn shard_sizes(&self) -> Vec<usize> {
+        let guards: Vec<_> = self.shards.iter().map(read_lock).collect();
+        guards.iter().map(|shard| shard.len()).collect()
+    }
+
+    pub fn clear(&self) {
+        let mut guards: Vec<_> = self.shards.iter().map(write_lock).collect();
+        for shard in guards.iter_mut() {
+            shard.clear();
+        }
+    }
+
+    fn shard_index(&self, key: &K) -> usize {
+        (self.hasher.hash_one(key) % self.shards.len() as u64) as usize
+    }
+
+    fn read_shard(&self, key: &K) -> RwLockReadGuard<'_, HashMap<K, V>> {
+        read_lock(&self.shards[self.shard_index(key)])
+    }
+
+    fn write_shard(&self, key: &K) -> RwLockWriteGuard<'_, HashMap<K, V>> {
+        write_lock(&self.shards[self.shard_index(key)])
+    }
+}
+
+impl<K: Eq + Hash, V> Default for ShardedMap<K, V> {
+    fn default() -> Self {
+        ShardedMap::new()
+    }
+}
+
+// A panic inside `update` poisons the shard's lock, but every mutation this
+// map makes is a single `HashMap` call, so the shard itself is still sound
+fn read_lock<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+fn write_lock<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(PoisonError::into_inner)
+}
@@ -0,0 +1,284 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Cheapest paths through a weighted `Graph`
+//!
+//! `shortest_path` is Dijkstra's algorithm and `shortest_path_astar` is A*,
+//! which is the same search steered towards the target by a heuristic. Both
+//! keep their frontier in a `PriorityMap`, so a node whose cost improves has
+//! its priority lowered in place instead of being queued twice.
+//!
+//! Edge weights can be any ordered type with addition whose `Default` is its
+//! zero, such as the unsigned and signed integers. Neither algorithm is
+//! correct with negative weights, and a negative edge in an undirected graph
+//! is already a negative cycle, so graphs containing one are rejected with
+//! `PathError::NegativeWeight` rather than given a wrong answer.
+
+use crate::graph::{Graph, NodeId};
+use crate::priority_map::PriorityMap;
+use std::error::Error;
+use std::fmt;
+use std::ops::Add;
+
+/// The cheapest route found between two nodes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Path<E> {
+    /// Every node on the route, both ends included
+    pub nodes: Vec<NodeId>,
+    /// The sum of the weights along the route
+    pub cost: E,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathError {
+    /// The edge from `from` to `to` has a weight below zero
+    NegativeWeight { from: NodeId, to: NodeId },
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PathError::NegativeWeight { from, to } => write!(
+                f,
+                "edge from node {} to node {} has a negative weight",
+                from.index(),
+                to.index()
+            ),
+        }
+    }
+}
+
+impl Error for PathError {}
+
+impl<N, E> Graph<N, E>
+where
+    E: Copy + Ord + Add<Output = E> + Default,
+{
+    /// Finds the cheapest path from `from` to `to` with Dijkstra's
+    /// algorithm, or `None` if `to` can't be reached
+    pub fn shortest_path(&self, from: NodeId, to: NodeId) -> Result<Option<Path<E>>, PathError> {
+        self.shortest_path_astar(from, to, |_| E::default())
+    }
+
+    /// Finds the cheapest path from `from` to `to` with A*, using
+    /// `heuristic` to estimate the remaining cost from a node to `to`
+    ///
+    /// The path is the cheapest one as long as the heuristic never
+    /// overestimates. A heuristic that always returns zero makes this
+    /// Dijkstra's algorithm.
+    pub fn shortest_path_astar<H>(
+        &self,
+        from: NodeId,
+        to: NodeId,
+        heuristic: H,
+    ) -> Result<Option<Path<E>>, PathError>
+    where
+        H: Fn(NodeId) -> E,
+    {
+        self.check_weights()?;
+        let zero = E::default();
+        let mut cost: Vec<Option<E>> = vec![None; self.node_count()];
+        let mut previous: Vec<Option<NodeId>> = vec![None; self.node_count()];
+        let mut frontier = PriorityMap::new();
+        cost[from.index()] = Some(zero);
+        frontier.push(from, heuristic(from));
+
+        while let Some((id, _)) = frontier.pop_min() {
+            let reached = cost[id.index()].expect("queued nodes have a cost");
+            if id == to {
+                return Ok(Some(Path {
+                    nodes: trace(&previous, to),
+                    cost: reached,
+                }));
+            }
+            for (next, &weight) in self.edges_from(id) {
+                let candidate = reached + weight;
+                if cost[next.index()].is_some_and(|known| known <= candidate) {
+                    continue;
+                }
+                cost[next.index()] = Some(candidate);
+                previous[next.index()] = Some(id);
+                // Requeues nodes that were already settled, which only
+                // happens when the heuristic is inconsistent
+                frontier.push(next, candidate + heuristic(next));
+            }
+        }
+        Ok(None)
+    }
+
+    fn check_weights(&self) -> Result<(), PathError> {
+        let zero = E::default();
+        for (from, _) in self.nodes() {
+            if let Some((to, _)) = self.edges_from(from).find(|(_, &weight)| weight < zero) {
+                return Err(PathError::NegativeWeight { from, to });
+            }
+        }
+        Ok(())
+    }
+}
+
+// Follows the `previous` links back from `to`, returning the path start first
+fn trace(previous: &[Option<NodeId>], to: NodeId) -> Vec<NodeId> {
+    let mut nodes = vec![to];
+    let mut current = to;
+    while let Some(prior) = previous[current.index()] {
+        nodes.push(prior);
+        current = prior;
+    }
+    nodes.reverse();
+    nodes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, XorShift64};
+
+    // Checks that `path` follows real edges from `from` to `to` and costs
+    // what it says
+    fn check_path<N>(graph: &Graph<N, u32>, path: &Path<u32>, from: NodeId, to: NodeId) {
+        assert_eq!(path.nodes.first(), Some(&from));
+        assert_eq!(path.nodes.last(), Some(&to));
+        let total: u32 = path
+            .nodes
+            .windows(2)
+            .map(|step| *graph.edge(step[0], step[1]).expect("path uses an edge"))
+            .sum();
+        assert_eq!(total, path.cost);
+    }
+
+    #[test]
+    fn the_cheapest_path_can_take_more_hops() {
+        let mut graph = Graph::directed();
+        let ids: Vec<_> = "sabct".chars().map(|name| graph.add_node(name)).collect();
+        let (s, a, b, c, t) = (ids[0], ids[1], ids[2], ids[3], ids[4]);
+        graph.add_edge(s, t, 10);
+        graph.add_edge(s, a, 1);
+        graph.add_edge(a, b, 2);
+        graph.add_edge(b, c, 3);
+        graph.add_edge(c, t, 1);
+        let path = graph.shortest_path(s, t).unwrap().unwrap();
+        assert_eq!(path.nodes, [s, a, b, c, t]);
+        assert_eq!(path.cost, 7);
+
+        graph.add_edge(s, t, 7);
+        let path = graph.shortest_path(s, t).unwrap().unwrap();
+        assert_eq!(path.cost, 7);
+        check_path(&graph, &path, s, t);
+
+        let here = graph.shortest_path(b, b).unwrap().unwrap();
+        assert_eq!(
+            here,
+            Path {
+                nodes: vec![b],
+                cost: 0
+            }
+        );
+    }
+
+    #[test]
+    fn unreachable_targets_have_no_path() {
+        let mut graph = Graph::directed();
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        let island = graph.add_node(());
+        graph.add_edge(a, b, 1u32);
+        assert_eq!(graph.shortest_path(a, island), Ok(None));
+        // Edges only run one way in a directed graph
+        assert_eq!(graph.shortest_path(b, a), Ok(None));
+        assert_eq!(graph.shortest_path_astar(a, island, |_| 0), Ok(None));
+    }
+
+    #[test]
+    fn negative_weights_are_rejected() {
+        let mut graph = Graph::undirected();
+        let a = graph.add_node(());
+        let b = graph.add_node(());
+        let c = graph.add_node(());
+        graph.add_edge(a, b, 2i32);
+        assert_eq!(graph.shortest_path(a, b).unwrap().unwrap().cost, 2);
+        graph.add_edge(b, c, -1);
+        // Rejected even when the negative edge is off the searched route
+        let error = PathError::NegativeWeight { from: b, to: c };
+        assert_eq!(graph.shortest_path(a, a), Err(error));
+        assert_eq!(graph.shortest_path_astar(a, b, |_| 0), Err(error));
+        assert_eq!(
+            error.to_string(),
+            "edge from node 1 to node 2 has a negative weight"
+        );
+    }
+
+    // A grid of random weights where stepping costs at least 1, so the
+    // Manhattan distance never overestimates
+    fn grid(rng: &mut XorShift64, size: usize) -> (Graph<(usize, usize), u32>, Vec<NodeId>) {
+        let mut graph = Graph::undirected();
+        let ids: Vec<_> = (0..size * size)
+            .map(|i| graph.add_node((i % size, i / size)))
+            .collect();
+        for y in 0..size {
+            for x in 0..size {
+                let here = ids[y * size + x];
+                if x + 1 < size && rng.below(5) > 0 {
+                    graph.add_edge(here, ids[y * size + x + 1], 1 + rng.below(9) as u32);
+                }
+                if y + 1 < size && rng.below(5) > 0 {
+                    graph.add_edge(here, ids[(y + 1) * size + x], 1 + rng.below(9) as u32);
+                }
+            }
+        }
+        (graph, ids)
+    }
+
+    // Cheapest costs from `from` by relaxing every edge until nothing improves
+    fn relaxed_costs<N>(graph: &Graph<N, u32>, from: NodeId) -> Vec<Option<u32>> {
+        let mut cost = vec![None; graph.node_count()];
+        cost[from.index()] = Some(0);
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for (id, _) in graph.nodes() {
+                let reached = match cost[id.index()] {
+                    Some(reached) => reached,
+                    None => continue,
+                };
+                for (next, &weight) in graph.edges_from(id) {
+                    if cost[next.index()].is_none_or(|known| reached + weight < known) {
+                        cost[next.index()] = Some(reached + weight);
+                        changed = true;
+                    }
+                }
+            }
+        }
+        cost
+    }
+
+    #[test]
+    fn dijkstra_and_astar_find_the_cheapest_costs() {
+        for seed in 1..=10 {
+            let mut rng = XorShift64::new(seed);
+            let (graph, ids) = grid(&mut rng, 8);
+            let from = ids[rng.below(ids.len() as u64) as usize];
+            let expected = relaxed_costs(&graph, from);
+            for &to in &ids {
+                let target = *graph.node(to).unwrap();
+                let manhattan = |id: NodeId| {
+                    let (x, y) = *graph.node(id).unwrap();
+                    (x.max(target.0) - x.min(target.0) + y.max(target.1) - y.min(target.1)) as u32
+                };
+                let dijkstra = graph.shortest_path(from, to).unwrap();
+                let astar = graph.shortest_path_astar(from, to, manhattan).unwrap();
+                let zero = graph.shortest_path_astar(from, to, |_| 0).unwrap();
+                assert_eq!(
+                    dijkstra.as_ref().map(|path| path.cost),
+                    expected[to.index()]
+                );
+                assert_eq!(astar.as_ref().map(|path| path.cost), expected[to.index()]);
+                // A zero heuristic is Dijkstra's search, step for step
+                assert_eq!(zero, dijkstra);
+                for path in dijkstra.iter().chain(astar.iter()) {
+                    check_path(&graph, path, from, to);
+                }
+            }
+        }
+    }
+}
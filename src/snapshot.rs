@@ -0,0 +1,455 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Saving a `HashMap` to disk in a compact binary form and loading it back
+//!
+//! A snapshot file is laid out as:
+//!
+//! - the magic bytes `RCMAP`, then a little-endian `u16` format version
+//! - the entry count as a `u64`
+//! - each entry as its key then its value, each prefixed with its length in
+//!   bytes as a `u32`
+//! - a CRC-32 of everything before it, as a `u32`
+//!
+//! The checksum catches truncated or damaged files, which `load_map` reports
+//! as an error rather than returning a partial map. `save_map` writes to a
+//! temporary file, flushes it to disk and renames it into place, then
+//! flushes the directory holding it, so a crash or power cut part-way
+//! through leaves either the previous snapshot or the new one. Renaming
+//! isn't made durable on platforms other than Unix, where a directory can't
+//! be flushed.
+//!
+//! Keys and values are converted with the `Encode` and `Decode` traits, which
+//! are implemented for the integer types, floats, `bool`, `char`, `String`,
+//! `Vec` and pairs.
+
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::hash::{BuildHasher, Hash};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+const MAGIC: &[u8; 5] = b"RCMAP";
+
+/// The format version written by `save_map`
+pub const FORMAT_VERSION: u16 = 1;
+
+/// Why a snapshot couldn't be written or read
+#[derive(Debug)]
+pub enum SnapshotError {
+    Io(io::Error),
+    /// The file doesn't start with the snapshot magic bytes
+    BadMagic,
+    /// The file was written by a format version this code can't read
+    UnsupportedVersion(u16),
+    /// The file's contents don't match its checksum
+    ChecksumMismatch,
+    /// The file passed its checksum but a key or value didn't decode
+    Malformed,
+    /// A key or value encoded to more bytes than its `u32` length prefix
+    /// can count
+    TooLarge,
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::Io(err) => write!(f, "snapshot I/O failed: {}", err),
+            SnapshotError::BadMagic => write!(f, "file is not a map snapshot"),
+            SnapshotError::UnsupportedVersion(version) => {
+                write!(f, "unsupported snapshot version {}", version)
+            }
+            SnapshotError::ChecksumMismatch => write!(f, "snapshot checksum does not match"),
+            SnapshotError::Malformed => write!(f, "snapshot contains a malformed entry"),
+            SnapshotError::TooLarge => write!(f, "map entry is too large for a snapshot"),
+        }
+    }
+}
+
+impl Error for SnapshotError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            SnapshotError::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for SnapshotError {
+    fn from(err: io::Error) -> Self {
+        SnapshotError::Io(err)
+    }
+}
+
+/// Types that can be written into a snapshot
+pub trait Encode {
+    fn encode(&self, out: &mut Vec<u8>);
+}
+
+/// Types that can be read back out of a snapshot
+pub trait Decode: Sized {
+    /// Reads a value from the front of `input`, advancing past it
+    fn decode(input: &mut &[u8]) -> Result<Self, SnapshotError>;
+}
+
+/// Writes `map` to `path`, replacing any existing snapshot there
+pub fn save_map<K, V, S, P>(map: &HashMap<K, V, S>, path: P) -> Result<(), SnapshotError>
+where
+    K: Encode,
+    V: Encode,
+    P: AsRef<Path>,
+{
+    let mut bytes = Vec::new();
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bytes.extend_from_slice(&(map.len() as u64).to_le_bytes());
+    for (key, value) in map {
+        encode_prefixed(key, &mut bytes)?;
+        encode_prefixed(value, &mut bytes)?;
+    }
+    let checksum = crc32(&bytes);
+    bytes.extend_from_slice(&checksum.to_le_bytes());
+
+    let path = path.as_ref();
+    let temporary = temporary_path(path);
+    let written = File::create(&temporary).and_then(|mut file| {
+        file.write_all(&bytes)?;
+        file.sync_all()
+    });
+    if let Err(err) = written.and_then(|()| fs::rename(&temporary, path)) {
+        let _ = fs::remove_file(&temporary);
+        return Err(err.into());
+    }
+    sync_directory(path)?;
+    Ok(())
+}
+
+/// Reads a map written by `save_map`
+pub fn load_map<K, V, S, P>(path: P) -> Result<HashMap<K, V, S>, SnapshotError>
+where
+    K: Decode + Eq + Hash,
+    V: Decode,
+    S: BuildHasher + Default,
+    P: AsRef<Path>,
+{
+    let bytes = fs::read(path)?;
+    if bytes.len() < MAGIC.len() || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    if bytes.len() < MAGIC.len() + 2 + 8 + 4 {
+        return Err(SnapshotError::ChecksumMismatch);
+    }
+
+    let (body, checksum) = bytes.split_at(bytes.len() - 4);
+    if crc32(body) != u32::decode(&mut &checksum[..])? {
+        return Err(SnapshotError::ChecksumMismatch);
+    }
+
+    let mut input = &body[MAGIC.len()..];
+    let version = u16::decode(&mut input)?;
+    if version != FORMAT_VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+
+    let count = u64::decode(&mut input)?;
+    let mut map = HashMap::with_hasher(S::default());
+    for _ in 0..count {
+        let key = decode_prefixed(&mut input)?;
+        let value = decode_prefixed(&mut input)?;
+        map.insert(key, value);
+    }
+    if !input.is_empty() {
+        return Err(SnapshotError::Malformed);
+    }
+    Ok(map)
+}
+
+fn temporary_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+// Flushes the directory entry that a rename into `path` changed
+#[cfg(unix)]
+fn sync_directory(path: &Path) -> io::Result<()> {
+    let directory = match path.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    File::open(directory)?.sync_all()
+}
+
+#[cfg(not(unix))]
+fn sync_directory(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
+
+fn encode_prefixed<T: Encode>(item: &T, out: &mut Vec<u8>) -> Result<(), SnapshotError> {
+    let start = out.len();
+    out.extend_from_slice(&[0; 4]);
+    item.encode(out);
+    let len = length_prefix(out.len() - start - 4)?;
+    out[start..start + 4].copy_from_slice(&len.to_le_bytes());
+    Ok(())
+}
+
+fn length_prefix(len: usize) -> Result<u32, SnapshotError> {
+    u32::try_from(len).map_err(|_| SnapshotError::TooLarge)
+}
+
+// The length prefix fences each item off, so an item that reads too little
+// or too much is caught here rather than corrupting the entries after it
+fn decode_prefixed<T: Decode>(input: &mut &[u8]) -> Result<T, SnapshotError> {
+    let len = u32::decode(input)? as usize;
+    let mut item = take(input, len)?;
+    let value = T::decode(&mut item)?;
+    if item.is_empty() {
+        Ok(value)
+    } else {
+        Err(SnapshotError::Malformed)
+    }
+}
+
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8], SnapshotError> {
+    if input.len() < len {
+        return Err(SnapshotError::Malformed);
+    }
+    let (front, rest) = input.split_at(len);
+    *input = rest;
+    Ok(front)
+}
+
+// CRC-32 as used by zip and PNG (reflected polynomial 0xEDB88320)
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut crc = n as u32;
+        let mut bit = 0;
+        while bit < 8 {
+            crc = if crc & 1 != 0 {
+                0xEDB8_8320 ^ (crc >> 1)
+            } else {
+                crc >> 1
+            };
+            bit += 1;
+        }
+        table[n] = crc;
+        n += 1;
+    }
+    table
+}
+
+fn crc32(bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!0, |crc, &byte| {
+        CRC_TABLE[((crc ^ u32::from(byte)) & 0xFF) as usize] ^ (crc >> 8)
+    })
+}
+
+macro_rules! impl_codec_for_numbers {
+    ($($ty:ty),*) => {
+        $(
+            impl Encode for $ty {
+                fn encode(&self, out: &mut Vec<u8>) {
+                    out.extend_from_slice(&self.to_le_bytes());
+                }
+            }
+
+            impl Decode for $ty {
+                fn decode(input: &mut &[u8]) -> Result<Self, SnapshotError> {
+                    let mut bytes = [0; std::mem::size_of::<$ty>()];
+                    bytes.copy_from_slice(take(input, std::mem::size_of::<$ty>())?);
+                    Ok(<$ty>::from_le_bytes(bytes))
+                }
+            }
+        )*
+    };
+}
+
+impl_codec_for_numbers!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, f32, f64);
+
+// Sizes are always stored as 64 bits, so snapshots move between platforms
+impl Encode for usize {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as u64).encode(out);
+    }
+}
+
+impl Decode for usize {
+    fn decode(input: &mut &[u8]) -> Result<Self, SnapshotError> {
+        let value = u64::decode(input)?;
+        if value > usize::MAX as u64 {
+            return Err(SnapshotError::Malformed);
+        }
+        Ok(value as usize)
+    }
+}
+
+impl Encode for isize {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (*self as i64).encode(out);
+    }
+}
+
+impl Decode for isize {
+    fn decode(input: &mut &[u8]) -> Result<Self, SnapshotError> {
+        let value = i64::decode(input)?;
+        if value < isize::MIN as i64 || value > isize::MAX as i64 {
+            return Err(SnapshotError::Malformed);
+        }
+        Ok(value as isize)
+    }
+}
+
+impl Encode for bool {
+    fn encode(&self, out: &mut Vec<u8>) {
+        out.push(*self as u8);
+    }
+}
+
+impl Decode for bool {
+    fn decode(input: &mut &[u8]) -> Result<Self, SnapshotError> {
+        match u8::decode(input)? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(SnapshotError::Malformed),
+        }
+    }
+}
+
+impl Encode for char {
+    fn encode(&self, out: &mut Vec<u8>) {
+        u32::from(*self).encode(out);
+    }
+}
+
+impl Decode for char {
+    fn decode(input: &mut &[u8]) -> Result<Self, SnapshotError> {
+        std::char::from_u32(u32::decode(input)?).ok_or(SnapshotError::Malformed)
+    }
+}
+
+impl Encode for String {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.len() as u64).encode(out);
+        out.extend_from_slice(self.as_bytes());
+    }
+}
+
+impl Decode for String {
+    fn decode(input: &mut &[u8]) -> Result<Self, SnapshotError> {
+        let len = usize::decode(input)?;
+        let bytes = take(input, len)?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| SnapshotError::Malformed)
+    }
+}
+
+impl<T: Encode> Encode for Vec<T> {
+    fn encode(&self, out: &mut Vec<u8>) {
+        (self.len() as u64).encode(out);
+        for item in self {
+            item.encode(out);
+        }
+    }
+}
+
+impl<T: Decode> Decode for Vec<T> {
+    fn decode(input: &mut &[u8]) -> Result<Self, SnapshotError> {
+        let len = usize::decode(input)?;
+        // Every item takes at least a byte, which bounds a damaged length
+        // before it turns into a huge allocation
+        let mut items = Vec::with_capacity(len.min(input.len()));
+        for _ in 0..len {
+            items.push(T::decode(input)?);
+        }
+        Ok(items)
+    }
+}
+
+impl<A: Encode, B: Encode> Encode for (A, B) {
+    fn encode(&self, out: &mut Vec<u8>) {
+        self.0.encode(out);
+        self.1.encode(out);
+    }
+}
+
+impl<A: Decode, B: Decode> Decode for (A, B) {
+    fn decode(input: &mut &[u8]) -> Result<Self, SnapshotError> {
+        Ok((A::decode(input)?, B::decode(input)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::env;
+
+    // A path in the temporary directory no other test uses
+    fn scratch(name: &str) -> PathBuf {
+        env::temp_dir().join(format!("snapshot-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn round_trips_and_leaves_no_temporary_file() {
+        let path = scratch("round-trip");
+        let mut map: HashMap<String, (u32, Vec<char>)> = HashMap::new();
+        map.insert("first".to_string(), (1, vec!['a', 'é']));
+        map.insert(String::new(), (u32::MAX, Vec::new()));
+        save_map(&map, &path).unwrap();
+        assert!(!temporary_path(&path).exists());
+        let loaded: HashMap<String, (u32, Vec<char>)> = load_map(&path).unwrap();
+        assert_eq!(loaded, map);
+
+        // Saving again replaces the old snapshot
+        map.remove("first");
+        save_map(&map, &path).unwrap();
+        let loaded: HashMap<String, (u32, Vec<char>)> = load_map(&path).unwrap();
+        assert_eq!(loaded, map);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn damaged_files_are_rejected() {
+        let path = scratch("damaged");
+        let map: HashMap<u64, String> = (0..10).map(|n| (n, n.to_string())).collect();
+        save_map(&map, &path).unwrap();
+        let mut bytes = fs::read(&path).unwrap();
+
+        let last = bytes.len() - 5;
+        bytes[last] ^= 1;
+        fs::write(&path, &bytes).unwrap();
+        let loaded: Result<HashMap<u64, String>, _> = load_map(&path);
+        assert!(matches!(loaded, Err(SnapshotError::ChecksumMismatch)));
+
+        fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+        let loaded: Result<HashMap<u64, String>, _> = load_map(&path);
+        assert!(matches!(loaded, Err(SnapshotError::ChecksumMismatch)));
+
+        fs::write(&path, b"not a snapshot").unwrap();
+        let loaded: Result<HashMap<u64, String>, _> = load_map(&path);
+        assert!(matches!(loaded, Err(SnapshotError::BadMagic)));
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn a_failed_save_cleans_up() {
+        let path = scratch("missing-directory").join("map");
+        let map: HashMap<u8, u8> = HashMap::new();
+        assert!(matches!(save_map(&map, &path), Err(SnapshotError::Io(_))));
+        assert!(!temporary_path(&path).exists());
+    }
+
+    #[test]
+    fn lengths_past_u32_are_too_large() {
+        assert_eq!(length_prefix(u32::MAX as usize).ok(), Some(u32::MAX));
+        if let Some(len) = (u32::MAX as usize).checked_add(1) {
+            assert!(matches!(length_prefix(len), Err(SnapshotError::TooLarge)));
+        }
+    }
+}
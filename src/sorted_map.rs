@@ -0,0 +1,107 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Helpers for ordered maps built on `BTreeMap`
+//!
+//! A `BTreeMap` keeps its keys sorted, which makes range queries, "the first
+//! few" and "the closest key to" questions cheap. These helpers give those
+//! questions names so callers don't have to rebuild them from `range` calls.
+
+use crate::merge::{MergeConflict, MergeStrategy};
+use std::collections::BTreeMap;
+use std::ops::{Bound, RangeBounds};
+
+/// Which side of a key `nearest_key` should look on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Nearest {
+    /// The largest key less than or equal to the target
+    AtOrBelow,
+    /// The smallest key greater than or equal to the target
+    AtOrAbove,
+}
+
+/// Iterates over the values whose keys fall within `range`, in key order
+pub fn range_values<'a, K, V, R>(
+    map: &'a BTreeMap<K, V>,
+    range: R,
+) -> impl DoubleEndedIterator<Item = &'a V> + 'a
+where
+    K: Ord,
+    R: RangeBounds<K>,
+{
+    map.range(range).map(|(_, value)| value)
+}
+
+/// Returns the `n` entries with the smallest keys, smallest first
+pub fn first_n<K: Ord, V>(map: &BTreeMap<K, V>, n: usize) -> Vec<(&K, &V)> {
+    map.iter().take(n).collect()
+}
+
+/// Returns the `n` entries with the largest keys, still in ascending order
+pub fn last_n<K: Ord, V>(map: &BTreeMap<K, V>, n: usize) -> Vec<(&K, &V)> {
+    let mut entries: Vec<(&K, &V)> = map.iter().rev().take(n).collect();
+    entries.reverse();
+    entries
+}
+
+/// Returns the key closest to `key` on the chosen side, which is `key`
+/// itself if it is present
+pub fn nearest_key<'a, K: Ord, V>(
+    map: &'a BTreeMap<K, V>,
+    key: &K,
+    side: Nearest,
+) -> Option<&'a K> {
+    let found = match side {
+        Nearest::AtOrBelow => map
+            .range((Bound::Unbounded, Bound::Included(key)))
+            .next_back(),
+        Nearest::AtOrAbove => map.range((Bound::Included(key), Bound::Unbounded)).next(),
+    };
+    found.map(|(key, _)| key)
+}
+
+/// Splits `map` in two: keys below `key` on the left, the rest on the right
+pub fn split_at<K: Ord, V>(mut map: BTreeMap<K, V>, key: &K) -> (BTreeMap<K, V>, BTreeMap<K, V>) {
+    let right = map.split_off(key);
+    (map, right)
+}
+
+/// Merges two sorted maps, resolving shared keys with `strategy` just like
+/// `merge::merge` does for `HashMap`s
+pub fn merge_sorted<K: Ord, V>(
+    mut left: BTreeMap<K, V>,
+    right: BTreeMap<K, V>,
+    strategy: MergeStrategy<V>,
+) -> Result<BTreeMap<K, V>, MergeConflict<K>> {
+    match strategy {
+        MergeStrategy::KeepLeft => {
+            for (key, value) in right {
+                left.entry(key).or_insert(value);
+            }
+        }
+        MergeStrategy::KeepRight => {
+            let mut right = right;
+            left.append(&mut right);
+        }
+        MergeStrategy::Combine(combine) => {
+            for (key, value) in right {
+                let merged = match left.remove(&key) {
+                    Some(existing) => combine(existing, value),
+                    None => value,
+                };
+                left.insert(key, merged);
+            }
+        }
+        MergeStrategy::Error => {
+            if let Some(index) = right.keys().position(|key| left.contains_key(key)) {
+                let key = right.into_keys().nth(index);
+                return Err(MergeConflict {
+                    key: key.expect("the conflicting key came from `right`"),
+                });
+            }
+            let mut right = right;
+            left.append(&mut right);
+        }
+    }
+    Ok(left)
+}
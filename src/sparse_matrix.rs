@@ -0,0 +1,216 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Matrices that store only their non-zero entries
+//!
+//! A matrix is assembled as a `CooMatrix`, a map from `(row, column)` to
+//! value that accepts entries in any order, and then frozen with `to_csr`
+//! into a `SparseMatrix` in compressed sparse row form: the entries sorted by
+//! row and column in two flat arrays, plus the offset where each row starts.
+//! That layout makes row iteration and matrix-vector products a straight
+//! scan, and keeps memory proportional to the number of entries rather than
+//! `rows * cols`.
+
+use std::collections::HashMap;
+use std::ops::{Add, Mul};
+
+/// A matrix under construction, keyed by coordinate
+#[derive(Debug, Clone)]
+pub struct CooMatrix<T> {
+    rows: usize,
+    cols: usize,
+    entries: HashMap<(usize, usize), T>,
+}
+
+impl<T> CooMatrix<T> {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        CooMatrix {
+            rows,
+            cols,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Number of stored entries
+    pub fn nnz(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Sets the entry at `(row, col)`, returning the value it replaced
+    ///
+    /// Panics if the coordinate is outside the matrix.
+    pub fn insert(&mut self, row: usize, col: usize, value: T) -> Option<T> {
+        self.check(row, col);
+        self.entries.insert((row, col), value)
+    }
+
+    /// Adds `value` to the entry at `(row, col)`, so repeated coordinates
+    /// accumulate as they do in most COO formats
+    ///
+    /// Panics if the coordinate is outside the matrix.
+    pub fn add(&mut self, row: usize, col: usize, value: T)
+    where
+        T: Copy + Add<Output = T>,
+    {
+        self.check(row, col);
+        self.entries
+            .entry((row, col))
+            .and_modify(|existing| *existing = *existing + value)
+            .or_insert(value);
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        self.entries.get(&(row, col))
+    }
+
+    /// Converts into compressed sparse row form
+    pub fn to_csr(self) -> SparseMatrix<T> {
+        let mut entries: Vec<((usize, usize), T)> = self.entries.into_iter().collect();
+        entries.sort_unstable_by_key(|&(coordinate, _)| coordinate);
+        SparseMatrix::from_sorted(self.rows, self.cols, entries)
+    }
+
+    fn check(&self, row: usize, col: usize) {
+        assert!(
+            row < self.rows && col < self.cols,
+            "({}, {}) is outside a {}x{} matrix",
+            row,
+            col,
+            self.rows,
+            self.cols
+        );
+    }
+}
+
+/// An immutable matrix in compressed sparse row form
+#[derive(Debug, Clone, PartialEq)]
+pub struct SparseMatrix<T> {
+    rows: usize,
+    cols: usize,
+    // Entries of row `r` live at `row_starts[r]..row_starts[r + 1]`
+    row_starts: Vec<usize>,
+    col_indices: Vec<usize>,
+    values: Vec<T>,
+}
+
+impl<T> SparseMatrix<T> {
+    /// Builds a matrix from `(row, col, value)` triplets, with later
+    /// triplets replacing earlier ones at the same coordinate
+    ///
+    /// Panics if a coordinate is outside the matrix.
+    pub fn from_triplets<I>(rows: usize, cols: usize, triplets: I) -> Self
+    where
+        I: IntoIterator<Item = (usize, usize, T)>,
+    {
+        let mut coo = CooMatrix::new(rows, cols);
+        for (row, col, value) in triplets {
+            coo.insert(row, col, value);
+        }
+        coo.to_csr()
+    }
+
+    // `entries` must be sorted by coordinate without duplicates
+    fn from_sorted(rows: usize, cols: usize, entries: Vec<((usize, usize), T)>) -> Self {
+        let mut row_starts = vec![0; rows + 1];
+        let mut col_indices = Vec::with_capacity(entries.len());
+        let mut values = Vec::with_capacity(entries.len());
+        for ((row, col), value) in entries {
+            row_starts[row + 1] += 1;
+            col_indices.push(col);
+            values.push(value);
+        }
+        for row in 0..rows {
+            row_starts[row + 1] += row_starts[row];
+        }
+        SparseMatrix {
+            rows,
+            cols,
+            row_starts,
+            col_indices,
+            values,
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    /// Number of stored entries
+    pub fn nnz(&self) -> usize {
+        self.values.len()
+    }
+
+    /// Returns the stored entry at `(row, col)`, or `None` where the matrix
+    /// is implicitly zero or the coordinate is out of range
+    pub fn get(&self, row: usize, col: usize) -> Option<&T> {
+        if row >= self.rows {
+            return None;
+        }
+        let span = self.row_starts[row]..self.row_starts[row + 1];
+        let offset = self.col_indices[span.clone()].binary_search(&col).ok()?;
+        Some(&self.values[span.start + offset])
+    }
+
+    /// Iterates over the stored entries of `row` as `(col, value)`, in
+    /// column order
+    ///
+    /// Panics if `row` is out of range.
+    pub fn row(&self, row: usize) -> impl Iterator<Item = (usize, &T)> {
+        let span = self.row_starts[row]..self.row_starts[row + 1];
+        self.col_indices[span.clone()]
+            .iter()
+            .copied()
+            .zip(&self.values[span])
+    }
+
+    /// Iterates over every stored entry as `(row, col, value)` in row-major
+    /// order
+    pub fn iter(&self) -> impl Iterator<Item = (usize, usize, &T)> {
+        (0..self.rows).flat_map(move |row| self.row(row).map(move |(col, value)| (row, col, value)))
+    }
+
+    /// Returns the transpose, with rows and columns swapped
+    pub fn transpose(&self) -> SparseMatrix<T>
+    where
+        T: Clone,
+    {
+        let mut entries: Vec<((usize, usize), T)> = self
+            .iter()
+            .map(|(row, col, value)| ((col, row), value.clone()))
+            .collect();
+        entries.sort_unstable_by_key(|&(coordinate, _)| coordinate);
+        SparseMatrix::from_sorted(self.cols, self.rows, entries)
+    }
+
+    /// Multiplies the matrix by the dense column vector `vector`
+    ///
+    /// Panics unless `vector` has one element per column.
+    pub fn mul_vec(&self, vector: &[T]) -> Vec<T>
+    where
+        T: Copy + Default + Add<Output = T> + Mul<Output = T>,
+    {
+        assert_eq!(
+            vector.len(),
+            self.cols,
+            "vector length must match the matrix's column count"
+        );
+        (0..self.rows)
+            .map(|row| {
+                self.row(row)
+                    .fold(T::default(), |sum, (col, &value)| sum + value * vector[col])
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,1039 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! The spreadsheet cell type from the `Vec` walkthrough in
+//! `src/bin/collections/vectors.rs`
+//!
+//! A `Vec` can only hold one type, but an `enum` can wrap several, so a row of
+//! cells holding numbers and text fits in a single `Vec<SpreadsheetCell>`.
+//!
+//! `Spreadsheet` holds rows of cells, which may differ in length, and hands
+//! them out as slices so callers never touch its storage directly.
+//!
+//! `Spreadsheet::snapshot` takes a `SheetSnapshot`, a read-only view of the
+//! sheet as it is at that moment that doesn't change as the sheet does. Rows
+//! are kept behind `Arc`s and copied on write, so a snapshot costs a
+//! reference count and each row is copied only when it's first changed
+//! while a snapshot still shares it.
+//!
+//! `parse_cells` turns delimited text into rows of cells one line at a time,
+//! so files too large to hold in memory can be processed as a stream.
+//! `parse_csv_borrowed` parses text already in memory without copying it:
+//! its text cells borrow from the input until converted with `into_owned`.
+
+use crate::aggregates::{ColumnAggregates, RunningAggregates};
+use crate::deep_size::DeepSize;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+use std::iter::FromIterator;
+use std::mem;
+use std::slice;
+use std::sync::Arc;
+
+/// A single cell that holds an integer, a float or text, or nothing at all
+#[derive(Debug, Clone, PartialEq)]
+pub enum SpreadsheetCell {
+    Int(i32),
+    Float(f64),
+    Text(String),
+    /// A cell with no value, such as padding added by `from_rows_padded`
+    Empty,
+}
+
+impl SpreadsheetCell {
+    /// Picks the narrowest type that `raw` parses as: an integer, then a
+    /// float, otherwise text
+    ///
+    /// Surrounding whitespace is ignored when parsing numbers. Words such as
+    /// `inf` or `NaN` stay text, since they're far more often labels than
+    /// numbers.
+    pub fn infer(raw: &str) -> SpreadsheetCell {
+        SpreadsheetCell::infer_number(raw).unwrap_or_else(|| SpreadsheetCell::Text(raw.to_string()))
+    }
+
+    // The `Int` or `Float` that `infer` would pick, if `raw` is a number
+    pub(crate) fn infer_number(raw: &str) -> Option<SpreadsheetCell> {
+        let trimmed = raw.trim();
+        if let Ok(int) = trimmed.parse() {
+            return Some(SpreadsheetCell::Int(int));
+        }
+        if trimmed.bytes().any(|byte| byte.is_ascii_digit()) {
+            if let Ok(float) = trimmed.parse() {
+                return Some(SpreadsheetCell::Float(float));
+            }
+        }
+        None
+    }
+
+    /// A total order for sorting: numbers first, compared by value whether
+    /// `Int` or `Float`, then text in byte order, then empty cells
+    ///
+    /// Floats are ordered with `f64::total_cmp`, so NaN sorts after every
+    /// other number.
+    pub fn total_cmp(&self, other: &SpreadsheetCell) -> Ordering {
+        use SpreadsheetCell::*;
+        match (self, other) {
+            (Int(a), Int(b)) => a.cmp(b),
+            (Int(a), Float(b)) => f64::from(*a).total_cmp(b),
+            (Float(a), Int(b)) => a.total_cmp(&f64::from(*b)),
+            (Float(a), Float(b)) => a.total_cmp(b),
+            (Text(a), Text(b)) => a.cmp(b),
+            _ => self.sort_rank().cmp(&other.sort_rank()),
+        }
+    }
+
+    fn sort_rank(&self) -> u8 {
+        match self {
+            SpreadsheetCell::Int(_) | SpreadsheetCell::Float(_) => 0,
+            SpreadsheetCell::Text(_) => 1,
+            SpreadsheetCell::Empty => 2,
+        }
+    }
+}
+
+impl fmt::Display for SpreadsheetCell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SpreadsheetCell::Int(int) => write!(f, "{}", int),
+            SpreadsheetCell::Float(float) => write!(f, "{}", float),
+            SpreadsheetCell::Text(text) => write!(f, "{}", text),
+            SpreadsheetCell::Empty => Ok(()),
+        }
+    }
+}
+
+impl DeepSize for SpreadsheetCell {
+    fn heap_bytes(&self) -> usize {
+        match self {
+            SpreadsheetCell::Text(text) => text.heap_bytes(),
+            _ => 0,
+        }
+    }
+}
+
+/// Rows of cells
+///
+/// Sheets built with `from_rows` or `from_columns` are rectangular. Pushing
+/// or collecting rows doesn't check their lengths, so those sheets may be
+/// ragged.
+///
+/// Columns registered with `track_column` have their aggregates kept up to
+/// date on every change, so `column_aggregates` doesn't have to scan them.
+#[derive(Debug, Clone, Default)]
+pub struct Spreadsheet {
+    rows: SharedRows,
+    // Column -> running aggregates, for the tracked columns
+    tracked: BTreeMap<usize, RunningAggregates>,
+}
+
+impl Spreadsheet {
+    pub fn new() -> Self {
+        Spreadsheet::with_rows(Vec::new())
+    }
+
+    fn with_rows(rows: Vec<Vec<SpreadsheetCell>>) -> Self {
+        Spreadsheet::with_shared_rows(Arc::new(rows.into_iter().map(Arc::new).collect()))
+    }
+
+    fn with_shared_rows(rows: SharedRows) -> Self {
+        Spreadsheet {
+            rows,
+            tracked: BTreeMap::new(),
+        }
+    }
+
+    /// Builds a sheet from rows that must all have the same length
+    pub fn from_rows(rows: Vec<Vec<SpreadsheetCell>>) -> Result<Self, ShapeError> {
+        check_shape(&rows, Axis::Row)?;
+        Ok(Spreadsheet::with_rows(rows))
+    }
+
+    /// Builds a sheet from rows of any length, padding the shorter ones with
+    /// `Empty` cells to the length of the longest
+    pub fn from_rows_padded(mut rows: Vec<Vec<SpreadsheetCell>>) -> Self {
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        for row in &mut rows {
+            row.resize(width, SpreadsheetCell::Empty);
+        }
+        Spreadsheet::with_rows(rows)
+    }
+
+    /// Builds a sheet from columns that must all have the same length
+    pub fn from_columns(columns: Vec<Vec<SpreadsheetCell>>) -> Result<Self, ShapeError> {
+        check_shape(&columns, Axis::Column)?;
+        let height = columns.first().map_or(0, Vec::len);
+        let mut rows: Vec<Vec<SpreadsheetCell>> = (0..height)
+            .map(|_| Vec::with_capacity(columns.len()))
+            .collect();
+        for column in columns {
+            for (row, cell) in rows.iter_mut().zip(column) {
+                row.push(cell);
+            }
+        }
+        Ok(Spreadsheet::with_rows(rows))
+    }
+
+    pub fn push_row(&mut self, row: Vec<SpreadsheetCell>) {
+        for (col, running) in &mut self.tracked {
+            if let Some(cell) = row.get(*col) {
+                running.add(cell);
+            }
+        }
+        Arc::make_mut(&mut self.rows).push(Arc::new(row));
+    }
+
+    /// Replaces the cell at `(row, col)`, returning the one it replaced
+    ///
+    /// A row too short to reach `col` is padded with `Empty` cells first.
+    /// Returns `None`, changing nothing, if `row` doesn't exist.
+    pub fn set(
+        &mut self,
+        row: usize,
+        col: usize,
+        cell: SpreadsheetCell,
+    ) -> Option<SpreadsheetCell> {
+        if row >= self.rows.len() {
+            return None;
+        }
+        let cells = Arc::make_mut(&mut Arc::make_mut(&mut self.rows)[row]);
+        if cells.len() <= col {
+            cells.resize(col + 1, SpreadsheetCell::Empty);
+        }
+        if let Some(running) = self.tracked.get_mut(&col) {
+            running.remove(&cells[col]);
+            running.add(&cell);
+        }
+        Some(mem::replace(&mut cells[col], cell))
+    }
+
+    /// Starts keeping running aggregates for column `col`
+    ///
+    /// Scans the column once; after that every `set`, `push_row` and
+    /// `extend` updates them. Tracking a column that is already tracked does
+    /// nothing.
+    pub fn track_column(&mut self, col: usize) {
+        if self.tracked.contains_key(&col) {
+            return;
+        }
+        let mut running = RunningAggregates::new();
+        for cell in self.rows.iter().filter_map(|row| row.get(col)) {
+            running.add(cell);
+        }
+        self.tracked.insert(col, running);
+    }
+
+    pub fn untrack_column(&mut self, col: usize) {
+        self.tracked.remove(&col);
+    }
+
+    pub fn is_tracked(&self, col: usize) -> bool {
+        self.tracked.contains_key(&col)
+    }
+
+    /// SUM, COUNT, MIN and MAX of the numbers in column `col`
+    ///
+    /// Free for a tracked column. Any other column is scanned.
+    pub fn column_aggregates(&self, col: usize) -> ColumnAggregates {
+        match self.tracked.get(&col) {
+            Some(running) => running.aggregates(),
+            None => ColumnAggregates::of(self.rows.iter().filter_map(|row| row.get(col))),
+        }
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Length of the longest row
+    pub fn column_count(&self) -> usize {
+        column_count(&self.rows)
+    }
+
+    pub fn row(&self, row: usize) -> Option<&[SpreadsheetCell]> {
+        self.rows.get(row).map(|cells| cells.as_slice())
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&SpreadsheetCell> {
+        self.rows.get(row)?.get(col)
+    }
+
+    /// Sorts the rows by their cells in column `col`, in the order of
+    /// `SpreadsheetCell::total_cmp`
+    ///
+    /// The sort is stable, and rows too short to reach `col` sort as if the
+    /// cell were empty. With the `parallel` feature, large sheets are sorted
+    /// on several threads.
+    pub fn sort_by_column(&mut self, col: usize) {
+        let compare = |a: &Arc<Vec<SpreadsheetCell>>, b: &Arc<Vec<SpreadsheetCell>>| {
+            let empty = SpreadsheetCell::Empty;
+            a.get(col)
+                .unwrap_or(&empty)
+                .total_cmp(b.get(col).unwrap_or(&empty))
+        };
+        #[cfg(feature = "parallel")]
+        crate::parallel::par_sort_by(Arc::make_mut(&mut self.rows), compare);
+        #[cfg(not(feature = "parallel"))]
+        Arc::make_mut(&mut self.rows).sort_by(compare);
+    }
+
+    /// Iterates over the rows as slices, top to bottom
+    pub fn rows(&self) -> Rows<'_> {
+        Rows {
+            rows: self.rows.iter(),
+        }
+    }
+
+    /// Iterates over every cell with its `(row, col)` position, row by row
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, &SpreadsheetCell)> {
+        cells(&self.rows)
+    }
+
+    /// A read-only view of the sheet as it is now, which later changes to
+    /// the sheet don't show up in
+    ///
+    /// Takes a reference count, not a copy. While the snapshot is alive, the
+    /// sheet's next change copies its list of rows, and the first change to
+    /// each row copies that row.
+    pub fn snapshot(&self) -> SheetSnapshot {
+        SheetSnapshot {
+            rows: Arc::clone(&self.rows),
+        }
+    }
+
+    /// `rows` spread over several threads for large sheets
+    #[cfg(feature = "parallel")]
+    pub fn par_rows(&self) -> crate::parallel::ParIter<Rows<'_>> {
+        crate::parallel::par_ranges(self.rows.len(), self.rows.len(), |range| Rows {
+            rows: self.rows[range].iter(),
+        })
+    }
+
+    /// `cells` spread over several threads for large sheets, a run of whole
+    /// rows to each
+    #[cfg(feature = "parallel")]
+    pub fn par_cells(
+        &self,
+    ) -> crate::parallel::ParIter<impl Iterator<Item = (usize, usize, &SpreadsheetCell)> + Send>
+    {
+        let cells = self.rows.iter().map(|cells| cells.len()).sum();
+        crate::parallel::par_ranges(self.rows.len(), cells, |range| {
+            let rows = &self.rows[range.clone()];
+            range.zip(rows).flat_map(|(row, cells)| {
+                cells
+                    .iter()
+                    .enumerate()
+                    .map(move |(col, cell)| (row, col, cell))
+            })
+        })
+    }
+}
+
+// Rows shared with a snapshot are counted in full by both
+impl DeepSize for Spreadsheet {
+    fn heap_bytes(&self) -> usize {
+        shared_rows_bytes(&self.rows)
+    }
+}
+
+// Each row is behind its own `Arc`, so a sheet and its snapshots can share
+// the rows they haven't changed
+type SharedRows = Arc<Vec<Arc<Vec<SpreadsheetCell>>>>;
+
+fn column_count(rows: &SharedRows) -> usize {
+    rows.iter().map(|cells| cells.len()).max().unwrap_or(0)
+}
+
+fn cells(rows: &SharedRows) -> impl Iterator<Item = (usize, usize, &SpreadsheetCell)> {
+    rows.iter().enumerate().flat_map(|(row, cells)| {
+        cells
+            .iter()
+            .enumerate()
+            .map(move |(col, cell)| (row, col, cell))
+    })
+}
+
+fn shared_rows_bytes(rows: &SharedRows) -> usize {
+    // An `Arc` allocation holds the two reference counts and the value
+    let arc_bytes = |value: usize| 2 * mem::size_of::<usize>() + value;
+    let row_bytes: usize = rows
+        .iter()
+        .map(|cells| arc_bytes(mem::size_of::<Vec<SpreadsheetCell>>()) + cells.heap_bytes())
+        .sum();
+    arc_bytes(mem::size_of::<Vec<Arc<Vec<SpreadsheetCell>>>>())
+        + rows.capacity() * mem::size_of::<Arc<Vec<SpreadsheetCell>>>()
+        + row_bytes
+}
+
+/// A point-in-time, read-only view of a `Spreadsheet`, from
+/// `Spreadsheet::snapshot`
+///
+/// Cloning a snapshot is as cheap as taking one, and snapshots can be sent
+/// to other threads to be read while the sheet carries on changing.
+#[derive(Debug, Clone)]
+pub struct SheetSnapshot {
+    rows: SharedRows,
+}
+
+impl SheetSnapshot {
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    /// Length of the longest row
+    pub fn column_count(&self) -> usize {
+        column_count(&self.rows)
+    }
+
+    pub fn row(&self, row: usize) -> Option<&[SpreadsheetCell]> {
+        self.rows.get(row).map(|cells| cells.as_slice())
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&SpreadsheetCell> {
+        self.rows.get(row)?.get(col)
+    }
+
+    /// Iterates over the rows as slices, top to bottom
+    pub fn rows(&self) -> Rows<'_> {
+        Rows {
+            rows: self.rows.iter(),
+        }
+    }
+
+    /// Iterates over every cell with its `(row, col)` position, row by row
+    pub fn cells(&self) -> impl Iterator<Item = (usize, usize, &SpreadsheetCell)> {
+        cells(&self.rows)
+    }
+
+    /// A sheet starting out with the snapshot's cells, sharing its rows
+    /// until they're changed
+    ///
+    /// No columns are tracked in the new sheet.
+    pub fn to_spreadsheet(&self) -> Spreadsheet {
+        Spreadsheet::with_shared_rows(Arc::clone(&self.rows))
+    }
+}
+
+impl PartialEq for SheetSnapshot {
+    fn eq(&self, other: &SheetSnapshot) -> bool {
+        self.rows == other.rows
+    }
+}
+
+impl DeepSize for SheetSnapshot {
+    fn heap_bytes(&self) -> usize {
+        shared_rows_bytes(&self.rows)
+    }
+}
+
+impl<'a> IntoIterator for &'a SheetSnapshot {
+    type Item = &'a [SpreadsheetCell];
+    type IntoIter = Rows<'a>;
+
+    fn into_iter(self) -> Rows<'a> {
+        self.rows()
+    }
+}
+
+/// Reading and writing cells, whichever way a sheet stores them
+///
+/// Implemented by the row-oriented `Spreadsheet` and the column-oriented
+/// `columnar::ColumnarSheet`, so code written against `SheetOps` works with
+/// either.
+pub trait SheetOps {
+    fn row_count(&self) -> usize;
+
+    /// Length of the longest row
+    fn column_count(&self) -> usize;
+
+    /// A copy of the cell at `(row, col)`, or `None` if there is no cell
+    /// there
+    fn cell(&self, row: usize, col: usize) -> Option<SpreadsheetCell>;
+
+    /// Replaces the cell at `(row, col)`, returning the one it replaced, or
+    /// `None`, changing nothing, if the sheet can't hold a cell there
+    fn set(&mut self, row: usize, col: usize, cell: SpreadsheetCell) -> Option<SpreadsheetCell>;
+
+    /// SUM, COUNT, MIN and MAX of the numbers in column `col`
+    fn column_aggregates(&self, col: usize) -> ColumnAggregates {
+        let cells: Vec<SpreadsheetCell> = (0..self.row_count())
+            .filter_map(|row| self.cell(row, col))
+            .collect();
+        ColumnAggregates::of(&cells)
+    }
+}
+
+impl SheetOps for Spreadsheet {
+    fn row_count(&self) -> usize {
+        Spreadsheet::row_count(self)
+    }
+
+    fn column_count(&self) -> usize {
+        Spreadsheet::column_count(self)
+    }
+
+    fn cell(&self, row: usize, col: usize) -> Option<SpreadsheetCell> {
+        self.get(row, col).cloned()
+    }
+
+    fn set(&mut self, row: usize, col: usize, cell: SpreadsheetCell) -> Option<SpreadsheetCell> {
+        Spreadsheet::set(self, row, col, cell)
+    }
+
+    fn column_aggregates(&self, col: usize) -> ColumnAggregates {
+        Spreadsheet::column_aggregates(self, col)
+    }
+}
+
+// Two sheets are equal when their cells are, whichever columns they track
+impl PartialEq for Spreadsheet {
+    fn eq(&self, other: &Spreadsheet) -> bool {
+        self.rows == other.rows
+    }
+}
+
+impl FromIterator<Vec<SpreadsheetCell>> for Spreadsheet {
+    fn from_iter<I: IntoIterator<Item = Vec<SpreadsheetCell>>>(iter: I) -> Self {
+        Spreadsheet::with_rows(iter.into_iter().collect())
+    }
+}
+
+impl Extend<Vec<SpreadsheetCell>> for Spreadsheet {
+    fn extend<I: IntoIterator<Item = Vec<SpreadsheetCell>>>(&mut self, iter: I) {
+        for row in iter {
+            self.push_row(row);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a Spreadsheet {
+    type Item = &'a [SpreadsheetCell];
+    type IntoIter = Rows<'a>;
+
+    fn into_iter(self) -> Rows<'a> {
+        self.rows()
+    }
+}
+
+/// Iterator over the rows of a `Spreadsheet` or `SheetSnapshot`
+#[derive(Debug, Clone)]
+pub struct Rows<'a> {
+    rows: slice::Iter<'a, Arc<Vec<SpreadsheetCell>>>,
+}
+
+impl<'a> Iterator for Rows<'a> {
+    type Item = &'a [SpreadsheetCell];
+
+    fn next(&mut self) -> Option<&'a [SpreadsheetCell]> {
+        self.rows.next().map(|cells| cells.as_slice())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.rows.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Rows<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.rows.next_back().map(|cells| cells.as_slice())
+    }
+}
+
+impl ExactSizeIterator for Rows<'_> {}
+
+/// Whether a `ShapeError` is about a row or a column
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Axis {
+    Row,
+    Column,
+}
+
+/// A row or column whose length differs from the first one's
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShapeError {
+    pub axis: Axis,
+    /// 0-based index of the first row or column of the wrong length
+    pub index: usize,
+    pub expected: usize,
+    pub found: usize,
+}
+
+impl fmt::Display for ShapeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let axis = match self.axis {
+            Axis::Row => "row",
+            Axis::Column => "column",
+        };
+        write!(
+            f,
+            "{} {} has {} cells, expected {}",
+            axis, self.index, self.found, self.expected
+        )
+    }
+}
+
+impl Error for ShapeError {}
+
+fn check_shape(lines: &[Vec<SpreadsheetCell>], axis: Axis) -> Result<(), ShapeError> {
+    let expected = match lines.first() {
+        Some(first) => first.len(),
+        None => return Ok(()),
+    };
+    match lines.iter().position(|line| line.len() != expected) {
+        Some(index) => Err(ShapeError {
+            axis,
+            index,
+            expected,
+            found: lines[index].len(),
+        }),
+        None => Ok(()),
+    }
+}
+
+/// What went wrong in a `RowParseError`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowErrorKind {
+    /// A quoted field was still open at the end of the line
+    UnterminatedQuote,
+    /// Something other than the delimiter followed a closing quote
+    TrailingCharacters,
+}
+
+/// A line that `parse_cells` couldn't split into cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RowParseError {
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based number of the field the problem is in
+    pub column: usize,
+    pub kind: RowErrorKind,
+}
+
+impl fmt::Display for RowParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let problem = match self.kind {
+            RowErrorKind::UnterminatedQuote => "unterminated quote",
+            RowErrorKind::TrailingCharacters => "unexpected characters after closing quote",
+        };
+        write!(f, "line {}, column {}: {}", self.line, self.column, problem)
+    }
+}
+
+impl Error for RowParseError {}
+
+/// Parses each line into a row of cells split on `delimiter`, inferring each
+/// cell's type with `SpreadsheetCell::infer`
+///
+/// A field wrapped in double quotes may contain the delimiter, writes a
+/// literal quote as `""`, and always becomes text, so `"42"` stays a string.
+/// Quoted fields can't span lines. An empty line gives an empty row.
+///
+/// The rows can be collected straight into a sheet:
+/// `parse_cells(text.lines(), ',').collect::<Result<Spreadsheet, _>>()`.
+pub fn parse_cells<'a, I>(lines: I, delimiter: char) -> ParseCells<I::IntoIter>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    ParseCells {
+        lines: lines.into_iter(),
+        delimiter,
+        line: 0,
+    }
+}
+
+/// Iterator returned by `parse_cells`
+#[derive(Debug, Clone)]
+pub struct ParseCells<I> {
+    lines: I,
+    delimiter: char,
+    // Number of the line most recently read
+    line: usize,
+}
+
+impl<'a, I: Iterator<Item = &'a str>> Iterator for ParseCells<I> {
+    type Item = Result<Vec<SpreadsheetCell>, RowParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let text = self.lines.next()?;
+        self.line += 1;
+        let text = text.strip_suffix('\r').unwrap_or(text);
+        Some(
+            parse_row(text, self.delimiter).map_err(|(column, kind)| RowParseError {
+                line: self.line,
+                column,
+                kind,
+            }),
+        )
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.lines.size_hint()
+    }
+}
+
+/// A cell from `parse_csv_borrowed`, whose text borrows from the input
+#[derive(Debug, Clone, PartialEq)]
+pub enum CellRefValue<'a> {
+    Int(i32),
+    Float(f64),
+    /// Borrowed from the input, unless the field had `""` escapes to
+    /// collapse
+    Text(Cow<'a, str>),
+}
+
+impl CellRefValue<'_> {
+    /// The text, if this is a text cell
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            CellRefValue::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    /// Copies the cell out into an owned `SpreadsheetCell`
+    pub fn into_owned(self) -> SpreadsheetCell {
+        match self {
+            CellRefValue::Int(int) => SpreadsheetCell::Int(int),
+            CellRefValue::Float(float) => SpreadsheetCell::Float(float),
+            CellRefValue::Text(text) => SpreadsheetCell::Text(text.into_owned()),
+        }
+    }
+}
+
+/// Parses comma-separated `text` like `parse_cells`, but without copying
+/// field text
+///
+/// Text cells are slices of `text`. Only quoted fields containing `""`
+/// escapes are allocated, since collapsing the escapes changes the text.
+/// Numbers are inferred as `SpreadsheetCell::infer` does, and the same
+/// quoting rules apply: quoted fields can't span lines.
+pub fn parse_csv_borrowed(text: &str) -> Result<Vec<Vec<CellRefValue<'_>>>, RowParseError> {
+    let mut scratch = String::new();
+    text.lines()
+        .enumerate()
+        .map(|(index, line)| {
+            let mut row = Vec::new();
+            split_row(line, ',', &mut scratch, |field| {
+                row.push(match field {
+                    Field::Plain(raw) => match SpreadsheetCell::infer_number(raw) {
+                        Some(SpreadsheetCell::Int(int)) => CellRefValue::Int(int),
+                        Some(SpreadsheetCell::Float(float)) => CellRefValue::Float(float),
+                        _ => CellRefValue::Text(Cow::Borrowed(raw)),
+                    },
+                    Field::Quoted(text) => CellRefValue::Text(Cow::Borrowed(text)),
+                    Field::Unescaped(text) => CellRefValue::Text(Cow::Owned(text.to_string())),
+                });
+            })
+            .map_err(|(column, kind)| RowParseError {
+                line: index + 1,
+                column,
+                kind,
+            })?;
+            Ok(row)
+        })
+        .collect()
+}
+
+/// Splits each line into its fields on `delimiter` with the quoting rules of
+/// `parse_cells`, but leaves them as text, copied into `bump`
+///
+/// Each row is a slice in the arena too, so a batch of lines costs the
+/// outer `Vec` and a few arena chunks rather than a `Vec` per row and a
+/// `String` per field.
+#[cfg(feature = "bump")]
+pub fn parse_fields_in<'a, 'b, I>(
+    lines: I,
+    delimiter: char,
+    bump: &'b crate::bump::Bump,
+) -> Result<Vec<&'b [&'b str]>, RowParseError>
+where
+    I: IntoIterator<Item = &'a str>,
+{
+    let mut scratch = String::new();
+    let mut fields = Vec::new();
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(index, line)| {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            fields.clear();
+            split_row(line, delimiter, &mut scratch, |field| {
+                fields.push(bump.alloc_str(match field {
+                    Field::Plain(text) | Field::Quoted(text) => text,
+                    Field::Unescaped(text) => text,
+                }));
+            })
+            .map_err(|(column, kind)| RowParseError {
+                line: index + 1,
+                column,
+                kind,
+            })?;
+            Ok(&*bump.alloc_slice_copy(&fields))
+        })
+        .collect()
+}
+
+// Fails with the 1-based field number and the problem
+fn parse_row(text: &str, delimiter: char) -> Result<Vec<SpreadsheetCell>, (usize, RowErrorKind)> {
+    let mut cells = Vec::new();
+    let mut scratch = String::new();
+    split_row(text, delimiter, &mut scratch, |field| {
+        cells.push(match field {
+            Field::Plain(raw) => SpreadsheetCell::infer(raw),
+            Field::Quoted(text) | Field::Unescaped(text) => SpreadsheetCell::Text(text.to_string()),
+        });
+    })?;
+    Ok(cells)
+}
+
+/// One field of a row, as `split_row` found it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Field<'t, 's> {
+    /// An unquoted field, as a slice of the row
+    Plain(&'t str),
+    /// A quoted field with no escaped quotes, as a slice of the row
+    Quoted(&'t str),
+    /// A quoted field whose `""` escapes were collapsed into the scratch
+    /// buffer
+    Unescaped(&'s str),
+}
+
+/// Calls `field` with each field of `text`
+///
+/// Fields are passed as slices of `text` where possible. Only quoted fields
+/// containing `""` need unescaping, which is done into `scratch`, so a
+/// caller that copies the text out can reuse one buffer for every row.
+/// Fails with the 1-based field number and the problem.
+pub(crate) fn split_row<'t, F>(
+    text: &'t str,
+    delimiter: char,
+    scratch: &mut String,
+    mut field: F,
+) -> Result<(), (usize, RowErrorKind)>
+where
+    F: FnMut(Field<'t, '_>),
+{
+    if text.is_empty() {
+        return Ok(());
+    }
+
+    let mut column = 1;
+    let mut rest = text;
+    loop {
+        if let Some(quoted) = rest.strip_prefix('"') {
+            let mut chars = quoted.char_indices().peekable();
+            let mut close = None;
+            let mut escaped = false;
+            while let Some((index, c)) = chars.next() {
+                if c != '"' {
+                    continue;
+                }
+                if chars.peek().map(|&(_, next)| next) == Some('"') {
+                    chars.next();
+                    escaped = true;
+                } else {
+                    close = Some(index);
+                    break;
+                }
+            }
+            let close = close.ok_or((column, RowErrorKind::UnterminatedQuote))?;
+            let contents = &quoted[..close];
+            if escaped {
+                scratch.clear();
+                let mut pieces = contents.split("\"\"");
+                scratch.extend(pieces.next());
+                for piece in pieces {
+                    scratch.push('"');
+                    scratch.push_str(piece);
+                }
+                field(Field::Unescaped(scratch));
+            } else {
+                field(Field::Quoted(contents));
+            }
+            rest = &quoted[close + 1..];
+            match rest.chars().next() {
+                None => return Ok(()),
+                Some(c) if c == delimiter => rest = &rest[c.len_utf8()..],
+                Some(_) => return Err((column, RowErrorKind::TrailingCharacters)),
+            }
+        } else {
+            match rest.find(delimiter) {
+                Some(index) => {
+                    field(Field::Plain(&rest[..index]));
+                    rest = &rest[index + delimiter.len_utf8()..];
+                }
+                None => {
+                    field(Field::Plain(rest));
+                    return Ok(());
+                }
+            }
+        }
+        column += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::random::{Rng, XorShift64};
+
+    const COLUMNS: usize = 5;
+
+    // Small integers and halves add up exactly in any order, so running sums
+    // can be compared with fresh ones exactly
+    fn random_cell(rng: &mut XorShift64) -> SpreadsheetCell {
+        match rng.below(8) {
+            0 => SpreadsheetCell::Empty,
+            1 => SpreadsheetCell::Text("x".to_string()),
+            2 => SpreadsheetCell::Float(-0.0),
+            3 => SpreadsheetCell::Float(0.0),
+            4 | 5 => SpreadsheetCell::Int(rng.below(7) as i32 - 3),
+            _ => SpreadsheetCell::Float((rng.below(9) as f64 - 4.0) / 2.0),
+        }
+    }
+
+    fn random_row(rng: &mut XorShift64) -> Vec<SpreadsheetCell> {
+        (0..rng.below(COLUMNS as u64 + 1))
+            .map(|_| random_cell(rng))
+            .collect()
+    }
+
+    fn recomputed(sheet: &Spreadsheet, col: usize) -> ColumnAggregates {
+        ColumnAggregates::of(sheet.rows().filter_map(|row| row.get(col)))
+    }
+
+    // Compares the extremes bit for bit, so `-0.0` and `0.0` differ
+    fn assert_same(running: ColumnAggregates, fresh: ColumnAggregates, context: &str) {
+        let bits = |value: Option<f64>| value.map(f64::to_bits);
+        assert_eq!(running.count, fresh.count, "count {}", context);
+        assert_eq!(running.sum, fresh.sum, "sum {}", context);
+        assert_eq!(bits(running.min), bits(fresh.min), "min {}", context);
+        assert_eq!(bits(running.max), bits(fresh.max), "max {}", context);
+    }
+
+    #[test]
+    fn tracked_aggregates_match_recomputation() {
+        for seed in 1..=20 {
+            let mut rng = XorShift64::new(seed);
+            let mut sheet = Spreadsheet::from_rows_padded(vec![random_row(&mut rng)]);
+            sheet.track_column(0);
+            sheet.track_column(2);
+            for step in 0..300 {
+                let col = rng.below(COLUMNS as u64) as usize;
+                match rng.below(10) {
+                    0..=4 => {
+                        // One past the end checks that a missing row is left alone
+                        let row = rng.below(sheet.row_count() as u64 + 1) as usize;
+                        let cell = random_cell(&mut rng);
+                        let expected = sheet.get(row, col).cloned();
+                        let replaced = sheet.set(row, col, cell);
+                        if row < sheet.row_count() {
+                            assert_eq!(replaced, Some(expected.unwrap_or(SpreadsheetCell::Empty)));
+                        } else {
+                            assert_eq!(replaced, None);
+                        }
+                    }
+                    5 | 6 => {
+                        let row = random_row(&mut rng);
+                        sheet.push_row(row);
+                    }
+                    7 => {
+                        let rows: Vec<_> =
+                            (0..rng.below(4)).map(|_| random_row(&mut rng)).collect();
+                        sheet.extend(rows);
+                    }
+                    8 => sheet.untrack_column(col),
+                    _ => sheet.track_column(col),
+                }
+                // A snapshot shares the rows, so the next change has to copy
+                let _snapshot = sheet.snapshot();
+                for col in 0..COLUMNS {
+                    let context = format!("seed {} step {} column {}", seed, step, col);
+                    assert_same(
+                        sheet.column_aggregates(col),
+                        recomputed(&sheet, col),
+                        &context,
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn set_pads_a_short_row_of_a_tracked_column() {
+        let mut sheet = Spreadsheet::from_rows_padded(vec![vec![SpreadsheetCell::Int(1)]]);
+        sheet.push_row(vec![]);
+        sheet.track_column(3);
+        assert_eq!(
+            sheet.set(1, 3, SpreadsheetCell::Float(2.5)),
+            Some(SpreadsheetCell::Empty)
+        );
+        assert_eq!(sheet.row(1).map(<[_]>::len), Some(4));
+        assert_same(
+            sheet.column_aggregates(3),
+            recomputed(&sheet, 3),
+            "after padding",
+        );
+        assert_eq!(sheet.column_aggregates(3).max, Some(2.5));
+    }
+
+    #[test]
+    fn negative_zero_is_below_zero() {
+        let mut sheet = Spreadsheet::new();
+        sheet.track_column(0);
+        sheet.push_row(vec![SpreadsheetCell::Float(0.0)]);
+        sheet.push_row(vec![SpreadsheetCell::Float(-0.0)]);
+        let aggregates = sheet.column_aggregates(0);
+        assert_same(aggregates, recomputed(&sheet, 0), "with both zeros");
+        assert!(aggregates.min.unwrap().is_sign_negative());
+        assert!(aggregates.max.unwrap().is_sign_positive());
+        sheet.set(1, 0, SpreadsheetCell::Int(0));
+        assert_same(
+            sheet.column_aggregates(0),
+            recomputed(&sheet, 0),
+            "without -0.0",
+        );
+    }
+
+    #[test]
+    fn nan_is_the_maximum_and_sticks_to_the_sum() {
+        let mut sheet = Spreadsheet::from_rows_padded(vec![vec![SpreadsheetCell::Int(1)]]);
+        sheet.track_column(0);
+        sheet.push_row(vec![SpreadsheetCell::Float(f64::NAN)]);
+        let running = sheet.column_aggregates(0);
+        let fresh = recomputed(&sheet, 0);
+        assert_eq!(running.count, fresh.count);
+        assert!(running.sum.is_nan() && fresh.sum.is_nan());
+        assert_eq!(running.min, Some(1.0));
+        assert!(running.max.unwrap().is_nan() && fresh.max.unwrap().is_nan());
+
+        // Taking the NaN back out fixes the count and extremes, but the
+        // running sum stays NaN until the column is tracked anew, as the
+        // `aggregates` module documents
+        sheet.set(1, 0, SpreadsheetCell::Int(2));
+        let running = sheet.column_aggregates(0);
+        let fresh = recomputed(&sheet, 0);
+        assert_eq!(
+            (running.count, running.min, running.max),
+            (fresh.count, fresh.min, fresh.max)
+        );
+        assert!(running.sum.is_nan());
+        sheet.untrack_column(0);
+        sheet.track_column(0);
+        assert_same(
+            sheet.column_aggregates(0),
+            recomputed(&sheet, 0),
+            "tracked anew",
+        );
+    }
+}
@@ -0,0 +1,432 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A work-stealing deque, after Chase and Lev
+//!
+//! Each worker thread owns a `StealDeque` of tasks. It pushes and pops at
+//! the bottom like a stack, with no locking, while any other thread holding
+//! a `Stealer` takes from the top. A worker that runs out of tasks steals
+//! from the others, so the work spreads itself out without a shared queue
+//! everyone contends on. `examples/steal_rows.rs` is a small thread pool
+//! built this way that sums the rows of a `Spreadsheet`.
+//!
+//! The tasks live in a ring buffer that doubles when full. A thief may
+//! still be reading the old buffer after it's replaced, so old buffers are
+//! only freed with the deque itself, which at worst doubles its memory.
+//!
+//! Only the owner may push and pop, which the types enforce: a `StealDeque`
+//! can be moved to another thread but not shared, and only a `Stealer` can
+//! be cloned.
+
+use std::cell::{Cell, UnsafeCell};
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+use std::ptr;
+use std::sync::atomic::{self, AtomicIsize, AtomicPtr, Ordering};
+use std::sync::{Arc, Mutex};
+
+const MIN_CAPACITY: usize = 16;
+
+// A ring of slots, indexed by the deque's ever-growing positions
+struct Buffer<T> {
+    slots: Box<[UnsafeCell<MaybeUninit<T>>]>,
+}
+
+impl<T> Buffer<T> {
+    fn new(capacity: usize) -> Buffer<T> {
+        Buffer {
+            slots: (0..capacity)
+                .map(|_| UnsafeCell::new(MaybeUninit::uninit()))
+                .collect(),
+        }
+    }
+
+    fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    fn slot(&self, index: isize) -> *mut MaybeUninit<T> {
+        self.slots[index as usize & (self.slots.len() - 1)].get()
+    }
+
+    // Safety: only the owner writes, and never to a slot between `top` and
+    // `bottom`
+    unsafe fn write(&self, index: isize, value: T) {
+        ptr::write_volatile(self.slot(index), MaybeUninit::new(value))
+    }
+
+    // A thief can race the owner overwriting the slot, in which case its
+    // claim on the index fails and the value it read is thrown away, never
+    // treated as a `T`
+    unsafe fn read(&self, index: isize) -> MaybeUninit<T> {
+        ptr::read_volatile(self.slot(index))
+    }
+}
+
+struct Inner<T> {
+    // Where thieves take from; only ever increases
+    top: AtomicIsize,
+    // Where the owner pushes and pops
+    bottom: AtomicIsize,
+    buffer: AtomicPtr<Buffer<T>>,
+    // Buffers outgrown, kept until the deque is dropped. Thieves may hold
+    // pointers to them, so they stay boxed rather than move into the `Vec`
+    #[allow(clippy::vec_box)]
+    retired: Mutex<Vec<Box<Buffer<T>>>>,
+}
+
+impl<T> Drop for Inner<T> {
+    fn drop(&mut self) {
+        let top = *self.top.get_mut();
+        let bottom = *self.bottom.get_mut();
+        // Safety: nothing else holds the deque any more, and the values
+        // between `top` and `bottom` were never taken
+        unsafe {
+            let buffer = Box::from_raw(*self.buffer.get_mut());
+            for index in top..bottom {
+                drop(buffer.read(index).assume_init());
+            }
+        }
+    }
+}
+
+/// The owner's end of a work-stealing deque
+pub struct StealDeque<T> {
+    inner: Arc<Inner<T>>,
+    // Shared references would let two threads push at once
+    _not_sync: PhantomData<Cell<()>>,
+}
+
+/// A thief's handle on a `StealDeque`, which can be cloned and shared
+pub struct Stealer<T> {
+    inner: Arc<Inner<T>>,
+}
+
+/// What `Stealer::steal` got
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Steal<T> {
+    Empty,
+    Success(T),
+    /// Another thread took the same task first; the deque may not be empty
+    Retry,
+}
+
+impl<T> Steal<T> {
+    pub fn success(self) -> Option<T> {
+        match self {
+            Steal::Success(value) => Some(value),
+            _ => None,
+        }
+    }
+}
+
+impl<T: Send> StealDeque<T> {
+    pub fn new() -> Self {
+        StealDeque::with_capacity(MIN_CAPACITY)
+    }
+
+    /// Creates a deque with room for `capacity` tasks before it grows
+    pub fn with_capacity(capacity: usize) -> Self {
+        let capacity = capacity.max(MIN_CAPACITY).next_power_of_two();
+        let buffer = Box::into_raw(Box::new(Buffer::new(capacity)));
+        StealDeque {
+            inner: Arc::new(Inner {
+                top: AtomicIsize::new(0),
+                bottom: AtomicIsize::new(0),
+                buffer: AtomicPtr::new(buffer),
+                retired: Mutex::new(Vec::new()),
+            }),
+            _not_sync: PhantomData,
+        }
+    }
+
+    /// A handle other threads can steal tasks through
+    pub fn stealer(&self) -> Stealer<T> {
+        Stealer {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+
+    /// The number of tasks left, which thieves may already be taking
+    pub fn len(&self) -> usize {
+        let bottom = self.inner.bottom.load(Ordering::Relaxed);
+        let top = self.inner.top.load(Ordering::Acquire);
+        (bottom - top).max(0) as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds a task at the bottom
+    pub fn push(&self, value: T) {
+        let inner = &*self.inner;
+        let bottom = inner.bottom.load(Ordering::Relaxed);
+        let top = inner.top.load(Ordering::Acquire);
+        let mut buffer = inner.buffer.load(Ordering::Relaxed);
+        // Safety: only the owner replaces the buffer, so it's still live
+        if (bottom - top) as usize >= unsafe { (*buffer).capacity() } {
+            buffer = self.grow(top, bottom);
+        }
+        // Safety: the slot at `bottom` is outside `top..bottom`
+        unsafe { (*buffer).write(bottom, value) };
+        // The value has to be visible before the thieves see the new bottom
+        atomic::fence(Ordering::Release);
+        inner.bottom.store(bottom + 1, Ordering::Relaxed);
+    }
+
+    /// Takes the task pushed most recently, unless a thief got it
+    pub fn pop(&self) -> Option<T> {
+        let inner = &*self.inner;
+        let bottom = inner.bottom.load(Ordering::Relaxed) - 1;
+        let buffer = inner.buffer.load(Ordering::Relaxed);
+        // Claim the slot before looking at the top, so that a thief either
+        // sees the claim or the owner sees the thief's
+        inner.bottom.store(bottom, Ordering::Relaxed);
+        atomic::fence(Ordering::SeqCst);
+        let top = inner.top.load(Ordering::Relaxed);
+
+        if top > bottom {
+            inner.bottom.store(bottom + 1, Ordering::Relaxed);
+            return None;
+        }
+        // Safety: the slot is in `top..=bottom`, so it holds a value
+        let value = unsafe { (*buffer).read(bottom) };
+        if top == bottom {
+            // The last task, which a thief may be taking at the same time
+            let won = inner
+                .top
+                .compare_exchange(top, top + 1, Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok();
+            inner.bottom.store(bottom + 1, Ordering::Relaxed);
+            if !won {
+                return None;
+            }
+        }
+        // Safety: the task is the owner's alone now
+        Some(unsafe { value.assume_init() })
+    }
+
+    // Moves the tasks into a buffer twice the size, returning it
+    fn grow(&self, top: isize, bottom: isize) -> *mut Buffer<T> {
+        let inner = &*self.inner;
+        let old = inner.buffer.load(Ordering::Relaxed);
+        // Safety: the old buffer stays live until the deque is dropped, and
+        // only the owner writes to the new one
+        unsafe {
+            let new = Buffer::new((*old).capacity() * 2);
+            for index in top..bottom {
+                ptr::copy_nonoverlapping((*old).slot(index), new.slot(index), 1);
+            }
+            let new = Box::into_raw(Box::new(new));
+            inner.buffer.store(new, Ordering::Release);
+            inner
+                .retired
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push(Box::from_raw(old));
+            new
+        }
+    }
+}
+
+impl<T: Send> Stealer<T> {
+    /// Takes the task pushed longest ago
+    pub fn steal(&self) -> Steal<T> {
+        let inner = &*self.inner;
+        let top = inner.top.load(Ordering::Acquire);
+        atomic::fence(Ordering::SeqCst);
+        let bottom = inner.bottom.load(Ordering::Acquire);
+        if top >= bottom {
+            return Steal::Empty;
+        }
+        let buffer = inner.buffer.load(Ordering::Acquire);
+        // Safety: buffers are never freed while a `Stealer` exists
+        let value = unsafe { (*buffer).read(top) };
+        match inner
+            .top
+            .compare_exchange(top, top + 1, Ordering::SeqCst, Ordering::Relaxed)
+        {
+            // Safety: claiming `top` makes the task this thread's alone
+            Ok(_) => Steal::Success(unsafe { value.assume_init() }),
+            Err(_) => Steal::Retry,
+        }
+    }
+
+    /// Steals until a task is taken or the deque is seen empty
+    pub fn steal_retrying(&self) -> Option<T> {
+        loop {
+            match self.steal() {
+                Steal::Success(value) => return Some(value),
+                Steal::Empty => return None,
+                Steal::Retry => {}
+            }
+        }
+    }
+
+    /// Whether the deque looked empty, which may have changed by the time
+    /// this returns
+    pub fn is_empty(&self) -> bool {
+        let top = self.inner.top.load(Ordering::Acquire);
+        let bottom = self.inner.bottom.load(Ordering::Acquire);
+        top >= bottom
+    }
+}
+
+impl<T: Send> Default for StealDeque<T> {
+    fn default() -> Self {
+        StealDeque::new()
+    }
+}
+
+impl<T> Clone for Stealer<T> {
+    fn clone(&self) -> Self {
+        Stealer {
+            inner: Arc::clone(&self.inner),
+        }
+    }
+}
+
+impl<T> fmt::Debug for StealDeque<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("StealDeque")
+            .field("top", &self.inner.top.load(Ordering::Relaxed))
+            .field("bottom", &self.inner.bottom.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+impl<T> fmt::Debug for Stealer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Stealer")
+            .field("top", &self.inner.top.load(Ordering::Relaxed))
+            .field("bottom", &self.inner.bottom.load(Ordering::Relaxed))
+            .finish()
+    }
+}
+
+// Safety: the tasks move between threads, but each is taken by one thread
+// only, claimed through `top` or `bottom`
+unsafe impl<T: Send> Send for Inner<T> {}
+unsafe impl<T: Send> Sync for Inner<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::thread;
+
+    #[test]
+    fn owner_pops_newest_and_thieves_take_oldest() {
+        let deque = StealDeque::new();
+        let stealer = deque.stealer();
+        assert_eq!(deque.pop(), None);
+        assert_eq!(stealer.steal(), Steal::Empty);
+        for value in 1..=4 {
+            deque.push(value);
+        }
+        assert_eq!(deque.len(), 4);
+        assert_eq!(deque.pop(), Some(4));
+        assert_eq!(stealer.steal(), Steal::Success(1));
+        assert_eq!(stealer.steal_retrying(), Some(2));
+        assert_eq!(deque.pop(), Some(3));
+        assert!(deque.is_empty());
+        assert!(stealer.is_empty());
+        assert_eq!(deque.pop(), None);
+        assert_eq!(stealer.steal_retrying(), None);
+    }
+
+    #[test]
+    fn growing_keeps_every_task() {
+        let deque = StealDeque::with_capacity(1);
+        let stealer = deque.stealer();
+        for value in 0..1000 {
+            deque.push(value);
+        }
+        assert_eq!(deque.inner.retired.lock().unwrap().len(), 6);
+        for value in 0..500 {
+            assert_eq!(stealer.steal().success(), Some(value));
+        }
+        for value in (500..1000).rev() {
+            assert_eq!(deque.pop(), Some(value));
+        }
+        assert_eq!(deque.pop(), None);
+    }
+
+    #[test]
+    fn dropping_drops_the_tasks_left() {
+        let task = Arc::new(());
+        let deque = StealDeque::new();
+        for _ in 0..100 {
+            deque.push(Arc::clone(&task));
+        }
+        let stealer = deque.stealer();
+        drop(deque.pop());
+        drop(stealer.steal());
+        drop(deque);
+        assert_eq!(Arc::strong_count(&task), 1 + 98);
+        drop(stealer);
+        assert_eq!(Arc::strong_count(&task), 1);
+    }
+
+    // The owner pushes in small batches and pops most of each, so it keeps
+    // racing the thieves for the last task. Every task has to come out
+    // exactly once.
+    #[test]
+    fn concurrent_steals_and_pops_take_each_task_once() {
+        const TASKS: usize = 100_000;
+        const THIEVES: usize = 4;
+        let deque = StealDeque::new();
+        let done = AtomicBool::new(false);
+        let (popped, stolen) = thread::scope(|scope| {
+            let thieves: Vec<_> = (0..THIEVES)
+                .map(|_| {
+                    let stealer = deque.stealer();
+                    let done = &done;
+                    scope.spawn(move || {
+                        let mut taken = Vec::new();
+                        loop {
+                            match stealer.steal() {
+                                Steal::Success(task) => taken.push(task),
+                                Steal::Retry => {}
+                                Steal::Empty if done.load(Ordering::Acquire) => break,
+                                Steal::Empty => thread::yield_now(),
+                            }
+                        }
+                        taken
+                    })
+                })
+                .collect();
+
+            let mut popped = Vec::new();
+            for batch in (0..TASKS).collect::<Vec<_>>().chunks(3) {
+                for &task in batch {
+                    deque.push(task);
+                }
+                for _ in 0..2 {
+                    popped.extend(deque.pop());
+                }
+            }
+            while let Some(task) = deque.pop() {
+                popped.push(task);
+            }
+            done.store(true, Ordering::Release);
+            let stolen: Vec<Vec<usize>> = thieves
+                .into_iter()
+                .map(|thief| thief.join().unwrap())
+                .collect();
+            (popped, stolen)
+        });
+
+        let mut seen = vec![0u8; TASKS];
+        for &task in popped.iter().chain(stolen.iter().flatten()) {
+            seen[task] += 1;
+        }
+        assert!(seen.iter().all(|&count| count == 1));
+        // Each thief takes its tasks in the order they were pushed
+        for taken in &stolen {
+            assert!(taken.windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+}
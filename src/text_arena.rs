@@ -0,0 +1,329 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A spreadsheet that keeps all of its text in one shared buffer
+//!
+//! Every `SpreadsheetCell::Text` owns a separate `String`, so importing a
+//! sheet with a million text cells makes a million allocations. An
+//! `ArenaSpreadsheet` appends the text of every cell to a single
+//! `TextArena` and stores each `Text` cell as the offset and length of its
+//! bytes there. `ArenaSpreadsheet::parse` fills the arena straight from the
+//! input lines, so unquoted fields are never copied into a `String` of their
+//! own at all.
+//!
+//! With `SheetOptions::intern_text` set, text goes through an `Interner`
+//! instead, so a value repeated down a category column is stored once and
+//! every cell holding it shares one `TextRef`.
+//!
+//! Cells can't be edited in place, since changing a string's length would
+//! shift everything after it. Convert to a `Spreadsheet` with
+//! `to_spreadsheet` to edit, and back with `from_spreadsheet` to store.
+
+use crate::deep_size::DeepSize;
+use crate::interner::Interner;
+use crate::spreadsheet::{self, Field, RowParseError, Spreadsheet, SpreadsheetCell};
+use std::convert::TryFrom;
+
+/// Where one string lives in a `TextArena`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextRef {
+    offset: u32,
+    len: u32,
+}
+
+impl TextRef {
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+/// Append-only storage for many strings in one buffer
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextArena {
+    text: String,
+}
+
+impl TextArena {
+    pub fn new() -> Self {
+        TextArena {
+            text: String::new(),
+        }
+    }
+
+    /// Copies `text` into the arena and returns where it went
+    ///
+    /// Panics if the arena would grow past 4 GiB, the most a `TextRef` can
+    /// address.
+    pub fn push(&mut self, text: &str) -> TextRef {
+        let offset = u32::try_from(self.text.len()).expect("text arena is full");
+        let len = u32::try_from(text.len()).expect("string too long for a text arena");
+        assert!(offset.checked_add(len).is_some(), "text arena is full");
+        self.text.push_str(text);
+        TextRef { offset, len }
+    }
+
+    /// The string behind `text`, which must have come from this arena
+    ///
+    /// Panics if `text` points past the end of the arena.
+    pub fn get(&self, text: TextRef) -> &str {
+        let start = text.offset as usize;
+        &self.text[start..start + text.len as usize]
+    }
+
+    /// Total bytes of text stored
+    pub fn len(&self) -> usize {
+        self.text.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+}
+
+/// How an `ArenaSpreadsheet` is built
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SheetOptions {
+    /// Store each distinct text value once, through an `Interner`
+    pub intern_text: bool,
+}
+
+// Where an `ArenaSpreadsheet` keeps its text
+#[derive(Debug, Clone, PartialEq)]
+enum TextStore {
+    Arena(TextArena),
+    Interned(Interner),
+}
+
+impl TextStore {
+    fn new(options: SheetOptions) -> Self {
+        if options.intern_text {
+            TextStore::Interned(Interner::new())
+        } else {
+            TextStore::Arena(TextArena::new())
+        }
+    }
+
+    fn push(&mut self, text: &str) -> TextRef {
+        match self {
+            TextStore::Arena(arena) => arena.push(text),
+            TextStore::Interned(interner) => interner.intern(text),
+        }
+    }
+
+    fn arena(&self) -> &TextArena {
+        match self {
+            TextStore::Arena(arena) => arena,
+            TextStore::Interned(interner) => interner.arena(),
+        }
+    }
+}
+
+impl Default for TextStore {
+    fn default() -> Self {
+        TextStore::Arena(TextArena::new())
+    }
+}
+
+/// A cell of an `ArenaSpreadsheet`, whose text lives in the sheet's arena
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ArenaCell {
+    Int(i32),
+    Float(f64),
+    Text(TextRef),
+    Empty,
+}
+
+/// Rows of cells sharing one `TextArena` for their text
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ArenaSpreadsheet {
+    text: TextStore,
+    rows: Vec<Vec<ArenaCell>>,
+}
+
+impl ArenaSpreadsheet {
+    pub fn new() -> Self {
+        ArenaSpreadsheet::with_options(SheetOptions::default())
+    }
+
+    pub fn with_options(options: SheetOptions) -> Self {
+        ArenaSpreadsheet {
+            text: TextStore::new(options),
+            rows: Vec::new(),
+        }
+    }
+
+    /// Parses delimited lines the way `spreadsheet::parse_cells` does,
+    /// stopping at the first line that fails
+    pub fn parse<'a, I>(lines: I, delimiter: char) -> Result<Self, RowParseError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        ArenaSpreadsheet::parse_with_options(lines, delimiter, SheetOptions::default())
+    }
+
+    /// `parse`, building the sheet as `options` says
+    pub fn parse_with_options<'a, I>(
+        lines: I,
+        delimiter: char,
+        options: SheetOptions,
+    ) -> Result<Self, RowParseError>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let mut sheet = ArenaSpreadsheet::with_options(options);
+        let mut scratch = String::new();
+        for (index, line) in lines.into_iter().enumerate() {
+            let line = line.strip_suffix('\r').unwrap_or(line);
+            let text = &mut sheet.text;
+            let mut row = Vec::new();
+            spreadsheet::split_row(line, delimiter, &mut scratch, |field| {
+                row.push(match field {
+                    Field::Plain(raw) => match SpreadsheetCell::infer_number(raw) {
+                        Some(SpreadsheetCell::Int(int)) => ArenaCell::Int(int),
+                        Some(SpreadsheetCell::Float(float)) => ArenaCell::Float(float),
+                        _ => ArenaCell::Text(text.push(raw)),
+                    },
+                    Field::Quoted(field) | Field::Unescaped(field) => {
+                        ArenaCell::Text(text.push(field))
+                    }
+                });
+            })
+            .map_err(|(column, kind)| RowParseError {
+                line: index + 1,
+                column,
+                kind,
+            })?;
+            sheet.rows.push(row);
+        }
+        Ok(sheet)
+    }
+
+    /// Copies the cells of `sheet`, moving their text into one arena
+    pub fn from_spreadsheet(sheet: &Spreadsheet) -> Self {
+        ArenaSpreadsheet::from_spreadsheet_with_options(sheet, SheetOptions::default())
+    }
+
+    /// `from_spreadsheet`, building the sheet as `options` says
+    pub fn from_spreadsheet_with_options(sheet: &Spreadsheet, options: SheetOptions) -> Self {
+        let mut store = TextStore::new(options);
+        let rows = sheet
+            .rows()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| match cell {
+                        SpreadsheetCell::Int(int) => ArenaCell::Int(*int),
+                        SpreadsheetCell::Float(float) => ArenaCell::Float(*float),
+                        SpreadsheetCell::Text(text) => ArenaCell::Text(store.push(text)),
+                        SpreadsheetCell::Empty => ArenaCell::Empty,
+                    })
+                    .collect()
+            })
+            .collect();
+        ArenaSpreadsheet { text: store, rows }
+    }
+
+    /// Builds the equivalent sheet with an owned `String` per text cell
+    pub fn to_spreadsheet(&self) -> Spreadsheet {
+        self.rows
+            .iter()
+            .map(|row| row.iter().map(|cell| self.to_cell(cell)).collect())
+            .collect()
+    }
+
+    /// The owned form of `cell`, which must belong to this sheet
+    pub fn to_cell(&self, cell: &ArenaCell) -> SpreadsheetCell {
+        match *cell {
+            ArenaCell::Int(int) => SpreadsheetCell::Int(int),
+            ArenaCell::Float(float) => SpreadsheetCell::Float(float),
+            ArenaCell::Text(text) => SpreadsheetCell::Text(self.arena().get(text).to_string()),
+            ArenaCell::Empty => SpreadsheetCell::Empty,
+        }
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.rows.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+
+    pub fn row(&self, row: usize) -> Option<&[ArenaCell]> {
+        self.rows.get(row).map(Vec::as_slice)
+    }
+
+    pub fn get(&self, row: usize, col: usize) -> Option<&ArenaCell> {
+        self.rows.get(row)?.get(col)
+    }
+
+    /// The text of the cell at `(row, col)`, if it is a `Text` cell
+    pub fn text(&self, row: usize, col: usize) -> Option<&str> {
+        match self.get(row, col)? {
+            ArenaCell::Text(text) => Some(self.arena().get(*text)),
+            _ => None,
+        }
+    }
+
+    pub fn arena(&self) -> &TextArena {
+        self.text.arena()
+    }
+
+    /// The interner holding this sheet's text, if it was built with
+    /// `intern_text`
+    pub fn interner(&self) -> Option<&Interner> {
+        match &self.text {
+            TextStore::Interned(interner) => Some(interner),
+            TextStore::Arena(_) => None,
+        }
+    }
+
+    /// Text cells per distinct text value stored, if the sheet interns its
+    /// text; see `Interner::dedup_ratio`
+    pub fn dedup_ratio(&self) -> Option<f64> {
+        self.interner().map(Interner::dedup_ratio)
+    }
+}
+
+impl From<&Spreadsheet> for ArenaSpreadsheet {
+    fn from(sheet: &Spreadsheet) -> Self {
+        ArenaSpreadsheet::from_spreadsheet(sheet)
+    }
+}
+
+impl From<&ArenaSpreadsheet> for Spreadsheet {
+    fn from(sheet: &ArenaSpreadsheet) -> Self {
+        sheet.to_spreadsheet()
+    }
+}
+
+impl DeepSize for TextRef {
+    fn heap_bytes(&self) -> usize {
+        0
+    }
+}
+
+impl DeepSize for ArenaCell {
+    fn heap_bytes(&self) -> usize {
+        0
+    }
+}
+
+impl DeepSize for TextArena {
+    fn heap_bytes(&self) -> usize {
+        self.text.heap_bytes()
+    }
+}
+
+impl DeepSize for ArenaSpreadsheet {
+    fn heap_bytes(&self) -> usize {
+        let text = match &self.text {
+            TextStore::Arena(arena) => arena.heap_bytes(),
+            TextStore::Interned(interner) => interner.heap_bytes(),
+        };
+        text + self.rows.heap_bytes()
+    }
+}
@@ -0,0 +1,333 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Sliding windows over the characters of a string
+//!
+//! A `&str` can't be windowed with `slice::windows` because its characters
+//! take a varying number of bytes. `char_windows` walks the string one `char`
+//! at a time and yields each window as a borrowed slice, so no window is
+//! ever allocated. `grapheme_windows` does the same with user-perceived
+//! characters, so an accented letter written as a base letter plus a
+//! combining mark is never split across windows.
+//!
+//! Without a Unicode segmentation table, grapheme clusters are approximated:
+//! a cluster is one character followed by any combining marks, variation
+//! selectors and emoji skin-tone modifiers, with zero-width joiners gluing
+//! the next character on, `\r\n` kept together and regional indicators
+//! (flag letters) taken in pairs. In the Indic scripts from Devanagari to
+//! Sinhala, vowel signs and the other marks stay on their letter, and a
+//! virama joins the consonant after it into a conjunct, so "नमस्ते" is the
+//! three clusters "न", "म" and "स्ते". That covers accented text, the main
+//! Indic scripts and common emoji but not every rule of the full Unicode
+//! algorithm, such as Hangul syllables built from separate jamo or the marks
+//! of scripts further east.
+
+/// Yields every run of `n` consecutive `char`s in `text`, as slices of it
+///
+/// A string with fewer than `n` characters yields nothing. Panics if `n` is
+/// zero.
+pub fn char_windows(text: &str, n: usize) -> TextWindows<'_> {
+    TextWindows::new(text, n, Unit::Char)
+}
+
+/// Yields every run of `n` consecutive grapheme clusters in `text`, as slices
+/// of it
+///
+/// See the module documentation for how clusters are found. A string with
+/// fewer than `n` clusters yields nothing. Panics if `n` is zero.
+pub fn grapheme_windows(text: &str, n: usize) -> TextWindows<'_> {
+    TextWindows::new(text, n, Unit::Grapheme)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Unit {
+    Char,
+    Grapheme,
+}
+
+/// Iterator returned by `char_windows` and `grapheme_windows`
+#[derive(Debug, Clone)]
+pub struct TextWindows<'a> {
+    text: &'a str,
+    n: usize,
+    unit: Unit,
+    // Byte offsets of the current window, `None` until the first window has
+    // been measured
+    window: Option<(usize, usize)>,
+    done: bool,
+}
+
+impl<'a> TextWindows<'a> {
+    fn new(text: &'a str, n: usize, unit: Unit) -> Self {
+        assert!(n > 0, "window size must be positive");
+        TextWindows {
+            text,
+            n,
+            unit,
+            window: None,
+            done: false,
+        }
+    }
+
+    fn next_boundary(&self, from: usize) -> Option<usize> {
+        match self.unit {
+            Unit::Char => self.text[from..]
+                .chars()
+                .next()
+                .map(|c| from + c.len_utf8()),
+            Unit::Grapheme => next_grapheme_boundary(self.text, from),
+        }
+    }
+}
+
+impl<'a> Iterator for TextWindows<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        if self.done {
+            return None;
+        }
+        let (start, end) = match self.window {
+            None => {
+                let mut end = 0;
+                for _ in 0..self.n {
+                    match self.next_boundary(end) {
+                        Some(boundary) => end = boundary,
+                        None => {
+                            self.done = true;
+                            return None;
+                        }
+                    }
+                }
+                (0, end)
+            }
+            Some((start, end)) => match self.next_boundary(end) {
+                Some(new_end) => (self.next_boundary(start).unwrap_or(end), new_end),
+                None => {
+                    self.done = true;
+                    return None;
+                }
+            },
+        };
+        self.window = Some((start, end));
+        Some(&self.text[start..end])
+    }
+}
+
+// Byte offset just past the grapheme cluster starting at `from`
+fn next_grapheme_boundary(text: &str, from: usize) -> Option<usize> {
+    let mut chars = text[from..].char_indices().peekable();
+    let (_, first) = chars.next()?;
+    let mut end = from + first.len_utf8();
+    let mut joined = first == ZERO_WIDTH_JOINER;
+
+    if first == '\r' {
+        if let Some(&(offset, '\n')) = chars.peek() {
+            return Some(from + offset + 1);
+        }
+        return Some(end);
+    }
+    if is_regional_indicator(first) {
+        if let Some(&(offset, next)) = chars.peek() {
+            if is_regional_indicator(next) {
+                chars.next();
+                end = from + offset + next.len_utf8();
+            }
+        }
+    }
+
+    // The block of the last virama seen, whose next consonant joins on
+    let mut linked = None;
+    while let Some(&(offset, next)) = chars.peek() {
+        let conjunct = linked.is_some() && linked == indic_consonant_block(next);
+        if !(joined || conjunct || is_extending(next)) {
+            break;
+        }
+        joined = next == ZERO_WIDTH_JOINER;
+        if is_virama(next) {
+            linked = indic_block(next);
+        } else if conjunct {
+            linked = None;
+        }
+        end = from + offset + next.len_utf8();
+        chars.next();
+    }
+    Some(end)
+}
+
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+
+// Characters that attach to the one before them
+fn is_extending(c: char) -> bool {
+    matches!(
+        c,
+        '\u{0300}'..='\u{036F}'
+            | '\u{1AB0}'..='\u{1AFF}'
+            | '\u{1DC0}'..='\u{1DFF}'
+            | '\u{20D0}'..='\u{20FF}'
+            | '\u{FE20}'..='\u{FE2F}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{1F3FB}'..='\u{1F3FF}'
+            | ZERO_WIDTH_JOINER
+    ) || (('\u{0900}'..='\u{0DFF}').contains(&c)
+        && INDIC_MARKS
+            .iter()
+            .any(|&(low, high)| (low..=high).contains(&c)))
+}
+
+// Vowel signs, viramas, nuktas and the other combining marks of the Indic
+// blocks. A few ranges take in unassigned code points between marks.
+const INDIC_MARKS: [(char, char); 43] = [
+    // Devanagari
+    ('\u{0900}', '\u{0903}'),
+    ('\u{093A}', '\u{093C}'),
+    ('\u{093E}', '\u{094F}'),
+    ('\u{0951}', '\u{0957}'),
+    ('\u{0962}', '\u{0963}'),
+    // Bengali
+    ('\u{0981}', '\u{0983}'),
+    ('\u{09BC}', '\u{09BC}'),
+    ('\u{09BE}', '\u{09CD}'),
+    ('\u{09D7}', '\u{09D7}'),
+    ('\u{09E2}', '\u{09E3}'),
+    ('\u{09FE}', '\u{09FE}'),
+    // Gurmukhi
+    ('\u{0A01}', '\u{0A03}'),
+    ('\u{0A3C}', '\u{0A51}'),
+    ('\u{0A70}', '\u{0A71}'),
+    ('\u{0A75}', '\u{0A75}'),
+    // Gujarati
+    ('\u{0A81}', '\u{0A83}'),
+    ('\u{0ABC}', '\u{0ABC}'),
+    ('\u{0ABE}', '\u{0ACD}'),
+    ('\u{0AE2}', '\u{0AE3}'),
+    ('\u{0AFA}', '\u{0AFF}'),
+    // Oriya
+    ('\u{0B01}', '\u{0B03}'),
+    ('\u{0B3C}', '\u{0B3C}'),
+    ('\u{0B3E}', '\u{0B57}'),
+    ('\u{0B62}', '\u{0B63}'),
+    // Tamil
+    ('\u{0B82}', '\u{0B82}'),
+    ('\u{0BBE}', '\u{0BCD}'),
+    ('\u{0BD7}', '\u{0BD7}'),
+    // Telugu
+    ('\u{0C00}', '\u{0C04}'),
+    ('\u{0C3C}', '\u{0C3C}'),
+    ('\u{0C3E}', '\u{0C56}'),
+    ('\u{0C62}', '\u{0C63}'),
+    // Kannada
+    ('\u{0C81}', '\u{0C83}'),
+    ('\u{0CBC}', '\u{0CBC}'),
+    ('\u{0CBE}', '\u{0CD6}'),
+    ('\u{0CE2}', '\u{0CE3}'),
+    // Malayalam
+    ('\u{0D00}', '\u{0D03}'),
+    ('\u{0D3B}', '\u{0D3C}'),
+    ('\u{0D3E}', '\u{0D4D}'),
+    ('\u{0D57}', '\u{0D57}'),
+    ('\u{0D62}', '\u{0D63}'),
+    // Sinhala
+    ('\u{0D81}', '\u{0D83}'),
+    ('\u{0DCA}', '\u{0DDF}'),
+    ('\u{0DF2}', '\u{0DF3}'),
+];
+
+// The viramas Unicode's segmentation rules let join the next consonant
+// into a conjunct. Those of Gurmukhi, Tamil and Kannada aren't among them,
+// and end the cluster like any other mark.
+fn is_virama(c: char) -> bool {
+    matches!(
+        c,
+        '\u{094D}' | '\u{09CD}' | '\u{0ACD}' | '\u{0B4D}' | '\u{0C4D}' | '\u{0D4D}'
+    )
+}
+
+// From Devanagari to Malayalam, each script takes a 128-character block
+// laid out alike
+fn indic_block(c: char) -> Option<u32> {
+    let code = u32::from(c);
+    if (0x0900..0x0D80).contains(&code) {
+        Some(code & !0x7F)
+    } else {
+        None
+    }
+}
+
+// The block of `c` if it is one of the consonants, which every one of those
+// blocks keeps between the same offsets
+fn indic_consonant_block(c: char) -> Option<u32> {
+    let offset = u32::from(c) & 0x7F;
+    indic_block(c).filter(|_| (0x15..=0x39).contains(&offset))
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c, '\u{1F1E6}'..='\u{1F1FF}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graphemes(text: &str) -> Vec<&str> {
+        grapheme_windows(text, 1).collect()
+    }
+
+    #[test]
+    fn char_windows_slide_one_char_at_a_time() {
+        assert_eq!(
+            char_windows("héllo", 3).collect::<Vec<_>>(),
+            ["hél", "éll", "llo"]
+        );
+        assert_eq!(char_windows("ab", 3).count(), 0);
+        assert_eq!(char_windows("", 1).count(), 0);
+    }
+
+    #[test]
+    fn marks_stay_on_their_letter() {
+        assert_eq!(
+            graphemes("e\u{301}a\u{308}\u{323}"),
+            ["e\u{301}", "a\u{308}\u{323}"]
+        );
+        assert_eq!(graphemes("a\r\nb\n\r"), ["a", "\r\n", "b", "\n", "\r"]);
+    }
+
+    #[test]
+    fn emoji_sequences_and_flags_hold_together() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let waving = "\u{1F44B}\u{1F3FD}";
+        let flags = "\u{1F1EC}\u{1F1E7}\u{1F1EB}\u{1F1F7}";
+        assert_eq!(graphemes(family), [family]);
+        assert_eq!(graphemes(waving), [waving]);
+        assert_eq!(
+            graphemes(flags),
+            ["\u{1F1EC}\u{1F1E7}", "\u{1F1EB}\u{1F1F7}"]
+        );
+    }
+
+    #[test]
+    fn indic_vowel_signs_and_conjuncts() {
+        assert_eq!(graphemes("नमस्ते"), ["न", "म", "स्ते"]);
+        assert_eq!(graphemes("हिन्दी"), ["हि", "न्दी"]);
+        assert_eq!(graphemes("বাংলা"), ["বাং", "লা"]);
+        assert_eq!(graphemes("தமிழ்"), ["த", "மி", "ழ்"]);
+        // A virama only joins a consonant of its own script
+        assert_eq!(graphemes("क्a"), ["क्", "a"]);
+        assert_eq!(graphemes("क् ख"), ["क्", " ", "ख"]);
+        assert_eq!(graphemes("ಕ್ಷ"), ["ಕ್", "ಷ"]);
+    }
+
+    #[test]
+    fn grapheme_windows_never_split_a_cluster() {
+        assert_eq!(
+            grapheme_windows("नमस्ते", 2).collect::<Vec<_>>(),
+            ["नम", "मस्ते"]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "window size must be positive")]
+    fn empty_windows_panic() {
+        char_windows("abc", 0);
+    }
+}
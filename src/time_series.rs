@@ -0,0 +1,184 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A series of values keyed by timestamp
+//!
+//! Samples are stored in a `BTreeMap`, so they're always in time order and
+//! any window of time is one range query away. Timestamps are plain `u64`s in
+//! whatever unit the caller picks, typically milliseconds since the epoch.
+//! `resample` condenses the series into fixed-width buckets with one of the
+//! `AggFn` aggregations, and the `trim_*` methods drop samples that have aged
+//! out.
+
+use std::collections::BTreeMap;
+use std::ops::{Range, RangeBounds};
+
+/// How `resample` combines the samples falling in one bucket
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AggFn {
+    Sum,
+    Mean,
+    Min,
+    Max,
+    Count,
+    /// The sample with the earliest timestamp in the bucket
+    First,
+    /// The sample with the latest timestamp in the bucket
+    Last,
+}
+
+impl AggFn {
+    /// Combines `values`, which must not be empty
+    fn apply(self, values: &[f64]) -> f64 {
+        match self {
+            AggFn::Sum => values.iter().sum(),
+            AggFn::Mean => values.iter().sum::<f64>() / values.len() as f64,
+            AggFn::Min => values.iter().copied().fold(f64::INFINITY, f64::min),
+            AggFn::Max => values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+            AggFn::Count => values.len() as f64,
+            AggFn::First => values[0],
+            AggFn::Last => values[values.len() - 1],
+        }
+    }
+}
+
+/// Values ordered by their timestamps, at most one per timestamp
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimeSeries<V> {
+    samples: BTreeMap<u64, V>,
+}
+
+impl<V> TimeSeries<V> {
+    pub fn new() -> Self {
+        TimeSeries {
+            samples: BTreeMap::new(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    /// Records `value` at `timestamp`, returning any value it replaced
+    pub fn insert(&mut self, timestamp: u64, value: V) -> Option<V> {
+        self.samples.insert(timestamp, value)
+    }
+
+    pub fn get(&self, timestamp: u64) -> Option<&V> {
+        self.samples.get(&timestamp)
+    }
+
+    pub fn remove(&mut self, timestamp: u64) -> Option<V> {
+        self.samples.remove(&timestamp)
+    }
+
+    /// Iterates over the samples whose timestamps fall in `window`, oldest
+    /// first
+    pub fn range<R: RangeBounds<u64>>(
+        &self,
+        window: R,
+    ) -> impl DoubleEndedIterator<Item = (u64, &V)> {
+        self.samples
+            .range(window)
+            .map(|(&timestamp, value)| (timestamp, value))
+    }
+
+    pub fn earliest(&self) -> Option<(u64, &V)> {
+        self.samples
+            .iter()
+            .next()
+            .map(|(&timestamp, value)| (timestamp, value))
+    }
+
+    pub fn latest(&self) -> Option<(u64, &V)> {
+        self.samples
+            .iter()
+            .next_back()
+            .map(|(&timestamp, value)| (timestamp, value))
+    }
+
+    /// The timestamps of the earliest and latest samples, as a half-open
+    /// range that just covers them both
+    pub fn span(&self) -> Option<Range<u64>> {
+        let (start, _) = self.earliest()?;
+        let (end, _) = self.latest()?;
+        Some(start..end.saturating_add(1))
+    }
+
+    /// Iterates over every sample, oldest first
+    pub fn iter(&self) -> impl DoubleEndedIterator<Item = (u64, &V)> {
+        self.range(..)
+    }
+
+    /// Drops every sample older than `cutoff`, returning how many went
+    pub fn trim_before(&mut self, cutoff: u64) -> usize {
+        let kept = self.samples.split_off(&cutoff);
+        let dropped = self.samples.len();
+        self.samples = kept;
+        dropped
+    }
+
+    /// Keeps only the samples within `retention` of the latest one,
+    /// returning how many were dropped
+    pub fn retain_last(&mut self, retention: u64) -> usize {
+        match self.latest() {
+            Some((latest, _)) => self.trim_before(latest.saturating_sub(retention)),
+            None => 0,
+        }
+    }
+
+    /// Condenses the series into buckets `bucket` units wide, aligned to
+    /// multiples of `bucket`, with one aggregated sample per non-empty bucket
+    /// keyed by the bucket's start
+    ///
+    /// Panics if `bucket` is zero.
+    pub fn resample(&self, bucket: u64, agg: AggFn) -> TimeSeries<f64>
+    where
+        V: Copy + Into<f64>,
+    {
+        assert!(bucket > 0, "bucket width must be positive");
+        let mut resampled = TimeSeries::new();
+        let mut current: Option<u64> = None;
+        let mut values = Vec::new();
+
+        for (&timestamp, &value) in &self.samples {
+            let start = timestamp - timestamp % bucket;
+            if current != Some(start) {
+                if let Some(previous) = current {
+                    resampled.insert(previous, agg.apply(&values));
+                    values.clear();
+                }
+                current = Some(start);
+            }
+            values.push(value.into());
+        }
+        if let Some(previous) = current {
+            resampled.insert(previous, agg.apply(&values));
+        }
+        resampled
+    }
+}
+
+impl<V> Default for TimeSeries<V> {
+    fn default() -> Self {
+        TimeSeries::new()
+    }
+}
+
+impl<V> Extend<(u64, V)> for TimeSeries<V> {
+    fn extend<I: IntoIterator<Item = (u64, V)>>(&mut self, iter: I) {
+        self.samples.extend(iter);
+    }
+}
+
+impl<V> std::iter::FromIterator<(u64, V)> for TimeSeries<V> {
+    fn from_iter<I: IntoIterator<Item = (u64, V)>>(iter: I) -> Self {
+        TimeSeries {
+            samples: iter.into_iter().collect(),
+        }
+    }
+}
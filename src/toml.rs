@@ -0,0 +1,416 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Parsing a basic subset of TOML into a `JsonValue`
+//!
+//! A document becomes a `JsonValue::Object` whose tables are nested objects,
+//! so settings can be read back with `JsonValue::get_str_at` and the other
+//! typed path helpers, e.g. `config.get_i64_at(&["server", "port"])`. The
+//! parser accepts:
+//!
+//! - `[table]` and `[dotted.table]` headers, and `key = value` lines whose
+//!   keys may be bare, quoted or dotted
+//! - basic `"..."` strings with the usual backslash escapes, and literal
+//!   `'...'` strings taken as written
+//! - integers and floats, with `_` between digits, plus `inf` and `nan`
+//! - `true` and `false`
+//! - arrays of any of these, which may span several lines and end with a
+//!   trailing comma
+//! - `#` comments anywhere outside a string
+//!
+//! Arrays of tables, inline tables, multi-line strings and dates are not
+//! supported. Defining a key or table twice is an error, as in TOML itself.
+//!
+//! Integers are stored as `f64`, like every `JsonValue` number, so integers
+//! beyond 2^53 lose precision.
+
+use crate::json::{JsonObject, JsonValue};
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+// Deeper nesting of arrays than this is rejected rather than risking a stack
+// overflow
+const MAX_DEPTH: usize = 128;
+
+/// What went wrong in a TOML document
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TomlErrorKind {
+    UnexpectedEnd,
+    /// A character that can't start or continue anything here
+    UnexpectedChar(char),
+    /// A value that isn't a string, number, boolean or array
+    InvalidValue(String),
+    /// A backslash escape TOML doesn't define, or a malformed `\u` escape
+    InvalidEscape,
+    /// A string with no closing quote on its line
+    UnterminatedString,
+    /// A key that already has a value
+    DuplicateKey(String),
+    /// A `[table]` header that was already used
+    DuplicateTable(String),
+    /// A key used as a table that already holds a value
+    NotATable(String),
+    TooDeep,
+}
+
+/// A parse failure and the 1-based line it happened on
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TomlError {
+    pub line: usize,
+    pub kind: TomlErrorKind,
+}
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            TomlErrorKind::UnexpectedEnd => write!(f, "unexpected end of input"),
+            TomlErrorKind::UnexpectedChar(c) => write!(f, "unexpected character {:?}", c),
+            TomlErrorKind::InvalidValue(value) => write!(f, "invalid value '{}'", value),
+            TomlErrorKind::InvalidEscape => write!(f, "invalid escape sequence"),
+            TomlErrorKind::UnterminatedString => write!(f, "string is never closed"),
+            TomlErrorKind::DuplicateKey(key) => write!(f, "key '{}' is already defined", key),
+            TomlErrorKind::DuplicateTable(table) => {
+                write!(f, "table '{}' is already defined", table)
+            }
+            TomlErrorKind::NotATable(key) => write!(f, "'{}' is a value, not a table", key),
+            TomlErrorKind::TooDeep => write!(f, "nesting deeper than {} levels", MAX_DEPTH),
+        }
+    }
+}
+
+impl Error for TomlError {}
+
+/// Parses `text` into an object of its top-level keys and tables
+pub fn parse_toml_basic(text: &str) -> Result<JsonValue, TomlError> {
+    let mut parser = Parser { text, pos: 0 };
+    let mut root = JsonObject::new();
+    // The table the following keys belong to, and every header seen so far
+    let mut table: Vec<String> = Vec::new();
+    let mut headers = HashSet::new();
+
+    loop {
+        parser.skip_blank();
+        let start = parser.pos;
+        match parser.peek() {
+            None => return Ok(JsonValue::Object(root)),
+            Some('[') => {
+                parser.expect('[')?;
+                let path = parser.key_path()?;
+                parser.expect(']')?;
+                parser.end_of_line()?;
+                if !headers.insert(path.clone()) {
+                    return Err(
+                        parser.error_at(start, TomlErrorKind::DuplicateTable(path.join(".")))
+                    );
+                }
+                table_mut(&mut root, &path).map_err(|kind| parser.error_at(start, kind))?;
+                table = path;
+            }
+            Some(_) => {
+                let path = parser.key_path()?;
+                parser.expect('=')?;
+                let value = parser.value(0)?;
+                parser.end_of_line()?;
+                let path = [table.as_slice(), path.as_slice()].concat();
+                insert(&mut root, &path, value).map_err(|kind| parser.error_at(start, kind))?;
+            }
+        }
+    }
+}
+
+// The table at `path`, created along with any missing parents
+fn table_mut<'t>(
+    root: &'t mut JsonObject,
+    path: &[String],
+) -> Result<&'t mut JsonObject, TomlErrorKind> {
+    let mut table = root;
+    for (depth, key) in path.iter().enumerate() {
+        let entry = table
+            .entry(key.clone())
+            .or_insert_with(|| JsonValue::Object(JsonObject::new()));
+        table = match entry {
+            JsonValue::Object(members) => members,
+            _ => return Err(TomlErrorKind::NotATable(path[..=depth].join("."))),
+        };
+    }
+    Ok(table)
+}
+
+fn insert(root: &mut JsonObject, path: &[String], value: JsonValue) -> Result<(), TomlErrorKind> {
+    let (key, parents) = path.split_last().expect("a key path is never empty");
+    let table = table_mut(root, parents)?;
+    if table.contains_key(key) {
+        return Err(TomlErrorKind::DuplicateKey(path.join(".")));
+    }
+    table.insert(key.clone(), value);
+    Ok(())
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    // Byte offset of the next unread character
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn error_at(&self, pos: usize, kind: TomlErrorKind) -> TomlError {
+        TomlError {
+            line: self.text[..pos].matches('\n').count() + 1,
+            kind,
+        }
+    }
+
+    fn error(&self, kind: TomlErrorKind) -> TomlError {
+        self.error_at(self.pos, kind)
+    }
+
+    // The error for whatever is at the current position
+    fn unexpected(&self) -> TomlError {
+        match self.peek() {
+            Some(c) => self.error(TomlErrorKind::UnexpectedChar(c)),
+            None => self.error(TomlErrorKind::UnexpectedEnd),
+        }
+    }
+
+    // Expects `expected`, then skips any spaces after it
+    fn expect(&mut self, expected: char) -> Result<(), TomlError> {
+        if self.peek() != Some(expected) {
+            return Err(self.unexpected());
+        }
+        self.pos += expected.len_utf8();
+        self.skip_spaces();
+        Ok(())
+    }
+
+    fn skip_spaces(&mut self) {
+        while let Some(' ') | Some('\t') = self.peek() {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_comment(&mut self) {
+        if self.peek() == Some('#') {
+            self.pos = self.text[self.pos..]
+                .find('\n')
+                .map_or(self.text.len(), |newline| self.pos + newline);
+        }
+    }
+
+    // Skips whitespace, line breaks and comments
+    fn skip_blank(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ') | Some('\t') | Some('\r') | Some('\n') => self.pos += 1,
+                Some('#') => self.skip_comment(),
+                _ => return,
+            }
+        }
+    }
+
+    // Only spaces and a comment may follow a header or a value on its line
+    fn end_of_line(&mut self) -> Result<(), TomlError> {
+        self.skip_spaces();
+        self.skip_comment();
+        if self.text[self.pos..].starts_with("\r\n") {
+            self.pos += 2;
+            return Ok(());
+        }
+        match self.peek() {
+            None => Ok(()),
+            Some('\n') => {
+                self.pos += 1;
+                Ok(())
+            }
+            Some(_) => Err(self.unexpected()),
+        }
+    }
+
+    // A key such as `name`, `"quoted name"` or `server.http.port`, and the
+    // spaces after it
+    fn key_path(&mut self) -> Result<Vec<String>, TomlError> {
+        let mut path = Vec::new();
+        loop {
+            let key = match self.peek() {
+                Some('"') => self.basic_string()?,
+                Some('\'') => self.literal_string()?,
+                _ => {
+                    let key = self.bare(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+                    if key.is_empty() {
+                        return Err(self.unexpected());
+                    }
+                    key.to_string()
+                }
+            };
+            path.push(key);
+            self.skip_spaces();
+            if self.peek() != Some('.') {
+                return Ok(path);
+            }
+            self.expect('.')?;
+        }
+    }
+
+    // The run of characters matching `accept` at the current position
+    fn bare<F: Fn(char) -> bool>(&mut self, accept: F) -> &'a str {
+        let start = self.pos;
+        while self.peek().is_some_and(&accept) {
+            self.pos += 1;
+        }
+        &self.text[start..self.pos]
+    }
+
+    fn value(&mut self, depth: usize) -> Result<JsonValue, TomlError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error(TomlErrorKind::TooDeep));
+        }
+        match self.peek() {
+            Some('"') => self.basic_string().map(JsonValue::String),
+            Some('\'') => self.literal_string().map(JsonValue::String),
+            Some('[') => self.array(depth),
+            Some(c) if c.is_ascii_alphanumeric() || c == '+' || c == '-' => {
+                let start = self.pos;
+                let word = self.bare(|c| c.is_ascii_alphanumeric() || "+-._".contains(c));
+                scalar(word).ok_or_else(|| {
+                    self.error_at(start, TomlErrorKind::InvalidValue(word.to_string()))
+                })
+            }
+            _ => Err(self.unexpected()),
+        }
+    }
+
+    fn array(&mut self, depth: usize) -> Result<JsonValue, TomlError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        loop {
+            self.skip_blank();
+            if self.peek() == Some(']') {
+                self.pos += 1;
+                return Ok(JsonValue::Array(items));
+            }
+            items.push(self.value(depth + 1)?);
+            self.skip_blank();
+            match self.peek() {
+                Some(',') => self.pos += 1,
+                Some(']') => {
+                    self.pos += 1;
+                    return Ok(JsonValue::Array(items));
+                }
+                _ => return Err(self.unexpected()),
+            }
+        }
+    }
+
+    fn basic_string(&mut self) -> Result<String, TomlError> {
+        self.pos += 1;
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => return Err(self.error(TomlErrorKind::UnterminatedString)),
+                Some('"') => return Ok(s),
+                Some('\\') => {
+                    let escaped = match self.bump() {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => self.unicode_escape(4)?,
+                        Some('U') => self.unicode_escape(8)?,
+                        _ => return Err(self.error(TomlErrorKind::InvalidEscape)),
+                    };
+                    s.push(escaped);
+                }
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn literal_string(&mut self) -> Result<String, TomlError> {
+        self.pos += 1;
+        let rest = &self.text[self.pos..];
+        match rest.find(['\'', '\n']) {
+            Some(end) if rest[end..].starts_with('\'') => {
+                self.pos += end + 1;
+                Ok(rest[..end].to_string())
+            }
+            _ => Err(self.error(TomlErrorKind::UnterminatedString)),
+        }
+    }
+
+    fn unicode_escape(&mut self, len: usize) -> Result<char, TomlError> {
+        let code = self
+            .text
+            .get(self.pos..self.pos + len)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .and_then(|digits| u32::from_str_radix(digits, 16).ok())
+            .and_then(std::char::from_u32)
+            .ok_or_else(|| self.error(TomlErrorKind::InvalidEscape))?;
+        self.pos += len;
+        Ok(code)
+    }
+}
+
+// A boolean or a number written as `word`
+fn scalar(word: &str) -> Option<JsonValue> {
+    match word {
+        "true" => return Some(JsonValue::Bool(true)),
+        "false" => return Some(JsonValue::Bool(false)),
+        "inf" | "+inf" => return Some(JsonValue::Number(f64::INFINITY)),
+        "-inf" => return Some(JsonValue::Number(f64::NEG_INFINITY)),
+        "nan" | "+nan" | "-nan" => return Some(JsonValue::Number(f64::NAN)),
+        _ => {}
+    }
+
+    // Underscores may only sit between two digits
+    let bytes = word.as_bytes();
+    let separated = bytes.iter().enumerate().all(|(index, &b)| {
+        b != b'_'
+            || (index > 0
+                && bytes[index - 1].is_ascii_digit()
+                && bytes.get(index + 1).is_some_and(u8::is_ascii_digit))
+    });
+    if !separated {
+        return None;
+    }
+    let number = word.replace('_', "");
+    let unsigned = number.trim_start_matches(['+', '-']);
+    // A sign may only come once, and leading zeros aren't allowed
+    if number.len() - unsigned.len() > 1 || !unsigned.starts_with(|c: char| c.is_ascii_digit()) {
+        return None;
+    }
+    let integer_part = unsigned
+        .find(|c: char| !c.is_ascii_digit())
+        .map_or(unsigned, |end| &unsigned[..end]);
+    if integer_part.len() > 1 && integer_part.starts_with('0') {
+        return None;
+    }
+
+    if unsigned.contains(['.', 'e', 'E']) {
+        // A decimal point needs a digit on both sides
+        if let Some(point) = unsigned.find('.') {
+            if !unsigned[point + 1..].starts_with(|c: char| c.is_ascii_digit()) {
+                return None;
+            }
+        }
+        number.parse().ok().map(JsonValue::Number)
+    } else {
+        number
+            .parse::<i64>()
+            .ok()
+            .map(|n| JsonValue::Number(n as f64))
+    }
+}
@@ -0,0 +1,345 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A map whose entries expire after a per-entry time-to-live
+//!
+//! Expired entries are invisible to every read. They are dropped lazily when
+//! a lookup runs into them, or all at once with `purge_expired`.
+
+use std::cell::Cell;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::iter::FromIterator;
+use std::time::{Duration, Instant};
+
+/// A source of the current time for `TtlMap`
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+/// The real clock, backed by `Instant::now`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when told to, for deterministic tests
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Cell<Instant>,
+}
+
+impl ManualClock {
+    pub fn new() -> Self {
+        ManualClock {
+            now: Cell::new(Instant::now()),
+        }
+    }
+
+    /// Moves the clock forward by `by`
+    pub fn advance(&self, by: Duration) {
+        self.now.set(self.now.get() + by);
+    }
+}
+
+impl Default for ManualClock {
+    fn default() -> Self {
+        ManualClock::new()
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
+
+impl<C: Clock> Clock for &C {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Slot<V> {
+    value: V,
+    // `None` means the entry never expires
+    expires_at: Option<Instant>,
+}
+
+impl<V> Slot<V> {
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|at| now >= at)
+    }
+}
+
+/// A `HashMap` whose entries can be given a time-to-live
+#[derive(Debug, Clone)]
+pub struct TtlMap<K, V, C = SystemClock> {
+    entries: HashMap<K, Slot<V>>,
+    clock: C,
+}
+
+impl<K: Eq + Hash, V> TtlMap<K, V> {
+    /// Creates an empty map driven by the system clock
+    pub fn new() -> Self {
+        TtlMap::with_clock(SystemClock)
+    }
+}
+
+impl<K: Eq + Hash, V> Default for TtlMap<K, V> {
+    fn default() -> Self {
+        TtlMap::new()
+    }
+}
+
+// Entries collected or extended into a map never expire; use
+// `insert_with_ttl` for entries that should
+
+impl<K: Eq + Hash, V> FromIterator<(K, V)> for TtlMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = TtlMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K: Eq + Hash, V, C: Clock> Extend<(K, V)> for TtlMap<K, V, C> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Eq + Hash, V, C: Clock> TtlMap<K, V, C> {
+    /// Creates an empty map driven by `clock`
+    pub fn with_clock(clock: C) -> Self {
+        TtlMap {
+            entries: HashMap::new(),
+            clock,
+        }
+    }
+
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Inserts an entry that never expires, returning the previous live value
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.insert_slot(key, value, None)
+    }
+
+    /// Inserts an entry that expires `ttl` from now, returning the previous
+    /// live value
+    ///
+    /// A `ttl` too long for the clock to represent, such as `Duration::MAX`,
+    /// means the entry never expires.
+    pub fn insert_with_ttl(&mut self, key: K, value: V, ttl: Duration) -> Option<V> {
+        let expires_at = self.clock.now().checked_add(ttl);
+        self.insert_slot(key, value, expires_at)
+    }
+
+    /// Looks up a live entry, dropping it if it has expired
+    pub fn get(&mut self, key: &K) -> Option<&V> {
+        self.purge_if_expired(key);
+        self.entries.get(key).map(|slot| &slot.value)
+    }
+
+    /// Like `get`, but hands back a mutable reference
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.purge_if_expired(key);
+        self.entries.get_mut(key).map(|slot| &mut slot.value)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        let now = self.clock.now();
+        self.entries
+            .get(key)
+            .is_some_and(|slot| !slot.is_expired(now))
+    }
+
+    /// Returns how long a live entry has left, or `None` if it never expires
+    /// or is not present
+    pub fn time_to_live(&self, key: &K) -> Option<Duration> {
+        let now = self.clock.now();
+        let slot = self.entries.get(key)?;
+        slot.expires_at
+            .filter(|_| !slot.is_expired(now))
+            .map(|at| at - now)
+    }
+
+    /// Removes an entry, returning its value if it was still live
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let now = self.clock.now();
+        self.entries
+            .remove(key)
+            .filter(|slot| !slot.is_expired(now))
+            .map(|slot| slot.value)
+    }
+
+    /// Drops every expired entry, returning how many were removed
+    pub fn purge_expired(&mut self) -> usize {
+        let now = self.clock.now();
+        let before = self.entries.len();
+        self.entries.retain(|_, slot| !slot.is_expired(now));
+        before - self.entries.len()
+    }
+
+    /// Returns the number of live entries
+    pub fn len(&self) -> usize {
+        let now = self.clock.now();
+        self.entries
+            .values()
+            .filter(|slot| !slot.is_expired(now))
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Iterates over the live entries in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        let now = self.clock.now();
+        self.entries
+            .iter()
+            .filter(move |(_, slot)| !slot.is_expired(now))
+            .map(|(key, slot)| (key, &slot.value))
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    fn insert_slot(&mut self, key: K, value: V, expires_at: Option<Instant>) -> Option<V> {
+        let now = self.clock.now();
+        self.entries
+            .insert(key, Slot { value, expires_at })
+            .filter(|old| !old.is_expired(now))
+            .map(|old| old.value)
+    }
+
+    fn purge_if_expired(&mut self, key: &K) {
+        let now = self.clock.now();
+        if self
+            .entries
+            .get(key)
+            .is_some_and(|slot| slot.is_expired(now))
+        {
+            self.entries.remove(key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TTL: Duration = Duration::from_secs(10);
+
+    #[test]
+    fn entries_vanish_once_their_ttl_is_up() {
+        let clock = ManualClock::new();
+        let mut map = TtlMap::with_clock(&clock);
+        map.insert_with_ttl("short", 1, TTL);
+        map.insert("forever", 2);
+
+        clock.advance(TTL - Duration::from_secs(1));
+        assert_eq!(map.get(&"short"), Some(&1));
+        assert_eq!(map.time_to_live(&"short"), Some(Duration::from_secs(1)));
+        assert_eq!(map.len(), 2);
+
+        // Expiry is inclusive of the instant the TTL runs out
+        clock.advance(Duration::from_secs(1));
+        assert_eq!(map.get(&"short"), None);
+        assert!(!map.contains_key(&"short"));
+        assert_eq!(map.time_to_live(&"short"), None);
+        assert_eq!(map.get(&"forever"), Some(&2));
+        assert_eq!(map.time_to_live(&"forever"), None);
+        assert_eq!(map.len(), 1);
+        assert_eq!(map.iter().collect::<Vec<_>>(), vec![(&"forever", &2)]);
+    }
+
+    #[test]
+    fn lookups_drop_the_expired_entry_they_find() {
+        let clock = ManualClock::new();
+        let mut map = TtlMap::with_clock(&clock);
+        map.insert_with_ttl("a", 1, TTL);
+        map.insert_with_ttl("b", 2, TTL);
+        clock.advance(TTL);
+
+        // Reads that take `&self` leave the entry in place
+        assert!(!map.contains_key(&"a"));
+        assert_eq!(map.entries.len(), 2);
+
+        assert_eq!(map.get(&"a"), None);
+        assert_eq!(map.entries.len(), 1);
+        assert_eq!(map.get_mut(&"b"), None);
+        assert!(map.entries.is_empty());
+        assert_eq!(map.purge_expired(), 0);
+    }
+
+    #[test]
+    fn purge_expired_counts_what_it_drops() {
+        let clock = ManualClock::new();
+        let mut map = TtlMap::with_clock(&clock);
+        for i in 0..5 {
+            map.insert_with_ttl(i, i, Duration::from_secs(i as u64 + 1));
+        }
+        map.insert(99, 99);
+        clock.advance(Duration::from_secs(3));
+        assert_eq!(map.purge_expired(), 3);
+        assert_eq!(map.entries.len(), 3);
+        assert_eq!(map.purge_expired(), 0);
+        let mut live: Vec<i32> = map.iter().map(|(&key, _)| key).collect();
+        live.sort_unstable();
+        assert_eq!(live, vec![3, 4, 99]);
+    }
+
+    #[test]
+    fn reinserting_resets_the_ttl() {
+        let clock = ManualClock::new();
+        let mut map = TtlMap::with_clock(&clock);
+        map.insert_with_ttl("key", 1, TTL);
+        clock.advance(Duration::from_secs(8));
+        assert_eq!(map.insert_with_ttl("key", 2, TTL), Some(1));
+        clock.advance(Duration::from_secs(8));
+        assert_eq!(map.get(&"key"), Some(&2));
+        assert_eq!(map.time_to_live(&"key"), Some(Duration::from_secs(2)));
+
+        // An expired value isn't handed back as the previous one
+        clock.advance(Duration::from_secs(2));
+        assert_eq!(map.insert_with_ttl("key", 3, TTL), None);
+        assert_eq!(map.insert("key", 4), Some(3));
+        clock.advance(TTL * 100);
+        assert_eq!(map.get(&"key"), Some(&4));
+    }
+
+    #[test]
+    fn a_ttl_past_the_clock_never_expires() {
+        let clock = ManualClock::new();
+        let mut map = TtlMap::with_clock(&clock);
+        assert_eq!(map.insert_with_ttl("key", 1, Duration::MAX), None);
+        assert_eq!(map.time_to_live(&"key"), None);
+        clock.advance(TTL * 1000);
+        assert_eq!(map.get(&"key"), Some(&1));
+        assert_eq!(map.purge_expired(), 0);
+    }
+
+    #[test]
+    fn removing_an_expired_entry_returns_nothing() {
+        let clock = ManualClock::new();
+        let mut map = TtlMap::with_clock(&clock);
+        map.insert_with_ttl("a", 1, TTL);
+        map.insert_with_ttl("b", 2, TTL);
+        assert_eq!(map.remove(&"a"), Some(1));
+        clock.advance(TTL);
+        assert_eq!(map.remove(&"b"), None);
+        assert!(map.is_empty());
+    }
+}
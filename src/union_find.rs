@@ -0,0 +1,206 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Disjoint sets that can be merged and queried for membership
+//!
+//! `UnionFind` tracks which of the elements `0..n` belong together. Each set
+//! is a tree whose root stands for the whole set; `union` hangs the smaller
+//! tree under the larger root and `find` points every node it passes closer
+//! to the root (path halving), so both run in effectively constant amortised
+//! time. `KeyedUnionFind` does the same for arbitrary hashable items by
+//! numbering them as they are first seen.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Disjoint sets over the elements `0..len()`
+///
+/// Methods taking an element panic if it is out of range.
+#[derive(Debug, Clone, Default)]
+pub struct UnionFind {
+    parent: Vec<usize>,
+    // Only meaningful at roots: the number of elements in that set
+    size: Vec<usize>,
+    sets: usize,
+}
+
+impl UnionFind {
+    /// Creates `n` singleton sets, one for each element
+    pub fn new(n: usize) -> Self {
+        UnionFind {
+            parent: (0..n).collect(),
+            size: vec![1; n],
+            sets: n,
+        }
+    }
+
+    /// Number of elements across every set
+    pub fn len(&self) -> usize {
+        self.parent.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parent.is_empty()
+    }
+
+    /// Adds a new element in a set of its own and returns it
+    pub fn add(&mut self) -> usize {
+        let element = self.parent.len();
+        self.parent.push(element);
+        self.size.push(1);
+        self.sets += 1;
+        element
+    }
+
+    /// Returns the representative of the set containing `x`
+    pub fn find(&mut self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            let grandparent = self.parent[self.parent[x]];
+            self.parent[x] = grandparent;
+            x = grandparent;
+        }
+        x
+    }
+
+    /// Merges the sets containing `a` and `b`, returning `false` if they
+    /// were already the same set
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return false;
+        }
+        if self.size[a] < self.size[b] {
+            std::mem::swap(&mut a, &mut b);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+        self.sets -= 1;
+        true
+    }
+
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// Number of elements in the set containing `x`
+    pub fn set_size(&mut self, x: usize) -> usize {
+        let root = self.find(x);
+        self.size[root]
+    }
+
+    /// Number of disjoint sets
+    pub fn set_count(&self) -> usize {
+        self.sets
+    }
+
+    /// Returns every set, each sorted, ordered by its smallest element
+    pub fn groups(&mut self) -> Vec<Vec<usize>> {
+        let mut slots: HashMap<usize, usize> = HashMap::with_capacity(self.sets);
+        let mut groups: Vec<Vec<usize>> = Vec::with_capacity(self.sets);
+        for x in 0..self.len() {
+            let root = self.find(x);
+            let slot = *slots.entry(root).or_insert_with(|| {
+                groups.push(Vec::new());
+                groups.len() - 1
+            });
+            groups[slot].push(x);
+        }
+        groups
+    }
+}
+
+/// Disjoint sets over arbitrary items, which join as singletons the first
+/// time they are mentioned
+#[derive(Debug, Clone)]
+pub struct KeyedUnionFind<T> {
+    indices: HashMap<T, usize>,
+    items: Vec<T>,
+    sets: UnionFind,
+}
+
+impl<T: Eq + Hash + Clone> KeyedUnionFind<T> {
+    pub fn new() -> Self {
+        KeyedUnionFind {
+            indices: HashMap::new(),
+            items: Vec::new(),
+            sets: UnionFind::default(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Adds `item` in a set of its own, returning `false` if it was already
+    /// known
+    pub fn insert(&mut self, item: T) -> bool {
+        if self.indices.contains_key(&item) {
+            return false;
+        }
+        self.index_of(item);
+        true
+    }
+
+    pub fn contains(&self, item: &T) -> bool {
+        self.indices.contains_key(item)
+    }
+
+    /// Returns the representative of the set containing `item`, or `None`
+    /// if the item has never been seen
+    pub fn find(&mut self, item: &T) -> Option<&T> {
+        let index = *self.indices.get(item)?;
+        let root = self.sets.find(index);
+        Some(&self.items[root])
+    }
+
+    /// Merges the sets containing `a` and `b`, adding either item if it is
+    /// new, and returns `false` if they were already the same set
+    pub fn union(&mut self, a: T, b: T) -> bool {
+        let a = self.index_of(a);
+        let b = self.index_of(b);
+        self.sets.union(a, b)
+    }
+
+    /// Whether both items are known and in the same set
+    pub fn connected(&mut self, a: &T, b: &T) -> bool {
+        match (self.indices.get(a), self.indices.get(b)) {
+            (Some(&a), Some(&b)) => self.sets.connected(a, b),
+            _ => false,
+        }
+    }
+
+    pub fn set_count(&self) -> usize {
+        self.sets.set_count()
+    }
+
+    /// Returns every set, with items and sets in the order the items were
+    /// first seen
+    pub fn groups(&mut self) -> Vec<Vec<&T>> {
+        let items = &self.items;
+        self.sets
+            .groups()
+            .into_iter()
+            .map(|group| group.into_iter().map(|index| &items[index]).collect())
+            .collect()
+    }
+
+    fn index_of(&mut self, item: T) -> usize {
+        if let Some(&index) = self.indices.get(&item) {
+            return index;
+        }
+        let index = self.sets.add();
+        self.items.push(item.clone());
+        self.indices.insert(item, index);
+        index
+    }
+}
+
+impl<T: Eq + Hash + Clone> Default for KeyedUnionFind<T> {
+    fn default() -> Self {
+        KeyedUnionFind::new()
+    }
+}
@@ -0,0 +1,59 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Closure-based wrappers around the `HashMap` entry API
+//!
+//! `or_insert` covers the simple case of filling in a missing value. These
+//! helpers cover the rest of the usual `match map.entry(key)` block, where a
+//! missing key is inserted one way and an existing value is updated another.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::hash::{BuildHasher, Hash};
+
+/// Inserts `insert_fn()` if `key` is missing, otherwise runs `update_fn` on
+/// the existing value, and returns the value either way
+pub fn upsert<K, V, S, I, U>(
+    map: &mut HashMap<K, V, S>,
+    key: K,
+    insert_fn: I,
+    update_fn: U,
+) -> &mut V
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+    I: FnOnce() -> V,
+    U: FnOnce(&mut V),
+{
+    match map.entry(key) {
+        Entry::Occupied(entry) => {
+            let value = entry.into_mut();
+            update_fn(value);
+            value
+        }
+        Entry::Vacant(entry) => entry.insert(insert_fn()),
+    }
+}
+
+/// Inserts `value` if `key` is missing, otherwise folds it into the existing
+/// value with `combine_fn`, and returns the stored value either way
+pub fn merge_value<K, V, S, F>(
+    map: &mut HashMap<K, V, S>,
+    key: K,
+    value: V,
+    combine_fn: F,
+) -> &mut V
+where
+    K: Eq + Hash,
+    S: BuildHasher,
+    F: FnOnce(&mut V, V),
+{
+    match map.entry(key) {
+        Entry::Occupied(entry) => {
+            let existing = entry.into_mut();
+            combine_fn(existing, value);
+            existing
+        }
+        Entry::Vacant(entry) => entry.insert(value),
+    }
+}
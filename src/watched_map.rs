@@ -0,0 +1,192 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A `HashMap` that tells listeners about every change made to it
+//!
+//! Each insert, update and removal calls every registered listener with a
+//! `MapEvent` carrying the values involved, which is enough to refresh a view
+//! or drop a stale cache entry. The map offers no `get_mut`, since a change
+//! made through a bare reference couldn't be reported; replace the value with
+//! `insert` instead.
+//!
+//! Notifications can be paused around a bulk load, then resumed; events that
+//! happen while paused are dropped rather than queued.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+/// A change to a `WatchedMap`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapEvent<'a, K, V> {
+    /// `key` was not present and now maps to `value`
+    Inserted { key: &'a K, value: &'a V },
+    /// `key` mapped to `old` and now maps to `new`
+    Updated { key: &'a K, old: &'a V, new: &'a V },
+    /// `key` mapped to `value` and has been removed
+    Removed { key: &'a K, value: &'a V },
+}
+
+/// Identifies a listener so it can be unsubscribed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ListenerId(u64);
+
+type Listener<K, V> = Box<dyn FnMut(&MapEvent<'_, K, V>)>;
+
+/// A `HashMap` that reports its changes to listeners
+pub struct WatchedMap<K, V> {
+    map: HashMap<K, V>,
+    listeners: Vec<(ListenerId, Listener<K, V>)>,
+    next_id: u64,
+    paused: bool,
+}
+
+impl<K: Eq + Hash, V> WatchedMap<K, V> {
+    pub fn new() -> Self {
+        WatchedMap {
+            map: HashMap::new(),
+            listeners: Vec::new(),
+            next_id: 0,
+            paused: false,
+        }
+    }
+
+    /// Registers `listener` to be called after every change
+    pub fn subscribe<F>(&mut self, listener: F) -> ListenerId
+    where
+        F: FnMut(&MapEvent<'_, K, V>) + 'static,
+    {
+        let id = ListenerId(self.next_id);
+        self.next_id += 1;
+        self.listeners.push((id, Box::new(listener)));
+        id
+    }
+
+    /// Removes a listener, returning false if it was already gone
+    pub fn unsubscribe(&mut self, id: ListenerId) -> bool {
+        let before = self.listeners.len();
+        self.listeners.retain(|(listener_id, _)| *listener_id != id);
+        self.listeners.len() < before
+    }
+
+    /// Stops notifying listeners until `resume` is called
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self) {
+        self.paused = false;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.map.get(key)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map.contains_key(key)
+    }
+
+    /// Inserts `value`, reporting `Inserted` for a new key or `Updated` for
+    /// an existing one
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.map.entry(key) {
+            Entry::Occupied(mut entry) => {
+                let old = entry.insert(value);
+                let event = MapEvent::Updated {
+                    key: entry.key(),
+                    old: &old,
+                    new: entry.get(),
+                };
+                notify(&mut self.listeners, self.paused, &event);
+                Some(old)
+            }
+            Entry::Vacant(entry) => {
+                let entry = entry.insert_entry(value);
+                let event = MapEvent::Inserted {
+                    key: entry.key(),
+                    value: entry.get(),
+                };
+                notify(&mut self.listeners, self.paused, &event);
+                None
+            }
+        }
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let (key, value) = self.map.remove_entry(key)?;
+        notify(
+            &mut self.listeners,
+            self.paused,
+            &MapEvent::Removed {
+                key: &key,
+                value: &value,
+            },
+        );
+        Some(value)
+    }
+
+    /// Removes every entry, reporting a `Removed` event for each
+    pub fn clear(&mut self) {
+        for (key, value) in self.map.drain() {
+            notify(
+                &mut self.listeners,
+                self.paused,
+                &MapEvent::Removed {
+                    key: &key,
+                    value: &value,
+                },
+            );
+        }
+    }
+
+    pub fn iter(&self) -> std::collections::hash_map::Iter<'_, K, V> {
+        self.map.iter()
+    }
+
+    /// Gives back the underlying map, dropping the listeners
+    pub fn into_inner(self) -> HashMap<K, V> {
+        self.map
+    }
+}
+
+fn notify<K, V>(
+    listeners: &mut [(ListenerId, Listener<K, V>)],
+    paused: bool,
+    event: &MapEvent<'_, K, V>,
+) {
+    if paused {
+        return;
+    }
+    for (_, listener) in listeners.iter_mut() {
+        listener(event);
+    }
+}
+
+impl<K: Eq + Hash, V> Default for WatchedMap<K, V> {
+    fn default() -> Self {
+        WatchedMap::new()
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for WatchedMap<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WatchedMap")
+            .field("map", &self.map)
+            .field("listeners", &self.listeners.len())
+            .field("paused", &self.paused)
+            .finish()
+    }
+}
@@ -0,0 +1,115 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! A map that holds its values weakly
+//!
+//! `WeakValueMap` stores `Weak<V>` handles, so it never keeps a value alive on
+//! its own. Once every `Rc<V>` outside the map is dropped, the entry is dead:
+//! `get` stops returning it and `prune` clears it out. That makes it a good
+//! index for shared resources that should be freed as soon as nobody uses
+//! them.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::rc::{Rc, Weak};
+
+/// How many insertions and removals happen between automatic prunes
+pub const DEFAULT_PRUNE_INTERVAL: usize = 64;
+
+/// A `HashMap` of `Weak<V>` that forgets values once they are dropped
+#[derive(Debug)]
+pub struct WeakValueMap<K, V> {
+    map: HashMap<K, Weak<V>>,
+    prune_interval: usize,
+    ops_since_prune: usize,
+}
+
+impl<K: Eq + Hash, V> WeakValueMap<K, V> {
+    pub fn new() -> Self {
+        WeakValueMap::with_prune_interval(DEFAULT_PRUNE_INTERVAL)
+    }
+
+    /// Creates a map that prunes dead entries after every `interval`
+    /// insertions or removals; an interval of zero turns automatic pruning off
+    pub fn with_prune_interval(interval: usize) -> Self {
+        WeakValueMap {
+            map: HashMap::new(),
+            prune_interval: interval,
+            ops_since_prune: 0,
+        }
+    }
+
+    /// Stores a weak handle to `value`, returning the previous value if it
+    /// was still alive
+    pub fn insert(&mut self, key: K, value: &Rc<V>) -> Option<Rc<V>> {
+        let old = self.map.insert(key, Rc::downgrade(value));
+        self.count_op();
+        old.and_then(|weak| weak.upgrade())
+    }
+
+    /// Returns the value for `key` if it is still alive
+    pub fn get(&self, key: &K) -> Option<Rc<V>> {
+        self.map.get(key).and_then(Weak::upgrade)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.map
+            .get(key)
+            .is_some_and(|weak| weak.strong_count() > 0)
+    }
+
+    /// Removes `key`, returning its value if it was still alive
+    pub fn remove(&mut self, key: &K) -> Option<Rc<V>> {
+        let old = self.map.remove(key);
+        self.count_op();
+        old.and_then(|weak| weak.upgrade())
+    }
+
+    /// Drops every dead entry, returning how many were removed
+    pub fn prune(&mut self) -> usize {
+        let before = self.map.len();
+        self.map.retain(|_, weak| weak.strong_count() > 0);
+        self.ops_since_prune = 0;
+        before - self.map.len()
+    }
+
+    /// Returns the number of live entries
+    pub fn len(&self) -> usize {
+        self.map
+            .values()
+            .filter(|weak| weak.strong_count() > 0)
+            .count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of stored entries, dead ones included
+    pub fn raw_len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Iterates over the live entries in arbitrary order
+    pub fn iter(&self) -> impl Iterator<Item = (&K, Rc<V>)> {
+        self.map
+            .iter()
+            .filter_map(|(key, weak)| weak.upgrade().map(|value| (key, value)))
+    }
+
+    fn count_op(&mut self) {
+        if self.prune_interval == 0 {
+            return;
+        }
+        self.ops_since_prune += 1;
+        if self.ops_since_prune >= self.prune_interval {
+            self.prune();
+        }
+    }
+}
+
+impl<K: Eq + Hash, V> Default for WeakValueMap<K, V> {
+    fn default() -> Self {
+        WeakValueMap::new()
+    }
+}
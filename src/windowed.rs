@@ -0,0 +1,122 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Sliding windows over any iterator
+//!
+//! `slice::windows` needs the whole input in a slice and always moves one
+//! item at a time. `windowed` buffers just one window's worth of items in a
+//! `VecDeque`, so it works on streams, and moves `step` items between
+//! windows: less than `size` overlaps them, more than `size` skips items
+//! between them. When the input doesn't end on a window boundary, the
+//! trailing shorter windows are dropped unless `emit_partial` asks for them.
+
+use std::collections::VecDeque;
+
+/// Yields windows of `size` consecutive items from `iter`, starting a new
+/// window every `step` items
+///
+/// Panics if `size` or `step` is zero.
+pub fn windowed<I>(iter: I, size: usize, step: usize) -> Windowed<I::IntoIter>
+where
+    I: IntoIterator,
+    I::Item: Clone,
+{
+    assert!(size > 0, "window size must be positive");
+    assert!(step > 0, "window step must be positive");
+    Windowed {
+        iter: iter.into_iter(),
+        buffer: VecDeque::with_capacity(size),
+        size,
+        step,
+        partial: false,
+        exhausted: false,
+    }
+}
+
+/// Iterator returned by `windowed`
+#[derive(Debug, Clone)]
+pub struct Windowed<I: Iterator> {
+    iter: I,
+    buffer: VecDeque<I::Item>,
+    size: usize,
+    step: usize,
+    partial: bool,
+    // Set once `iter` has returned `None`, so it isn't polled again
+    exhausted: bool,
+}
+
+impl<I: Iterator> Windowed<I> {
+    /// Whether to also yield the windows at the end that have fewer than
+    /// `size` items
+    pub fn emit_partial(mut self, partial: bool) -> Self {
+        self.partial = partial;
+        self
+    }
+
+    fn pull(&mut self) -> Option<I::Item> {
+        if self.exhausted {
+            return None;
+        }
+        let item = self.iter.next();
+        self.exhausted = item.is_none();
+        item
+    }
+
+    // How many windows `remaining` more items would produce
+    fn windows_for(&self, remaining: usize) -> usize {
+        if self.partial {
+            remaining.div_ceil(self.step)
+        } else if remaining >= self.size {
+            (remaining - self.size) / self.step + 1
+        } else {
+            0
+        }
+    }
+}
+
+impl<I> Iterator for Windowed<I>
+where
+    I: Iterator,
+    I::Item: Clone,
+{
+    type Item = Vec<I::Item>;
+
+    fn next(&mut self) -> Option<Vec<I::Item>> {
+        while self.buffer.len() < self.size {
+            match self.pull() {
+                Some(item) => self.buffer.push_back(item),
+                None => break,
+            }
+        }
+        if self.buffer.is_empty() || (self.buffer.len() < self.size && !self.partial) {
+            return None;
+        }
+        let window = self.buffer.iter().cloned().collect();
+
+        // Move the window start on by `step`, discarding items that fall
+        // between windows when the step is larger than the window
+        let dropped = self.step.min(self.buffer.len());
+        self.buffer.drain(..dropped);
+        for _ in dropped..self.step {
+            if self.pull().is_none() {
+                break;
+            }
+        }
+        Some(window)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = if self.exhausted {
+            (0, Some(0))
+        } else {
+            self.iter.size_hint()
+        };
+        let buffered = self.buffer.len();
+        (
+            self.windows_for(lower.saturating_add(buffered)),
+            upper
+                .and_then(|upper| upper.checked_add(buffered))
+                .map(|upper| self.windows_for(upper)),
+        )
+    }
+}
@@ -0,0 +1,294 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Counting the words of input too large for one `HashMap` of counts
+//!
+//! `stream_word_count` counts into a `HashMap` until its estimated size goes
+//! over a memory budget, then writes the counts so far to a temporary file
+//! sorted by word, empties the map and carries on. At the end the sorted
+//! runs are merged with `merge_sorted`, adding up the counts of each word as
+//! it comes past, so the result is produced in word order without ever
+//! holding more than one word per run in memory.
+//!
+//! Words are runs of non-whitespace, counted exactly as written, and input
+//! is read a line at a time. The budget covers the map's table and the text
+//! of its keys as `capacity::table_bytes` estimates them; the table can
+//! overshoot it briefly while it grows. At most `MERGE_WIDTH` runs are open
+//! at once: with more than that, groups of them are first merged into
+//! longer runs, as many times over as it takes.
+
+use crate::capacity;
+use crate::merge_sorted::{merge_sorted, MergeSorted};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Lines, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::vec;
+
+/// Most spill files merged at once
+pub const MERGE_WIDTH: usize = 64;
+
+// Numbers the spill files of every count in this process
+static NEXT_RUN: AtomicUsize = AtomicUsize::new(0);
+
+/// Counts the words read from `reader`, keeping the map of counts under
+/// roughly `memory_budget` bytes and spilling to the system temporary
+/// directory when it grows past that
+pub fn stream_word_count<R: BufRead>(reader: R, memory_budget: usize) -> io::Result<WordCounts> {
+    stream_word_count_in(reader, memory_budget, &env::temp_dir())
+}
+
+/// `stream_word_count`, spilling into `dir` instead
+pub fn stream_word_count_in<R: BufRead>(
+    mut reader: R,
+    memory_budget: usize,
+    dir: &Path,
+) -> io::Result<WordCounts> {
+    let mut counts: HashMap<String, u64> = HashMap::new();
+    let mut text_bytes = 0;
+    let mut runs = Vec::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 {
+        for word in line.split_whitespace() {
+            if let Some(count) = counts.get_mut(word) {
+                *count += 1;
+                continue;
+            }
+            counts.insert(word.to_string(), 1);
+            text_bytes += word.len();
+            let table = capacity::table_bytes(counts.capacity(), mem::size_of::<(String, u64)>());
+            if table + text_bytes > memory_budget {
+                runs.push(spill(&mut counts, dir)?);
+                text_bytes = 0;
+            }
+        }
+        line.clear();
+    }
+
+    let mut words: Vec<(String, u64)> = counts.into_iter().collect();
+    if runs.is_empty() {
+        words.sort_unstable();
+        return Ok(WordCounts::in_memory(words));
+    }
+    if !words.is_empty() {
+        runs.push(spill_sorted(words, dir)?);
+    }
+    let spills = runs.len();
+    let mut counts = WordCounts::merging(merge_down(runs, dir)?)?;
+    counts.spills = spills;
+    Ok(counts)
+}
+
+// Merges groups of runs into longer runs until there are few enough to
+// merge in one go
+fn merge_down(mut runs: Vec<PathBuf>, dir: &Path) -> io::Result<Vec<PathBuf>> {
+    while runs.len() > MERGE_WIDTH {
+        let mut merged = Vec::with_capacity(runs.len().div_ceil(MERGE_WIDTH));
+        let mut rest = runs.into_iter();
+        while rest.len() > 0 {
+            let group: Vec<PathBuf> = rest.by_ref().take(MERGE_WIDTH).collect();
+            // The group's files go with the `WordCounts` reading them
+            match WordCounts::merging(group).and_then(|counts| write_run(counts, dir)) {
+                Ok(path) => merged.push(path),
+                Err(err) => {
+                    remove_runs(&merged);
+                    remove_runs(rest.as_slice());
+                    return Err(err);
+                }
+            }
+        }
+        runs = merged;
+    }
+    Ok(runs)
+}
+
+// Writes out and empties `counts`, giving back the memory of its table too
+fn spill(counts: &mut HashMap<String, u64>, dir: &Path) -> io::Result<PathBuf> {
+    let words = mem::take(counts).into_iter().collect();
+    spill_sorted(words, dir)
+}
+
+fn spill_sorted(mut words: Vec<(String, u64)>, dir: &Path) -> io::Result<PathBuf> {
+    words.sort_unstable();
+    write_run(words.into_iter().map(Ok), dir)
+}
+
+// Writes entries already in word order to a new run file
+fn write_run<I>(entries: I, dir: &Path) -> io::Result<PathBuf>
+where
+    I: IntoIterator<Item = io::Result<(String, u64)>>,
+{
+    let (path, file) = create_run_file(dir)?;
+    let mut out = BufWriter::new(file);
+    let written = entries
+        .into_iter()
+        .try_for_each(|entry| {
+            let (word, count) = entry?;
+            writeln!(out, "{} {}", word, count)
+        })
+        .and_then(|()| out.flush());
+    if let Err(err) = written {
+        let _ = fs::remove_file(&path);
+        return Err(err);
+    }
+    Ok(path)
+}
+
+fn create_run_file(dir: &Path) -> io::Result<(PathBuf, File)> {
+    loop {
+        let run = NEXT_RUN.fetch_add(1, Ordering::Relaxed);
+        let path = dir.join(format!("word-count-{}-{}.run", process::id(), run));
+        match OpenOptions::new().write(true).create_new(true).open(&path) {
+            Ok(file) => return Ok((path, file)),
+            // Left behind by an earlier process with the same id
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err),
+        }
+    }
+}
+
+// Reads one sorted run back, putting the first error where `WordCounts` can
+// report it, since `merge_sorted` only deals in plain items
+struct RunReader {
+    lines: Lines<BufReader<File>>,
+    error: Rc<RefCell<Option<io::Error>>>,
+}
+
+impl Iterator for RunReader {
+    type Item = (String, u64);
+
+    fn next(&mut self) -> Option<(String, u64)> {
+        let parsed = match self.lines.next()? {
+            Ok(line) => parse_run_line(&line),
+            Err(err) => Err(err),
+        };
+        match parsed {
+            Ok(entry) => Some(entry),
+            Err(err) => {
+                self.error.borrow_mut().get_or_insert(err);
+                None
+            }
+        }
+    }
+}
+
+fn parse_run_line(line: &str) -> io::Result<(String, u64)> {
+    let malformed = || io::Error::new(io::ErrorKind::InvalidData, "malformed word count run");
+    let (word, count) = line.rsplit_once(' ').ok_or_else(malformed)?;
+    let count = count.parse().map_err(|_| malformed())?;
+    Ok((word.to_string(), count))
+}
+
+enum Source {
+    Memory(vec::IntoIter<(String, u64)>),
+    Runs(MergeSorted<RunReader>),
+}
+
+/// Word counts from `stream_word_count`, in word order
+///
+/// Yields an error and stops if a spill file can't be read back. The spill
+/// files are removed when this is dropped.
+pub struct WordCounts {
+    source: Source,
+    runs: Vec<PathBuf>,
+    spills: usize,
+    error: Rc<RefCell<Option<io::Error>>>,
+    // The first entry of the next word, already pulled from `source`
+    pending: Option<(String, u64)>,
+    failed: bool,
+}
+
+impl WordCounts {
+    fn in_memory(words: Vec<(String, u64)>) -> Self {
+        WordCounts {
+            source: Source::Memory(words.into_iter()),
+            runs: Vec::new(),
+            spills: 0,
+            error: Rc::new(RefCell::new(None)),
+            pending: None,
+            failed: false,
+        }
+    }
+
+    fn merging(runs: Vec<PathBuf>) -> io::Result<Self> {
+        let error = Rc::new(RefCell::new(None));
+        let mut readers = Vec::with_capacity(runs.len());
+        for path in &runs {
+            match File::open(path) {
+                Ok(file) => readers.push(RunReader {
+                    lines: BufReader::new(file).lines(),
+                    error: Rc::clone(&error),
+                }),
+                Err(err) => {
+                    remove_runs(&runs);
+                    return Err(err);
+                }
+            }
+        }
+        Ok(WordCounts {
+            source: Source::Runs(merge_sorted(readers)),
+            spills: runs.len(),
+            runs,
+            error,
+            pending: None,
+            failed: false,
+        })
+    }
+
+    /// Number of times the counts were spilled to disk
+    pub fn spill_count(&self) -> usize {
+        self.spills
+    }
+
+    fn pull(&mut self) -> Option<(String, u64)> {
+        match &mut self.source {
+            Source::Memory(words) => words.next(),
+            Source::Runs(merged) => merged.next(),
+        }
+    }
+}
+
+impl Iterator for WordCounts {
+    type Item = io::Result<(String, u64)>;
+
+    fn next(&mut self) -> Option<io::Result<(String, u64)>> {
+        if self.failed {
+            return None;
+        }
+        let first = self.pending.take().or_else(|| self.pull());
+        let result = first.map(|(word, mut count)| {
+            // Runs are sorted, so every count of a word arrives together
+            while let Some((next_word, next_count)) = self.pull() {
+                if next_word != word {
+                    self.pending = Some((next_word, next_count));
+                    break;
+                }
+                count += next_count;
+            }
+            (word, count)
+        });
+        if let Some(err) = self.error.borrow_mut().take() {
+            self.failed = true;
+            return Some(Err(err));
+        }
+        result.map(Ok)
+    }
+}
+
+impl Drop for WordCounts {
+    fn drop(&mut self) {
+        remove_runs(&self.runs);
+    }
+}
+
+fn remove_runs(runs: &[PathBuf]) {
+    for path in runs {
+        let _ = fs::remove_file(path);
+    }
+}
@@ -0,0 +1,391 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Parameterised workloads for timing one container at a time
+//!
+//! Where `comparison` times a fixed set of maps over fixed inputs, a
+//! `Workload` describes a single run: which container, how many operations,
+//! how the keys are drawn and how many of the operations are lookups rather
+//! than inserts. `run` carries it out twice on fresh containers, once
+//! untimed for the throughput and once timing every operation for the
+//! latency percentiles, since reading the clock around every operation
+//! slows the run down. The `collections bench` subcommand is a front end
+//! for it.
+//!
+//! The keys are generated before either pass starts, so drawing them isn't
+//! part of the timings.
+
+use crate::cuckoo_map::CuckooMap;
+use crate::dense_set::DenseSet;
+use crate::lfu_cache::LfuCache;
+use crate::probe_map::ProbeMap;
+use crate::random::{Rng, XorShift64};
+use crate::robin_hood_map::RobinHoodMap;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::hint::black_box;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+/// A container a `Workload` can run against
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Target {
+    HashMap,
+    BTreeMap,
+    ProbeMap,
+    RobinHoodMap,
+    CuckooMap,
+    DenseSet,
+    LfuCache,
+}
+
+impl Target {
+    pub const ALL: [Target; 7] = [
+        Target::HashMap,
+        Target::BTreeMap,
+        Target::ProbeMap,
+        Target::RobinHoodMap,
+        Target::CuckooMap,
+        Target::DenseSet,
+        Target::LfuCache,
+    ];
+
+    /// The short name `from_str` accepts
+    pub fn name(self) -> &'static str {
+        match self {
+            Target::HashMap => "hashmap",
+            Target::BTreeMap => "btreemap",
+            Target::ProbeMap => "probe",
+            Target::RobinHoodMap => "robin-hood",
+            Target::CuckooMap => "cuckoo",
+            Target::DenseSet => "dense-set",
+            Target::LfuCache => "lfu",
+        }
+    }
+
+    /// Whether the target drops entries once it holds `Workload::capacity`
+    pub fn is_cache(self) -> bool {
+        self == Target::LfuCache
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+/// The error from parsing an unknown `Target` or `KeyPattern` name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownName {
+    pub name: String,
+    /// The names that would have been accepted
+    pub expected: Vec<&'static str>,
+}
+
+impl fmt::Display for UnknownName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "unknown name '{}', expected one of {}",
+            self.name,
+            self.expected.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for UnknownName {}
+
+impl FromStr for Target {
+    type Err = UnknownName;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Target::ALL
+            .iter()
+            .copied()
+            .find(|target| target.name() == name)
+            .ok_or_else(|| UnknownName {
+                name: name.to_string(),
+                expected: Target::ALL.iter().map(|target| target.name()).collect(),
+            })
+    }
+}
+
+/// How a `Workload` picks the key for each operation out of its key space
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KeyPattern {
+    /// `0, 1, 2, ...`, starting again at the end of the key space
+    Sequential,
+    /// Every key equally likely
+    Uniform,
+    /// Key `k` is drawn in proportion to `1 / (k + 1)^exponent`, so a few
+    /// keys take most of the operations
+    Zipf { exponent: f64 },
+}
+
+impl KeyPattern {
+    /// The exponent a `Zipf` pattern parsed from `"zipf"` gets
+    pub const DEFAULT_ZIPF_EXPONENT: f64 = 1.0;
+
+    const NAMES: [&'static str; 3] = ["sequential", "uniform", "zipf"];
+
+    /// Generates `count` keys below `key_space`, the same ones every time
+    /// for the same `seed`
+    ///
+    /// `Zipf` keys are drawn from a table of 8 bytes per key in the key
+    /// space. Panics if `key_space` is zero.
+    pub fn keys(self, count: usize, key_space: u64, seed: u64) -> Vec<u64> {
+        assert!(key_space > 0, "the key space must not be empty");
+        let mut rng = XorShift64::new(seed);
+        match self {
+            KeyPattern::Sequential => (0..count as u64).map(|key| key % key_space).collect(),
+            KeyPattern::Uniform => (0..count).map(|_| rng.below(key_space)).collect(),
+            KeyPattern::Zipf { exponent } => {
+                // Cumulative weights, searched for a uniform point on them
+                let mut total = 0.0;
+                let cumulative: Vec<f64> = (0..key_space)
+                    .map(|key| {
+                        total += 1.0 / ((key + 1) as f64).powf(exponent);
+                        total
+                    })
+                    .collect();
+                (0..count)
+                    .map(|_| {
+                        let point = rng.next_f64() * total;
+                        let key = cumulative.partition_point(|&weight| weight <= point);
+                        (key as u64).min(key_space - 1)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+impl fmt::Display for KeyPattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyPattern::Sequential => f.write_str("sequential"),
+            KeyPattern::Uniform => f.write_str("uniform"),
+            KeyPattern::Zipf { exponent } => write!(f, "zipf({})", exponent),
+        }
+    }
+}
+
+impl FromStr for KeyPattern {
+    type Err = UnknownName;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "sequential" => Ok(KeyPattern::Sequential),
+            "uniform" => Ok(KeyPattern::Uniform),
+            "zipf" => Ok(KeyPattern::Zipf {
+                exponent: KeyPattern::DEFAULT_ZIPF_EXPONENT,
+            }),
+            _ => Err(UnknownName {
+                name: name.to_string(),
+                expected: KeyPattern::NAMES.to_vec(),
+            }),
+        }
+    }
+}
+
+/// One run of operations against one container
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Workload {
+    pub target: Target,
+    pub ops: usize,
+    pub keys: KeyPattern,
+    /// Keys are drawn from `0..key_space`
+    pub key_space: u64,
+    /// The fraction of operations that look their key up; the rest insert it
+    pub lookup_ratio: f64,
+    /// The most entries a cache target holds, ignored by the others
+    pub capacity: usize,
+    pub seed: u64,
+}
+
+impl Workload {
+    /// A workload of `ops` operations on `target`, with 80% lookups over
+    /// 100,000 uniformly drawn keys and room for a tenth of them in a cache
+    pub fn new(target: Target, ops: usize) -> Self {
+        Workload {
+            target,
+            ops,
+            keys: KeyPattern::Uniform,
+            key_space: 100_000,
+            lookup_ratio: 0.8,
+            capacity: 10_000,
+            seed: 0x5EED,
+        }
+    }
+}
+
+/// What `run` measured
+#[derive(Debug, Clone, PartialEq)]
+pub struct WorkloadReport {
+    pub workload: Workload,
+    /// Wall-clock time of the untimed pass
+    pub elapsed: Duration,
+    pub lookups: usize,
+    /// Lookups that found their key
+    pub hits: usize,
+    // Nanoseconds each operation took in the timed pass, sorted
+    latencies: Vec<u64>,
+}
+
+impl WorkloadReport {
+    pub fn ops_per_second(&self) -> f64 {
+        self.workload.ops as f64 / self.elapsed.as_secs_f64()
+    }
+
+    /// The fraction of lookups that found their key, or zero with no
+    /// lookups
+    pub fn hit_rate(&self) -> f64 {
+        if self.lookups == 0 {
+            0.0
+        } else {
+            self.hits as f64 / self.lookups as f64
+        }
+    }
+
+    /// The time within which `percent` of the operations finished in the
+    /// timed pass, or zero with no operations
+    ///
+    /// Panics unless `percent` is between 0 and 100.
+    pub fn percentile(&self, percent: f64) -> Duration {
+        assert!(
+            (0.0..=100.0).contains(&percent),
+            "percentile must be between 0 and 100"
+        );
+        if self.latencies.is_empty() {
+            return Duration::ZERO;
+        }
+        let rank = (percent / 100.0 * self.latencies.len() as f64).ceil() as usize;
+        let index = rank.clamp(1, self.latencies.len()) - 1;
+        Duration::from_nanos(self.latencies[index])
+    }
+
+    /// The slowest operation in the timed pass
+    pub fn max_latency(&self) -> Duration {
+        self.percentile(100.0)
+    }
+}
+
+/// Runs `workload`, once for throughput and once for latencies
+///
+/// Panics if the key space is empty or a cache target has no capacity.
+pub fn run(workload: &Workload) -> WorkloadReport {
+    assert!(
+        !workload.target.is_cache() || workload.capacity > 0,
+        "a cache needs room for at least one entry"
+    );
+    let keys = workload
+        .keys
+        .keys(workload.ops, workload.key_space, workload.seed);
+    // Pick each operation up front as well, from a generator of its own
+    let mut rng = XorShift64::new(workload.seed ^ LOOKUP_SEED);
+    let lookups: Vec<bool> = (0..workload.ops)
+        .map(|_| rng.next_f64() < workload.lookup_ratio)
+        .collect();
+    let ops: Vec<(u64, bool)> = keys.into_iter().zip(lookups).collect();
+
+    let (elapsed, lookups, hits, latencies) = match workload.target {
+        Target::HashMap => measure(HashMap::new, &ops),
+        Target::BTreeMap => measure(BTreeMap::new, &ops),
+        Target::ProbeMap => measure(ProbeMap::new, &ops),
+        Target::RobinHoodMap => measure(RobinHoodMap::new, &ops),
+        Target::CuckooMap => measure(CuckooMap::new, &ops),
+        Target::DenseSet => measure(DenseSet::new, &ops),
+        Target::LfuCache => measure(|| LfuCache::new(workload.capacity), &ops),
+    };
+    WorkloadReport {
+        workload: *workload,
+        elapsed,
+        lookups,
+        hits,
+        latencies,
+    }
+}
+
+const LOOKUP_SEED: u64 = 0x100C_0F5E_ED00;
+
+// The operations a workload needs from each container
+trait Subject {
+    fn lookup(&mut self, key: u64) -> bool;
+    fn insert(&mut self, key: u64);
+}
+
+macro_rules! impl_subject_for_map {
+    ($($map:ident),+) => {
+        $(
+            impl Subject for $map<u64, u64> {
+                fn lookup(&mut self, key: u64) -> bool {
+                    black_box($map::get(self, &key)).is_some()
+                }
+
+                fn insert(&mut self, key: u64) {
+                    $map::insert(self, key, key);
+                }
+            }
+        )+
+    };
+}
+
+impl_subject_for_map!(HashMap, BTreeMap, ProbeMap, RobinHoodMap, CuckooMap);
+
+impl Subject for DenseSet<u64> {
+    fn lookup(&mut self, key: u64) -> bool {
+        self.contains(&key)
+    }
+
+    fn insert(&mut self, key: u64) {
+        DenseSet::insert(self, key);
+    }
+}
+
+impl Subject for LfuCache<u64, u64> {
+    fn lookup(&mut self, key: u64) -> bool {
+        black_box(self.get(&key)).is_some()
+    }
+
+    fn insert(&mut self, key: u64) {
+        black_box(self.put(key, key));
+    }
+}
+
+// The untimed pass's wall-clock time, the lookups and hits it saw, and the
+// timed pass's sorted latencies in nanoseconds
+fn measure<S, F>(make: F, ops: &[(u64, bool)]) -> (Duration, usize, usize, Vec<u64>)
+where
+    S: Subject,
+    F: Fn() -> S,
+{
+    let mut subject = make();
+    let mut lookups = 0;
+    let mut hits = 0;
+    let start = Instant::now();
+    for &(key, lookup) in ops {
+        if lookup {
+            lookups += 1;
+            hits += subject.lookup(key) as usize;
+        } else {
+            subject.insert(key);
+        }
+    }
+    let elapsed = start.elapsed();
+    drop(subject);
+
+    let mut subject = make();
+    let mut latencies = Vec::with_capacity(ops.len());
+    for &(key, lookup) in ops {
+        let start = Instant::now();
+        if lookup {
+            black_box(subject.lookup(key));
+        } else {
+            subject.insert(key);
+        }
+        latencies.push(start.elapsed().as_nanos() as u64);
+    }
+    latencies.sort_unstable();
+    (elapsed, lookups, hits, latencies)
+}
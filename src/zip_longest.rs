@@ -0,0 +1,111 @@
+// Project: rust-collections
+// Author: Greg Folker
+
+//! Zipping two iterators without losing the tail of the longer one
+//!
+//! `Iterator::zip` stops as soon as either side runs out, silently dropping
+//! whatever is left on the other. `zip_longest` keeps going until both are
+//! exhausted and says for each step which sides had an item, and
+//! `unzip_longest` splits such a sequence back into its two sides.
+
+use std::cmp;
+
+/// One step of `zip_longest`: an item from either side, or from both
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EitherOrBoth<L, R> {
+    Both(L, R),
+    /// The right side had already run out
+    Left(L),
+    /// The left side had already run out
+    Right(R),
+}
+
+impl<L, R> EitherOrBoth<L, R> {
+    pub fn is_both(&self) -> bool {
+        matches!(self, EitherOrBoth::Both(..))
+    }
+
+    pub fn left(self) -> Option<L> {
+        match self {
+            EitherOrBoth::Both(left, _) | EitherOrBoth::Left(left) => Some(left),
+            EitherOrBoth::Right(_) => None,
+        }
+    }
+
+    pub fn right(self) -> Option<R> {
+        match self {
+            EitherOrBoth::Both(_, right) | EitherOrBoth::Right(right) => Some(right),
+            EitherOrBoth::Left(_) => None,
+        }
+    }
+
+    /// Both sides as options, at least one of which is `Some`
+    pub fn into_options(self) -> (Option<L>, Option<R>) {
+        match self {
+            EitherOrBoth::Both(left, right) => (Some(left), Some(right)),
+            EitherOrBoth::Left(left) => (Some(left), None),
+            EitherOrBoth::Right(right) => (None, Some(right)),
+        }
+    }
+}
+
+/// Pairs up the items of `left` and `right` until both have run out
+pub fn zip_longest<A, B>(left: A, right: B) -> ZipLongest<A::IntoIter, B::IntoIter>
+where
+    A: IntoIterator,
+    B: IntoIterator,
+{
+    ZipLongest {
+        left: left.into_iter(),
+        right: right.into_iter(),
+    }
+}
+
+/// Splits a sequence of `EitherOrBoth` into the items from each side, so
+/// that `zip_longest` of the two halves gives the sequence back whenever each
+/// side's items came before its gaps
+pub fn unzip_longest<L, R, I>(iter: I) -> (Vec<L>, Vec<R>)
+where
+    I: IntoIterator<Item = EitherOrBoth<L, R>>,
+{
+    let mut lefts = Vec::new();
+    let mut rights = Vec::new();
+    for item in iter {
+        let (left, right) = item.into_options();
+        lefts.extend(left);
+        rights.extend(right);
+    }
+    (lefts, rights)
+}
+
+/// Iterator returned by `zip_longest`
+#[derive(Debug, Clone)]
+pub struct ZipLongest<A, B> {
+    left: A,
+    right: B,
+}
+
+impl<A: Iterator, B: Iterator> Iterator for ZipLongest<A, B> {
+    type Item = EitherOrBoth<A::Item, B::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match (self.left.next(), self.right.next()) {
+            (Some(left), Some(right)) => Some(EitherOrBoth::Both(left, right)),
+            (Some(left), None) => Some(EitherOrBoth::Left(left)),
+            (None, Some(right)) => Some(EitherOrBoth::Right(right)),
+            (None, None) => None,
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (left_lower, left_upper) = self.left.size_hint();
+        let (right_lower, right_upper) = self.right.size_hint();
+        let upper = match (left_upper, right_upper) {
+            (Some(left), Some(right)) => Some(cmp::max(left, right)),
+            _ => None,
+        };
+        (cmp::max(left_lower, right_lower), upper)
+    }
+}
+
+impl<A: ExactSizeIterator, B: ExactSizeIterator> ExactSizeIterator for ZipLongest<A, B> {}